@@ -0,0 +1,146 @@
+//! Per-pin GPIO interrupt-latency calibration.
+//!
+//! [`Test::observe`](crate::test::Test::observe) timestamps a [`Response`](crate::test::Response)
+//! with [`Instant::now()`] only after [`Gpio::poll_interrupts`](rppal::gpio::Gpio::poll_interrupts)
+//! returns, so every recorded offset carries a fixed bias from GPIO interrupt dispatch and poll
+//! latency. [`TimingCalibration`] measures that bias per pin by driving known edges through a
+//! host-side loopback (an [`OutputPin`] wired directly to the [`InputPin`] being calibrated) and
+//! records how late each one is observed, so the bias can be subtracted back out.
+
+use std::collections::HashMap;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rppal::gpio::{self, InputPin, OutputPin, Trigger};
+
+/// Number of loopback edges driven per pin during calibration.
+const DEFAULT_SAMPLE_COUNT: usize = 32;
+/// Time between successive edges, giving the previous one time to be observed and settle.
+const DEFAULT_EDGE_SPACING: Duration = Duration::from_millis(20);
+/// Outlier cutoff for the median-absolute-deviation filter, in MADs.
+const DEFAULT_MAD_THRESHOLD: f64 = 3.0;
+
+/** Per-pin interrupt-latency corrections, measured by loopback calibration.
+
+Build one with [`TimingCalibration::new`], call [`TimingCalibration::measure_pin`] once per input
+pin that timing-sensitive criteria depend on, then pass the result into
+[`Test::observe`](crate::test::Test::observe) so recorded [`Response`](crate::test::Response)
+timestamps are corrected as they're produced. A caller holding onto already-recorded, uncorrected
+responses can instead apply a correction after the fact with
+[`Response::get_corrected_offset`](crate::test::Response::get_corrected_offset).
+ */
+#[derive(Clone, Debug, Default)]
+pub struct TimingCalibration {
+    corrections: HashMap<u8, Duration>,
+}
+
+impl TimingCalibration {
+    /// Create an empty calibration; every pin's correction defaults to zero until measured.
+    pub fn new() -> TimingCalibration {
+        TimingCalibration::default()
+    }
+
+    /** Measure the interrupt latency of `input_pin`, recording the result under `pin_no`.
+
+    `input_pin` must be wired in loopback to `output_pin`. Drives [`DEFAULT_SAMPLE_COUNT`] edges
+    [`DEFAULT_EDGE_SPACING`] apart, alternating level each time; see [`measure_pin_with`] to
+    override these.
+
+    [`measure_pin_with`]: TimingCalibration::measure_pin_with
+     */
+    pub fn measure_pin(&mut self,
+                       pin_no: u8,
+                       output_pin: &mut OutputPin,
+                       input_pin: &mut InputPin) -> gpio::Result<()>
+    {
+        self.measure_pin_with(
+            pin_no,
+            output_pin,
+            input_pin,
+            DEFAULT_SAMPLE_COUNT,
+            DEFAULT_EDGE_SPACING,
+            DEFAULT_MAD_THRESHOLD)
+    }
+
+    /** Measure the interrupt latency of `input_pin`, as [`measure_pin`](TimingCalibration::measure_pin),
+    with explicit control over the sample count, edge spacing, and outlier threshold.
+
+    The stored correction is the median of the observed delays, after discarding samples further
+    than `mad_threshold` median absolute deviations from it, so a single stray scheduling hiccup
+    can't drag the pin's correction off by itself.
+     */
+    pub fn measure_pin_with(&mut self,
+                            pin_no: u8,
+                            output_pin: &mut OutputPin,
+                            input_pin: &mut InputPin,
+                            sample_count: usize,
+                            edge_spacing: Duration,
+                            mad_threshold: f64) -> gpio::Result<()>
+    {
+        input_pin.set_interrupt(Trigger::Both)?;
+
+        let gpio = gpio::Gpio::new()?;
+        let mut delays = Vec::with_capacity(sample_count);
+        let mut level_high = output_pin.is_set_high();
+
+        for _ in 0..sample_count {
+            thread::sleep(edge_spacing);
+
+            let commanded_at = Instant::now();
+            level_high = !level_high;
+            if level_high {
+                output_pin.set_high();
+            } else {
+                output_pin.set_low();
+            }
+
+            if let Some((_pin, _level)) = gpio.poll_interrupts(&[input_pin], false, Some(edge_spacing))? {
+                delays.push(Instant::now().saturating_duration_since(commanded_at));
+            }
+        }
+
+        input_pin.clear_interrupt()?;
+
+        self.corrections.insert(pin_no, robust_central_offset(&delays, mad_threshold));
+
+        Ok(())
+    }
+
+    /// Returns the correction measured for `pin_no`, or zero if it hasn't been measured.
+    pub fn correction_for(&self, pin_no: u8) -> Duration {
+        self.corrections.get(&pin_no).copied().unwrap_or(Duration::from_millis(0))
+    }
+}
+
+/// Returns the median of `delays`, after discarding samples more than `mad_threshold` median
+/// absolute deviations from it. Returns zero if `delays` is empty.
+fn robust_central_offset(delays: &[Duration], mad_threshold: f64) -> Duration {
+    if delays.is_empty() {
+        return Duration::from_millis(0);
+    }
+
+    let mut micros: Vec<f64> = delays.iter().map(|d| d.as_secs_f64() * 1_000_000.0).collect();
+    let center = median(&mut micros);
+
+    let mut deviations: Vec<f64> = micros.iter().map(|v| (v - center).abs()).collect();
+    let mad = median(&mut deviations);
+
+    let mut filtered: Vec<f64> = micros.into_iter()
+        .filter(|v| mad == 0.0 || (v - center).abs() <= mad_threshold * mad)
+        .collect();
+
+    let corrected = if filtered.is_empty() { center } else { median(&mut filtered) };
+    Duration::from_micros(corrected.max(0.0) as u64)
+}
+
+/// Returns the median of `values`, sorting them in place.
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let n = values.len();
+    if n % 2 == 0 {
+        (values[n / 2 - 1] + values[n / 2]) / 2.0
+    } else {
+        values[n / 2]
+    }
+}
@@ -5,6 +5,8 @@ use std::fmt;
 use std::fmt::Display;
 use std::time::{Duration, Instant};
 
+use flexbed_shared::mem::CounterId;
+
 use super::trace::SerialTrace;
 
 /** Defined response to look for from the device under test.
@@ -20,6 +22,10 @@ pub enum Criterion {
     Energy(EnergyCriterion),
     /// Serial-based activity tracing.
     SerialTrace(SerialTraceCriterion),
+    /// Memory counter budget.
+    Memory(MemoryCriterion),
+    /// Ordered performance-waypoint tracing.
+    PerformanceTrace(PerformanceTraceCriterion),
 }
 
 impl Display for Criterion {
@@ -28,6 +34,8 @@ impl Display for Criterion {
             Criterion::GPIO(ref c) => write!(f, "GPIO activity: {}", c),
             Criterion::Energy(ref c) => write!(f, "Energy: {}", c),
             Criterion::SerialTrace(ref c) => write!(f, "Serial trace: {}", c),
+            Criterion::Memory(ref c) => write!(f, "Memory: {}", c),
+            Criterion::PerformanceTrace(ref c) => write!(f, "Performance trace: {}", c),
         }
     }
 }
@@ -38,12 +46,15 @@ impl Display for Criterion {
 pub enum GPIOCriterion {
     /// Any and all activity on a GPIO pin.
     Any(u8),
+    /// Periodic analog readings from a GPIO pin; see [`super::test::Test::analog_observe`].
+    Analog(u8),
 }
 
 impl Display for GPIOCriterion {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             GPIOCriterion::Any(pin_no) => write!(f, "any output on device pin {}", pin_no),
+            GPIOCriterion::Analog(pin_no) => write!(f, "analog readings on device pin {}", pin_no),
         }
     }
 }
@@ -78,6 +89,19 @@ impl Display for Timing {
     }
 }
 
+/// Which version of a meter's sample trace an [`EnergyCriterion`]'s statistic is evaluated
+/// against; see [`EnergyCriterion::with_low_pass`]/[`EnergyCriterion::with_high_pass`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FilterStage {
+    /// The raw, unfiltered sample trace.
+    #[default]
+    Raw,
+    /// The trace passed through the criterion's [low-pass](EnergyCriterion::with_low_pass) filter.
+    LowPass,
+    /// The trace passed through the criterion's [high-pass](EnergyCriterion::with_high_pass) filter.
+    HighPass,
+}
+
 /// Energy criterion specification details.
 #[derive(Clone, Debug)]
 pub struct EnergyCriterion {
@@ -85,11 +109,20 @@ pub struct EnergyCriterion {
     stat: EnergyStat,
     min: Option<f32>,
     max: Option<f32>,
+    baseline_window: Option<(Duration, Duration)>,
+    active_window: Option<(Duration, Duration)>,
+    baseline_estimator: BaselineEstimator,
+    low_pass_cutoff_hz: Option<f64>,
+    high_pass_cutoff_hz: Option<f64>,
+    filter_stage: FilterStage,
 }
 
 /// Energy-specific criterion of interest.
 impl EnergyCriterion {
     /// Create a new EnergyCriterion.
+    ///
+    /// By default, no baseline window is set (no idle power is subtracted from `EnergyStat::Total`)
+    /// and no active window is set (the entire execution is considered).
     #[allow(dead_code)]
     pub fn new(meter: &str, stat: EnergyStat) -> Self {
         Self {
@@ -97,6 +130,12 @@ impl EnergyCriterion {
             stat,
             min: None,
             max: None,
+            baseline_window: None,
+            active_window: None,
+            baseline_estimator: BaselineEstimator::Mode,
+            low_pass_cutoff_hz: None,
+            high_pass_cutoff_hz: None,
+            filter_stage: FilterStage::Raw,
         }
     }
 
@@ -118,6 +157,68 @@ impl EnergyCriterion {
         }
     }
 
+    /** Define the window, relative to execution start, over which idle power is estimated.
+
+    When set, `EnergyStat::Total` estimates idle power from samples falling within
+    `(start, end)` and subtracts it from every sample counted towards the total. When unset
+    (the default), idle subtraction is skipped entirely and raw sample values are totaled.
+     */
+    #[allow(unused)]
+    pub fn with_baseline_window(self, start: Duration, end: Duration) -> Self {
+        Self {
+            baseline_window: Some((start, end)),
+            ..self
+        }
+    }
+
+    /** Define the window, relative to execution start, over which `EnergyStat::Total` counts
+    samples.
+
+    When unset (the default), the entire execution is counted.
+     */
+    #[allow(unused)]
+    pub fn with_active_window(self, start: Duration, end: Duration) -> Self {
+        Self {
+            active_window: Some((start, end)),
+            ..self
+        }
+    }
+
+    /// Choose how idle power is estimated from the baseline window's samples.
+    #[allow(unused)]
+    pub fn with_baseline_estimator(self, estimator: BaselineEstimator) -> Self {
+        Self {
+            baseline_estimator: estimator,
+            ..self
+        }
+    }
+
+    /** Configure a low-pass [`Biquad`](crate::filter::Biquad) section at `cutoff_hz` and select it
+    (via [`FilterStage::LowPass`]) as the trace [`EnergyStat::Average`]/[`EnergyStat::Max`]/
+    [`EnergyStat::Min`] are measured against.
+     */
+    #[allow(unused)]
+    pub fn with_low_pass(self, cutoff_hz: f64) -> Self {
+        Self {
+            low_pass_cutoff_hz: Some(cutoff_hz),
+            filter_stage: FilterStage::LowPass,
+            ..self
+        }
+    }
+
+    /** Configure a high-pass [`Biquad`](crate::filter::Biquad) section at `cutoff_hz` and select it
+    (via [`FilterStage::HighPass`]) as the trace [`EnergyStat::Average`]/[`EnergyStat::Max`]/
+    [`EnergyStat::Min`] are measured against.
+     */
+    #[allow(unused)]
+    pub fn with_high_pass(self, cutoff_hz: f64) -> Self {
+        Self {
+            high_pass_cutoff_hz: Some(cutoff_hz),
+            filter_stage: FilterStage::HighPass,
+            ..self
+        }
+    }
+
     /// Returns the name of the target energy meter.
     pub fn get_meter(&self) -> &str {
         &self.meter
@@ -128,6 +229,36 @@ impl EnergyCriterion {
         self.stat
     }
 
+    /// Returns the configured baseline (idle power) window, if any.
+    pub fn get_baseline_window(&self) -> Option<(Duration, Duration)> {
+        self.baseline_window
+    }
+
+    /// Returns the configured active measurement window, if any.
+    pub fn get_active_window(&self) -> Option<(Duration, Duration)> {
+        self.active_window
+    }
+
+    /// Returns the estimator used to derive idle power from the baseline window's samples.
+    pub fn get_baseline_estimator(&self) -> BaselineEstimator {
+        self.baseline_estimator
+    }
+
+    /// Returns the configured low-pass cutoff frequency (Hz), if any.
+    pub fn get_low_pass_cutoff_hz(&self) -> Option<f64> {
+        self.low_pass_cutoff_hz
+    }
+
+    /// Returns the configured high-pass cutoff frequency (Hz), if any.
+    pub fn get_high_pass_cutoff_hz(&self) -> Option<f64> {
+        self.high_pass_cutoff_hz
+    }
+
+    /// Returns which version of the sample trace the statistic is measured against.
+    pub fn get_filter_stage(&self) -> FilterStage {
+        self.filter_stage
+    }
+
     /** Returns true if the given value violates the criterion.
 
     If there is no part of the criterion can be violated this function will return None.
@@ -151,6 +282,7 @@ impl Display for EnergyCriterion {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let unit = match self.stat {
             EnergyStat::Total => "mJ",
+            EnergyStat::TimeAbove(_) => "ms",
             _ => "mJ/s"
         };
 
@@ -174,6 +306,10 @@ pub enum EnergyStat {
     Max,
     /// Track the minimum energy consumption rate.
     Min,
+    /// Track the consumption rate at a given percentile (`0.0`-`1.0`) of the sample distribution.
+    Percentile(f64),
+    /// Track how long (in ms) interpolated consumption stays above a threshold (in mW).
+    TimeAbove(f32),
 }
 
 impl Display for EnergyStat {
@@ -183,10 +319,94 @@ impl Display for EnergyStat {
             EnergyStat::Average => write!(f, "average consumption rate"),
             EnergyStat::Max => write!(f, "max consumption"),
             EnergyStat::Min => write!(f, "min consumption"),
+            EnergyStat::Percentile(p) => write!(f, "p{:.0} consumption", p.clamp(0.0, 1.0) * 100.0),
+            EnergyStat::TimeAbove(threshold) => write!(f, "time above {:.2}mW", threshold),
         }
     }
 }
 
+/// How idle power is estimated from a baseline window's samples.
+#[allow(unused)]
+#[derive(Clone, Copy, Debug)]
+pub enum BaselineEstimator {
+    /// Arithmetic mean of the baseline window's samples.
+    Mean,
+    /// Mode of the baseline window's samples, via a bucketed [`crate::histogram::Histogram`].
+    Mode,
+}
+
+/// Memory-budget criterion specification details.
+#[derive(Clone, Debug)]
+pub struct MemoryCriterion {
+    counter: CounterId,
+    min: Option<u32>,
+    max: Option<u32>,
+}
+
+/// Memory-specific criterion of interest.
+impl MemoryCriterion {
+    /// Create a new MemoryCriterion for the given counter.
+    #[allow(dead_code)]
+    pub fn new(counter: CounterId) -> Self {
+        Self {
+            counter,
+            min: None,
+            max: None,
+        }
+    }
+
+    /// Specify a minimum value for the criterion.
+    #[allow(unused)]
+    pub fn with_min(self, min: u32) -> Self {
+        Self {
+            min: Some(min),
+            ..self
+        }
+    }
+
+    /// Specify a maximum value for the criterion.
+    #[allow(unused)]
+    pub fn with_max(self, max: u32) -> Self {
+        Self {
+            max: Some(max),
+            ..self
+        }
+    }
+
+    /// Returns the counter this criterion tracks.
+    pub fn get_counter(&self) -> &CounterId {
+        &self.counter
+    }
+
+    /** Returns true if the given value violates the criterion.
+
+    If there is no part of the criterion that can be violated this function will return None.
+     */
+    pub fn violated(&self, value: u32) -> Option<bool> {
+        if self.min.is_none() && self.max.is_none() {
+            None
+        } else {
+            let b = self.min.map(|min| value < min)
+                .unwrap_or(false)
+                ||
+                self.max.map(|max| value > max)
+                .unwrap_or(false);
+
+            Some(b)
+        }
+    }
+}
+
+impl Display for MemoryCriterion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ", self.counter)?;
+        write!(f, "(min: {},", self.min.map(|x| x.to_string()).unwrap_or("-".to_string()))?;
+        write!(f, " max: {})", self.max.map(|x| x.to_string()).unwrap_or("-".to_string()))?;
+
+        Ok(())
+    }
+}
+
 /// Component condition of a [`SerialTraceCriterion`].
 #[allow(unused)]
 #[derive(Clone, Debug)]
@@ -357,3 +577,81 @@ impl Display for SerialTraceCriterion {
         Ok(())
     }
 }
+
+/// A single named point along a traced execution path.
+#[derive(Clone, Debug)]
+pub struct PerformanceWaypoint {
+    label: String,
+    max_elapsed: Option<Duration>,
+}
+
+impl PerformanceWaypoint {
+    /// Create a new waypoint identified by `label`.
+    pub fn new(label: &str) -> PerformanceWaypoint {
+        PerformanceWaypoint {
+            label: label.to_string(),
+            max_elapsed: None,
+        }
+    }
+
+    /// Bound the length of time allowed to elapse since the previous waypoint (or test start, for
+    /// the first waypoint) before the criterion fails.
+    #[allow(dead_code)]
+    pub fn with_max_elapsed(self, max_elapsed: Duration) -> Self {
+        Self {
+            max_elapsed: Some(max_elapsed),
+            ..self
+        }
+    }
+
+    /// Returns the waypoint's identifying label.
+    pub fn get_label(&self) -> &str {
+        &self.label
+    }
+
+    /// Returns the maximum length of time allowed to elapse since the previous waypoint, if any.
+    pub fn get_max_elapsed(&self) -> Option<Duration> {
+        self.max_elapsed
+    }
+}
+
+impl Display for PerformanceWaypoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.label)
+    }
+}
+
+/** Performance-waypoint tracing criterion specification details.
+
+Waypoints are matched in order against the trace events captured during a test's execution
+(e.g. `sam4l-adc → adc-capsule → application → dac-capsule`); the criterion reports the elapsed
+time between each consecutive pair plus the total critical-path duration.
+ */
+#[derive(Clone, Debug)]
+pub struct PerformanceTraceCriterion {
+    waypoints: Vec<PerformanceWaypoint>,
+}
+
+impl PerformanceTraceCriterion {
+    /// Create a new criterion from an ordered sequence of waypoints.
+    pub fn new<'a, T>(waypoints: T) -> PerformanceTraceCriterion
+    where
+        T: IntoIterator<Item = &'a PerformanceWaypoint>,
+    {
+        PerformanceTraceCriterion {
+            waypoints: waypoints.into_iter().cloned().collect(),
+        }
+    }
+
+    /// Returns the waypoints making up the traced critical path, in order.
+    pub fn get_waypoints(&self) -> &Vec<PerformanceWaypoint> {
+        &self.waypoints
+    }
+}
+
+impl Display for PerformanceTraceCriterion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let labels: Vec<&str> = self.waypoints.iter().map(|w| w.get_label()).collect();
+        write!(f, "{}", labels.join(" → "))
+    }
+}
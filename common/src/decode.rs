@@ -0,0 +1,148 @@
+/*! A reusable incremental byte-stream decoder for frame-parser loops.
+
+This generalizes the "accumulate bytes, try to parse, keep whatever's left over for the next read"
+pattern that a stream of discrete records arriving over a slow link (like UART) needs: each call
+to the underlying read may return a partial record, a whole record, or several, and a caller
+shouldn't have to hand-roll the `bytes_read`/`bytes_parsed` index bookkeeping (and the temptation
+to `panic!` on a parser it assumes can't fail) every time it wants to consume one.
+
+[`IncrementalDecoder`] owns the growable buffer and the read cursor; a caller repeatedly
+[`IncrementalDecoder::feed`]s it whatever a read produced along with a [`FrameParser`], and gets
+back every item that could be fully decoded from the bytes buffered so far. An incomplete trailing
+frame is retained rather than discarded, so the next `feed` can pick up where the last one left
+off. [`Test::memtrack`](crate::test::Test::memtrack) is built on this.
+ */
+
+use std::fmt;
+use std::fmt::Display;
+
+/// Result of one [`FrameParser::parse`] attempt over the bytes currently buffered.
+#[derive(Debug)]
+pub enum ParseOutcome<T> {
+    /// A complete item was decoded, consuming `consumed` bytes from the front of the buffer.
+    Item {
+        /// The decoded item.
+        item: T,
+        /// How many bytes of the buffer the item consumed.
+        consumed: usize,
+    },
+    /// Not enough data buffered yet. `needed` is a lower bound on how many more bytes must
+    /// arrive before another parse attempt can make progress; a parser that can't estimate this
+    /// precisely may conservatively return `1`.
+    Incomplete {
+        /// Lower bound on additional bytes needed before parsing can proceed.
+        needed: usize,
+    },
+}
+
+/// Parses one frame at a time out of a byte buffer. Implemented per wire format; an
+/// [`IncrementalDecoder`] drives it across however many `Uart::read`-sized chunks a full frame
+/// happens to straddle.
+pub trait FrameParser {
+    /// Type of a successfully decoded frame.
+    type Item;
+    /// Type of a genuine parse failure (malformed data, as opposed to merely incomplete data).
+    type Error;
+
+    /// Attempt to parse one item from the front of `data`. `data` may contain more than one
+    /// frame's worth of bytes; implementations should parse only the first and let the decoder
+    /// call again for the rest.
+    fn parse(&mut self, data: &[u8]) -> Result<ParseOutcome<Self::Item>, Self::Error>;
+}
+
+/// Error surfaced by [`IncrementalDecoder::feed`] when the supplied [`FrameParser`] rejects the
+/// buffered data, wrapping the parser's own error with the byte offset it failed at.
+#[derive(Debug)]
+pub struct DecodeError<E> {
+    /// Offset (from the start of the stream) of the first byte of the frame that failed to parse.
+    pub offset: u64,
+    /// The underlying parser error.
+    pub source: E,
+}
+
+impl<E: Display> Display for DecodeError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "frame parse error at byte offset {}: {}", self.offset, self.source)
+    }
+}
+
+/** Accumulates bytes fed in arbitrary-sized chunks and decodes complete frames from them as soon
+as they're available, retaining any incomplete trailing frame for the next [`feed`](Self::feed).
+
+Compacts its internal buffer once the consumed prefix grows past [`COMPACT_THRESHOLD`] so a long
+run of small frames doesn't leave an ever-growing discarded prefix sitting in memory.
+ */
+pub struct IncrementalDecoder {
+    buffer: Vec<u8>,
+    start: usize,
+    offset: u64,
+}
+
+/// Consumed-prefix size (in bytes) past which [`IncrementalDecoder::feed`] compacts its buffer.
+const COMPACT_THRESHOLD: usize = 64 * 1024;
+
+impl IncrementalDecoder {
+    /// Creates an empty decoder with nothing buffered.
+    pub fn new() -> IncrementalDecoder {
+        IncrementalDecoder {
+            buffer: Vec::new(),
+            start: 0,
+            offset: 0,
+        }
+    }
+
+    /** Buffers `chunk` and repeatedly invokes `parser` until it reports [`ParseOutcome::Incomplete`]
+    or the buffer is exhausted, returning every item decoded along the way.
+
+    Returns an error (wrapping the parser's own error with the offset it occurred at) as soon as
+    `parser` reports one; bytes already buffered are left in place so a caller can still inspect
+    [`IncrementalDecoder::pending`] afterwards.
+     */
+    pub fn feed<P: FrameParser>(&mut self, chunk: &[u8], parser: &mut P) -> Result<Vec<P::Item>, DecodeError<P::Error>> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut items = Vec::new();
+        loop {
+            let data = &self.buffer[self.start..];
+            if data.is_empty() {
+                break;
+            }
+
+            match parser.parse(data) {
+                Ok(ParseOutcome::Item { item, consumed }) => {
+                    debug_assert!(consumed > 0 && consumed <= data.len(),
+                                  "FrameParser::parse must consume between 1 and data.len() bytes");
+                    self.start += consumed;
+                    self.offset += consumed as u64;
+                    items.push(item);
+                },
+                Ok(ParseOutcome::Incomplete { .. }) => break,
+                Err(source) => return Err(DecodeError { offset: self.offset, source }),
+            }
+        }
+
+        self.compact();
+
+        Ok(items)
+    }
+
+    /// Returns the number of bytes fully decoded into items so far.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns the number of buffered bytes not yet consumed into a complete item.
+    pub fn pending(&self) -> usize {
+        self.buffer.len() - self.start
+    }
+
+    fn compact(&mut self) {
+        if self.start == self.buffer.len() {
+            self.buffer.clear();
+            self.start = 0;
+        } else if self.start >= COMPACT_THRESHOLD {
+            self.buffer.drain(..self.start);
+            self.start = 0;
+        }
+    }
+}
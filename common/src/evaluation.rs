@@ -1,15 +1,21 @@
 //! Process and evaluate test data.
 
-use std::collections::HashMap;
-use std::fs::File;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::fmt::{self, Display};
 
 use crate::criteria::{
+    BaselineEstimator,
     Criterion,
+    EnergyCriterion,
+    FilterStage,
     GPIOCriterion,
     EnergyStat,
 };
+use crate::filter::{Biquad, BiquadCascade};
+use crate::histogram::Histogram;
+use crate::mem::reconstruct_timelines;
+use crate::metrics::{EnergySink, FileEnergySink};
+use crate::test::Sample;
 use crate::testbed::Observation;
 
 /// Judged outcome.
@@ -23,6 +29,10 @@ pub enum Status {
     Fail,
     /// Execution did not complete successfully.
     Error,
+    /// The test was not run, because it's marked ignored and the run didn't ask for it.
+    Ignored,
+    /// The test is marked `should_panic`, but its execution succeeded anyway.
+    UnexpectedPass,
 }
 
 impl Display for Status {
@@ -32,6 +42,8 @@ impl Display for Status {
             Status::Pass => write!(f, "Pass"),
             Status::Fail => write!(f, "Fail"),
             Status::Error => write!(f, "Error"),
+            Status::Ignored => write!(f, "Ignored"),
+            Status::UnexpectedPass => write!(f, "UnexpectedPass"),
         }
     }
 }
@@ -100,6 +112,16 @@ impl<'a> Evaluation<'a> {
             data,
         }
     }
+
+    /// Return the overall status of the evaluation.
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    /// Return the observation the evaluation was produced from.
+    pub fn observation(&self) -> &'a Observation<'a> {
+        self.data
+    }
 }
 
 impl<'a> Display for Evaluation<'a> {
@@ -107,6 +129,7 @@ impl<'a> Display for Evaluation<'a> {
         write!(f, "{}\t", self.data.source_test().get_id())?;
         match self.status {
             Status::Error => write!(f, "Error ({})", self.data.execution_result().as_ref().unwrap_err()),
+            Status::Ignored => write!(f, "Ignored"),
             test_outcome => write!(f, "{} (in {:?})", test_outcome, self.data.execution_result().as_ref().unwrap().duration()),
         }?;
         write!(f, "\n")?;
@@ -127,22 +150,44 @@ impl<'a> Display for Evaluation<'a> {
 }
 
 /// Basic, built-in evaluator.
-pub struct StandardEvaluator;
+pub struct StandardEvaluator {
+    energy_sink: Box<dyn EnergySink>,
+}
 
 impl StandardEvaluator {
-    /// Create a new `StandardEvaluator`.
+    /// Create a new `StandardEvaluator` that writes energy data to scratch CSV files under `/tmp`,
+    /// matching this evaluator's original behavior.
     pub fn new() -> StandardEvaluator {
-        StandardEvaluator
+        StandardEvaluator { energy_sink: Box::new(FileEnergySink::new()) }
+    }
+
+    /// Create a new `StandardEvaluator` that sends energy samples and aggregates to `energy_sink`
+    /// instead of the default scratch CSV files.
+    pub fn with_energy_sink(energy_sink: Box<dyn EnergySink>) -> StandardEvaluator {
+        StandardEvaluator { energy_sink }
     }
 }
 
 impl Evaluator for StandardEvaluator {
     fn evaluate<'a>(&self, observation: &'a Observation<'a>) -> Evaluation<'a> {
+        let test = observation.source_test();
+
+        if test.is_ignored() {
+            return Evaluation::new(Status::Ignored, Vec::new(), observation);
+        }
+
+        if test.should_panic() {
+            return match observation.execution_result() {
+                Ok(_) => Evaluation::new(Status::UnexpectedPass, Vec::new(), observation),
+                Err(_) => Evaluation::new(Status::Pass, Vec::new(), observation),
+            };
+        }
+
         match observation.execution_result() {
             Ok(_execution_info) => {
                 let criteria = observation.source_test().get_criteria();
                 let outcomes: Vec<_> = criteria.iter()
-                    .map(|criterion| evaluate(criterion, observation))
+                    .map(|criterion| evaluate(criterion, observation, self.energy_sink.as_ref()))
                     .collect();
 
                 // Summarize the evaluation's outcome by inspecting the component criteria's outcomes.
@@ -179,104 +224,142 @@ impl Evaluator for StandardEvaluator {
     }
 }
 
+/** Return `samples`' values, passed through the `Biquad` cascade `criterion`'s filter settings
+select, or unchanged if the criterion is set to `FilterStage::Raw` (the default).
+ */
+fn filtered_values(criterion: &EnergyCriterion, samples: &[Sample]) -> Vec<f32> {
+    let cutoff_hz = match criterion.get_filter_stage() {
+        FilterStage::Raw => None,
+        FilterStage::LowPass => criterion.get_low_pass_cutoff_hz(),
+        FilterStage::HighPass => criterion.get_high_pass_cutoff_hz(),
+    };
+
+    let cutoff_hz = match cutoff_hz {
+        Some(cutoff_hz) => cutoff_hz,
+        None => return samples.iter().map(Sample::get_value).collect(),
+    };
+
+    // Estimate the sampling interval from the average spacing between samples, since meters
+    // aren't guaranteed to sample at a perfectly fixed rate.
+    let sample_interval = if samples.len() > 1 {
+        let span = samples.last().unwrap().get_offset() - samples.first().unwrap().get_offset();
+        span / (samples.len() as u32 - 1)
+    } else {
+        return samples.iter().map(Sample::get_value).collect();
+    };
+
+    let biquad = match criterion.get_filter_stage() {
+        FilterStage::LowPass => Biquad::low_pass(cutoff_hz, sample_interval),
+        FilterStage::HighPass => Biquad::high_pass(cutoff_hz, sample_interval),
+        FilterStage::Raw => unreachable!(),
+    };
+
+    BiquadCascade::new([biquad]).filter(samples.iter().map(Sample::get_value))
+}
+
 /// Evaluate criterion defined within Clockwise.
-pub fn evaluate<'a>(criterion: &'a Criterion, data: &Observation<'a>) -> Outcome<'a> {
+pub fn evaluate<'a>(
+    criterion: &'a Criterion,
+    data: &Observation<'a>,
+    energy_sink: &dyn EnergySink,
+) -> Outcome<'a> {
+    let test_id = data.source_test().get_id();
+
     let (status, message) = match criterion {
         Criterion::GPIO(criterion) => {
             match criterion {
                 GPIOCriterion::Any(_pin) => (Status::Complete, None),
+                GPIOCriterion::Analog(_pin) => (Status::Complete, None),
             }
         },
 
         Criterion::Energy(criterion) => {
             match criterion.get_stat() {
                 EnergyStat::Total => {
-                    use std::io::Write as _;
-                    let mut sample_file = {
-                        let file_name = format!("energy-total-{}.csv", criterion.get_meter());
-                        let path = format!("/tmp/{}", file_name);
-                        File::create(path).unwrap()
-                    };
-                    sample_file.write("time,consumed\n".as_bytes()).unwrap();
-
                     // Should exist in map because criterion stated it should be tracked.
                     let samples = data.energy_metrics()
                         .get(criterion.get_meter())
                         .unwrap();
 
-                    let (execution_start, execution_duration) = data.execution_result()
+                    let execution_duration = data.execution_result()
                         .as_ref()
                     // Evaluation results are only relevant when the exec_result is Ok(...).
-                        .map(|exec| (exec.get_start(), exec.duration()))
+                        .map(|exec| exec.duration())
                         .expect("Attempted to evaluate criterion when execution result failed");
-                    let sample_count = samples.len();
-                    // Approximate the time slice of each sample from the number of samples taken.
-                    let sample_time_repr: Duration = execution_duration / sample_count as u32;
-                    let rate_to_total_factor: f64 = sample_time_repr.as_micros() as f64
-                        / Duration::from_secs(1).as_micros() as f64;
-
-                    // let idle_average = samples.iter()
-                    //     .filter(|(t, _sample)| *t > execution_start && (*t - execution_start) > Duration::from_millis(400) && (*t - execution_start) < Duration::from_millis(500))
-                    //     .map(|(_t, sample)| *sample)
-                    //     .fold(0.0, |acc, cur| acc + cur) / (samples.len() as f32);
-
-                    let mut idle_average: f32;
-
-                    // Use an average to calculate the idle power.
-                    // let idle_average_samples: Vec<_> = samples.iter()
-                    //     .filter(|(t, _sample)| *t > execution_start && (*t - execution_start) > Duration::from_millis(400) && (*t - execution_start) < Duration::from_millis(500))
-                    //     .map(|(_t, sample)| *sample)
-                    //     .collect();
-                    // idle_average = idle_average_samples.iter().copied().sum::<f32>() / idle_average_samples.len() as f32;
-
-                    // Use mode to calculate the idle power.
-                    let idle_average_samples: Vec<_> = samples.iter()
-                        .filter(|(t, _sample)| *t > execution_start && (*t - execution_start) > Duration::from_millis(400) && (*t - execution_start) < Duration::from_millis(500))
-                        .map(|(_t, sample)| (*sample * 100.0) as u32)
+
+                    // Estimate idle power from the criterion's baseline window, if one was given;
+                    // otherwise skip idle subtraction entirely.
+                    let idle_average = match criterion.get_baseline_window() {
+                        Some((baseline_start, baseline_end)) => {
+                            let baseline_samples = samples.iter()
+                                .filter(|s| s.get_offset() > baseline_start
+                                        && s.get_offset() < baseline_end)
+                                .map(|s| s.get_value());
+
+                            let idle_average = match criterion.get_baseline_estimator() {
+                                BaselineEstimator::Mode =>
+                                    Histogram::from_samples(baseline_samples).mode(),
+                                BaselineEstimator::Mean => {
+                                    let (sum, count) = baseline_samples
+                                        .fold((0f32, 0u32), |(sum, count), s| (sum + s, count + 1));
+                                    if count > 0 { sum / count as f32 } else { 0f32 }
+                                },
+                            };
+
+                            println!("Idle average is: {:.2} mW", idle_average);
+                            idle_average
+                        },
+                        None => 0f32,
+                    };
+
+                    // Each sample already carries its offset from when metering began, relative to
+                    // the same `t0` as the rest of the test's timeline; just subtract idle power,
+                    // keeping the raw reading around too since that's what's reported to
+                    // `energy_sink`.
+                    let offsets: Vec<(Duration, f32, f32)> = samples.iter()
+                        .map(|sample| (sample.get_offset(), sample.get_value(), sample.get_value() - idle_average))
                         .collect();
-                    let mut buckets: HashMap<u32, u32> = HashMap::new();
-                    for sample in idle_average_samples.iter() {
-                        let counter = buckets.entry(*sample).or_insert(0);
-                        *counter += 1;
-                    }
-                    let mut mode = (0, 0);
-                    for (k, v) in buckets.iter() {
-                        if *v > mode.1 {
-                            mode.0 = *k;
-                            mode.1 = *v;
-                        }
+
+                    for (offset, raw_sample, _adjusted) in offsets.iter().copied() {
+                        energy_sink.sample(test_id, criterion.get_meter(), offset.as_nanos(), raw_sample);
                     }
-                    idle_average = (mode.0 as f32) / 100.0;
-
-                    println!("Idle average is: {:.2} mW", idle_average);
-
-                    let mut total = 0f64;
-                    let t_start = data.execution_result().as_ref().unwrap()
-                        .get_start();
-                    let a_start: Option<Duration> = Some(Duration::from_millis(500));
-                    let a_end: Option<Duration> = None;
-                    for (time, sample) in samples.iter().copied() {
-                        let t = if time > t_start {
-                            time - t_start
-                        } else {
-                            Duration::from_millis(0)
-                        };
 
-                        if let Some(tb_start) = a_start {
-                            if let Some(tb_end) = a_end {
-                                if tb_start < t && t < tb_end {
-                                    total += (sample - idle_average) as f64 * rate_to_total_factor;
-                                }
+                    let in_active_window = |offset: Duration| match criterion.get_active_window() {
+                        Some((active_start, active_end)) => offset > active_start && offset < active_end,
+                        None => true,
+                    };
+
+                    // Integrate power over time with the trapezoidal rule rather than assuming
+                    // every sample represents an equal `execution_duration / sample_count` slice
+                    // -- that assumption breaks down as soon as sampling is jittery.
+                    let total: f64 = match offsets.as_slice() {
+                        [] => 0f64,
+                        [(offset, _raw, adjusted)] => {
+                            // Only one sample: nothing to interpolate between, so fall back to
+                            // treating it as a flat rate over the whole execution.
+                            if in_active_window(*offset) {
+                                *adjusted as f64 * execution_duration.as_secs_f64()
                             } else {
-                                if tb_start < t {
-                                    total += sample as f64 * rate_to_total_factor;
-                                }
+                                0f64
                             }
-                        } else {
-                            // total += (sample - idle_average) as f64 * rate_to_total_factor;
-                            total += sample as f64 * rate_to_total_factor;
-                        }
-                    }
+                        },
+                        _ => offsets.windows(2)
+                            .map(|pair| {
+                                let (t0, _, p0) = pair[0];
+                                let (t1, _, p1) = pair[1];
+
+                                // Reject non-monotonic timestamps (and intervals that fall
+                                // outside the active window) rather than letting a negative or
+                                // nonsensical `dt` corrupt the running total.
+                                if t1 <= t0 || !in_active_window(t0) || !in_active_window(t1) {
+                                    return 0f64;
+                                }
+
+                                let dt = (t1 - t0).as_secs_f64();
+                                (p0 as f64 + p1 as f64) / 2.0 * dt
+                            })
+                            .sum(),
+                    };
 
                     let status = if let Some(violated) = criterion.violated(total as f32) {
                         if violated {
@@ -288,31 +371,19 @@ pub fn evaluate<'a>(criterion: &'a Criterion, data: &Observation<'a>) -> Outcome
                         Status::Complete
                     };
 
+                    energy_sink.aggregate(test_id, criterion.get_meter(), EnergyStat::Total, total as f32);
+
                     (status, Some(format!("{:.2}mJ consumed", total)))
                 },
 
                 EnergyStat::Average => {
-                    use std::io::Write as _;
-                    let mut sample_file = {
-                        let file_name = format!("energy-average-{}.csv", criterion.get_meter());
-                        let path = format!("/tmp/{}", file_name);
-                        File::create(path).unwrap()
-                    };
-                    sample_file.write("time,energy_mj\n".as_bytes()).unwrap();
-
                     let samples = data.energy_metrics().get(criterion.get_meter()).unwrap();
+                    let values = filtered_values(criterion, samples);
                     // ASSUMPTION: timer intervals represented by all samples are equal.
-                    let avg: f32 = samples.iter().map(|(_t, s)| s).sum::<f32>() / samples.len() as f32;
+                    let avg: f32 = values.iter().sum::<f32>() / values.len() as f32;
 
-                    let t_start = data.execution_result().as_ref().unwrap()
-                        .get_start();
-                    for (t, energy_val) in samples.iter() {
-                        let offset = if *t < t_start {
-                            format!("-{}", (t_start - *t).as_micros())
-                        } else {
-                            format!("{}", (*t - t_start).as_micros())
-                        };
-                        sample_file.write(format!("{},{}\n", offset, energy_val).as_bytes()).unwrap();
+                    for sample in samples.iter() {
+                        energy_sink.sample(test_id, criterion.get_meter(), sample.get_offset().as_nanos(), sample.get_value());
                     }
 
                     let status = if let Some(violated) = criterion.violated(avg as f32) {
@@ -325,14 +396,14 @@ pub fn evaluate<'a>(criterion: &'a Criterion, data: &Observation<'a>) -> Outcome
                         Status::Complete
                     };
 
+                    energy_sink.aggregate(test_id, criterion.get_meter(), EnergyStat::Average, avg);
+
                     (status, Some(format!("{:.2}mJ/s average", avg)))
                 },
 
                 EnergyStat::Max => {
                     let samples = data.energy_metrics().get(criterion.get_meter()).unwrap();
-                    let max = samples.iter()
-                        .map(|(_t, sample)| sample)
-                        .copied()
+                    let max = filtered_values(criterion, samples).into_iter()
                         .fold(0f32, |curr, n| if n > curr { n } else { curr });
 
                     let status = if let Some(violated) = criterion.violated(max as f32) {
@@ -345,15 +416,15 @@ pub fn evaluate<'a>(criterion: &'a Criterion, data: &Observation<'a>) -> Outcome
                         Status::Complete
                     };
 
+                    energy_sink.aggregate(test_id, criterion.get_meter(), EnergyStat::Max, max);
+
                     (status, Some(format!("{:.2}mJ/s max", max)))
                 },
 
                 EnergyStat::Min => {
                     let samples = data.energy_metrics().get(criterion.get_meter()).unwrap();
                     let min = if samples.len() > 0 {
-                        samples.iter()
-                            .map(|(_t, sample)| sample)
-                            .copied()
+                        filtered_values(criterion, samples).into_iter()
                             .fold(f32::MAX, |curr, n| if n < curr { n } else { curr })
                     } else {
                         0f32
@@ -369,8 +440,91 @@ pub fn evaluate<'a>(criterion: &'a Criterion, data: &Observation<'a>) -> Outcome
                         Status::Complete
                     };
 
+                    energy_sink.aggregate(test_id, criterion.get_meter(), EnergyStat::Min, min);
+
                     (status, Some(format!("{:.2}mJ/s min", min)))
                 },
+
+                EnergyStat::Percentile(p) => {
+                    let samples = data.energy_metrics().get(criterion.get_meter()).unwrap();
+                    let histogram = Histogram::from_samples(samples.iter().map(Sample::get_value));
+                    let value = histogram.percentile(p);
+
+                    let status = if let Some(violated) = criterion.violated(value) {
+                        if violated {
+                            Status::Fail
+                        } else {
+                            Status::Pass
+                        }
+                    } else {
+                        Status::Complete
+                    };
+
+                    energy_sink.aggregate(test_id, criterion.get_meter(), EnergyStat::Percentile(p), value);
+
+                    (status, Some(format!("{:.2}mJ/s at p{:.0}", value, p.clamp(0.0, 1.0) * 100.0)))
+                },
+
+                EnergyStat::TimeAbove(threshold) => {
+                    let samples = data.energy_metrics().get(criterion.get_meter()).unwrap();
+
+                    let offsets: Vec<(Duration, f32)> = samples.iter()
+                        .map(|sample| (sample.get_offset(), sample.get_value()))
+                        .collect();
+
+                    for (offset, sample) in offsets.iter().copied() {
+                        energy_sink.sample(test_id, criterion.get_meter(), offset.as_nanos(), sample);
+                    }
+
+                    // Accumulate, interval by interval, how long linearly-interpolated power
+                    // stays above `threshold`, proportioning intervals the threshold crosses
+                    // partway through rather than counting them whole or not at all.
+                    let above_ms: f64 = if offsets.len() < 2 {
+                        0f64
+                    } else {
+                        offsets.windows(2)
+                            .map(|pair| {
+                                let (t0, p0) = pair[0];
+                                let (t1, p1) = pair[1];
+
+                                if t1 <= t0 {
+                                    return 0f64;
+                                }
+
+                                let dt_ms = (t1 - t0).as_secs_f64() * 1000.0;
+                                let (p0, p1, threshold) = (p0 as f64, p1 as f64, threshold as f64);
+
+                                let fraction_above = if p0 >= threshold && p1 >= threshold {
+                                    1.0
+                                } else if p0 < threshold && p1 < threshold {
+                                    0.0
+                                } else {
+                                    // Exactly one endpoint is above: the threshold is crossed
+                                    // somewhere inside the interval, at the point linear
+                                    // interpolation between the two readings puts it.
+                                    let crossing = (threshold - p0) / (p1 - p0);
+                                    if p1 >= threshold { 1.0 - crossing } else { crossing }
+                                };
+
+                                dt_ms * fraction_above
+                            })
+                            .sum()
+                    };
+
+                    let status = if let Some(violated) = criterion.violated(above_ms as f32) {
+                        if violated {
+                            Status::Fail
+                        } else {
+                            Status::Pass
+                        }
+                    } else {
+                        Status::Complete
+                    };
+
+                    energy_sink.aggregate(test_id, criterion.get_meter(), EnergyStat::TimeAbove(threshold), above_ms as f32);
+
+                    (status, Some(format!("{:.2}ms above {:.2}mW", above_ms, threshold)))
+                },
             }
         },
 
@@ -395,6 +549,72 @@ pub fn evaluate<'a>(criterion: &'a Criterion, data: &Observation<'a>) -> Outcome
                 (Status::Fail, None)
             }
         },
+
+        Criterion::PerformanceTrace(trace_criterion) => {
+            let execution_start = data.execution_result()
+                .as_ref()
+                .expect("Attempted to evaluate performance trace criterion when execution result failed")
+                .get_start();
+
+            let waypoints = trace_criterion.get_waypoints();
+            let traces = data.traces();
+
+            if traces.len() < waypoints.len() {
+                (Status::Fail, Some(format!(
+                    "missing waypoint(s): expected {}, only {} trace event(s) arrived",
+                    waypoints.len(), traces.len())))
+            } else {
+                let mut status = Status::Pass;
+                let mut segments: Vec<String> = Vec::new();
+                let mut previous_time = execution_start;
+
+                for (waypoint, trace) in waypoints.iter().zip(traces.iter()) {
+                    let arrived_at = trace.get_time();
+
+                    if arrived_at < previous_time {
+                        status = Status::Fail;
+                        segments.push(format!("{} (out of order)", waypoint.get_label()));
+                        continue;
+                    }
+
+                    let elapsed = arrived_at - previous_time;
+                    if let Some(max_elapsed) = waypoint.get_max_elapsed() {
+                        if elapsed > max_elapsed {
+                            status = Status::Fail;
+                        }
+                    }
+
+                    segments.push(format!("{} (+{:?})", waypoint.get_label(), elapsed));
+                    previous_time = arrived_at;
+                }
+
+                let critical_path = previous_time.saturating_duration_since(execution_start);
+                let message = format!("{}; critical path: {:?}", segments.join(" → "), critical_path);
+
+                (status, Some(message))
+            }
+        },
+
+        Criterion::Memory(memory_criterion) => {
+            let timelines = reconstruct_timelines(data.memory_traces());
+            match timelines.get(memory_criterion.get_counter()) {
+                Some(timeline) => {
+                    let (peak_at, peak_value) = timeline.iter()
+                        .max_by_key(|(_t, value)| *value)
+                        .copied()
+                        .unwrap_or((Instant::now(), 0));
+                    let status = match memory_criterion.violated(peak_value) {
+                        Some(true) => Status::Fail,
+                        Some(false) => Status::Pass,
+                        None => Status::Complete,
+                    };
+
+                    (status, Some(format!("{} (peak, at {:?})", peak_value, peak_at)))
+                },
+                None => (Status::Error, Some(format!(
+                    "no memory trace data for {}", memory_criterion.get_counter()))),
+            }
+        },
     };
 
     Outcome::new(criterion, status, message)
@@ -0,0 +1,190 @@
+//! IIR filtering for energy-meter sample post-processing.
+
+use std::f64::consts::PI;
+use std::time::Duration;
+
+/// Q factor used for the [`Biquad::low_pass`]/[`Biquad::high_pass`] constructors, giving a
+/// maximally-flat (Butterworth) response.
+const BUTTERWORTH_Q: f64 = std::f64::consts::FRAC_1_SQRT_2;
+
+/** A single second-order IIR filter section, in Direct Form I.
+
+[`Biquad::process`] computes `y[n] = b0*x[n] + b1*x[n-1] + b2*x[n-2] - a1*y[n-1] - a2*y[n-2]` per
+sample, retaining the last two input/output samples as state between calls. Chain several sections
+together with [`BiquadCascade`] for a steeper rolloff than one section alone provides.
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    x1: f64,
+    x2: f64,
+    y1: f64,
+    y2: f64,
+}
+
+impl Biquad {
+    /// Create a section directly from its coefficients, with zeroed state.
+    pub fn new(b0: f64, b1: f64, b2: f64, a1: f64, a2: f64) -> Biquad {
+        Biquad { b0, b1, b2, a1, a2, x1: 0.0, x2: 0.0, y1: 0.0, y2: 0.0 }
+    }
+
+    /** RBJ cookbook low-pass/high-pass coefficients, normalized by `a0`. `cutoff_hz` is expressed
+    relative to the sampling rate implied by `sample_interval` (i.e. `cutoff_hz` vs.
+    `1.0 / sample_interval`).
+     */
+    fn cookbook(cutoff_hz: f64, sample_interval: Duration, low_pass: bool) -> Biquad {
+        let sample_rate_hz = 1.0 / sample_interval.as_secs_f64();
+        let w0 = 2.0 * PI * cutoff_hz / sample_rate_hz;
+        let (sin_w0, cos_w0) = w0.sin_cos();
+        let alpha = sin_w0 / (2.0 * BUTTERWORTH_Q);
+
+        let (b0, b1, b2) = if low_pass {
+            ((1.0 - cos_w0) / 2.0, 1.0 - cos_w0, (1.0 - cos_w0) / 2.0)
+        } else {
+            ((1.0 + cos_w0) / 2.0, -(1.0 + cos_w0), (1.0 + cos_w0) / 2.0)
+        };
+        let a0 = 1.0 + alpha;
+        let a1 = -2.0 * cos_w0;
+        let a2 = 1.0 - alpha;
+
+        Biquad::new(b0 / a0, b1 / a0, b2 / a0, a1 / a0, a2 / a0)
+    }
+
+    /// Create a low-pass section with the given cutoff; see [`Biquad::cookbook`].
+    pub fn low_pass(cutoff_hz: f64, sample_interval: Duration) -> Biquad {
+        Biquad::cookbook(cutoff_hz, sample_interval, true)
+    }
+
+    /// Create a high-pass section with the given cutoff; see [`Biquad::cookbook`].
+    pub fn high_pass(cutoff_hz: f64, sample_interval: Duration) -> Biquad {
+        Biquad::cookbook(cutoff_hz, sample_interval, false)
+    }
+
+    /// Filter one sample, updating the section's state and returning the filtered output.
+    pub fn process(&mut self, x0: f64) -> f64 {
+        let y0 = self.b0 * x0 + self.b1 * self.x1 + self.b2 * self.x2
+            - self.a1 * self.y1 - self.a2 * self.y2;
+
+        self.x2 = self.x1;
+        self.x1 = x0;
+        self.y2 = self.y1;
+        self.y1 = y0;
+
+        y0
+    }
+}
+
+/** A cascade of [`Biquad`] sections, each filtering the previous section's output.
+
+Every section's state persists across calls to [`BiquadCascade::filter`], so a cascade can be fed
+successive chunks of a longer stream rather than the whole trace at once.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct BiquadCascade {
+    sections: Vec<Biquad>,
+}
+
+impl BiquadCascade {
+    /// Create a cascade from its sections, in the order each filters the last.
+    pub fn new<T>(sections: T) -> BiquadCascade
+    where
+        T: IntoIterator<Item = Biquad>,
+    {
+        BiquadCascade {
+            sections: sections.into_iter().collect(),
+        }
+    }
+
+    /// Run `samples` through every section of the cascade in order, returning the filtered trace.
+    pub fn filter<T>(&mut self, samples: T) -> Vec<f32>
+    where
+        T: IntoIterator<Item = f32>,
+    {
+        samples.into_iter()
+            .map(|x0| {
+                let mut v = x0 as f64;
+                for section in &mut self.sections {
+                    v = section.process(v);
+                }
+                v as f32
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn identity_section_passes_samples_through_unchanged() {
+        let mut section = Biquad::new(1.0, 0.0, 0.0, 0.0, 0.0);
+        assert_eq!(section.process(1.0), 1.0);
+        assert_eq!(section.process(2.0), 2.0);
+        assert_eq!(section.process(-3.0), -3.0);
+    }
+
+    #[test]
+    pub fn process_applies_the_direct_form_one_difference_equation() {
+        // b1 feeds back the previous input, so the first sample sees no history yet.
+        let mut section = Biquad::new(0.0, 1.0, 0.0, 0.0, 0.0);
+        assert_eq!(section.process(5.0), 0.0);
+        assert_eq!(section.process(7.0), 5.0);
+        assert_eq!(section.process(9.0), 7.0);
+    }
+
+    #[test]
+    pub fn process_feeds_back_prior_output_via_a1() {
+        // a1 = -1 makes y[n] = x[n] + y[n-1], i.e. a running sum of the input.
+        let mut section = Biquad::new(1.0, 0.0, 0.0, -1.0, 0.0);
+        assert_eq!(section.process(1.0), 1.0);
+        assert_eq!(section.process(1.0), 2.0);
+        assert_eq!(section.process(1.0), 3.0);
+    }
+
+    #[test]
+    pub fn low_pass_has_unity_dc_gain_at_steady_state() {
+        let mut section = Biquad::low_pass(10.0, Duration::from_millis(1));
+        let mut y = 0.0;
+        for _ in 0..1000 {
+            y = section.process(1.0);
+        }
+        assert!((y - 1.0).abs() < 1e-6, "expected steady-state output near 1.0, got {}", y);
+    }
+
+    #[test]
+    pub fn high_pass_blocks_dc_at_steady_state() {
+        let mut section = Biquad::high_pass(10.0, Duration::from_millis(1));
+        let mut y = 0.0;
+        for _ in 0..1000 {
+            y = section.process(1.0);
+        }
+        assert!(y.abs() < 1e-6, "expected steady-state output near 0.0, got {}", y);
+    }
+
+    #[test]
+    pub fn cascade_runs_samples_through_every_section_in_order() {
+        // Two passthrough sections should leave the trace unchanged.
+        let mut cascade = BiquadCascade::new([
+            Biquad::new(1.0, 0.0, 0.0, 0.0, 0.0),
+            Biquad::new(1.0, 0.0, 0.0, 0.0, 0.0),
+        ]);
+        assert_eq!(cascade.filter([1.0, 2.0, 3.0]), vec![1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    pub fn cascade_state_persists_across_calls_to_filter() {
+        let mut whole = BiquadCascade::new([Biquad::new(1.0, 0.0, 0.0, -1.0, 0.0)]);
+        let whole_result = whole.filter([1.0, 1.0, 1.0, 1.0]);
+
+        let mut split = BiquadCascade::new([Biquad::new(1.0, 0.0, 0.0, -1.0, 0.0)]);
+        let mut split_result = split.filter([1.0, 1.0]);
+        split_result.extend(split.filter([1.0, 1.0]));
+
+        assert_eq!(whole_result, split_result);
+    }
+}
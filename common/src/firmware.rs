@@ -0,0 +1,106 @@
+//! Flashing and verifying firmware on the device under test.
+//!
+//! [`Test::execute`](crate::test::Test::execute) assumes the firmware it exercises is already
+//! loaded on the device. [`Test::flash_firmware`](crate::test::Test::flash_firmware) closes that
+//! gap: given a [`FirmwareUpdater`] and the [`FirmwareImage`]s available for a test's `app_ids`, it
+//! erases the target region, writes the image in page-sized chunks, then reads it back to confirm
+//! the flash took, all before the test's first [`Operation`](crate::test::Operation) runs.
+
+use std::error;
+use std::fmt;
+use std::fmt::Display;
+
+/// A firmware binary ready to be flashed by a [`FirmwareUpdater`].
+#[derive(Clone, Debug)]
+pub struct FirmwareImage {
+    data: Vec<u8>,
+}
+
+impl FirmwareImage {
+    /// Wrap a raw firmware binary.
+    pub fn new(data: Vec<u8>) -> FirmwareImage {
+        FirmwareImage { data }
+    }
+
+    /// Returns the raw binary content of the image.
+    pub fn get_data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// Errors while flashing or verifying firmware.
+#[derive(Debug)]
+pub enum FirmwareError {
+    /// Failure while erasing the target flash region.
+    Erase(String),
+    /// Failure while writing a page of the image.
+    Write(String),
+    /// Failure while reading back a page for verification.
+    Read(String),
+    /// The device's flashed content didn't match the image after writing it.
+    VerificationFailed,
+}
+
+impl error::Error for FirmwareError {}
+
+impl Display for FirmwareError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use FirmwareError::*;
+        match self {
+            Erase(ref reason) => write!(f, "failed to erase target flash region: {}", reason),
+            Write(ref reason) => write!(f, "failed to write firmware page: {}", reason),
+            Read(ref reason) => write!(f, "failed to read back firmware for verification: {}", reason),
+            VerificationFailed => write!(f, "flashed firmware did not match the image written"),
+        }
+    }
+}
+
+/// Result type for firmware operations.
+pub type Result<T> = std::result::Result<T, FirmwareError>;
+
+/** Drives a target's programming interface to flash and verify a [`FirmwareImage`].
+
+Implementors provide the low-level primitives for a specific transport (DFU, a debug probe, a UART
+bootloader, ...): [`page_size`](FirmwareUpdater::page_size), [`erase`](FirmwareUpdater::erase),
+[`write_page`](FirmwareUpdater::write_page), and [`read_back`](FirmwareUpdater::read_back).
+[`write_firmware`](FirmwareUpdater::write_firmware) and [`verify`](FirmwareUpdater::verify) are
+provided in terms of those and shouldn't normally need overriding.
+ */
+pub trait FirmwareUpdater: fmt::Debug {
+    /// Size, in bytes, of one flashable page for this transport.
+    fn page_size(&self) -> usize;
+
+    /// Erase the flash region `image` will occupy. Called once before any page is written.
+    fn erase(&mut self, image: &FirmwareImage) -> Result<()>;
+
+    /// Write one `page_size()`-sized (or shorter, for the final page) chunk of `image`'s data,
+    /// `offset` bytes in.
+    fn write_page(&mut self, offset: usize, data: &[u8]) -> Result<()>;
+
+    /// Read back `len` bytes starting at `offset`, for [`verify`](FirmwareUpdater::verify) to
+    /// compare against the image that was written.
+    fn read_back(&mut self, offset: usize, len: usize) -> Result<Vec<u8>>;
+
+    /// Erase the target region, write `image` in `page_size()`-sized chunks, then [`verify`](
+    /// FirmwareUpdater::verify) it.
+    fn write_firmware(&mut self, image: &FirmwareImage) -> Result<()> {
+        self.erase(image)?;
+
+        let page_size = self.page_size();
+        for (i, page) in image.get_data().chunks(page_size).enumerate() {
+            self.write_page(i * page_size, page)?;
+        }
+
+        self.verify(image)
+    }
+
+    /// Read back the image and confirm it matches what was written.
+    fn verify(&mut self, image: &FirmwareImage) -> Result<()> {
+        let written = self.read_back(0, image.get_data().len())?;
+        if written == image.get_data() {
+            Ok(())
+        } else {
+            Err(FirmwareError::VerificationFailed)
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! A fixed-width histogram for quantized energy-sample statistics.
+
+use std::collections::HashMap;
+
+/// Quantization resolution: samples are bucketed as `(value * RESOLUTION) as u32`, i.e. 0.01 mW.
+const RESOLUTION: f32 = 100.0;
+
+/** Histogram of millwatt samples, quantized into fixed-width buckets.
+
+Backs both the idle-power estimator (the mode of the distribution) and
+[`EnergyStat::Percentile`](crate::criteria::EnergyStat::Percentile) evaluation, so the two share one
+bucketing implementation instead of each hand-rolling its own bucket table. Each builds its own
+`Histogram` from the samples it needs.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct Histogram {
+    buckets: HashMap<u32, u32>,
+    count: u32,
+}
+
+impl Histogram {
+    /// Create an empty histogram.
+    pub fn new() -> Histogram {
+        Histogram::default()
+    }
+
+    /// Build a histogram from an iterator of millwatt samples.
+    pub fn from_samples<T>(samples: T) -> Histogram
+    where
+        T: IntoIterator<Item = f32>,
+    {
+        let mut histogram = Histogram::new();
+        for sample in samples {
+            histogram.record(sample);
+        }
+
+        histogram
+    }
+
+    /// Record one millwatt sample.
+    pub fn record(&mut self, sample: f32) {
+        let bucket = (sample * RESOLUTION) as u32;
+        *self.buckets.entry(bucket).or_insert(0) += 1;
+        self.count += 1;
+    }
+
+    /// Returns the most frequently occurring sample value, or `0.0` if nothing was recorded.
+    pub fn mode(&self) -> f32 {
+        self.buckets.iter()
+            .max_by_key(|(_bucket, count)| **count)
+            .map(|(bucket, _count)| *bucket as f32 / RESOLUTION)
+            .unwrap_or(0.0)
+    }
+
+    /** Returns the value at the `p`-th percentile, `p` clamped to `[0, 1]`.
+
+    Walks buckets in ascending order, accumulating counts until the cumulative fraction of samples
+    seen reaches `p`. Returns `0.0` if nothing was recorded.
+     */
+    pub fn percentile(&self, p: f64) -> f32 {
+        if self.count == 0 {
+            return 0.0;
+        }
+
+        let p = p.clamp(0.0, 1.0);
+        let mut buckets: Vec<&u32> = self.buckets.keys().collect();
+        buckets.sort();
+
+        let mut cumulative = 0u32;
+        for bucket in buckets {
+            cumulative += self.buckets[bucket];
+            if (cumulative as f64 / self.count as f64) >= p {
+                return *bucket as f32 / RESOLUTION;
+            }
+        }
+
+        // Unreachable: the last bucket always carries the cumulative fraction to 1.0.
+        0.0
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn empty_histogram_has_no_mode_or_percentile() {
+        let histogram = Histogram::new();
+        assert_eq!(histogram.mode(), 0.0);
+        assert_eq!(histogram.percentile(0.5), 0.0);
+    }
+
+    #[test]
+    pub fn mode_is_most_frequent_sample() {
+        let histogram = Histogram::from_samples([1.0, 1.0, 1.0, 2.0, 3.0]);
+        assert_eq!(histogram.mode(), 1.0);
+    }
+
+    #[test]
+    pub fn samples_quantize_to_the_same_bucket() {
+        // Both values round to the same 0.01mW bucket, so they should merge into one mode.
+        let histogram = Histogram::from_samples([1.001, 1.004, 1.004]);
+        assert_eq!(histogram.mode(), 1.0);
+    }
+
+    #[test]
+    pub fn percentile_zero_is_the_minimum() {
+        let histogram = Histogram::from_samples([3.0, 1.0, 2.0]);
+        assert_eq!(histogram.percentile(0.0), 1.0);
+    }
+
+    #[test]
+    pub fn percentile_one_is_the_maximum() {
+        let histogram = Histogram::from_samples([3.0, 1.0, 2.0]);
+        assert_eq!(histogram.percentile(1.0), 3.0);
+    }
+
+    #[test]
+    pub fn percentile_fifty_is_the_median_of_an_odd_count() {
+        let histogram = Histogram::from_samples([1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(histogram.percentile(0.5), 3.0);
+    }
+
+    #[test]
+    pub fn percentile_clamps_out_of_range_fractions() {
+        let histogram = Histogram::from_samples([1.0, 2.0, 3.0]);
+        assert_eq!(histogram.percentile(-1.0), histogram.percentile(0.0));
+        assert_eq!(histogram.percentile(2.0), histogram.percentile(1.0));
+    }
+}
@@ -1,9 +1,9 @@
-//! Support for the INA219 sensor.
+//! Support for INA2xx-family current/power sensors (INA219, INA220, INA226, INA230).
 
 use std::cell::{RefCell, RefMut};
 use std::sync::Mutex;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use rppal::i2c::I2c;
 
@@ -20,38 +20,423 @@ mod register {
     pub const CALIBRATION: u8   = 0x05;
 }
 
-/// Conversion factor when reading bus voltage (4mV per value).
-const BUS_VOLTAGE_LSB: f32 = 0.004;
+/// Conversion-ready bit (CNVR) within the BUS_VOLTAGE register.
+const CNVR_BIT: u16 = 0b10;
+
+/** Constants distinguishing one member of the INA2xx family from another.
+
+The family shares a register map and access pattern (see `register`); chips differ only in their
+power-on-reset CONFIGURATION value and in a handful of scaling factors used to turn raw register
+reads into physical units. Mirrors the `struct ina2xx_config` table the Linux hwmon `ina2xx`
+driver uses to cover the same family with one code path.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ina2xxConfig {
+    /// Power-on-reset value of the CONFIGURATION register.
+    pub config_default: u16,
+    /// Numerator of the calibration register formula: `cal = calibration_factor / (current_lsb *
+    /// shunt_ohms)`.
+    pub calibration_factor: f32,
+    /// Right-shift applied to a raw BUS_VOLTAGE reading before scaling to volts.
+    pub bus_voltage_shift: u8,
+    /// Volts per bus voltage LSB, applied after `bus_voltage_shift`.
+    pub bus_voltage_lsb_volts: f32,
+    /// Power LSB, expressed as a multiple of the current LSB.
+    pub power_lsb_multiplier: f32,
+    /// Divisor converting a raw SHUNT_VOLTAGE reading to volts.
+    pub shunt_div: f32,
+}
+
+/// A specific chip in the INA2xx family.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Chip {
+    /// TI INA219: PGA-equipped, +/-16V or +/-32V bus range.
+    Ina219,
+    /// TI INA220: pin- and register-compatible with the INA219.
+    Ina220,
+    /// TI INA226: no PGA, wider bus range, finer bus voltage LSB.
+    Ina226,
+    /// TI INA230: register-compatible with the INA226.
+    Ina230,
+}
+
+impl Chip {
+    /// Return this chip's constants.
+    pub fn descriptor(self) -> Ina2xxConfig {
+        match self {
+            Chip::Ina219 | Chip::Ina220 => Ina2xxConfig {
+                config_default: 0x399F,
+                calibration_factor: 0.04096,
+                bus_voltage_shift: 3,
+                bus_voltage_lsb_volts: 0.004,
+                power_lsb_multiplier: 20.0,
+                shunt_div: 100_000.0,
+            },
+            Chip::Ina226 | Chip::Ina230 => Ina2xxConfig {
+                config_default: 0x4127,
+                calibration_factor: 0.00512,
+                bus_voltage_shift: 0,
+                bus_voltage_lsb_volts: 0.00125,
+                power_lsb_multiplier: 25.0,
+                shunt_div: 400_000.0,
+            },
+        }
+    }
+}
+
+/// Bus voltage measurement range (`BRNG` field of the CONFIGURATION register).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BusVoltageRange {
+    /// 0-16V.
+    V16,
+    /// 0-32V.
+    V32,
+}
+
+impl BusVoltageRange {
+    fn bits(self) -> u16 {
+        match self {
+            BusVoltageRange::V16 => 0b0,
+            BusVoltageRange::V32 => 0b1,
+        }
+    }
+
+    fn from_bits(bits: u16) -> BusVoltageRange {
+        if bits & 0b1 == 0b1 {
+            BusVoltageRange::V32
+        } else {
+            BusVoltageRange::V16
+        }
+    }
+}
+
+/// Shunt voltage PGA gain (`PG` field), trading off measurable shunt voltage range for resolution.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Gain {
+    /// /1, +/-40mV.
+    Div1,
+    /// /2, +/-80mV.
+    Div2,
+    /// /4, +/-160mV.
+    Div4,
+    /// /8, +/-320mV.
+    Div8,
+}
+
+impl Gain {
+    fn bits(self) -> u16 {
+        match self {
+            Gain::Div1 => 0b00,
+            Gain::Div2 => 0b01,
+            Gain::Div4 => 0b10,
+            Gain::Div8 => 0b11,
+        }
+    }
+
+    fn from_bits(bits: u16) -> Gain {
+        match bits & 0b11 {
+            0b00 => Gain::Div1,
+            0b01 => Gain::Div2,
+            0b10 => Gain::Div4,
+            _ => Gain::Div8,
+        }
+    }
+}
+
+/** ADC resolution/averaging for a bus or shunt voltage conversion (`BADC`/`SADC` fields).
+
+`Average*` variants always sample at 12-bit resolution; the device's 4-bit field conflates
+resolution and averaging, so `BitsN` and `AverageN` are mutually exclusive by construction here
+instead of letting a caller pick an invalid combination.
+ */
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Resolution {
+    /// 9-bit, 1 sample, 84us conversion time.
+    Bits9,
+    /// 10-bit, 1 sample, 148us conversion time.
+    Bits10,
+    /// 11-bit, 1 sample, 276us conversion time.
+    Bits11,
+    /// 12-bit, 1 sample, 532us conversion time.
+    Bits12,
+    /// 12-bit, averaged over 2 samples, 1.06ms conversion time.
+    Average2,
+    /// 12-bit, averaged over 4 samples, 2.13ms conversion time.
+    Average4,
+    /// 12-bit, averaged over 8 samples, 4.26ms conversion time.
+    Average8,
+    /// 12-bit, averaged over 16 samples, 8.51ms conversion time.
+    Average16,
+    /// 12-bit, averaged over 32 samples, 17.02ms conversion time.
+    Average32,
+    /// 12-bit, averaged over 64 samples, 34.05ms conversion time.
+    Average64,
+    /// 12-bit, averaged over 128 samples, 68.10ms conversion time.
+    Average128,
+}
+
+impl Resolution {
+    fn bits(self) -> u16 {
+        use Resolution::*;
+        match self {
+            Bits9 => 0b0000,
+            Bits10 => 0b0001,
+            Bits11 => 0b0010,
+            Bits12 => 0b0011,
+            Average2 => 0b1000,
+            Average4 => 0b1001,
+            Average8 => 0b1010,
+            Average16 => 0b1011,
+            Average32 => 0b1100,
+            Average64 => 0b1101,
+            Average128 => 0b1110,
+        }
+    }
+
+    fn from_bits(bits: u16) -> Resolution {
+        use Resolution::*;
+        match bits & 0b1111 {
+            0b0000 => Bits9,
+            0b0001 => Bits10,
+            0b0010 => Bits11,
+            0b1000 => Average2,
+            0b1001 => Average4,
+            0b1010 => Average8,
+            0b1011 => Average16,
+            0b1100 => Average32,
+            0b1101 => Average64,
+            0b1110 | 0b1111 => Average128,
+            // 0011..0111 all mean "12-bit, 1 sample" per the datasheet's redundant codes.
+            _ => Bits12,
+        }
+    }
+
+    /// Conversion time for a single ADC (bus or shunt) at this resolution/averaging setting.
+    fn conversion_time(self) -> Duration {
+        use Resolution::*;
+        Duration::from_micros(match self {
+            Bits9 => 84,
+            Bits10 => 148,
+            Bits11 => 276,
+            Bits12 => 532,
+            Average2 => 1_060,
+            Average4 => 2_130,
+            Average8 => 4_260,
+            Average16 => 8_510,
+            Average32 => 17_020,
+            Average64 => 34_050,
+            Average128 => 68_100,
+        })
+    }
+}
+
+/// Operating mode (`MODE` field): which voltages are converted, and whether conversion is
+/// triggered per-read or runs continuously in the background.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Mode {
+    /// ADC off; lowest power.
+    PowerDown,
+    /// Convert shunt voltage once, then return to power-down.
+    ShuntTriggered,
+    /// Convert bus voltage once, then return to power-down.
+    BusTriggered,
+    /// Convert shunt and bus voltage once, then return to power-down.
+    ShuntAndBusTriggered,
+    /// ADC off; equivalent to `PowerDown`.
+    AdcOff,
+    /// Continuously convert shunt voltage.
+    ShuntContinuous,
+    /// Continuously convert bus voltage.
+    BusContinuous,
+    /// Continuously convert shunt and bus voltage.
+    ShuntAndBusContinuous,
+}
+
+impl Mode {
+    fn bits(self) -> u16 {
+        use Mode::*;
+        match self {
+            PowerDown => 0b000,
+            ShuntTriggered => 0b001,
+            BusTriggered => 0b010,
+            ShuntAndBusTriggered => 0b011,
+            AdcOff => 0b100,
+            ShuntContinuous => 0b101,
+            BusContinuous => 0b110,
+            ShuntAndBusContinuous => 0b111,
+        }
+    }
+
+    fn from_bits(bits: u16) -> Mode {
+        use Mode::*;
+        match bits & 0b111 {
+            0b000 => PowerDown,
+            0b001 => ShuntTriggered,
+            0b010 => BusTriggered,
+            0b011 => ShuntAndBusTriggered,
+            0b100 => AdcOff,
+            0b101 => ShuntContinuous,
+            0b110 => BusContinuous,
+            _ => ShuntAndBusContinuous,
+        }
+    }
+}
+
+/** Typed view of the INA219 CONFIGURATION register (0x00).
+
+[`Default`] matches the device's power-on-reset state: 32V bus range, /8 gain, 12-bit/1-sample
+bus and shunt ADCs, continuous shunt+bus conversion.
+ */
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Config {
+    /// Bus voltage measurement range.
+    pub bus_voltage_range: BusVoltageRange,
+    /// Shunt voltage PGA gain.
+    pub gain: Gain,
+    /// Bus voltage ADC resolution/averaging.
+    pub bus_resolution: Resolution,
+    /// Shunt voltage ADC resolution/averaging.
+    pub shunt_resolution: Resolution,
+    /// Operating mode.
+    pub mode: Mode,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            bus_voltage_range: BusVoltageRange::V32,
+            gain: Gain::Div8,
+            bus_resolution: Resolution::Bits12,
+            shunt_resolution: Resolution::Bits12,
+            mode: Mode::ShuntAndBusContinuous,
+        }
+    }
+}
+
+impl Config {
+    fn to_bits(self) -> u16 {
+        (self.bus_voltage_range.bits() << 13)
+            | (self.gain.bits() << 11)
+            | (self.bus_resolution.bits() << 7)
+            | (self.shunt_resolution.bits() << 3)
+            | self.mode.bits()
+    }
+
+    fn from_bits(bits: u16) -> Config {
+        Config {
+            bus_voltage_range: BusVoltageRange::from_bits(bits >> 13),
+            gain: Gain::from_bits(bits >> 11),
+            bus_resolution: Resolution::from_bits(bits >> 7),
+            shunt_resolution: Resolution::from_bits(bits >> 3),
+            mode: Mode::from_bits(bits),
+        }
+    }
+}
+
+/// A batch of raw register readings, stamped with when they were taken.
+#[derive(Debug, Clone, Copy)]
+struct RegisterCache {
+    shunt_voltage: u16,
+    bus_voltage: u16,
+    power: u16,
+    current: u16,
+    refreshed_at: Instant,
+}
 
 /// Driver for the TI INA219 current sensor.
 #[derive(Debug)]
 pub struct INA219 {
     address: u8,
     i2c: Mutex<RefCell<I2c>>,
-    current_lsb: f32,
+    current_lsb: Mutex<f32>,
+    shunt_resistor_ohms: Mutex<f32>,
+    chip: Ina2xxConfig,
+    last_current: Mutex<f32>,
+    last_power: Mutex<f32>,
+    cache: Mutex<Option<RegisterCache>>,
 }
 
 impl INA219 {
     const MAX_CURRENT_AMPS: f32 = 0.8;
     const SHUNT_RESISTOR_OHMS: f32 = 0.1;
 
-    /// Create a new INA219 driver.
-    pub fn new(i2c: I2c, address: u8) -> Result<INA219, String> {
+    /** Create a new driver for `chip` with this driver's historical configuration: 32V range, /4
+    gain (+/-160mV), 12-bit/532us bus and shunt ADCs, continuous shunt+bus conversion. Yields a
+    +/-1.6A range with 0.390625mA resolution.
+
+    See [`INA219::with_config`] to pick a different trade-off between range, noise, and sample
+    rate.
+     */
+    pub fn new(i2c: I2c, address: u8, chip: Chip) -> Result<INA219, String> {
+        Self::with_config(i2c, address, chip, Config {
+            bus_voltage_range: BusVoltageRange::V32,
+            gain: Gain::Div4,
+            bus_resolution: Resolution::Bits12,
+            shunt_resolution: Resolution::Bits12,
+            mode: Mode::ShuntAndBusContinuous,
+        })
+    }
+
+    /// Create a new driver for `chip`, writing `config` to the CONFIGURATION register.
+    pub fn with_config(i2c: I2c, address: u8, chip: Chip, config: Config) -> Result<INA219, String> {
         let ina = INA219 {
             address,
             i2c: Mutex::new(RefCell::new(i2c)),
-            current_lsb: Self::MAX_CURRENT_AMPS / 2f32.powi(15),
+            current_lsb: Mutex::new(Self::MAX_CURRENT_AMPS / 2f32.powi(15)),
+            shunt_resistor_ohms: Mutex::new(Self::SHUNT_RESISTOR_OHMS),
+            chip: chip.descriptor(),
+            last_current: Mutex::new(0.0),
+            last_power: Mutex::new(0.0),
+            cache: Mutex::new(None),
         };
-        ina.init()?;
-        println!("Current LSB: {}", ina.current_lsb);
+        ina.init(config)?;
+        println!("Current LSB: {}", ina.current_lsb());
 
         Ok(ina)
     }
 
-    /// Reset the INA219.
+    /// Set the shunt resistance in ohms and recompute the calibration register to match.
+    pub fn set_shunt_resistance(&self, ohms: f32) -> Result<(), String> {
+        *self.shunt_resistor_ohms.lock()
+            .map_err(|e| format!("failed to lock shunt resistance: {}", e))? = ohms;
+        self.recalibrate()
+    }
+
+    /// Set the expected maximum current in amps and recompute the calibration register to match.
+    pub fn set_expected_max_current(&self, amps: f32) -> Result<(), String> {
+        *self.current_lsb.lock()
+            .map_err(|e| format!("failed to lock current LSB: {}", e))? = amps / 2f32.powi(15);
+        self.recalibrate()
+    }
+
+    /// Re-derive and write the calibration register from the current `current_lsb` and shunt
+    /// resistance, e.g. after [`set_shunt_resistance`](Self::set_shunt_resistance) or
+    /// [`set_expected_max_current`](Self::set_expected_max_current).
+    fn recalibrate(&self) -> Result<(), String> {
+        let cal = (self.chip.calibration_factor / (self.current_lsb() * self.shunt_resistor_ohms())) as u16;
+        self.write(register::CALIBRATION, cal)?;
+        println!("Calibration: {}", cal);
+
+        Ok(())
+    }
+
+    fn current_lsb(&self) -> f32 {
+        *self.current_lsb.lock().unwrap()
+    }
+
+    fn shunt_resistor_ohms(&self) -> f32 {
+        *self.shunt_resistor_ohms.lock().unwrap()
+    }
+
+    /// Read the CONFIGURATION register back and decode it.
+    pub fn configuration(&self) -> Result<Config, String> {
+        self.read(register::CONFIGURATION).map(Config::from_bits)
+    }
+
+    /// Reset the chip.
     pub fn reset(&self) -> Result<(), String> {
-        // Just write the default configuration, as that should be safe.
-        let config = 0x399F | ((1 as u16) << 15);
+        // Just write the chip's default configuration, as that should be safe.
+        let config = self.chip.config_default | (1u16 << 15);
         self.write(register::CONFIGURATION, config)?;
         thread::sleep(Duration::from_micros(40)); // need >=40us after reset.
 
@@ -60,43 +445,117 @@ impl INA219 {
 
     /// Return the current current draw in milliamps.
     pub fn current(&self) -> Result<f32, String> {
-        Ok(self.read(register::CURRENT)? as f32 * self.current_lsb * 1_000f32)
+        let cache = self.cached()?;
+        Ok(cache.current as f32 * self.current_lsb() * 1_000f32)
     }
 
     /// Return the current power measurement in milliwatts.
     pub fn power(&self) -> Result<f32, String> {
-        Ok(self.read(register::POWER)? as f32 * 20.0f32 * self.current_lsb * 1_000f32)
+        let cache = self.cached()?;
+        let power_lsb = self.chip.power_lsb_multiplier * self.current_lsb();
+        Ok(cache.power as f32 * power_lsb * 1_000f32)
     }
 
     /// Return the bus voltage in volts.
     pub fn bus_voltage(&self) -> Result<f32, String> {
-        let raw = self.read(register::BUS_VOLTAGE)?;
-        Ok(((raw >> 3) as f32) * BUS_VOLTAGE_LSB)
+        let cache = self.cached()?;
+        Ok(((cache.bus_voltage >> self.chip.bus_voltage_shift) as f32) * self.chip.bus_voltage_lsb_volts)
+    }
+
+    /** Read shunt voltage, bus voltage, power, and current in one batch and cache the result,
+    stamped with when the batch was taken.
+
+    [`cached`](Self::cached) uses this to keep per-register I2C chatter down to one batch per
+    configured conversion interval, rather than one transaction per accessor call.
+     */
+    fn refresh(&self) -> Result<(), String> {
+        let shunt_voltage = self.read(register::SHUNT_VOLTAGE)?;
+        let bus_voltage = self.read(register::BUS_VOLTAGE)?;
+        let power = self.read(register::POWER)?;
+        let current = self.read(register::CURRENT)?;
+
+        *self.cache.lock().map_err(|e| format!("failed to lock register cache: {}", e))? = Some(RegisterCache {
+            shunt_voltage,
+            bus_voltage,
+            power,
+            current,
+            refreshed_at: Instant::now(),
+        });
+
+        Ok(())
     }
 
-    fn init(&self) -> Result<(), String> {
+    /// Return the last [`refresh`](Self::refresh)ed batch of registers, refreshing first if it's
+    /// older than the configured conversion interval (see [`cooldown_duration`](
+    /// EnergyMetering::cooldown_duration)).
+    fn cached(&self) -> Result<RegisterCache, String> {
+        let stale = match *self.cache.lock().map_err(|e| format!("failed to lock register cache: {}", e))? {
+            Some(cache) => Instant::now().duration_since(cache.refreshed_at) >= self.cooldown_duration(),
+            None => true,
+        };
+        if stale {
+            self.refresh()?;
+        }
+
+        self.cache.lock()
+            .map_err(|e| format!("failed to lock register cache: {}", e))?
+            .ok_or_else(|| "register cache unexpectedly empty after refresh".to_string())
+    }
+
+    /** Trigger a single one-shot shunt+bus conversion, then return to power-down.
+
+    Between triggers the part can sit in power-down mode, avoiding the power draw of continuous
+    conversion. Call [`read_when_ready`](Self::read_when_ready) to wait for the result, then read
+    it with [`current`](Self::current)/[`power`](Self::power)/[`bus_voltage`](Self::bus_voltage).
+     */
+    pub fn trigger(&self) -> Result<(), String> {
+        let mut config = self.configuration()?;
+        config.mode = Mode::ShuntAndBusTriggered;
+        self.write(register::CONFIGURATION, config.to_bits())
+    }
+
+    /** Poll the CNVR (conversion-ready) bit of the BUS_VOLTAGE register until a triggered
+    conversion completes, timing out after twice the configured shunt+bus conversion time.
+     */
+    pub fn read_when_ready(&self) -> Result<(), String> {
+        let config = self.configuration()?;
+        let timeout = (config.shunt_resolution.conversion_time()
+            + config.bus_resolution.conversion_time()) * 2;
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if self.read(register::BUS_VOLTAGE)? & CNVR_BIT != 0 {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                return Err(format!(
+                    "timed out after {:?} waiting for conversion to complete", timeout));
+            }
+            thread::sleep(Duration::from_micros(50));
+        }
+    }
+
+    fn init(&self, config: Config) -> Result<(), String> {
         self.with_i2c(|mut i2c| {
             i2c.set_slave_address(self.address as u16)
                 .map_err(|e| format!("failed to set peripheral address: {}", e))
         })?;
         self.reset()?;
 
-        /* Set configuration; see INA219 documentation for details.
-
-        - gain amplifier: /4 (+/- 160mV)
-        - ADC resolution/averaging: 12-bit
-        - bus ADC resolution: 12-bit, 532 us conversion time
-        - shunt ADC resolution: 12-bit, 532 us conversion time
-        - operating mode: shunt + bus, continuous
-        -----
-        Should yield a +/- 1.6A range with 0.390625mA resolution.
-         */
-        let config = 0b0_0_0_1_10_0011_0011_111;
-        self.write(register::CONFIGURATION, config)?;
+        self.write(register::CONFIGURATION, config.to_bits())?;
 
-        let cal = (0.04096f32 / (self.current_lsb * Self::SHUNT_RESISTOR_OHMS)) as u16;
-        self.write(register::CALIBRATION, cal)?;
-        println!("Calibration: {}", cal);
+        // Bail out here rather than registering a device that never actually took the write: an
+        // unpowered or mis-wired sensor will happily ACK the write but read back garbage (or the
+        // bus's idle value) instead of what was just sent.
+        let readback = self.read(register::CONFIGURATION)?;
+        if readback != config.to_bits() {
+            return Err(format!(
+                "device at address {:#04x} did not accept CONFIGURATION write (wrote {:#06x}, \
+                 read back {:#06x}); is it powered and wired correctly?",
+                self.address, config.to_bits(), readback));
+        }
+
+        self.recalibrate()?;
 
         let current_current = self.current()?;
         println!("Current draw: {}", current_current);
@@ -139,14 +598,39 @@ impl INA219 {
 
 impl EnergyMetering for INA219 {
     fn current(&self) -> f32 {
-        self.current().unwrap()
+        match self.current() {
+            Ok(value) => {
+                *self.last_current.lock().unwrap() = value;
+                value
+            },
+            Err(e) => {
+                eprintln!("failed to read INA219 current, reusing last known value: {}", e);
+                *self.last_current.lock().unwrap()
+            },
+        }
     }
 
     fn power(&self) -> f32 {
-        self.power().unwrap()
+        match self.power() {
+            Ok(value) => {
+                *self.last_power.lock().unwrap() = value;
+                value
+            },
+            Err(e) => {
+                eprintln!("failed to read INA219 power, reusing last known value: {}", e);
+                *self.last_power.lock().unwrap()
+            },
+        }
     }
 
     fn cooldown_duration(&self) -> Duration {
-        Duration::from_micros(532)
+        match self.configuration() {
+            Ok(config) => config.shunt_resolution.conversion_time()
+                + config.bus_resolution.conversion_time(),
+            Err(e) => {
+                eprintln!("failed to read INA219 configuration, assuming 12-bit/532us: {}", e);
+                Duration::from_micros(532)
+            },
+        }
     }
 }
@@ -4,11 +4,25 @@ use std::convert::From;
 use std::error;
 use std::fmt;
 use std::fmt::{Debug, Display};
+use std::future::Future;
+use std::pin::Pin;
 
 use crate::io::IOError;
 use crate::test::Test;
 use crate::testbed::Testbed;
 
+/** ABI version shared-library providers must agree on with the host before anything else is
+loaded from them.
+
+A `TestbedProvider`/`TestProvider` shared library is expected to export a `clockwise_abi_version`
+symbol returning this constant. The host compares it against its own build of `ABI_VERSION` before
+resolving `get_testbed`/`get_test_adapter`, so a plugin built against an incompatible layout of
+`Testbed`, `Test`, or these traits is rejected with a descriptive error instead of producing a
+`Box<dyn TestProvider>` the host silently misinterprets. Bump this whenever a change here would
+change the ABI a plugin built against an older version relies on.
+ */
+pub const ABI_VERSION: u32 = 1;
+
 /// Adapter producing a testbed from some input source.
 pub trait TestbedProvider: Debug {
     /// Create a configured testbed ready to run tests.
@@ -20,3 +34,42 @@ pub trait TestProvider: Debug {
     /// Create a Test-producing iterator.
     fn tests<'a>(&'a self) -> Box<dyn Iterator<Item = Test> + 'a>;
 }
+
+/** Non-blocking sibling of [`TestbedProvider`], for providers whose provisioning (flashing
+firmware, waiting for a board to enumerate) is slow enough that a caller bringing up several
+testbeds at once shouldn't serialize on it.
+
+Split into a fire-and-forget `begin_create`, which kicks provisioning off and returns immediately,
+and `ready`, a future that resolves once it has actually finished. A caller driving many testbeds
+calls `begin_create` on each first, then awaits their `ready` futures together, so the slow part of
+each provider's work overlaps instead of running one after another. Methods return boxed futures
+rather than being declared `async fn` so the trait stays object-safe, matching
+[`super::testing::executor::Source`](../../src/testing/executor.rs) (not directly reachable from
+`common`, but the same reasoning applies: callers hold providers as `Box<dyn AsyncTestbedProvider>`
+without knowing the concrete future type).
+ */
+pub trait AsyncTestbedProvider: Debug {
+    /// Begin creating a testbed in the background; returns immediately without waiting for
+    /// provisioning to finish. Calling this more than once before `ready` resolves is
+    /// implementation-defined.
+    fn begin_create(&self);
+
+    /// A future that resolves once the provisioning `begin_create` started has finished, yielding
+    /// the configured testbed or an error describing why creation failed.
+    fn ready(&self) -> Pin<Box<dyn Future<Output = Result<Testbed, String>> + '_>>;
+}
+
+/// Non-blocking sibling of [`TestProvider`], for providers whose test data isn't available
+/// immediately (e.g. loaded from a shared library on a blocking pool). Mirrors
+/// [`AsyncTestbedProvider`]'s fire-and-forget/confirm-ready split: `begin_create` starts loading
+/// in the background, and `ready` is the future that resolves to the usable provider.
+pub trait AsyncTestProvider: Debug {
+    /// Begin loading this provider's test data in the background; returns immediately without
+    /// waiting for it to finish. Calling this more than once before `ready` resolves is
+    /// implementation-defined.
+    fn begin_create(&self);
+
+    /// A future that resolves once the loading `begin_create` started has finished, yielding a
+    /// ready-to-use [`TestProvider`] or an error describing why loading failed.
+    fn ready(&self) -> Pin<Box<dyn Future<Output = Result<Box<dyn TestProvider>, String>> + '_>>;
+}
@@ -3,17 +3,21 @@
 This module contains types for organizing and managing the I/O between the Raspberry Pi and the device under test.
  */
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::From;
 use std::fmt;
 use std::fmt::Display;
 use std::iter::{Iterator, IntoIterator};
 use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use rppal::gpio;
-use rppal::gpio::{Gpio, InputPin, OutputPin};
+use rppal::gpio::{Gpio, InputPin, IoPin, OutputPin};
 use rppal::i2c;
 use rppal::i2c::I2c;
+use rppal::spi;
+use rppal::spi::{Bus, Mode, SlaveSelect, Spi};
 use rppal::uart;
 use rppal::uart::{
     Uart,
@@ -32,6 +36,27 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub type DeviceInputs = Pins<OutputPin>;
 /// Set of pins that accept output _from_ the device under test.
 pub type DeviceOutputs = Pins<InputPin>;
+/// Set of pins whose direction can be flipped at runtime; see [`Mapping::get_gpio_dynamic`].
+pub type DynamicPins = Pins<IoPin>;
+
+/** A single channel that can sample an analog level coming from the device under test.
+
+Unlike [`DeviceOutputs`]'s digital pins, there's no generic way to read an analog level off a
+Raspberry Pi GPIO pin, so analog sampling is pluggable: a testbed supplies one `AnalogInput` per
+device pin it wants to sample (see [`AnalogInputs`]), the same way it supplies energy meters via
+[`crate::facility::EnergyMetering`].
+ */
+pub trait AnalogInput: fmt::Debug {
+    /// Take one sample, returning a raw reading with [`AnalogInput::resolution_bits`] of precision.
+    fn sample(&mut self) -> Result<u32>;
+
+    /// Bit width of the value [`AnalogInput::sample`] returns.
+    fn resolution_bits(&self) -> u32;
+}
+
+/// Analog sampling channels supplied for the device's [`SignalClass::Analog`] pins, keyed by
+/// device pin number; see [`AnalogInput`].
+pub type AnalogInputs = HashMap<u8, Box<dyn AnalogInput>>;
 
 /// Errors related to acquiring and configuring I/O.
 #[derive(Debug)]
@@ -44,6 +69,10 @@ pub enum Error {
     I2C(i2c::Error),
     /// Reset functionality not defined.
     NoReset,
+    /// Mapping does not allow SPI.
+    SPIUnavailable,
+    /// SPI initialization error.
+    SPI(spi::Error),
     /// Mapping does not allow UART.
     UARTUnavailable,
     /// UART initialization error.
@@ -69,6 +98,8 @@ impl Display for Error {
             I2CUnavailable => write!(f, "I2C pins (2, 3) are mapped"),
             I2C(ref e) => write!(f, "could not obtain I2C interface: {}", e),
             NoReset => write!(f, "reset functionality is not defined for the device"),
+            SPIUnavailable => write!(f, "SPI0 pins (7, 8, 9, 10, 11) are mapped"),
+            SPI(ref e) => write!(f, "could not obtain SPI interface: {}", e),
             UARTUnavailable => write!(f, "UART pins (14, 15) are mapped"),
             UART(ref e) => write!(f, "could not obtain UART interface: {}", e),
             UndefinedPin(pin_no) => write!(f, "undefined pin ({}) used", pin_no),
@@ -88,6 +119,12 @@ impl From<i2c::Error> for Error {
     }
 }
 
+impl From<spi::Error> for Error {
+    fn from(e: spi::Error) -> Self {
+        Error::SPI(e)
+    }
+}
+
 impl From<uart::Error> for Error {
     fn from(e: uart::Error) -> Self {
         Error::UART(e)
@@ -179,9 +216,166 @@ impl<'a, T> IntoIterator for &'a mut Pins<T> {
     }
 }
 
+impl Pins<IoPin> {
+    /// Switch `pin_no` to input mode in place, without re-acquiring it from [`Gpio`].
+    pub fn reconfigure_input(&mut self, pin_no: u8) -> Result<()> {
+        self.get_pin_mut(pin_no)?.set_mode(gpio::Mode::Input);
+        Ok(())
+    }
+
+    /// Switch `pin_no` to output mode in place, without re-acquiring it from [`Gpio`].
+    pub fn reconfigure_output(&mut self, pin_no: u8) -> Result<()> {
+        self.get_pin_mut(pin_no)?.set_mode(gpio::Mode::Output);
+        Ok(())
+    }
+}
+
+/** A single channel that can drive an analog level into the device under test.
+
+[`OutputPin`] implements this by approximating a DAC with rppal's own software PWM (runs on a
+background thread; stops automatically when the pin is dropped or reconfigured), the same way
+[`AnalogInput`] leaves sampling hardware pluggable on the input side.
+ */
+pub trait AnalogOutput: fmt::Debug {
+    /// Drive `level`, a raw code with `resolution_bits` of precision, onto the channel at
+    /// `frequency_hz`.
+    fn set_level(&mut self, level: u32, resolution_bits: u32, frequency_hz: f64) -> Result<()>;
+
+    /// Stop driving the channel, returning it to whatever static state it was in before.
+    fn stop(&mut self) -> Result<()>;
+}
+
+impl AnalogOutput for OutputPin {
+    fn set_level(&mut self, level: u32, resolution_bits: u32, frequency_hz: f64) -> Result<()> {
+        let max_level = (1u32 << resolution_bits) - 1;
+        let duty_cycle = (level as f64 / max_level as f64).min(1.0);
+        self.set_pwm_frequency(frequency_hz, duty_cycle)?;
+
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        self.clear_pwm()?;
+
+        Ok(())
+    }
+}
+
+/// A single recorded transition on a [`DeviceOutputs`] pin, captured by an [`OutputMonitor`].
+#[derive(Clone, Copy, Debug)]
+pub struct EdgeEvent {
+    /// When the transition was observed, per [`std::time::Instant`] sampled inside the interrupt
+    /// callback -- monotonic, but not comparable across process runs.
+    pub time: Instant,
+    /// The device pin number (not the host pin) the transition was observed on.
+    pub pin_no: u8,
+    /// `true` for a rising edge (now high), `false` for a falling edge (now low).
+    pub level: bool,
+}
+
+/** Captures device output transitions in the background, for tests that need to assert a signal
+toggled within some time window rather than only sampling it synchronously.
+
+[`arm`](OutputMonitor::arm) registers an async interrupt handler (rppal's `set_async_interrupt`) on
+each requested pin of `outputs`, so transitions are timestamped from inside the interrupt callback
+rather than however long it takes a polling loop to notice them. Events accumulate in a shared
+buffer until [`drain`](OutputMonitor::drain) is called, and [`disarm`](OutputMonitor::disarm) stops
+capture and releases the interrupt handlers.
+
+`debounce` filters out repeat transitions on the same pin that arrive less than that long after the
+last one recorded for it -- rppal's async interrupts fire on every electrical edge, and a mechanical
+switch or a noisy line can chatter several of those within a few milliseconds of the "real" edge. A
+`debounce` of [`Duration::ZERO`] records every edge the hardware reports.
+ */
+pub struct OutputMonitor {
+    events: Arc<Mutex<Vec<EdgeEvent>>>,
+    last_seen: Arc<Mutex<HashMap<u8, Instant>>>,
+    debounce: Duration,
+    armed_pins: Vec<u8>,
+}
+
+impl fmt::Debug for OutputMonitor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OutputMonitor")
+            .field("debounce", &self.debounce)
+            .field("armed_pins", &self.armed_pins)
+            .finish()
+    }
+}
+
+impl OutputMonitor {
+    /// Create a monitor that records every edge reported by the hardware (no debounce).
+    pub fn new() -> OutputMonitor {
+        OutputMonitor::with_debounce(Duration::ZERO)
+    }
+
+    /// Create a monitor that discards a pin's transitions that arrive less than `debounce` after
+    /// the last one recorded for it.
+    pub fn with_debounce(debounce: Duration) -> OutputMonitor {
+        OutputMonitor {
+            events: Arc::new(Mutex::new(Vec::new())),
+            last_seen: Arc::new(Mutex::new(HashMap::new())),
+            debounce,
+            armed_pins: Vec::new(),
+        }
+    }
+
+    /// Arm rising/falling/both-edge interrupts (per `trigger`) on `pins` of `outputs`, recording
+    /// every accepted transition into this monitor's buffer until [`disarm`](Self::disarm) is
+    /// called.
+    pub fn arm(&mut self, outputs: &mut DeviceOutputs, pins: &[u8], trigger: gpio::Trigger) -> Result<()> {
+        for &pin_no in pins {
+            let events = Arc::clone(&self.events);
+            let last_seen = Arc::clone(&self.last_seen);
+            let debounce = self.debounce;
+
+            outputs.get_pin_mut(pin_no)?
+                .set_async_interrupt(trigger, move |level| {
+                    let now = Instant::now();
+
+                    let mut last_seen = last_seen.lock().unwrap();
+                    if let Some(&last) = last_seen.get(&pin_no) {
+                        if now.duration_since(last) < debounce {
+                            return;
+                        }
+                    }
+                    last_seen.insert(pin_no, now);
+                    drop(last_seen);
+
+                    events.lock().unwrap().push(EdgeEvent {
+                        time: now,
+                        pin_no,
+                        level: level == gpio::Level::High,
+                    });
+                })?;
+            self.armed_pins.push(pin_no);
+        }
+
+        Ok(())
+    }
+
+    /// Stop capture, clearing the interrupt handler on every pin this monitor armed.
+    pub fn disarm(&mut self, outputs: &mut DeviceOutputs) -> Result<()> {
+        for pin_no in self.armed_pins.drain(..) {
+            outputs.get_pin_mut(pin_no)?.clear_interrupt()?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove and return every event recorded so far, in the order it occurred.
+    pub fn drain(&self) -> Vec<EdgeEvent> {
+        let mut events = self.events.lock().unwrap();
+        let mut drained: Vec<EdgeEvent> = events.drain(..).collect();
+        drained.sort_by_key(|e| e.time);
+        drained
+    }
+}
+
 /// Properties of a device under test.
 pub struct Device {
     io: HashMap<u8, (Direction, SignalClass)>,
+    bidirectional: HashSet<u8>,
     hold_reset: Option<Rc<dyn Fn(&mut DeviceInputs) -> Result<()>>>,
     release_reset: Option<Rc<dyn Fn(&mut DeviceInputs) -> Result<()>>>,
 }
@@ -196,11 +390,31 @@ impl Device {
         T: IntoIterator<Item = &'b (u8, (Direction, SignalClass))> {
         Device {
             io: pin_map.into_iter().map(|x| *x).collect(),
+            bidirectional: HashSet::new(),
             hold_reset: None,
             release_reset: None,
         }
     }
 
+    /** Mark `pins` as bidirectional (e.g. an open-drain handshake line, or a bootstrap strap that's
+    driven then released to sense).
+
+    `direction_of` still reports each pin's declared (initial) direction, and [`Mapping::get_gpio_dynamic`]
+    still validates against it when first acquiring the pin -- this only controls which pins that
+    method is willing to hand out as a reconfigurable [`DynamicPins`] in the first place.
+    */
+    pub fn with_bidirectional<T>(mut self, pins: T) -> Self where
+        T: IntoIterator<Item = u8>
+    {
+        self.bidirectional.extend(pins);
+        self
+    }
+
+    /// Returns true if `pin` has been marked bidirectional via [`Device::with_bidirectional`].
+    pub fn is_bidirectional(&self, pin: u8) -> bool {
+        self.bidirectional.contains(&pin)
+    }
+
     /// Define reset functionality for the device.
     pub fn with_reset(self,
                       hold_reset: Rc<dyn Fn(&mut DeviceInputs) -> Result<()>>,
@@ -247,7 +461,6 @@ impl Device {
     /// Returns the signal of the pin.
     ///
     /// Returns an error if the pin is not defined.
-    #[allow(dead_code)]
     pub fn signal_of(&self, pin: u8) -> Result<SignalClass> {
         self.io.get(&pin)
             .map(|&(_dir, sig)| sig)
@@ -293,6 +506,89 @@ impl UART {
     }
 }
 
+/// Pull resistor configuration for a host pin configured as an input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pull {
+    Off,
+    Down,
+    Up,
+}
+
+/// Output drive strength for a host pin configured as an output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Drive {
+    Low,
+    Medium,
+    High,
+    Max,
+}
+
+/// Output slew rate for a host pin configured as an output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlewRate {
+    Slow,
+    Fast,
+}
+
+/** Electrical configuration applied to a host pin when it is materialized into a live GPIO pin.
+
+Left unset (`None`) fields keep the underlying GPIO implementation's default. A [`Pull`] keeps a
+floating DUT output from producing spurious edge captures. [`Drive`] and [`SlewRate`] are stored but
+not applied to any pin.
+ */
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PinConfig {
+    pull: Option<Pull>,
+    #[allow(dead_code)]
+    drive: Option<Drive>,
+    #[allow(dead_code)]
+    slew_rate: Option<SlewRate>,
+}
+
+impl PinConfig {
+    pub fn new() -> PinConfig {
+        PinConfig::default()
+    }
+
+    pub fn with_pull(self, pull: Pull) -> PinConfig {
+        PinConfig { pull: Some(pull), ..self }
+    }
+
+    pub fn with_drive(self, drive: Drive) -> PinConfig {
+        PinConfig { drive: Some(drive), ..self }
+    }
+
+    pub fn with_slew_rate(self, slew_rate: SlewRate) -> PinConfig {
+        PinConfig { slew_rate: Some(slew_rate), ..self }
+    }
+}
+
+/** Line configuration for a UART bus, passed to [`Mapping::get_uart`].
+
+Defaults (via [`Default`]) to this testbed's original hard-coded configuration --
+115200/8E1, no hardware flow control -- so existing callers that don't care keep working unchanged.
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct UartConfig {
+    pub baud_rate: u32,
+    pub parity: UARTParity,
+    pub data_bits: u8,
+    pub stop_bits: u8,
+    pub hardware_flow_control: bool,
+}
+
+impl Default for UartConfig {
+    fn default() -> UartConfig {
+        UartConfig {
+            baud_rate: 115_200,
+            parity: UARTParity::Even,
+            data_bits: 8,
+            stop_bits: 1,
+            hardware_flow_control: false,
+        }
+    }
+}
+
 /** Interface to I/O between the testbed and the device under test.
 
 `Mapping` defines the interface between the testbed and the device under test.
@@ -303,6 +599,7 @@ pub struct Mapping {
     device: Device,
     numbering: HashMap<u8, u8>,
     reset_pin: Option<u8>,
+    pin_configs: HashMap<u8, PinConfig>,
 }
 
 impl Mapping {
@@ -335,9 +632,23 @@ impl Mapping {
             device,
             numbering,
             reset_pin,
+            pin_configs: HashMap::new(),
         })
     }
 
+    /** Record electrical configuration (pull resistor, drive strength, slew rate) for a host pin.
+
+    `host_pin` need not be mapped yet; the configuration is consulted when the pin is
+    materialized. Only the pull setting is applied, and only to pins acquired as host inputs, by
+    [`get_gpio_outputs`](Mapping::get_gpio_outputs) and by [`get_gpio_dynamic`](Mapping::get_gpio_dynamic)
+    for pins it starts in input mode. [`get_gpio_inputs`](Mapping::get_gpio_inputs) does not consult
+    the configuration, and drive strength and slew rate are not applied to any pin.
+     */
+    pub fn with_pin_config(mut self, host_pin: u8, config: PinConfig) -> Mapping {
+        self.pin_configs.insert(host_pin, config);
+        self
+    }
+
     /// Returns the device definition.
     pub fn get_device(&self) -> &Device {
         &self.device
@@ -367,7 +678,12 @@ impl Mapping {
 
         for (h_pin, t_pin) in input_numbering {
             let pin = gpio.get(h_pin)?;
-            inputs.push((t_pin, pin.into_output()));
+            let output = pin.into_output();
+            // NOTE: rppal doesn't expose per-pin drive strength/slew rate control on the BCM28xx
+            // GPIO hardware this testbed targets, so `PinConfig::drive`/`slew_rate` aren't wired up
+            // to anything yet; they're captured for API parity with DUT-side boards (e.g. RP2040)
+            // whose HAL does support them.
+            inputs.push((t_pin, output));
         }
 
         Ok(DeviceInputs::new(inputs))
@@ -387,12 +703,82 @@ impl Mapping {
 
         for (h_pin, t_pin) in output_numbering {
             let pin = gpio.get(h_pin)?;
-            outputs.push((t_pin, pin.into_input()));
+            let mut input = pin.into_input();
+            if let Some(pull) = self.pin_configs.get(&h_pin).and_then(|config| config.pull) {
+                input.set_pullupdown(match pull {
+                    Pull::Off => gpio::Pull::Off,
+                    Pull::Down => gpio::Pull::Down,
+                    Pull::Up => gpio::Pull::Up,
+                });
+            }
+            outputs.push((t_pin, input));
         }
 
         Ok(DeviceOutputs::new(outputs))
     }
 
+    /** Validate that `inputs` supplies an [`AnalogInput`] channel for every device pin declared
+    [`SignalClass::Analog`] and [`Direction::Out`] -- i.e., every pin the device drives an analog
+    level onto for the testbed to sample. [`get_gpio_outputs`](Mapping::get_gpio_outputs) hands
+    such pins out as plain digital [`InputPin`]s too, since the underlying host pin is the same
+    either way; this only confirms the analog side has what it needs to actually read them.
+
+    Returns an error naming the first analog pin found missing a channel.
+     */
+    pub fn validate_analog_inputs(&self, inputs: &AnalogInputs) -> Result<()> {
+        let analog_output_pins = self.numbering.values()
+            .copied()
+            .filter(|t| self.device.direction_of(*t).unwrap() == Direction::Out
+                    && matches!(self.device.signal_of(*t).unwrap(), SignalClass::Analog));
+
+        for t_pin in analog_output_pins {
+            if !inputs.contains_key(&t_pin) {
+                return Err(Error::UndefinedPin(t_pin));
+            }
+        }
+
+        Ok(())
+    }
+
+    /** Returns pins the [`Device`] has marked [bidirectional](Device::with_bidirectional) as a
+    [`DynamicPins`], each initialized in its declared [`direction_of`](Device::direction_of) mode
+    but reconfigurable afterward via [`Pins::reconfigure_input`]/[`Pins::reconfigure_output`]
+    without being re-acquired from [`Gpio`].
+
+    Unlike [`get_gpio_inputs`](Mapping::get_gpio_inputs)/[`get_gpio_outputs`](Mapping::get_gpio_outputs),
+    this isn't split by direction: a strap pin is driven for bootstrap and then released to sense, or
+    an open-drain line is driven low and then floated, so both directions need to live in the same
+    collection as the test flips between them.
+     */
+    pub fn get_gpio_dynamic(&self) -> Result<DynamicPins> {
+        let dynamic_numbering = self.numbering.iter()
+            .map(|(h, t)| (*h, *t))
+            .filter(|(_h, t)| self.device.is_bidirectional(*t));
+        let mut dynamic = Vec::new();
+        let gpio = Gpio::new()?;
+
+        for (h_pin, t_pin) in dynamic_numbering {
+            let initial_mode = match self.device.direction_of(t_pin)? {
+                Direction::In => gpio::Mode::Output,
+                Direction::Out => gpio::Mode::Input,
+            };
+            let pin = gpio.get(h_pin)?;
+            let mut io_pin = pin.into_io(initial_mode);
+            if initial_mode == gpio::Mode::Input {
+                if let Some(pull) = self.pin_configs.get(&h_pin).and_then(|config| config.pull) {
+                    io_pin.set_pullupdown(match pull {
+                        Pull::Off => gpio::Pull::Off,
+                        Pull::Down => gpio::Pull::Down,
+                        Pull::Up => gpio::Pull::Up,
+                    });
+                }
+            }
+            dynamic.push((t_pin, io_pin));
+        }
+
+        Ok(DynamicPins::new(dynamic))
+    }
+
     /** Configures and returns the I2C interface.
 
     # Errors
@@ -410,10 +796,51 @@ impl Mapping {
         }
     }
 
-    /// Retrieves the UART interface.
+    /** Scans the I2C bus for responsive devices.
+
+    Obtains the interface via [`Mapping::get_i2c`], then probes every non-reserved 7-bit address
+    (`0x08..=0x77`; `0x00..=0x07` and `0x78..=0x7F` are reserved for other bus protocols) with a
+    single-byte read, collecting the addresses that ACK. The probe only reads, so it never writes
+    to or otherwise disturbs whatever is on the bus.
+
+    # Errors
+    - If the I/O mapping has mapped the pins used for the I2C bus, this function returns `Error::I2CUnavailable`.
+    - If the underlying implementation encounters an error initializing I2C, this function returns `Error::I2C`.
+     */
+    pub fn scan_i2c(&self) -> Result<Vec<u8>> {
+        let mut i2c = self.get_i2c()?;
+
+        let mut responsive = Vec::new();
+        let mut probe = [0u8; 1];
+        for address in 0x08..=0x77u16 {
+            i2c.set_slave_address(address)?;
+            if i2c.read(&mut probe).is_ok() {
+                responsive.push(address as u8);
+            }
+        }
+
+        Ok(responsive)
+    }
+
+    /** Configures and returns the SPI0 interface.
+
+    # Errors
+    - If the I/O mapping has mapped the pins used for the SPI0 bus (GPIO 7, 8, 9, 10, 11), this function returns `Error::SPIUnavailable`.
+    - If the underlying implementation encounters an error initializing SPI, this function returns `Error::SPI`.
+     */
+    pub fn get_spi(&self, bus: Bus, slave_select: SlaveSelect, clock_speed: u32, mode: Mode) -> Result<Spi> {
+        let spi_pins_mapped = (7..=11).any(|pin_no| self.numbering.contains_key(&pin_no));
+        if spi_pins_mapped {
+            Err(Error::SPIUnavailable)
+        } else {
+            Ok(Spi::new(bus, slave_select, clock_speed, mode)?)
+        }
+    }
+
+    /// Retrieves the UART interface, configured per `config`.
     ///
     /// If using the UART built into the Raspberry Pi, `which_uart` must be `UART::PL011` to do pin mapping checking.
-    pub fn get_uart(&self, which_uart: &UART) -> Result<Uart>
+    pub fn get_uart(&self, which_uart: &UART, config: &UartConfig) -> Result<Uart>
     {
         // Must check the pins that this UART uses.
         if *which_uart == UART::PL011
@@ -421,11 +848,14 @@ impl Mapping {
         {
             Err(Error::UARTUnavailable)
         } else {
-            // Use hard-coded values here to avoid complexity
-            // in code wanting to use the UART.
             println!("Opening UART: {}", which_uart.path());
-            let mut uart = Uart::with_path(which_uart.path(), 115_200, UARTParity::Even, 8, 1)?;
-            uart.set_hardware_flow_control(false)?;
+            let mut uart = Uart::with_path(
+                which_uart.path(),
+                config.baud_rate,
+                config.parity,
+                config.data_bits,
+                config.stop_bits)?;
+            uart.set_hardware_flow_control(config.hardware_flow_control)?;
             Ok(uart)
         }
     }
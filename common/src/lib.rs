@@ -2,17 +2,24 @@
 
 #![deny(missing_docs)]
 
+pub mod calibration;
 pub mod comm;
 pub mod criteria;
+pub mod decode;
 pub mod evaluation;
 pub mod facility;
+pub mod filter;
+pub mod firmware;
+pub mod histogram;
 pub mod hw;
 pub mod input;
 pub mod io;
 pub mod mem;
+pub mod metrics;
 pub mod output;
 pub mod parsing;
 pub mod sw;
+pub mod telemetry;
 pub mod test;
 pub mod testbed;
 pub mod trace;
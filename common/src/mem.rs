@@ -1,5 +1,7 @@
 //! Aggregate memory statistics sent over the wire.
 
+use std::collections::HashMap;
+use std::io::{self, Read};
 use std::time::Instant;
 use std::fmt::{self, Display};
 
@@ -159,6 +161,102 @@ pub fn parse_counter(input: &[u8], time: Instant) -> BitsResult<MemoryTrace> {
     streamed_counter((input, 0), time)
 }
 
+/** Decode a buffer of back-to-back [`MemoryTrace`] records.
+
+Counters arrive over the wire as a continuous stream rather than as isolated records, so this repeatedly applies
+[`streamed_counter`] to whatever is left of `input`, stamping every decoded record with the same `time`.
+Decoding stops at the first record that is incomplete (a trailing, partially-received record) or malformed, and
+at that point the bytes making up the undecoded remainder are returned alongside whatever traces were recovered.
+ */
+pub fn parse_counter_stream(input: &[u8], time: Instant) -> (Vec<MemoryTrace>, &[u8]) {
+    let mut traces = Vec::new();
+    let mut remaining = input;
+
+    while !remaining.is_empty() {
+        match streamed_counter((remaining, 0), time) {
+            Ok(((rest, _bit_offset), trace)) => {
+                traces.push(trace);
+                remaining = rest;
+            },
+            Err(_) => break,
+        }
+    }
+
+    (traces, remaining)
+}
+
+/** Reconstruct the per-counter value timeline from a sequence of [`MemoryTrace`] events.
+
+Each [`MemoryTrace`] describes an update to one counter ([`StreamOperation::Add`] accumulates onto the counter's
+running value, [`StreamOperation::Set`] replaces it outright). This walks the events in order and returns, for
+every counter seen, the sequence of `(Instant, value)` pairs the counter took on over time.
+ */
+pub fn reconstruct_timelines<'a, T>(traces: T) -> HashMap<CounterId, Vec<(Instant, u32)>>
+where
+    T: IntoIterator<Item = &'a MemoryTrace>,
+{
+    let mut timelines: HashMap<CounterId, Vec<(Instant, u32)>> = HashMap::new();
+
+    for trace in traces {
+        let timeline = timelines.entry(*trace.counter()).or_insert_with(Vec::new);
+        let running = timeline.last().map(|(_t, value)| *value).unwrap_or(0);
+
+        let value = match trace.operation() {
+            StreamOperation::Add => running + trace.value(),
+            StreamOperation::Set => trace.value(),
+        };
+
+        timeline.push((trace.time(), value));
+    }
+
+    timelines
+}
+
+/** Live receiver that frames and decodes [`MemoryTrace`] records as bytes arrive.
+
+Wraps any byte-oriented source (a UART, a TCP socket, ...) and repeatedly drains it, feeding whatever
+comes in through [`parse_counter_stream`]. Bytes belonging to a record that hasn't fully arrived yet are
+held over to the next [`FrameReceiver::poll`] call rather than discarded.
+ */
+#[derive(Debug)]
+pub struct FrameReceiver<R> {
+    source: R,
+    buffer: Vec<u8>,
+}
+
+impl<R: Read> FrameReceiver<R> {
+    /// Wrap `source` in a new `FrameReceiver`.
+    pub fn new(source: R) -> FrameReceiver<R> {
+        FrameReceiver {
+            source,
+            buffer: Vec::new(),
+        }
+    }
+
+    /** Read whatever bytes are currently available from the source and decode any complete records.
+
+    Returns the [`MemoryTrace`]s decoded from this poll, stamped with the time the poll was made.
+    Bytes that make up an incomplete trailing record are retained for the next call.
+     */
+    pub fn poll(&mut self) -> io::Result<Vec<MemoryTrace>> {
+        let time = Instant::now();
+
+        let mut chunk = [0u8; 4096];
+        let read = self.source.read(&mut chunk)?;
+        self.buffer.extend_from_slice(&chunk[..read]);
+
+        let (traces, remaining_len) = {
+            let (traces, remaining) = parse_counter_stream(&self.buffer, time);
+            (traces, remaining.len())
+        };
+
+        let consumed = self.buffer.len() - remaining_len;
+        self.buffer.drain(..consumed);
+
+        Ok(traces)
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
@@ -293,6 +391,118 @@ pub mod tests {
             });
     }
 
+    #[test]
+    pub fn stream_decodes_back_to_back_records() {
+        let input = [0b1000_0001,
+                     0b0000_0110,
+                     0b0000_0000,
+                     0b0000_0000,
+                     0b0000_0000,
+                     0b0000_0011,
+                     0b0000_0000,
+                     0b0000_0000,
+                     0b0000_0000,
+
+                     0b0000_0010,
+                     0b0000_0111,
+                     0b0000_0000,
+                     0b0000_0000,
+                     0b0000_0000,
+                     0b0000_0001,
+                     0b0000_0000,
+                     0b0000_0000,
+                     0b0000_0000];
+        let now = Instant::now();
+
+        let (traces, remaining) = parse_counter_stream(&input, now);
+
+        assert_eq!(traces.len(), 2);
+        assert_eq!(remaining.len(), 0);
+    }
+
+    #[test]
+    pub fn stream_stops_at_trailing_partial_record() {
+        let input = [0b1000_0001,
+                     0b0000_0110,
+                     0b0000_0000,
+                     0b0000_0000,
+                     0b0000_0000,
+                     0b0000_0011,
+                     0b0000_0000,
+                     0b0000_0000,
+                     0b0000_0000,
+
+                     // Trailing partial record: only the header and the first counter byte.
+                     0b0000_0010,
+                     0b0000_0111];
+        let now = Instant::now();
+
+        let (traces, remaining) = parse_counter_stream(&input, now);
+
+        assert_eq!(traces.len(), 1);
+        assert_eq!(remaining, &input[9..]);
+    }
+
+    #[test]
+    pub fn reconstruct_accumulates_add_operations() {
+        let now = Instant::now();
+        let traces = vec![
+            MemoryTrace { time: now, op: StreamOperation::Add, counter: CounterId::PCB(1), value: 4 },
+            MemoryTrace { time: now, op: StreamOperation::Add, counter: CounterId::PCB(1), value: 3 },
+        ];
+
+        let timelines = reconstruct_timelines(&traces);
+        let timeline = timelines.get(&CounterId::PCB(1)).unwrap();
+
+        assert_eq!(timeline.iter().map(|(_t, v)| *v).collect::<Vec<_>>(), vec![4, 7]);
+    }
+
+    #[test]
+    pub fn reconstruct_set_replaces_running_value() {
+        let now = Instant::now();
+        let traces = vec![
+            MemoryTrace { time: now, op: StreamOperation::Add, counter: CounterId::PCB(1), value: 4 },
+            MemoryTrace { time: now, op: StreamOperation::Set, counter: CounterId::PCB(1), value: 10 },
+        ];
+
+        let timelines = reconstruct_timelines(&traces);
+        let timeline = timelines.get(&CounterId::PCB(1)).unwrap();
+
+        assert_eq!(timeline.iter().map(|(_t, v)| *v).collect::<Vec<_>>(), vec![4, 10]);
+    }
+
+    #[test]
+    pub fn frame_receiver_decodes_across_polls() {
+        use std::io::Read;
+
+        struct Chunked(Vec<Vec<u8>>);
+        impl Read for Chunked {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                if self.0.is_empty() {
+                    return Ok(0);
+                }
+                let chunk = self.0.remove(0);
+                buf[..chunk.len()].copy_from_slice(&chunk);
+                Ok(chunk.len())
+            }
+        }
+
+        let record = [0b1000_0001,
+                      0b0000_0110,
+                      0b0000_0000,
+                      0b0000_0000,
+                      0b0000_0000,
+                      0b0000_0011,
+                      0b0000_0000,
+                      0b0000_0000,
+                      0b0000_0000];
+        let source = Chunked(vec![record[..3].to_vec(), record[3..].to_vec()]);
+        let mut receiver = FrameReceiver::new(source);
+
+        assert_eq!(receiver.poll().unwrap().len(), 0);
+        assert_eq!(receiver.poll().unwrap().len(), 1);
+    }
+
     #[test]
     pub fn incomplete_counter() {
         let input = [0b1000_0001,
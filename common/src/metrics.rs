@@ -0,0 +1,153 @@
+//! Pluggable destinations for energy samples and aggregates produced during evaluation.
+
+use std::io::Write as _;
+use std::fs::{File, OpenOptions};
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+use crate::criteria::EnergyStat;
+
+/// Short, stable name for an [`EnergyStat`] variant, suitable for use as a line-protocol tag value.
+fn stat_tag(stat: EnergyStat) -> String {
+    match stat {
+        EnergyStat::Total => "total".to_string(),
+        EnergyStat::Average => "average".to_string(),
+        EnergyStat::Max => "max".to_string(),
+        EnergyStat::Min => "min".to_string(),
+        EnergyStat::Percentile(p) => format!("p{:.0}", p.clamp(0.0, 1.0) * 100.0),
+        EnergyStat::TimeAbove(threshold) => format!("time_above_{:.0}mw", threshold),
+    }
+}
+
+/** Destination for the energy data produced while evaluating `Criterion::Energy`.
+
+An evaluation emits two kinds of points: individual samples as they're walked, and the derived
+aggregate (e.g. total mJ consumed) computed from them. Implementors decide what to do with both;
+[`FileEnergySink`] preserves this crate's original scratch-CSV behavior, and [`InfluxEnergySink`]
+streams both kinds as InfluxDB line protocol.
+ */
+pub trait EnergySink {
+    /// Record one energy sample taken `offset_ns` nanoseconds after execution started, in
+    /// milliwatts. The timestamp is relative to execution start, not wall-clock time, since
+    /// sampling is timed from a monotonic [`std::time::Instant`].
+    fn sample(&self, test_id: &str, meter: &str, offset_ns: u128, value_mw: f32);
+
+    /// Record a derived aggregate (total mJ, average/max/min mW) computed over a test's samples,
+    /// anchored at execution start.
+    fn aggregate(&self, test_id: &str, meter: &str, stat: EnergyStat, value: f32);
+}
+
+/** The original behavior: one CSV file per meter/stat combination under `/tmp`.
+
+Kept as the default so evaluating without configuring a sink behaves exactly as before.
+ */
+#[derive(Debug, Default)]
+pub struct FileEnergySink;
+
+impl FileEnergySink {
+    /// Create a new `FileEnergySink`.
+    pub fn new() -> FileEnergySink {
+        FileEnergySink
+    }
+}
+
+impl EnergySink for FileEnergySink {
+    fn sample(&self, _test_id: &str, meter: &str, offset_ns: u128, value_mw: f32) {
+        let path = format!("/tmp/energy-{}.csv", meter);
+        let existed = std::path::Path::new(&path).exists();
+        if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(&path) {
+            if !existed {
+                let _ = file.write(b"time,consumed\n");
+            }
+            let _ = file.write(format!("{},{}\n", offset_ns / 1000, value_mw).as_bytes());
+        }
+    }
+
+    fn aggregate(&self, test_id: &str, meter: &str, stat: EnergyStat, value: f32) {
+        let path = format!("/tmp/energy-{}-{}.csv", meter, stat_tag(stat));
+        if let Ok(mut file) = File::create(&path) {
+            let _ = file.write(format!("test,value\n{},{}\n", test_id, value).as_bytes());
+        }
+    }
+}
+
+/** Streams energy samples and aggregates to InfluxDB as line protocol.
+
+Points are buffered on a channel and flushed by a dedicated background thread over a raw HTTP POST
+to `/write?db=<database>`, so evaluation never blocks on network I/O. Samples are written to the
+`energy` measurement tagged with `meter`, `test_id`, and `stat` ("sample"), with the millwatt value
+as the `value` field; aggregates reuse the same measurement with `stat` set to the aggregate's name
+("total", "average", "max", "min", "p<N>" for a percentile, "time_above_<N>mw") and the field carrying the aggregate's unit-appropriate value.
+ */
+#[derive(Debug)]
+pub struct InfluxEnergySink {
+    lines: Sender<String>,
+}
+
+impl InfluxEnergySink {
+    /** Connect to an InfluxDB HTTP endpoint and start the background flush thread.
+
+    `host`/`port` address the InfluxDB HTTP API, and `database` names the target database. Returns
+    an error if the background writer thread could not be spawned.
+     */
+    pub fn new(host: &str, port: u16, database: &str) -> std::io::Result<InfluxEnergySink> {
+        let (tx, rx) = mpsc::channel::<String>();
+        let host = host.to_string();
+        let database = database.to_string();
+
+        thread::Builder::new()
+            .name("influx-energy-sink".to_string())
+            .spawn(move || {
+                while let Ok(line) = rx.recv() {
+                    if let Err(e) = Self::flush(&host, port, &database, &line) {
+                        println!("influx-energy-sink: failed to write point: {}", e);
+                    }
+                }
+            })?;
+
+        Ok(InfluxEnergySink { lines: tx })
+    }
+
+    /// Send a single line-protocol point over a fresh connection.
+    fn flush(host: &str, port: u16, database: &str, line: &str) -> std::io::Result<()> {
+        let mut stream = TcpStream::connect((host, port))?;
+        let body = line.as_bytes();
+        let request = format!(
+            "POST /write?db={} HTTP/1.1\r\nHost: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            database, host, body.len());
+
+        stream.write_all(request.as_bytes())?;
+        stream.write_all(body)?;
+        Ok(())
+    }
+
+    /// Enqueue a line-protocol point to be flushed by the background thread.
+    fn enqueue(&self, measurement: &str, tags: &[(&str, &str)], value: f32, timestamp_ns: u128) {
+        let tag_set: String = tags.iter()
+            .map(|(k, v)| format!(",{}={}", k, v))
+            .collect();
+        let line = format!("{}{} value={} {}\n", measurement, tag_set, value, timestamp_ns);
+
+        // The only failure mode is the background thread having exited; nothing useful to do.
+        let _ = self.lines.send(line);
+    }
+}
+
+impl EnergySink for InfluxEnergySink {
+    fn sample(&self, test_id: &str, meter: &str, offset_ns: u128, value_mw: f32) {
+        self.enqueue(
+            "energy",
+            &[("meter", meter), ("test_id", test_id), ("stat", "sample")],
+            value_mw,
+            offset_ns);
+    }
+
+    fn aggregate(&self, test_id: &str, meter: &str, stat: EnergyStat, value: f32) {
+        self.enqueue(
+            "energy",
+            &[("meter", meter), ("test_id", test_id), ("stat", stat_tag(stat).as_str())],
+            value,
+            0);
+    }
+}
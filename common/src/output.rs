@@ -2,9 +2,8 @@
 
 use std::fmt::Debug;
 use std::collections::HashMap;
-use std::time::Instant;
 
-use crate::test::{Execution, Response, Test};
+use crate::test::{Execution, Response, Sample, Test};
 use crate::trace::SerialTrace;
 
 /// Writer for raw data from tests.
@@ -15,6 +14,6 @@ pub trait DataWriter: Debug {
                    execution: &Execution,
                    responses: &[Response],
                    traces: &[SerialTrace],
-                   energy: &HashMap<String, Vec<(Instant, f32)>>)
+                   energy: &HashMap<String, Vec<Sample>>)
                    -> Result<(), String>;
 }
@@ -0,0 +1,91 @@
+//! Live streaming of test telemetry to a TCP dashboard.
+//!
+//! [`Test::observe`](crate::test::Test::observe), [`Test::analog_observe`](crate::test::Test::analog_observe),
+//! [`Test::meter`](crate::test::Test::meter), and [`Test::trace`](crate::test::Test::trace) all
+//! buffer everything they record into `Vec`s that only become available once the method returns.
+//! A [`TelemetrySink`], passed in alongside those buffers, additionally forwards each record to a
+//! TCP socket the instant it's produced, so an external dashboard can watch a run live instead of
+//! waiting on it to finish. The in-memory buffers are always filled regardless of whether a sink
+//! is attached, so a send failure (or no sink at all) never loses data from the caller's point of
+//! view.
+
+use std::io::{self, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+use crate::comm::Signal;
+use crate::test::{Response, Sample};
+use crate::trace::SerialTrace;
+
+/// Which kind of telemetry record a frame carries.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u8)]
+enum Channel {
+    /// A [`Response`] recorded by `observe`/`analog_observe`.
+    Response = 0,
+    /// An energy [`Sample`] recorded by `meter`.
+    EnergySample = 1,
+    /// A [`SerialTrace`] recorded by `trace`.
+    SerialTrace = 2,
+}
+
+/** Streams test telemetry to a TCP socket as it's produced.
+
+Each record is framed as `[offset_ns: u64 LE][channel: u8][payload_len: u32 LE][payload]`, where
+`offset_ns` is the record's timestamp offset from the test's `t0` for responses and serial traces,
+and from the start of metering for energy samples.
+ */
+#[derive(Debug)]
+pub struct TelemetrySink {
+    stream: TcpStream,
+}
+
+impl TelemetrySink {
+    /// Connect to a dashboard listening at `addr`.
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<TelemetrySink> {
+        Ok(TelemetrySink { stream: TcpStream::connect(addr)? })
+    }
+
+    fn send(&mut self, offset: Duration, channel: Channel, payload: &[u8]) -> io::Result<()> {
+        self.stream.write_all(&(offset.as_nanos() as u64).to_le_bytes())?;
+        self.stream.write_all(&[channel as u8])?;
+        self.stream.write_all(&(payload.len() as u32).to_le_bytes())?;
+        self.stream.write_all(payload)?;
+
+        Ok(())
+    }
+
+    /// Stream one device [`Response`], timestamped relative to `t0`.
+    pub fn send_response(&mut self, t0: Instant, response: &Response) -> io::Result<()> {
+        let mut payload = vec![response.get_pin()];
+        match response.get_output() {
+            Signal::Digital(level) => {
+                payload.push(0);
+                payload.push(level as u8);
+            },
+            Signal::Analog(level) => {
+                payload.push(1);
+                payload.extend_from_slice(&level.to_le_bytes());
+            },
+        }
+
+        self.send(response.get_offset(t0), Channel::Response, &payload)
+    }
+
+    /// Stream one energy [`Sample`].
+    pub fn send_sample(&mut self, sample: &Sample) -> io::Result<()> {
+        let meter_id = sample.get_meter_id().as_bytes();
+
+        let mut payload = Vec::with_capacity(4 + meter_id.len() + 4);
+        payload.extend_from_slice(&(meter_id.len() as u32).to_le_bytes());
+        payload.extend_from_slice(meter_id);
+        payload.extend_from_slice(&sample.get_value().to_le_bytes());
+
+        self.send(sample.get_offset(), Channel::EnergySample, &payload)
+    }
+
+    /// Stream one [`SerialTrace`], timestamped relative to `t0`.
+    pub fn send_trace(&mut self, t0: Instant, trace: &SerialTrace) -> io::Result<()> {
+        self.send(trace.get_offset(t0), Channel::SerialTrace, trace.get_data())
+    }
+}
@@ -17,17 +17,29 @@ use rppal::gpio::{
 };
 use rppal::uart::{self, Uart};
 
+use crate::calibration::TimingCalibration;
 use crate::comm::Signal;
 use crate::criteria::{
     Criterion,
     GPIOCriterion,
 };
+use crate::decode::{FrameParser, IncrementalDecoder, ParseOutcome};
 use crate::facility::EnergyMetering;
-use crate::io::{DeviceInputs, DeviceOutputs, IOError};
-use crate::mem::MemoryTrace;
+use crate::firmware::{FirmwareError, FirmwareImage, FirmwareUpdater};
+use crate::io::{AnalogInputs, AnalogOutput, DeviceInputs, DeviceOutputs, IOError};
+use crate::mem::{parse_counter, MemoryTrace};
+use crate::telemetry::TelemetrySink;
+use crate::trace::SerialTrace;
 
 type Result<T> = std::result::Result<T, TestingError>;
 
+/// Default software PWM frequency used to approximate a DAC on a [`Signal::Analog`] drive pin.
+const DEFAULT_ANALOG_PWM_HZ: f64 = 1000.0;
+/// Resolution, in bits, that [`Signal::Analog`] levels are driven at.
+const ANALOG_DRIVE_RESOLUTION_BITS: u32 = 12;
+/// Default interval between samples taken by [`Test::analog_observe`].
+const DEFAULT_ANALOG_SAMPLE_INTERVAL: Duration = Duration::from_millis(10);
+
 /// Testing error.
 #[derive(Debug)]
 pub enum TestingError {
@@ -37,6 +49,10 @@ pub enum TestingError {
     IO(IOError),
     /// Energy meter does not exist.
     NoSuchMeter(String),
+    /// Analog input channel for the given device pin does not exist.
+    NoSuchAnalogChannel(u8),
+    /// Flashing or verifying firmware failed.
+    Firmware(FirmwareError),
     /// Invalid test protocol data received.
     Protocol,
     /// Reset requested when [`io::Mapping`] does not specify one.
@@ -51,6 +67,7 @@ impl error::Error for TestingError {
         match self {
             GPIO(ref e) => Some(e),
             IO(ref e) => Some(e),
+            Firmware(ref e) => Some(e),
             Protocol => None,
             Reset(ref e) => Some(e),
             UART(ref e) => Some(e),
@@ -65,6 +82,12 @@ impl From<IOError> for TestingError {
     }
 }
 
+impl From<FirmwareError> for TestingError {
+    fn from(e: FirmwareError) -> Self {
+        TestingError::Firmware(e)
+    }
+}
+
 impl From<gpio::Error> for TestingError {
     fn from(e: gpio::Error) -> Self {
         TestingError::GPIO(e)
@@ -84,6 +107,8 @@ impl Display for TestingError {
             GPIO(ref e) => write!(f, "GPIO error while testing: {}", e),
             IO(ref e) => write!(f, "I/O error: {}", e),
             NoSuchMeter(ref id) => write!(f, "the meter '{}' does not exist", id),
+            NoSuchAnalogChannel(pin_no) => write!(f, "no analog input channel for device pin {}", pin_no),
+            Firmware(ref e) => write!(f, "could not prepare device firmware: {}", e),
             Protocol => write!(f, "testbed/DUT test protocol mismatch"),
             Reset(ref e) => write!(f, "failed to reset device: {}", e),
             UART(ref e) => write!(f, "UART configuration error: {}", e),
@@ -91,13 +116,141 @@ impl Display for TestingError {
     }
 }
 
-/// An action that occurs as part of an operation.
+/// Error surfaced when [`MemoryTraceParser`] can't make sense of the buffered bytes -- either a
+/// malformed record or, formerly, the "should never happen on a well-formed stream" case that
+/// [`Test::memtrack`] used to `panic!` on.
+#[derive(Debug)]
+pub struct MemoryTraceParseError(String);
+
+impl Display for MemoryTraceParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Drives [`parse_counter`] as a [`FrameParser`] for [`IncrementalDecoder`], so
+/// [`Test::memtrack`] can decode [`MemoryTrace`]s off an arbitrarily-chunked byte stream. `time`
+/// is the [`Instant`] attached to every [`MemoryTrace`] decoded from the current buffer contents;
+/// the caller updates it between `feed` calls.
+struct MemoryTraceParser {
+    time: Instant,
+}
+
+impl FrameParser for MemoryTraceParser {
+    type Item = MemoryTrace;
+    type Error = MemoryTraceParseError;
+
+    fn parse(&mut self, data: &[u8]) -> std::result::Result<ParseOutcome<MemoryTrace>, MemoryTraceParseError> {
+        use nom::Err as NomError;
+
+        match parse_counter(data, self.time) {
+            Ok(((unparsed, _bit_offset), trace)) => Ok(ParseOutcome::Item {
+                item: trace,
+                consumed: data.len() - unparsed.len(),
+            }),
+            // Parser ran out of bytes in the middle of parsing; expected whenever a record
+            // straddles a read, so ask for at least one more byte and try again once it arrives.
+            Err(NomError::Incomplete(needed)) => Ok(ParseOutcome::Incomplete {
+                needed: match needed {
+                    nom::Needed::Size(n) => n.get(),
+                    nom::Needed::Unknown => 1,
+                },
+            }),
+            Err(NomError::Failure(parse_error)) | Err(NomError::Error(parse_error)) => {
+                Err(MemoryTraceParseError(format!("{:?}", parse_error)))
+            },
+        }
+    }
+}
+
+/// Bit order for a multi-bit burst [`Action`].
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BitOrder {
+    /// Most significant bit first.
+    MsbFirst,
+    /// Least significant bit first.
+    LsbFirst,
+}
+
+/// Pin/timing configuration for a bit-banged [`Action::Spi`] burst.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SpiBurst {
+    data_pin: u8,
+    clock_pin: u8,
+    bit_order: BitOrder,
+    clock_period: Duration,
+    data: Vec<u8>,
+}
+
+impl SpiBurst {
+    /** Describe a burst of `data` bytes driven on `data_pin`, bit order per `bit_order`, with
+    `clock_pin` toggling high then low once per bit (SPI mode 0: data is set up a half
+    `clock_period` before the rising edge).
+     */
+    pub fn new(data_pin: u8,
+              clock_pin: u8,
+              bit_order: BitOrder,
+              clock_period: Duration,
+              data: Vec<u8>) -> SpiBurst
+    {
+        SpiBurst { data_pin, clock_pin, bit_order, clock_period, data }
+    }
+}
+
+/// Pin/timing configuration for a bit-banged [`Action::I2c`] start/address/data/stop sequence.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct I2cTransaction {
+    sda_pin: u8,
+    scl_pin: u8,
+    clock_period: Duration,
+    address: u8,
+    data: Vec<u8>,
+}
+
+impl I2cTransaction {
+    /** Describe a start condition, a 7-bit `address` (write), `data` bytes, and a stop condition
+    on `sda_pin`/`scl_pin`, each bit clocked at `clock_period`. Each byte (including the address)
+    is followed by an ack clock pulse with `sda_pin` released high.
+     */
+    pub fn new(sda_pin: u8,
+              scl_pin: u8,
+              clock_period: Duration,
+              address: u8,
+              data: Vec<u8>) -> I2cTransaction
+    {
+        I2cTransaction { sda_pin, scl_pin, clock_period, address, data }
+    }
+}
+
+/// Pin/timing configuration for a bit-banged [`Action::Uart`] byte stream.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UartBurst {
+    tx_pin: u8,
+    baud: u32,
+    data: Vec<u8>,
+}
+
+impl UartBurst {
+    /// Describe `data` bytes bit-banged out `tx_pin` at `baud` (8N1: one low start bit, 8
+    /// LSB-first data bits, one high stop bit).
+    pub fn new(tx_pin: u8, baud: u32, data: Vec<u8>) -> UartBurst {
+        UartBurst { tx_pin, baud, data }
+    }
+}
+
+/// An action that occurs as part of an operation.
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Action {
     /// No-op
     Idle(Duration),
     /// Apply an input signal to a particular pin.
     Input(Signal, u8),
+    /// Clock a burst of bytes out a bit-banged SPI-style data/clock pin pair.
+    Spi(SpiBurst),
+    /// Drive a bit-banged I2C start/address/data/stop sequence.
+    I2c(I2cTransaction),
+    /// Bit-bang a byte stream out a single pin at a UART baud rate.
+    Uart(UartBurst),
 }
 
 impl Display for Action {
@@ -106,12 +259,18 @@ impl Display for Action {
         match self {
             Idle(d) => write!(f, "idle for {:?}", d),
             Input(signal, pin) => write!(f, "input {}, pin {}", signal, pin),
+            Spi(burst) => write!(f, "SPI burst of {} byte(s) on data pin {}, clock pin {}",
+                                 burst.data.len(), burst.data_pin, burst.clock_pin),
+            I2c(txn) => write!(f, "I2C transaction with {} data byte(s) to address {:#04x} on sda {}, scl {}",
+                               txn.data.len(), txn.address, txn.sda_pin, txn.scl_pin),
+            Uart(burst) => write!(f, "UART burst of {} byte(s) on pin {} at {} baud",
+                                  burst.data.len(), burst.tx_pin, burst.baud),
         }
     }
 }
 
 /// An input to perform at a specific time.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Operation {
     time: u64,
     action: Option<Action>,
@@ -145,11 +304,35 @@ impl Operation {
             ..self
         }
     }
+
+    /// Clock an [`SpiBurst`] out a bit-banged data/clock pin pair.
+    pub fn spi_burst(self, burst: SpiBurst) -> Self {
+        Self {
+            action: Some(Action::Spi(burst)),
+            ..self
+        }
+    }
+
+    /// Drive an [`I2cTransaction`] out a bit-banged sda/scl pin pair.
+    pub fn i2c_transaction(self, txn: I2cTransaction) -> Self {
+        Self {
+            action: Some(Action::I2c(txn)),
+            ..self
+        }
+    }
+
+    /// Bit-bang a [`UartBurst`] out a single pin.
+    pub fn uart_burst(self, burst: UartBurst) -> Self {
+        Self {
+            action: Some(Action::Uart(burst)),
+            ..self
+        }
+    }
 }
 
 impl Display for Operation {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let action_text = if let Some(action) = self.action {
+        let action_text = if let Some(ref action) = self.action {
             format!("{}", action)
         } else {
             "None".to_string()
@@ -206,6 +389,15 @@ impl Response {
         }
     }
 
+    /** Like [`Response::get_offset`], but subtracts this pin's [`TimingCalibration`] correction.
+
+    Useful when `calibration` wasn't available to [`Test::observe`] at the time this `Response`
+    was recorded, and the correction needs to be applied after the fact.
+     */
+    pub fn get_corrected_offset(&self, t0: Instant, calibration: &TimingCalibration) -> Duration {
+        self.get_offset(t0).saturating_sub(calibration.correction_for(self.pin_no))
+    }
+
     /// Returns the pin number the response occurred on.
     pub fn get_pin(&self) -> u8 {
         self.pin_no
@@ -228,6 +420,36 @@ impl Response {
     }
 }
 
+/// A single energy meter reading, timestamped relative to when metering for the test began.
+#[derive(Clone, Debug)]
+pub struct Sample {
+    meter_id: String,
+    value: f32,
+    t_offset: Duration,
+}
+
+impl Sample {
+    /// Create a new `Sample`.
+    pub fn new(meter_id: String, value: f32, t_offset: Duration) -> Sample {
+        Sample { meter_id, value, t_offset }
+    }
+
+    /// Returns the id of the meter this reading came from.
+    pub fn get_meter_id(&self) -> &str {
+        &self.meter_id
+    }
+
+    /// Returns the reading itself.
+    pub fn get_value(&self) -> f32 {
+        self.value
+    }
+
+    /// Returns how long after metering began this reading was taken.
+    pub fn get_offset(&self) -> Duration {
+        self.t_offset
+    }
+}
+
 impl Display for Response {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "response on P{:02} {}", self.pin_no, self.output)
@@ -278,6 +500,10 @@ pub struct Test {
     criteria: Vec<Criterion>,
     tail_duration: Option<Duration>,
     reset_device: bool,
+    ignored: bool,
+    should_panic: bool,
+    analog_pwm_hz: f64,
+    analog_sample_interval: Duration,
 }
 
 impl Test {
@@ -298,10 +524,44 @@ impl Test {
             id: id.to_string(),
             app_ids: app_id.into_iter().map(|id| id.to_string()).collect(),
             trace_points: trace_points.into_iter().map(|tp| tp.to_string()).collect(),
-            actions: ops.into_iter().map(|x| Reverse(*x)).collect(),
+            actions: ops.into_iter().map(|x| Reverse(x.clone())).collect(),
             criteria: criteria.into_iter().cloned().collect(),
             tail_duration: Some(Duration::from_millis(5)),
             reset_device,
+            ignored: false,
+            should_panic: false,
+            analog_pwm_hz: DEFAULT_ANALOG_PWM_HZ,
+            analog_sample_interval: DEFAULT_ANALOG_SAMPLE_INTERVAL,
+        }
+    }
+
+    /// Mark the test as ignored; it's skipped by [`Testbed::execute`](crate::testbed::Testbed::execute)
+    /// unless the caller's `RunOptions` explicitly asks for ignored tests to run.
+    pub fn with_ignored(mut self, ignored: bool) -> Test {
+        self.ignored = ignored;
+        self
+    }
+
+    /// Mark the test as expected to fail: a failing `exec_result` is recorded as a pass, and an
+    /// unexpected success is recorded as a failure.
+    pub fn with_should_panic(mut self, should_panic: bool) -> Test {
+        self.should_panic = should_panic;
+        self
+    }
+
+    /// Set the frequency of the software PWM waveform used to drive [`Signal::Analog`] levels.
+    pub fn with_analog_pwm_frequency(self, analog_pwm_hz: f64) -> Test {
+        Self {
+            analog_pwm_hz,
+            ..self
+        }
+    }
+
+    /// Set the interval between samples taken by [`Test::analog_observe`].
+    pub fn with_analog_sample_interval(self, analog_sample_interval: Duration) -> Test {
+        Self {
+            analog_sample_interval,
+            ..self
         }
     }
 
@@ -310,6 +570,16 @@ impl Test {
         &self.id
     }
 
+    /// Returns true if the test is ignored by default.
+    pub fn is_ignored(&self) -> bool {
+        self.ignored
+    }
+
+    /// Returns true if the test is expected to fail.
+    pub fn should_panic(&self) -> bool {
+        self.should_panic
+    }
+
     /// Returns the identifiers of the applications the test exercises.
     pub fn get_app_ids(&self) -> &HashSet<String> {
         &self.app_ids
@@ -330,25 +600,60 @@ impl Test {
         self.reset_device
     }
 
-    /// Drive test outputs (inputs to the device).
+    /** Flash firmware for this test's `app_ids` before `execute` runs.
+
+    For each of [`Test::get_app_ids`] that `images` has an entry for, drives `updater` through
+    [`FirmwareUpdater::write_firmware`] (erase, page-by-page write, then verify) before returning.
+    An `app_id` missing from `images` is left untouched -- it's assumed to already be on the
+    device, or to not need fresh firmware for this run. A flash or verification failure is
+    returned as [`TestingError::Firmware`] without touching any I/O pins, so a bad image fails the
+    run before the device is ever driven.
+     */
+    pub fn flash_firmware(&self,
+                          images: &HashMap<String, FirmwareImage>,
+                          updater: &mut dyn FirmwareUpdater) -> Result<()>
+    {
+        for app_id in &self.app_ids {
+            if let Some(image) = images.get(app_id) {
+                updater.write_firmware(image)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /** Drive test outputs (inputs to the device).
+
+    [`Action::Spi`], [`Action::I2c`], and [`Action::Uart`] don't correspond to a single GPIO
+    transition: each lowers to its own precisely-timed sequence of digital pin changes (see
+    [`Test::drive_spi_burst`], [`Test::drive_i2c_transaction`], [`Test::drive_uart_burst`]),
+    driven inline, relative to the `Operation`'s scheduled time `t`.
+     */
     pub fn execute(&self, t0: Instant, pins: &mut DeviceInputs) -> Result<Execution> {
         let timeline = self.actions.iter()
             .map(|Reverse(op)| (t0 + Duration::from_millis(op.time), op));
         for (t, op) in timeline {
             while Instant::now() < t {  } // spin wait?
 
-            if let Some(action) = op.action {
+            if let Some(ref action) = op.action {
                 match action {
                     Action::Idle(wait_length) => {
-                        let t_until = t + wait_length;
+                        let t_until = t + *wait_length;
                         while Instant::now() < t_until {  } // spin wait?
                     },
 
                     Action::Input(signal, pin_no) => match signal {
-                        Signal::Digital(true) => (*pins.get_pin_mut(pin_no)?).set_high(),
-                        Signal::Digital(false) => (*pins.get_pin_mut(pin_no)?).set_low(),
-                        input => panic!("Unhandled input type: {:?}", input),
+                        Signal::Digital(true) => (*pins.get_pin_mut(*pin_no)?).set_high(),
+                        Signal::Digital(false) => (*pins.get_pin_mut(*pin_no)?).set_low(),
+                        Signal::Analog(level) => {
+                            pins.get_pin_mut(*pin_no)?
+                                .set_level(*level, ANALOG_DRIVE_RESOLUTION_BITS, self.analog_pwm_hz)?;
+                        },
                     },
+
+                    Action::Spi(burst) => Self::drive_spi_burst(pins, t, burst)?,
+                    Action::I2c(txn) => Self::drive_i2c_transaction(pins, t, txn)?,
+                    Action::Uart(burst) => Self::drive_uart_burst(pins, t, burst)?,
                 };
             }
         }
@@ -356,6 +661,141 @@ impl Test {
         Ok(Execution::new(t0, Instant::now()))
     }
 
+    /// Wait, spinning, until `deadline`.
+    fn wait_until(deadline: Instant) {
+        while Instant::now() < deadline {  } // spin wait?
+    }
+
+    /// Drive `pin_no` to `level`.
+    fn drive(pins: &mut DeviceInputs, pin_no: u8, level: bool) -> Result<()> {
+        if level {
+            (*pins.get_pin_mut(pin_no)?).set_high();
+        } else {
+            (*pins.get_pin_mut(pin_no)?).set_low();
+        }
+
+        Ok(())
+    }
+
+    /// Lower an [`SpiBurst`] into timed transitions on its data and clock pins, starting at `t`.
+    fn drive_spi_burst(pins: &mut DeviceInputs, mut t: Instant, burst: &SpiBurst) -> Result<()> {
+        let half_period = burst.clock_period / 2;
+
+        for &byte in &burst.data {
+            let bit_indices: Box<dyn Iterator<Item = u8>> = match burst.bit_order {
+                BitOrder::MsbFirst => Box::new((0..8).rev()),
+                BitOrder::LsbFirst => Box::new(0..8),
+            };
+
+            for i in bit_indices {
+                Self::drive(pins, burst.data_pin, (byte >> i) & 1 == 1)?;
+                t += half_period;
+                Self::wait_until(t);
+
+                Self::drive(pins, burst.clock_pin, true)?;
+                t += half_period;
+                Self::wait_until(t);
+
+                Self::drive(pins, burst.clock_pin, false)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Lower an [`I2cTransaction`] into a timed start/address/data/stop sequence on its sda and
+    /// scl pins, starting at `t`.
+    fn drive_i2c_transaction(pins: &mut DeviceInputs, mut t: Instant, txn: &I2cTransaction) -> Result<()> {
+        let half_period = txn.clock_period / 2;
+
+        Self::drive(pins, txn.sda_pin, true)?;
+        Self::drive(pins, txn.scl_pin, true)?;
+        t += half_period;
+        Self::wait_until(t);
+
+        // Start condition: SDA falls while SCL is high.
+        Self::drive(pins, txn.sda_pin, false)?;
+        t += half_period;
+        Self::wait_until(t);
+        Self::drive(pins, txn.scl_pin, false)?;
+
+        let address_byte = txn.address << 1; // 7-bit address, write (R/W bit clear).
+        t = Self::drive_i2c_byte(pins, t, txn.sda_pin, txn.scl_pin, half_period, address_byte)?;
+        for byte in &txn.data {
+            t = Self::drive_i2c_byte(pins, t, txn.sda_pin, txn.scl_pin, half_period, *byte)?;
+        }
+
+        // Stop condition: SDA rises while SCL is high.
+        Self::drive(pins, txn.sda_pin, false)?;
+        Self::drive(pins, txn.scl_pin, true)?;
+        t += half_period;
+        Self::wait_until(t);
+        Self::drive(pins, txn.sda_pin, true)?;
+
+        Ok(())
+    }
+
+    /// Clock one MSB-first byte out `sda_pin`, followed by an ack slot with `sda_pin` released
+    /// high. Returns the time the sequence finished at, for the next byte to continue from.
+    fn drive_i2c_byte(pins: &mut DeviceInputs,
+                      mut t: Instant,
+                      sda_pin: u8,
+                      scl_pin: u8,
+                      half_period: Duration,
+                      byte: u8) -> Result<Instant>
+    {
+        for i in (0..8).rev() {
+            Self::drive(pins, sda_pin, (byte >> i) & 1 == 1)?;
+            t += half_period;
+            Self::wait_until(t);
+
+            Self::drive(pins, scl_pin, true)?;
+            t += half_period;
+            Self::wait_until(t);
+
+            Self::drive(pins, scl_pin, false)?;
+        }
+
+        Self::drive(pins, sda_pin, true)?;
+        t += half_period;
+        Self::wait_until(t);
+
+        Self::drive(pins, scl_pin, true)?;
+        t += half_period;
+        Self::wait_until(t);
+
+        Self::drive(pins, scl_pin, false)?;
+
+        Ok(t)
+    }
+
+    /// Lower a [`UartBurst`] into a timed 8N1 bit stream on its tx pin, starting at `t`.
+    fn drive_uart_burst(pins: &mut DeviceInputs, mut t: Instant, burst: &UartBurst) -> Result<()> {
+        let bit_period = Duration::from_secs_f64(1.0 / burst.baud as f64);
+
+        Self::drive(pins, burst.tx_pin, true)?; // idle high
+
+        for &byte in &burst.data {
+            // Start bit.
+            Self::drive(pins, burst.tx_pin, false)?;
+            t += bit_period;
+            Self::wait_until(t);
+
+            for i in 0..8 {
+                Self::drive(pins, burst.tx_pin, (byte >> i) & 1 == 1)?; // LSB first
+                t += bit_period;
+                Self::wait_until(t);
+            }
+
+            // Stop bit.
+            Self::drive(pins, burst.tx_pin, true)?;
+            t += bit_period;
+            Self::wait_until(t);
+        }
+
+        Ok(())
+    }
+
     /// Set up to record test inputs.
     pub fn prep_observe(&self,
                         pins: &mut DeviceOutputs) -> Result<Vec<u8>>
@@ -384,14 +824,25 @@ impl Test {
         Ok(interrupt_pins)
     }
 
-    /// Record test inputs (outputs from the device).
-    ///
-    /// Watches for responses from the device under test for a slightly longer duration than the duration of the test.
-    /// This is done to catch any straggling responses from the device.
+    /** Record test inputs (outputs from the device).
+
+    Watches for responses from the device under test for a slightly longer duration than the
+    duration of the test. This is done to catch any straggling responses from the device.
+
+    When `sink` is given, each [`Response`] is also streamed to it as soon as it's recorded,
+    alongside being pushed onto `out` as usual; a failed send is logged and otherwise ignored, so a
+    dashboard dropping its connection never interrupts the test itself.
+
+    When `calibration` is given, each recorded `Response`'s timestamp is corrected by the
+    [`TimingCalibration`] measured for its pin before it's streamed or pushed onto `out`, so
+    downstream [`Response::get_offset`] calls don't need to know about calibration at all.
+     */
     pub fn observe(&self,
                    t0: Instant,
                    pins: &Vec<&InputPin>,
-                   out: &mut Vec<Response>) -> Result<()>
+                   out: &mut Vec<Response>,
+                   mut sink: Option<&mut TelemetrySink>,
+                   calibration: Option<&TimingCalibration>) -> Result<()>
     {
         let gpio = Gpio::new()?;
         let t_end = t0 + self.max_runtime();
@@ -404,16 +855,88 @@ impl Test {
                 Some(t_end - t))?;
 
             if let Some((pin, level)) = poll {
-                let response = Response::new(
+                let mut response = Response::new(
                     Instant::now(),
                     pin.pin(),
                     match level {
                         Level::High => Signal::Digital(true),
                         Level::Low => Signal::Digital(false),
                     });
+
+                if let Some(calibration) = calibration {
+                    let correction = calibration.correction_for(response.pin_no);
+                    response.time = response.time.checked_sub(correction).unwrap_or(response.time);
+                }
+
+                if let Some(ref mut sink) = sink {
+                    if let Err(e) = sink.send_response(t0, &response) {
+                        println!("observe: telemetry send failed: {}", e);
+                    }
+                }
+
+                out.push(response);
+            }
+
+            t = Instant::now();
+        }
+
+        Ok(())
+    }
+
+    /** Perform analog sampling.
+
+    Watches the analog input channels named by this test's [`GPIOCriterion::Analog`] criteria for
+    the duration of the test, recording one [`Response`] (carrying [`Signal::Analog`]) per sample
+    taken at `self.analog_sample_interval`. `inputs` must contain a channel for every such pin.
+
+    When `sink` is given, each `Response` is also streamed to it as soon as it's recorded; see
+    [`Test::observe`] for how send failures are handled.
+
+    When `calibration` is given, each `Response`'s timestamp is corrected before being streamed or
+    pushed onto `out`; see [`Test::observe`] for details.
+     */
+    pub fn analog_observe(&self,
+                          t0: Instant,
+                          inputs: &mut AnalogInputs,
+                          out: &mut Vec<Response>,
+                          mut sink: Option<&mut TelemetrySink>,
+                          calibration: Option<&TimingCalibration>) -> Result<()>
+    {
+        let tracked_pins: Vec<u8> = self.criteria.iter()
+            .filter_map(|criterion| {
+                if let Criterion::GPIO(GPIOCriterion::Analog(pin_no)) = criterion {
+                    Some(*pin_no)
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        let t_end = t0 + self.max_runtime();
+        let mut t = Instant::now();
+
+        while t < t_end {
+            for pin_no in &tracked_pins {
+                let input = inputs.get_mut(pin_no)
+                    .ok_or(TestingError::NoSuchAnalogChannel(*pin_no))?;
+                let level = input.sample()?;
+                let mut response = Response::new(Instant::now(), *pin_no, Signal::Analog(level));
+
+                if let Some(calibration) = calibration {
+                    let correction = calibration.correction_for(response.pin_no);
+                    response.time = response.time.checked_sub(correction).unwrap_or(response.time);
+                }
+
+                if let Some(ref mut sink) = sink {
+                    if let Err(e) = sink.send_response(t0, &response) {
+                        println!("analog_observe: telemetry send failed: {}", e);
+                    }
+                }
+
                 out.push(response);
             }
 
+            std::thread::sleep(self.analog_sample_interval);
             t = Instant::now();
         }
 
@@ -427,7 +950,7 @@ impl Test {
     /// [`Test::meter`] should be called when running the test.
     pub fn prep_meter(&self,
                       meters: &HashMap<String, Box<dyn EnergyMetering>>,
-                      out: &mut HashMap<String, Vec<(Instant, f32)>>,
+                      out: &mut HashMap<String, Vec<Sample>>,
     ) -> Result<bool> {
         // only care about meters defined in the criteria
         out.clear();
@@ -455,12 +978,16 @@ impl Test {
         Ok(has_energy_criteria)
     }
 
-    /// Perform energy metering.
-    ///
-    /// The `out` parameter should be the same `out` passed to [`Test::prep_meter`].
+    /** Perform energy metering.
+
+    The `out` parameter should be the same `out` passed to [`Test::prep_meter`]. When `sink` is
+    given, each [`Sample`] is also streamed to it as soon as it's taken; see [`Test::observe`] for
+    how send failures are handled.
+     */
     pub fn meter(&self,
                  meters: &HashMap<String, Box<dyn EnergyMetering>>,
-                 out: &mut HashMap<String, Vec<(Instant, f32)>>)
+                 out: &mut HashMap<String, Vec<Sample>>,
+                 mut sink: Option<&mut TelemetrySink>)
     {
         let start = Instant::now();
         let runtime = self.max_runtime();
@@ -487,7 +1014,15 @@ impl Test {
                 // if p > 97.0 { continue; }
                 // ra = (ra * 0.99) + (p * 0.01);
                 // buf.push((now, if buf.len() > 500 { ra } else { p }));
-                buf.push((now, p));
+                let sample = Sample::new(id.clone(), p, d_test);
+
+                if let Some(ref mut sink) = sink {
+                    if let Err(e) = sink.send_sample(&sample) {
+                        println!("meter: telemetry send failed: {}", e);
+                    }
+                }
+
+                buf.push(sample);
             }
         }
     }
@@ -513,11 +1048,23 @@ impl Test {
         Ok(())
     }
 
-    /// Perform the tracing specified by the test.
+    /** Perform the tracing specified by the test.
+
+    When `sink` is given, every chunk read over `uart` is also streamed to it as a [`SerialTrace`]
+    timestamped relative to `t0`, as soon as it's read; see [`Test::observe`] for how send failures
+    are handled.
+
+    Unlike [`Test::memtrack`], this isn't built on [`IncrementalDecoder`]: it frames UART bursts by
+    idle-line timing rather than by parsing a record grammar out of their bytes, so there's no
+    [`FrameParser`] to hand it -- each read is already a complete, self-contained unit as far as
+    this method is concerned.
+     */
     pub fn trace(&self,
                  uart: &mut Uart,
                  buffer: &mut Vec<u8>,
-                 schedule: &mut Vec<(Instant, usize)>) -> Result<usize> {
+                 schedule: &mut Vec<(Instant, usize)>,
+                 t0: Instant,
+                 mut sink: Option<&mut TelemetrySink>) -> Result<usize> {
         let buffer: &mut [u8] = buffer.as_mut_slice();
         let mut bytes_read: usize = 0;
 
@@ -530,6 +1077,13 @@ impl Test {
 
             let read = uart.read(&mut buffer[bytes_read..])?;
             if read > 0 {
+                if let Some(ref mut sink) = sink {
+                    let chunk = SerialTrace::new(now, &buffer[bytes_read..bytes_read + read]);
+                    if let Err(e) = sink.send_trace(t0, &chunk) {
+                        println!("trace: telemetry send failed: {}", e);
+                    }
+                }
+
                 bytes_read += read;
                 schedule.push((now, read));
             }
@@ -570,82 +1124,37 @@ impl Test {
         let max_runtime = self.max_runtime();
         let start = Instant::now();
 
-        let mut buffered_now = start;
-        let mut bytes_parsed = 0;
-
-        /* Strategy:
-        Read bytes received over UART into buffer.
-        Upon reception of data, always note the Instant the data is received.
-        Then, try to parse the data into one or more StreamOperations.
-        If successful, place the StreamOperation into the schedule vector
-        along with the noted time of reception of the first byte.
-        Repeat this process until the buffer is fully parsed
-        or this strategy yields no more StreamOperations.
-        If there is no data remaining in the buffer, then the noted Instant is considered as 'expired'.
-        It will not be used for the next sequence of data received.
-        If there is data remaining in the buffer, there is a StreamOperation that hasn't finished traversing the wire
-        and we must hold the prior noted Instant to attach to this incoming StreamOperation.
-         */
+        let mut decoder = IncrementalDecoder::new();
+        let mut parser = MemoryTraceParser { time: start };
 
         loop {
             let now = Instant::now();
             if now - start >= max_runtime { break; }
 
-            // Check if we still have data we must parse.
-            // If we do, we cannot discard the buffered Instant yet
-            // because we still have data received around that time.
-            //
-            // Not updating the buffered Instant does mean that data
-            // can appear to arrive earlier than it really did.
-            // This is a drawback of parsing these on-demand instead of
-            // afterwards.
-            if bytes_parsed < bytes_read {
-                buffered_now = now;
+            // If a record is still straddling the buffer (one we haven't finished decoding yet),
+            // we can't discard the noted Instant yet -- it's still the arrival time the straddling
+            // bytes should be stamped with. Once everything buffered so far has been decoded, the
+            // noted Instant is 'expired' and safe to move forward to whenever the next bytes show up.
+            if decoder.pending() == 0 {
+                parser.time = now;
             }
 
             let read = uart.read(&mut buffer[bytes_read..])?;
             if read > 0 {
+                let chunk = &buffer[bytes_read..bytes_read + read];
                 bytes_read += read;
 
-                // Try to parse the stream operations.
-                while bytes_parsed < bytes_read {
-                    use crate::mem::parse_counter as parse_mem_counter;
-                    use nom::Err as NomError;
-
-                    // The data that needs parsing.
-                    let to_parse = &buffer[bytes_parsed..bytes_read];
-                    match parse_mem_counter(to_parse, buffered_now) {
-                        // Parser successfully read a stream operation.
-                        // We advance our bytes_parsed marker forward by the number of bytes we parsed.
-                        Ok(((unparsed, _bit_offset), op)) => {
-                            schedule.push(op);
-                            bytes_parsed += to_parse.len() - unparsed.len();
-                        },
-                        Err(ref nom_error) => match nom_error {
-                            // Parser ran out of bytes in the middle of parsing.
-                            // This is fine and expected to happen at times.
-                            // We break out of this loop and try to read more data from UART.
-                            NomError::Incomplete(_need) => break,
-                            // Parser tried to parse data and it didn't understand.
-                            // We can't recover from this at this level (yet).
-                            NomError::Failure(_parse_error) => return Err(TestingError::Protocol),
-                            // Parser should not return an Error to us.
-                            NomError::Error(parse_error) => {
-                                let mut msg: String = format!("Temporary parser error surfaced.\nThis is a bug. Check byte offset {}. Buffer:\n",
-                                                              bytes_parsed);
-                                for (col, byte) in (0..8).cycle().zip(&buffer[0..bytes_read]) {
-                                    msg.push_str(&format!("{:#04X}{}", byte, if col == 7 { '\n' } else { ' ' }));
-                                }
-                                panic!("{}\nError: {:?}", msg, parse_error);
-                            }
-                        }
-                    };
-                }
+                let traces = decoder.feed(chunk, &mut parser)
+                    .map_err(|e| {
+                        println!("memtrack: {}", e);
+                        TestingError::Protocol
+                    })?;
+                schedule.extend(traces);
             }
         }
 
-        println!("memtrack: bytes rx: {}, bytes parsed: {}", bytes_read, bytes_parsed);
-        Ok(bytes_read - bytes_parsed)
+        println!("memtrack: bytes rx: {}, bytes parsed: {}", bytes_read, decoder.offset());
+        Ok(decoder.pending())
     }
 
     /// Return the maximum length of time the test can run.
@@ -4,23 +4,26 @@ use std::collections::HashMap;
 use std::error;
 use std::fmt;
 use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc;
 use std::sync::mpsc::SyncSender;
 use std::sync::{Arc,
-                Barrier,
                 Mutex,
                 RwLock};
 use std::thread;
 use std::thread::JoinHandle;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
+use crossbeam_channel::{Select, Sender as CommandSender, Receiver as AckReceiver};
+
+use crate::evaluation::{Evaluation, Evaluator};
 use crate::facility::EnergyMetering;
-use crate::io::{IOError, Mapping, UART};
+use crate::io::{IOError, Mapping, UartConfig, UART};
 use crate::mem::MemoryTrace;
 use crate::output::DataWriter;
 use crate::sw::{self, PlatformSupport};
 use crate::sw::instrument::Spec;
-use crate::test::{Execution, Response, Test, TestingError};
+use crate::test::{Execution, Response, Sample, Test, TestingError};
 use crate::trace;
 use crate::trace::{TraceData, TraceKind, SerialTrace};
 
@@ -32,10 +35,20 @@ type Result<T> = std::result::Result<T, TestbedError>;
 pub enum TestbedError {
     /// A problem occured while executing a test.
     Execution(TestingError),
+    /// The observer thread failed to set up or run its watch for a test.
+    Observer(TestingError),
+    /// The metering thread failed to set up or run metering for a test.
+    Metering(TestingError),
     /// A problem occured while performing a reset operation on the device.
     Reset(IOError),
+    /// A worker didn't ack a coordination phase within its allotted time -- most likely hung
+    /// waiting on the DUT.
+    Timeout(String),
     /// A problem occured while interacting with software ([`sw::error::Error`]).
     Software(sw::error::SoftwareError),
+    /// The test was never run -- it's marked ignored and the run's [`RunOptions`] didn't ask for
+    /// it. Not a real error; just the reason there's no [`Execution`] to report.
+    Skipped,
 }
 
 impl error::Error for TestbedError {
@@ -43,8 +56,12 @@ impl error::Error for TestbedError {
         use TestbedError::*;
         match self {
             Execution(ref e) => Some(e),
+            Observer(ref e) => Some(e),
+            Metering(ref e) => Some(e),
             Reset(ref e) => Some(e),
             Software(ref e) => Some(e),
+            Timeout(_) => None,
+            Skipped => None,
         }
     }
 }
@@ -66,10 +83,224 @@ impl Display for TestbedError {
         use TestbedError::*;
         match self {
             Execution(ref e) => write!(f, "test execution error: {}", e),
+            Observer(ref e) => write!(f, "observer thread failed: {}", e),
+            Metering(ref e) => write!(f, "metering thread failed: {}", e),
             Reset(ref e) => write!(f, "failed to reset device: {}", e),
             Software(ref e) => write!(f, "software interaction error: {}", e),
+            Timeout(ref worker) => write!(f, "worker '{}' did not ack within its phase timeout", worker),
+            Skipped => write!(f, "test was not run (ignored)"),
+        }
+    }
+}
+
+/** Selection options for [`Testbed::execute`], mirroring the filter/run-ignored knobs of a
+conventional test harness.
+
+`Test` itself carries the per-test `ignored`/`should_panic` declarations (see
+[`Test::with_ignored`] and [`Test::with_should_panic`]); `RunOptions` is just what the caller
+passes in to say which of those apply for this run.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct RunOptions {
+    filter: Option<String>,
+    include_ignored: bool,
+    cancellation: Option<CancellationToken>,
+}
+
+impl RunOptions {
+    /// Run every non-ignored test, unfiltered.
+    pub fn new() -> RunOptions {
+        RunOptions::default()
+    }
+
+    /// Only run tests whose id contains `filter`.
+    pub fn with_filter(self, filter: String) -> Self {
+        Self { filter: Some(filter), ..self }
+    }
+
+    /// Also run tests marked ignored via [`Test::with_ignored`].
+    pub fn with_ignored(self, include_ignored: bool) -> Self {
+        Self { include_ignored, ..self }
+    }
+
+    /// Let the run be stopped early via `token` -- e.g. from a SIGINT handler installed by the
+    /// caller. [`Testbed::execute`] checks it between tests, and the worker threads check it at
+    /// their coordination points, so a cancelled run winds down instead of running to completion.
+    pub fn with_cancellation(self, token: CancellationToken) -> Self {
+        Self { cancellation: Some(token), ..self }
+    }
+
+    /// Returns true if `test` is part of this run based on the filter alone -- an ignored test
+    /// still passes this check; [`Testbed::execute`] is what skips running it.
+    fn selects(&self, test: &Test) -> bool {
+        self.filter.as_ref().map_or(true, |f| test.get_id().contains(f.as_str()))
+    }
+
+    /// Returns true if this run has been asked to stop.
+    fn is_cancelled(&self) -> bool {
+        self.cancellation.as_ref().map_or(false, CancellationToken::is_cancelled)
+    }
+
+    /// The token to hand down to worker threads -- `options.cancellation` if the caller gave one,
+    /// or a token of our own that will just never fire.
+    fn cancellation_token(&self) -> CancellationToken {
+        self.cancellation.clone().unwrap_or_default()
+    }
+}
+
+/** A shared flag a caller can use to ask a running [`Testbed::execute`] to stop early, e.g. from a
+SIGINT handler.
+
+Cloning shares the same underlying flag: keep one handle to call [`CancellationToken::cancel`] on
+(from wherever the interrupt is noticed) and hand another, via [`RunOptions::with_cancellation`],
+to the `execute` call it should stop.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Create a token that starts out not cancelled.
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Ask the run using this token to stop as soon as it safely can.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns true if [`CancellationToken::cancel`] has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// Default per-phase ack timeout; see [`Testbed::with_phase_timeout`].
+const DEFAULT_PHASE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/** A coordination point every worker thread passes through in lockstep with the executor, in place
+of the old fixed-party [`std::sync::Barrier`] rendezvous.
+
+Sent by [`Testbed::execute`] via each worker's command channel; a worker acks on its paired result
+channel (carrying setup/teardown errors, same as before) once it has acted on the command. This
+makes the number of workers dynamic -- the executor just sends to however many command channels it
+holds, rather than a barrier count baked in at construction.
+ */
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum WorkerCommand {
+    /// Get set up for the test now in `current_test`, or exit if there isn't one.
+    Ready,
+    /// The DUT is about to run; begin watching/metering/tracing now.
+    Begin,
+    /// The DUT has finished running; stop and report what was collected.
+    Finish,
+    /// No more tests are coming.
+    Stop,
+}
+
+/// A worker thread's command/ack handles, as seen by [`Testbed::execute`].
+struct Worker {
+    name: String,
+    cmd_tx: CommandSender<WorkerCommand>,
+    ack_rx: AckReceiver<Result<()>>,
+    handle: JoinHandle<()>,
+}
+
+/** Send `cmd` to every worker in `workers`, then wait up to `timeout` for each one's ack.
+
+A worker that doesn't ack within `timeout` is reported as [`TestbedError::Timeout`] for that phase
+rather than blocking the rest of the suite -- unlike a `Barrier`, one hung or panicked worker can't
+wedge the others. Results are returned in the same order as `workers`.
+ */
+fn run_phase(workers: &[&Worker], cmd: WorkerCommand, timeout: Duration) -> Vec<Result<()>> {
+    for worker in workers {
+        // A worker that's genuinely stuck (e.g. blocked in a blocking rppal call) won't see this
+        // until it unblocks; that's a true hang we can only detect, not pre-empt, via the timeout
+        // on the recv below.
+        let _ = worker.cmd_tx.send(cmd);
+    }
+
+    let mut outcomes: Vec<Option<Result<()>>> = vec![None; workers.len()];
+    let deadline = Instant::now() + timeout;
+
+    while outcomes.iter().any(Option::is_none) {
+        let pending: Vec<usize> = outcomes.iter()
+            .enumerate()
+            .filter(|(_i, outcome)| outcome.is_none())
+            .map(|(i, _outcome)| i)
+            .collect();
+
+        let mut select = Select::new();
+        for &i in &pending {
+            select.recv(&workers[i].ack_rx);
+        }
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match select.select_timeout(remaining) {
+            Ok(op) => {
+                let worker_index = pending[op.index()];
+                let result = op.recv(&workers[worker_index].ack_rx)
+                    .unwrap_or_else(|_| Err(TestbedError::Timeout(workers[worker_index].name.clone())));
+                outcomes[worker_index] = Some(result);
+            },
+            Err(_timed_out) => {
+                for &i in &pending {
+                    outcomes[i] = Some(Err(TestbedError::Timeout(workers[i].name.clone())));
+                }
+            },
         }
     }
+
+    outcomes.into_iter()
+        .map(|outcome| outcome.unwrap())
+        .collect()
+}
+
+/// Fixed slots in the `workers`/`alive` vectors [`Testbed::execute`] builds; the user-defined
+/// tracing threads follow starting at [`WORKER_TRACING_KIND_BASE`].
+const WORKER_OBSERVER: usize = 0;
+const WORKER_METERING: usize = 1;
+const WORKER_TRACING: usize = 2;
+const WORKER_MEMSTAT: usize = 3;
+const WORKER_TRACING_KIND_BASE: usize = 4;
+
+/** Run one coordination phase for every still-[alive](Vec<bool>) worker in `workers`, folding the
+results into a single `Option<TestbedError>` -- the first error seen, if any.
+
+A worker that times out is marked dead in `alive` so later calls stop waiting on it; it keeps its
+slot in `workers` (indices must stay stable), it's just skipped from here on. This is the one place
+that decides a worker has dropped out of the suite for good.
+ */
+fn run_test_phase(
+    workers: &[Worker],
+    alive: &mut [bool],
+    cmd: WorkerCommand,
+    timeout: Duration,
+) -> Option<TestbedError> {
+    let live_indices: Vec<usize> = (0..workers.len())
+        .filter(|&i| alive[i])
+        .collect();
+    let live_workers: Vec<&Worker> = live_indices.iter()
+        .map(|&i| &workers[i])
+        .collect();
+
+    let results = run_phase(&live_workers, cmd, timeout);
+
+    let mut first_error = None;
+    for (&i, result) in live_indices.iter().zip(results.into_iter()) {
+        if let Err(e) = result {
+            if matches!(e, TestbedError::Timeout(_)) {
+                alive[i] = false;
+            }
+            if first_error.is_none() {
+                first_error = Some(e);
+            }
+        }
+    }
+
+    first_error
 }
 
 /// Test suite executor
@@ -82,6 +313,7 @@ pub struct Testbed {
     memory_uart: Option<UART>,
     tracing: Vec<(TraceKind, UART)>,
     data_writer: Option<Box<dyn DataWriter>>,
+    phase_timeout: Duration,
 }
 
 impl Testbed {
@@ -103,89 +335,152 @@ impl Testbed {
             memory_uart,
             tracing,
             data_writer: None,
+            phase_timeout: DEFAULT_PHASE_TIMEOUT,
         }
     }
 
+    /// Set how long [`Testbed::execute`] waits for a worker to ack a coordination phase before
+    /// treating it as timed out. Defaults to 30s.
+    pub fn with_phase_timeout(mut self, phase_timeout: Duration) -> Testbed {
+        self.phase_timeout = phase_timeout;
+        self
+    }
+
     /// Define a write for testing data.
     pub fn save_results_with(&mut self, formatter: Box<dyn DataWriter>) {
         self.data_writer = Some(formatter);
     }
 
-    /** Run tests.
+    /** Run tests, collecting every [`Observation`] into a `Vec` once the whole suite finishes.
 
-    Execute the given tests one after the other.
+    A thin wrapper over [`Testbed::execute_streaming`] for callers that don't need results as they
+    arrive -- see that method for the actual selection/classification/cancellation behavior.
 
     # Examples
     ```
-    let mut results = Vec::new();
-    testbed.execute(&[test], &mut results);
+    let results = testbed.execute(&mut tests.into_iter(), &RunOptions::new());
     ```
      */
-    pub fn execute<'b, T>(&self, tests: &mut T) -> Vec<Observation>
+    pub fn execute<'b, T>(&self, tests: &mut T, options: &RunOptions) -> Vec<Observation>
     where
         T: Iterator<Item = Test>,
     {
         let mut test_results = Vec::new();
+        self.execute_streaming(tests, options, |observation| test_results.push(observation));
+        test_results
+    }
 
-        let barrier = {
-            let barrier_count =
-            // One for each staticly allocated thread we have:
-            // - Main testbed thread
-            // - Observer thread
-            // - Energy metering thread
-            // - Serial tracing thread
-            // - Memory tracing thread
-                5
-            // One for each user-defined tracing thread
-                + self.tracing.len();
-
-            Arc::new(Barrier::new(barrier_count))
-        };
+    /** Run tests, handing each [`Observation`] to `sink` the instant that test finishes rather than
+    waiting for the whole suite.
+
+    A test excluded by `options`'s filter doesn't appear in the stream at all, and an ignored test
+    not explicitly requested is recorded as skipped ([`TestbedError::Skipped`]) without ever
+    touching the device. This lets a front end print live progress, or a CI runner bail out after
+    the first failing `sink` call, instead of waiting on the full `Vec` from [`Testbed::execute`].
 
+    # Examples
+    ```
+    testbed.execute_streaming(&mut tests.into_iter(), &RunOptions::new(), |observation| {
+        println!("finished: {}", observation.source_test().get_id());
+    });
+    ```
+     */
+    pub fn execute_streaming<'b, T, F>(&'b self, tests: &mut T, options: &RunOptions, mut sink: F)
+    where
+        T: Iterator<Item = Test>,
+        F: FnMut(Observation<'b>),
+    {
         let current_test: Arc<RwLock<Option<Test>>> = Arc::new(RwLock::new(None));
+        let cancellation = options.cancellation_token();
 
+        let (observer_cmd_tx, observer_cmd_rx) = crossbeam_channel::unbounded();
+        let (observer_ack_tx, observer_ack_rx) = crossbeam_channel::bounded(0);
         let (observer_schannel, observer_rchannel) = mpsc::sync_channel(0);
         let watch_thread = self.launch_observer(Arc::clone(&current_test),
-                                                Arc::clone(&barrier),
-                                                observer_schannel);
+                                                observer_cmd_rx,
+                                                observer_ack_tx,
+                                                observer_schannel,
+                                                cancellation.clone());
 
+        let (energy_cmd_tx, energy_cmd_rx) = crossbeam_channel::unbounded();
+        let (energy_ack_tx, energy_ack_rx) = crossbeam_channel::bounded(0);
         let (energy_schannel, energy_rchannel) = mpsc::sync_channel(0);
         let energy_thread = self.launch_metering(Arc::clone(&current_test),
-                                                 Arc::clone(&barrier),
-                                                 energy_schannel);
+                                                 energy_cmd_rx,
+                                                 energy_ack_tx,
+                                                 energy_schannel,
+                                                 cancellation.clone());
 
+        let (trace_cmd_tx, trace_cmd_rx) = crossbeam_channel::unbounded();
+        let (trace_ack_tx, trace_ack_rx) = crossbeam_channel::bounded(0);
         let (trace_schannel, trace_rchannel) = mpsc::sync_channel(0);
         let trace_thread = self.launch_tracing(Arc::clone(&current_test),
-                                               Arc::clone(&barrier),
+                                               trace_cmd_rx,
+                                               trace_ack_tx,
                                                trace_schannel,
-                                               self.tracing_uart.as_ref());
+                                               self.tracing_uart.as_ref(),
+                                               cancellation.clone());
 
+        let (mem_cmd_tx, mem_cmd_rx) = crossbeam_channel::unbounded();
+        let (mem_ack_tx, mem_ack_rx) = crossbeam_channel::bounded(0);
         let (mem_schannel, mem_rchannel) = mpsc::sync_channel(0);
         let mem_thread = self.launch_memstat(Arc::clone(&current_test),
-                                             Arc::clone(&barrier),
+                                             mem_cmd_rx,
+                                             mem_ack_tx,
                                              mem_schannel,
-                                             self.memory_uart.as_ref());
+                                             self.memory_uart.as_ref(),
+                                             cancellation.clone());
+
+        let mut workers = vec![
+            Worker { name: "observer".to_string(), cmd_tx: observer_cmd_tx, ack_rx: observer_ack_rx, handle: watch_thread },
+            Worker { name: "metering".to_string(), cmd_tx: energy_cmd_tx, ack_rx: energy_ack_rx, handle: energy_thread },
+            Worker { name: "tracing".to_string(), cmd_tx: trace_cmd_tx, ack_rx: trace_ack_rx, handle: trace_thread },
+            Worker { name: "memstat".to_string(), cmd_tx: mem_cmd_tx, ack_rx: mem_ack_rx, handle: mem_thread },
+        ];
 
         // Create threads for the defined tracing purposes.
         // Keep track of the receiving ends of their channels.
-        // The ordering must be consistent between the two vectors.
+        // The ordering must be consistent between the two vectors (and with `self.tracing`, since
+        // WORKER_TRACING_KIND_BASE + i must line up with `self.tracing[i]`).
         let tracing_rchannels = {
             let mut rchannels = Vec::new();
             for (kind, uart) in &self.tracing {
+                let (cmd_tx, cmd_rx) = crossbeam_channel::unbounded();
+                let (ack_tx, ack_rx) = crossbeam_channel::bounded(0);
                 let (schannel, rchannel) = mpsc::sync_channel(0);
-                self.launch_tracing_kind(
+                let handle = self.launch_tracing_kind(
                     kind.clone(),
                     uart,
                     Arc::clone(&current_test),
-                    Arc::clone(&barrier),
-                    schannel);
+                    cmd_rx,
+                    ack_tx,
+                    schannel,
+                    cancellation.clone());
+                workers.push(Worker { name: format!("trace-{}", kind), cmd_tx, ack_rx, handle });
                 rchannels.push(rchannel);
             }
 
             rchannels
         };
 
+        let mut alive = vec![true; workers.len()];
+
         for test in tests {
+            if options.is_cancelled() {
+                println!("executor: cancellation requested; not starting any more tests");
+                break;
+            }
+
+            if !options.selects(&test) {
+                continue;
+            }
+
+            if test.is_ignored() && !options.include_ignored {
+                println!("executor: skipping ignored test '{}'", test.get_id());
+                sink(Observation::skipped(test));
+                continue;
+            }
+
             println!("executor: running '{}'", test.get_id());
             println!("{}", test);
 
@@ -201,7 +496,7 @@ impl Testbed {
                     test.clone(),
                     None,
                     TestbedError::Software(reconfig_err));
-                test_results.push(observation);
+                sink(observation);
                 continue;
             }
             let platform_spec = res.unwrap();
@@ -213,7 +508,7 @@ impl Testbed {
                     test.clone(),
                     Some(platform_spec.clone()),
                     load_err);
-                test_results.push(observation);
+                sink(observation);
                 continue;
             }
 
@@ -222,87 +517,111 @@ impl Testbed {
             let mut inputs = self.pin_mapping.get_gpio_inputs()
                 .expect("Could not obtain GPIO inputs from executor thread.");
 
-            // wait for observer, metering thread to be ready
-            barrier.wait();
+            // Every worker still alive gets set up for this test, even if something below fails --
+            // that keeps the Ready/Begin/Finish handshake in lockstep instead of leaving a worker
+            // waiting on a phase that never comes.
+            let mut first_error = run_test_phase(&workers, &mut alive, WorkerCommand::Ready, self.phase_timeout);
 
             let use_reset = test.get_reset_on_start();
             if use_reset {
                 println!("Placing device in reset.");
-                let reset_res = self.pin_mapping.get_device().hold_in_reset(&mut inputs);
-                if let Err(e) = reset_res {
-                    let observation = Observation::failed(
-                        test.clone(),
-                        Some(platform_spec.clone()),
-                        TestbedError::Reset(e));
-                    test_results.push(observation);
-                    continue;
+                if let Err(e) = self.pin_mapping.get_device().hold_in_reset(&mut inputs) {
+                    if first_error.is_none() {
+                        first_error = Some(TestbedError::Reset(e));
+                    }
                 }
             }
 
-            // wait for test to begin
-            barrier.wait();
+            let begin_error = run_test_phase(&workers, &mut alive, WorkerCommand::Begin, self.phase_timeout);
+            if first_error.is_none() {
+                first_error = begin_error;
+            }
             println!("executor: starting test '{}'", test.get_id());
 
-            // make sure testing has _just_ started before releasing reset
-            if use_reset {
-                self.pin_mapping.get_device().release_from_reset(&mut inputs)
-                    // failed to release reset, no point in continuing
-                    .expect("failed to release device from reset");
-            }
-            let exec_result = test.execute(Instant::now(), &mut inputs)
-                .map_err(|e| TestbedError::Execution(e));
+            // Only actually run the DUT if setup went cleanly; otherwise there's nothing useful to
+            // exercise, and we still drive Finish below purely to keep workers in sync.
+            let exec_result = if first_error.is_none() {
+                // make sure testing has _just_ started before releasing reset
+                if use_reset {
+                    self.pin_mapping.get_device().release_from_reset(&mut inputs)
+                        // failed to release reset, no point in continuing
+                        .expect("failed to release device from reset");
+                }
+                Some(test.execute(Instant::now(), &mut inputs)
+                    .map_err(|e| TestbedError::Execution(e)))
+            } else {
+                None
+            };
 
-            // release observer thread
             println!("executor: test execution complete");
-            barrier.wait();
+            let finish_error = run_test_phase(&workers, &mut alive, WorkerCommand::Finish, self.phase_timeout);
+            if first_error.is_none() {
+                first_error = finish_error;
+            }
 
-            // get GPIO responses
+            // Drain whatever data each worker sent -- skipping a worker that's no longer alive,
+            // since it never got far enough to send anything for this test.
             let mut gpio_activity = Vec::new();
-            while let Some(response) = observer_rchannel.recv().unwrap() {
-                let response = response.remapped(self.pin_mapping.get_mapping());
-                gpio_activity.push(response);
+            if alive[WORKER_OBSERVER] {
+                while let Some(response) = observer_rchannel.recv().unwrap() {
+                    let response = response.remapped(self.pin_mapping.get_mapping());
+                    gpio_activity.push(response);
+                }
             }
 
-            // get energy data
-            let mut energy_data = HashMap::new();
-            while let Some((meter_id, (t, sample))) = energy_rchannel.recv().unwrap() {
-                energy_data.entry(meter_id)
-                    .or_insert(Vec::new())
-                    .push((t, sample));
+            let mut energy_data: HashMap<String, Vec<Sample>> = HashMap::new();
+            if alive[WORKER_METERING] {
+                while let Some(sample) = energy_rchannel.recv().unwrap() {
+                    energy_data.entry(sample.get_meter_id().to_string())
+                        .or_insert(Vec::new())
+                        .push(sample);
+                }
             }
 
-            // get tracing data
             println!("executor: receiving trace data");
             let mut serial_traces: Vec<SerialTrace> = Vec::new();
-            while let Some(trace) = trace_rchannel.recv().unwrap() {
-                serial_traces.push(trace);
+            if alive[WORKER_TRACING] {
+                while let Some(trace) = trace_rchannel.recv().unwrap() {
+                    serial_traces.push(trace);
+                }
             }
 
-            let start = exec_result.as_ref().map(|exec| exec.get_start()).unwrap();
-            for trace in &serial_traces {
-                println!("{} @ {:?}", trace, trace.get_offset(start));
+            if let Some(Ok(exec)) = exec_result.as_ref() {
+                let start = exec.get_start();
+                for trace in &serial_traces {
+                    println!("{} @ {:?}", trace, trace.get_offset(start));
+                }
             }
 
-            // get memory data
             println!("executor: receiving memory data");
             let mut mem_traces: Vec<MemoryTrace> = Vec::new();
-            println!("| {:^15} | op. | {:^35} | {:^6} |", "offset", "counter", "value");
-            while let Some(mem_event) = mem_rchannel.recv().unwrap() {
-                let offset = format!("@{:?}", mem_event.time() - exec_result.as_ref().unwrap().get_start());
-                let counter = format!("{}", mem_event.counter());
-                println!("| {:>15} | {:^5?} | {:^35} | {:>6} |",
-                         offset,
-                         mem_event.operation(),
-                         counter,
-                         mem_event.value());
-                mem_traces.push(mem_event);
+            if alive[WORKER_MEMSTAT] {
+                println!("| {:^15} | op. | {:^35} | {:^6} |", "offset", "counter", "value");
+                while let Some(mem_event) = mem_rchannel.recv().unwrap() {
+                    if let Some(Ok(exec)) = exec_result.as_ref() {
+                        let offset = format!("@{:?}", mem_event.time() - exec.get_start());
+                        let counter = format!("{}", mem_event.counter());
+                        println!("| {:>15} | {:^5?} | {:^35} | {:>6} |",
+                                 offset,
+                                 mem_event.operation(),
+                                 counter,
+                                 mem_event.value());
+                    }
+                    mem_traces.push(mem_event);
+                }
             }
 
-            // Receive tracing data.
+            // Receive tracing data, keeping the positions lined up with `self.tracing` even for a
+            // worker that's no longer alive -- `Observation` zips the two by index.
             let mut trace_data = Vec::new();
             let iter = tracing_rchannels.iter()
-                .zip(self.tracing.iter());
-            for (rchannel, (trace_kind, _uart)) in iter {
+                .zip(self.tracing.iter())
+                .enumerate();
+            for (i, (rchannel, (trace_kind, _uart))) in iter {
+                if !alive[WORKER_TRACING_KIND_BASE + i] {
+                    trace_data.push(None);
+                    continue;
+                }
                 println!("executor: receiving data from {} thread", trace_kind);
                 let data = rchannel.recv()
                     .expect("Failed to receive data from tracing channel.");
@@ -310,7 +629,7 @@ impl Testbed {
             }
 
             // save data
-            if let (Some(writer), Ok(execution)) = (self.data_writer.as_ref(), exec_result.as_ref()) {
+            if let (Some(writer), Some(Ok(execution))) = (self.data_writer.as_ref(), exec_result.as_ref()) {
                 println!("executor: sending test data to writer");
                 writer.save_output(
                     &test,
@@ -321,49 +640,73 @@ impl Testbed {
                     .expect("failed to save test data");
             }
 
+            if let Some(e) = first_error {
+                sink(Observation::failed(test.clone(), Some(platform_spec.clone()), e));
+                continue;
+            }
+
             let observation = Observation::completed(
                 test.clone(),
                 Some(platform_spec.clone()),
-                exec_result,
+                exec_result.unwrap(),
                 gpio_activity,
                 serial_traces,
                 self.tracing.iter()
                     .map(|(kind, _uart)| kind)
                     .collect(),
                 trace_data,
-                energy_data);
-            test_results.push(observation);
+                energy_data,
+                mem_traces);
+            sink(observation);
             println!("executor: test finished.");
         }
 
         *current_test.write().unwrap() = None;
-        println!("executor: final wait");
-        barrier.wait();
 
-        // Not too concerned with joining these without error
-        // since testing is complete at this point. It shouldn't
-        // result in a crash either.
-        watch_thread.join().unwrap_or_else(|_e| {
-            println!("executor: failed to join with observer thread");
-        });
-        energy_thread.join().unwrap_or_else(|_e| {
-            println!("executor: failed to join with metering thread");
-        });
-        trace_thread.join().unwrap_or_else(|_e| {
-            println!("executor: failed to join with tracing thread");
-        });
-        mem_thread.join().unwrap_or_else(|_e| {
-            println!("executor: failed to join with memory thread");
-        });
+        if options.is_cancelled() {
+            println!("executor: cancelled; putting device back in reset");
+            match self.pin_mapping.get_gpio_inputs() {
+                Ok(mut inputs) => {
+                    if let Err(e) = self.pin_mapping.get_device().hold_in_reset(&mut inputs) {
+                        println!("executor: failed to reset device after cancellation: {}", e);
+                    }
+                },
+                Err(e) => println!("executor: could not obtain GPIO inputs to reset device: {}", e),
+            }
+        }
 
-        test_results
+        println!("executor: final wait");
+        let live_indices: Vec<usize> = (0..workers.len())
+            .filter(|&i| alive[i])
+            .collect();
+        let live_workers: Vec<&Worker> = live_indices.iter()
+            .map(|&i| &workers[i])
+            .collect();
+        run_phase(&live_workers, WorkerCommand::Stop, self.phase_timeout);
+
+        // Not too concerned with joining these without error since testing is complete at this
+        // point. It shouldn't result in a crash either. A worker that previously timed out is
+        // presumed wedged (there's no safe way to pre-empt a running thread in Rust), so its
+        // handle is dropped without joining rather than blocking shutdown on it forever.
+        for (i, worker) in workers.into_iter().enumerate() {
+            if !alive[i] {
+                println!("executor: '{}' thread previously timed out; not waiting for it to join", worker.name);
+                continue;
+            }
+            let name = worker.name.clone();
+            worker.handle.join().unwrap_or_else(|_e| {
+                println!("executor: failed to join with {} thread", name);
+            });
+        }
     }
 
     fn launch_observer(
         &self,
         test_container: Arc<RwLock<Option<Test>>>,
-        barrier: Arc<Barrier>,
+        cmd_rx: crossbeam_channel::Receiver<WorkerCommand>,
+        ack_tx: crossbeam_channel::Sender<Result<()>>,
         response_schannel: SyncSender<Option<Response>>,
+        cancellation: CancellationToken,
     ) -> JoinHandle<()> {
         let mut outputs = self.pin_mapping.get_gpio_outputs()
             .expect("Could not obtain GPIO outputs from observer thread.");
@@ -376,41 +719,82 @@ impl Testbed {
                 let mut responses = Vec::new();
                 responses.reserve(1000);
                 loop {
-                    // wait for next test
-                    barrier.wait();
+                    match cmd_rx.recv() {
+                        Ok(WorkerCommand::Ready) => {},
+                        Ok(WorkerCommand::Stop) | Err(_) => break,
+                        Ok(_unexpected) => continue,
+                    }
+
+                    if cancellation.is_cancelled() {
+                        println!("observer: cancellation requested; winding down");
+                        let _ = ack_tx.send(Ok(()));
+                        break;
+                    }
+
+                    let test = match test_container.read().unwrap().clone() {
+                        Some(test) => test,
+                        None => { let _ = ack_tx.send(Ok(())); break; },
+                    };
 
                     // set up to watch for responses according to criteria
-                    if let Some(ref test) = *test_container.read().unwrap() {
-                        let interrupt_pin_nos = test.prep_observe(&mut outputs)
-                            .unwrap(); // <-- communicate back?
-                        let interrupt_pins = interrupt_pin_nos.into_iter()
-                            .map(|pin_no| outputs.get_pin(pin_no).unwrap())
-                            .collect();
-
-                        // wait for test to begin
+                    let mut setup_failed = false;
+                    let interrupt_pins = match test.prep_observe(&mut outputs) {
+                        Ok(pin_nos) => pin_nos.into_iter()
+                            .filter_map(|pin_no| outputs.get_pin(pin_no).ok())
+                            .collect(),
+                        Err(e) => {
+                            setup_failed = true;
+                            let _ = ack_tx.send(Err(TestbedError::Observer(e)));
+                            Vec::new()
+                        },
+                    };
+                    if !setup_failed {
                         println!("observer: ready to begin test");
-                        barrier.wait();
-                        println!("observer: starting watch");
+                        let _ = ack_tx.send(Ok(()));
+                    }
 
-                        let t0 = Instant::now();
-                        test.observe(t0, &interrupt_pins, &mut responses)
-                            .unwrap();
+                    match cmd_rx.recv() {
+                        Ok(WorkerCommand::Begin) => {},
+                        Ok(WorkerCommand::Stop) | Err(_) => break,
+                        Ok(_unexpected) => continue,
+                    }
 
-                        barrier.wait();
+                    // Still ack Begin even if setup already failed, so the phase handshake stays
+                    // in lockstep; there's just nothing new to watch for.
+                    let begin_result = if setup_failed {
+                        Ok(())
+                    } else {
+                        println!("observer: starting watch");
+                        let t0 = Instant::now();
+                        test.observe(t0, &interrupt_pins, &mut responses, None, None)
+                            .map_err(TestbedError::Observer)
+                    };
+                    let _ = ack_tx.send(begin_result);
+
+                    match cmd_rx.recv() {
+                        Ok(WorkerCommand::Finish) => {},
+                        Ok(WorkerCommand::Stop) | Err(_) => break,
+                        Ok(_unexpected) => continue,
+                    }
 
-                        println!("observer: cleaning up interrupts");
-                        for pin in &mut outputs {
-                            pin.clear_interrupt().unwrap();
+                    println!("observer: cleaning up interrupts");
+                    let mut cleanup_error = None;
+                    for pin in &mut outputs {
+                        if let Err(e) = pin.clear_interrupt() {
+                            if cleanup_error.is_none() {
+                                cleanup_error = Some(TestbedError::Observer(e.into()));
+                            }
                         }
+                    }
+                    let _ = ack_tx.send(match cleanup_error {
+                        Some(e) => Err(e),
+                        None => Ok(()),
+                    });
 
-                        for r in responses.drain(..) {
-                            response_schannel.send(Some(r)).unwrap();
-                        }
-                        response_schannel.send(None).unwrap();
-                    } else {
-                        // no more tests to run
-                        break;
+                    for r in responses.drain(..) {
+                        response_schannel.send(Some(r)).unwrap();
                     }
+                    response_schannel.send(None).unwrap();
                 }
 
                 println!("observer: exiting");
@@ -421,8 +805,10 @@ impl Testbed {
     fn launch_metering(
         &self,
         test_container: Arc<RwLock<Option<Test>>>,
-        barrier: Arc<Barrier>,
-        energy_schannel: SyncSender<Option<(String, (Instant, f32))>>,
+        cmd_rx: crossbeam_channel::Receiver<WorkerCommand>,
+        ack_tx: crossbeam_channel::Sender<Result<()>>,
+        energy_schannel: SyncSender<Option<Sample>>,
+        cancellation: CancellationToken,
     ) -> JoinHandle<()> {
         println!("Starting energy metering thread.");
 
@@ -434,41 +820,70 @@ impl Testbed {
                 println!("metering: started.");
 
                 let meters = meters.lock().unwrap();
-                let mut samples: HashMap<String, Vec<(Instant, f32)>> = meters.keys()
+                let mut samples: HashMap<String, Vec<Sample>> = meters.keys()
                     .map(|meter_id| { (meter_id.clone(), Vec::new()) })
                     .collect();
 
                 loop {
-                    // wait for next test
-                    barrier.wait();
+                    match cmd_rx.recv() {
+                        Ok(WorkerCommand::Ready) => {},
+                        Ok(WorkerCommand::Stop) | Err(_) => break,
+                        Ok(_unexpected) => continue,
+                    }
+
+                    if cancellation.is_cancelled() {
+                        println!("metering: cancellation requested; releasing meters and winding down");
+                        drop(meters);
+                        let _ = ack_tx.send(Ok(()));
+                        break;
+                    }
 
-                    if let Some(ref test) = *test_container.read().unwrap() {
-                        // here, better error management across threads would be nice!
-                        let need_metering = test.prep_meter(&meters, &mut samples).unwrap();
+                    let test = match test_container.read().unwrap().clone() {
+                        Some(test) => test,
+                        None => { let _ = ack_tx.send(Ok(())); break; },
+                    };
+
+                    let mut setup_failed = false;
+                    let need_metering = match test.prep_meter(&meters, &mut samples) {
+                        Ok(need) => need,
+                        Err(e) => {
+                            setup_failed = true;
+                            let _ = ack_tx.send(Err(TestbedError::Metering(e)));
+                            false
+                        },
+                    };
+                    if !setup_failed {
                         if !need_metering {
                             println!("metering: idling; not needed for this test");
-                            barrier.wait();
                         } else {
-                            // wait for test to begin
                             println!("metering: ready to begin test");
-                            barrier.wait();
-
-                            test.meter(&meters, &mut samples);
                         }
-                    } else {
-                        // no more tests to run
-                        break;
+                        let _ = ack_tx.send(Ok(()));
                     }
 
-                    barrier.wait();
+                    match cmd_rx.recv() {
+                        Ok(WorkerCommand::Begin) => {},
+                        Ok(WorkerCommand::Stop) | Err(_) => break,
+                        Ok(_unexpected) => continue,
+                    }
+
+                    if !setup_failed && need_metering {
+                        test.meter(&meters, &mut samples, None);
+                    }
+                    let _ = ack_tx.send(Ok(()));
+
+                    match cmd_rx.recv() {
+                        Ok(WorkerCommand::Finish) => {},
+                        Ok(WorkerCommand::Stop) | Err(_) => break,
+                        Ok(_unexpected) => continue,
+                    }
+
+                    let _ = ack_tx.send(Ok(()));
 
                     // communicate results back
-                    for (meter_id, samples) in &samples {
+                    for samples in samples.values() {
                         for sample in samples {
-                            // .to_string()... kinda wasteful, but it works;
-                            // perhaps better comm. types wanted?
-                            let message = Some((meter_id.to_string(), *sample));
-                            energy_schannel.send(message).unwrap();
+                            energy_schannel.send(Some(sample.clone())).unwrap();
                         }
                     }
                     energy_schannel.send(None).unwrap(); // done communicating results
@@ -480,14 +895,16 @@ impl Testbed {
     fn launch_tracing(
         &self,
         test_container: Arc<RwLock<Option<Test>>>,
-        barrier: Arc<Barrier>,
+        cmd_rx: crossbeam_channel::Receiver<WorkerCommand>,
+        ack_tx: crossbeam_channel::Sender<Result<()>>,
         trace_schannel: SyncSender<Option<SerialTrace>>,
         uart: Option<&UART>,
+        cancellation: CancellationToken,
     ) -> JoinHandle<()> {
 
         if let Some(uart) = uart {
             println!("Starting tracing thread.");
-            let uart = self.pin_mapping.get_uart(uart)
+            let uart = self.pin_mapping.get_uart(uart, &UartConfig::default())
                 .expect("Could not obtain UART from tracing thread.");
 
             thread::Builder::new()
@@ -498,27 +915,51 @@ impl Testbed {
                     let mut uart = uart;
                     let mut buffer: Vec<u8> = Vec::new();
                     let mut schedule: Vec<(Instant, usize)> = Vec::new();
-                    let mut bytes_rx;
+                    let mut bytes_rx = 0;
 
                     loop {
-                        // wait for next test
-                        barrier.wait();
-
-                        if let Some(ref test) = *test_container.read().unwrap() {
-                            test.prep_tracing(&mut uart, &mut buffer, &mut schedule).unwrap();
-
-                            barrier.wait();
-                            bytes_rx = test.trace(
-                                &mut uart,
-                                &mut buffer,
-                                &mut schedule).unwrap();
-                            println!("stracing: received {} bytes over UART", bytes_rx);
-                        } else {
-                            // no more tests to run
+                        match cmd_rx.recv() {
+                            Ok(WorkerCommand::Ready) => {},
+                            Ok(WorkerCommand::Stop) | Err(_) => break,
+                            Ok(_unexpected) => continue,
+                        }
+
+                        if cancellation.is_cancelled() {
+                            println!("stracing: cancellation requested; winding down");
+                            let _ = ack_tx.send(Ok(()));
                             break;
                         }
 
-                        barrier.wait();
+                        let test = match test_container.read().unwrap().clone() {
+                            Some(test) => test,
+                            None => { let _ = ack_tx.send(Ok(())); break; },
+                        };
+
+                        test.prep_tracing(&mut uart, &mut buffer, &mut schedule).unwrap();
+                        let _ = ack_tx.send(Ok(()));
+
+                        match cmd_rx.recv() {
+                            Ok(WorkerCommand::Begin) => {},
+                            Ok(WorkerCommand::Stop) | Err(_) => break,
+                            Ok(_unexpected) => continue,
+                        }
+
+                        let t0 = Instant::now();
+                        bytes_rx = test.trace(
+                            &mut uart,
+                            &mut buffer,
+                            &mut schedule,
+                            t0,
+                            None).unwrap();
+                        println!("stracing: received {} bytes over UART", bytes_rx);
+                        let _ = ack_tx.send(Ok(()));
+
+                        match cmd_rx.recv() {
+                            Ok(WorkerCommand::Finish) => {},
+                            Ok(WorkerCommand::Stop) | Err(_) => break,
+                            Ok(_unexpected) => continue,
+                        }
+                        let _ = ack_tx.send(Ok(()));
 
                         let serial_traces = trace::reconstruct_serial(
                             &buffer.as_slice()[0..bytes_rx],
@@ -537,17 +978,36 @@ impl Testbed {
             thread::Builder::new()
                 .name("test-stracing".to_string())
                 .spawn(move || {
-
                     loop {
-                        // wait for next test
-                        barrier.wait();
-                        if let Some(ref _test) = *test_container.read().unwrap() {
-                            barrier.wait();
-                        } else {
-                            // no more tests to run
+                        match cmd_rx.recv() {
+                            Ok(WorkerCommand::Ready) => {},
+                            Ok(WorkerCommand::Stop) | Err(_) => break,
+                            Ok(_unexpected) => continue,
+                        }
+                        if cancellation.is_cancelled() {
+                            let _ = ack_tx.send(Ok(()));
+                            break;
+                        }
+                        if test_container.read().unwrap().is_none() {
+                            let _ = ack_tx.send(Ok(()));
                             break;
                         }
-                        barrier.wait();
+                        let _ = ack_tx.send(Ok(()));
+
+                        match cmd_rx.recv() {
+                            Ok(WorkerCommand::Begin) => {},
+                            Ok(WorkerCommand::Stop) | Err(_) => break,
+                            Ok(_unexpected) => continue,
+                        }
+                        let _ = ack_tx.send(Ok(()));
+
+                        match cmd_rx.recv() {
+                            Ok(WorkerCommand::Finish) => {},
+                            Ok(WorkerCommand::Stop) | Err(_) => break,
+                            Ok(_unexpected) => continue,
+                        }
+                        let _ = ack_tx.send(Ok(()));
+
                         trace_schannel.send(None).unwrap(); // done communicating results
                     }
                 })
@@ -558,13 +1018,15 @@ impl Testbed {
     fn launch_memstat(
         &self,
         test_container: Arc<RwLock<Option<Test>>>,
-        barrier: Arc<Barrier>,
+        cmd_rx: crossbeam_channel::Receiver<WorkerCommand>,
+        ack_tx: crossbeam_channel::Sender<Result<()>>,
         mem_schannel: SyncSender<Option<MemoryTrace>>,
         uart: Option<&UART>,
+        cancellation: CancellationToken,
     ) -> JoinHandle<()> {
         if let Some(uart) = uart {
             println!("Starting memory tracking thread.");
-            let uart = self.pin_mapping.get_uart(uart)
+            let uart = self.pin_mapping.get_uart(uart, &UartConfig::default())
                 .expect("Could not obtain UART from tracing thread.");
 
             thread::Builder::new()
@@ -575,31 +1037,51 @@ impl Testbed {
                     let mut uart = uart;
                     let mut buffer: Vec<u8> = Vec::new();
                     let mut schedule: Vec<MemoryTrace> = Vec::new();
-                    let mut bytes_remaining;
 
                     loop {
-                        // wait for next test
-                        barrier.wait();
-
-                        if let Some(ref test) = *test_container.read().unwrap() {
-                            test.prep_memtrack(&mut uart, &mut buffer, &mut schedule).unwrap();
-
-                            barrier.wait();
-                            bytes_remaining = test.memtrack(
-                                &mut uart,
-                                &mut buffer,
-                                &mut schedule).unwrap();
-                            if bytes_remaining > 0 {
-                                println!("memtrack: {} bytes of unprocessed data!", bytes_remaining);
-                            } else {
-                                println!("memtrack: all data processed");
-                            }
-                        } else {
-                            // no more tests to run
+                        match cmd_rx.recv() {
+                            Ok(WorkerCommand::Ready) => {},
+                            Ok(WorkerCommand::Stop) | Err(_) => break,
+                            Ok(_unexpected) => continue,
+                        }
+
+                        if cancellation.is_cancelled() {
+                            println!("memtrack: cancellation requested; winding down");
+                            let _ = ack_tx.send(Ok(()));
                             break;
                         }
 
-                        barrier.wait();
+                        let test = match test_container.read().unwrap().clone() {
+                            Some(test) => test,
+                            None => { let _ = ack_tx.send(Ok(())); break; },
+                        };
+
+                        test.prep_memtrack(&mut uart, &mut buffer, &mut schedule).unwrap();
+                        let _ = ack_tx.send(Ok(()));
+
+                        match cmd_rx.recv() {
+                            Ok(WorkerCommand::Begin) => {},
+                            Ok(WorkerCommand::Stop) | Err(_) => break,
+                            Ok(_unexpected) => continue,
+                        }
+
+                        let bytes_remaining = test.memtrack(
+                            &mut uart,
+                            &mut buffer,
+                            &mut schedule).unwrap();
+                        if bytes_remaining > 0 {
+                            println!("memtrack: {} bytes of unprocessed data!", bytes_remaining);
+                        } else {
+                            println!("memtrack: all data processed");
+                        }
+                        let _ = ack_tx.send(Ok(()));
+
+                        match cmd_rx.recv() {
+                            Ok(WorkerCommand::Finish) => {},
+                            Ok(WorkerCommand::Stop) | Err(_) => break,
+                            Ok(_unexpected) => continue,
+                        }
+                        let _ = ack_tx.send(Ok(()));
 
                         for mem_event in &schedule {
                             mem_schannel.send(Some(mem_event.clone())).unwrap();
@@ -614,17 +1096,36 @@ impl Testbed {
             thread::Builder::new()
                 .name("test-memtrack".to_string())
                 .spawn(move || {
-
                     loop {
-                        // wait for next test
-                        barrier.wait();
-                        if let Some(ref _test) = *test_container.read().unwrap() {
-                            barrier.wait();
-                        } else {
-                            // no more tests to run
+                        match cmd_rx.recv() {
+                            Ok(WorkerCommand::Ready) => {},
+                            Ok(WorkerCommand::Stop) | Err(_) => break,
+                            Ok(_unexpected) => continue,
+                        }
+                        if cancellation.is_cancelled() {
+                            let _ = ack_tx.send(Ok(()));
+                            break;
+                        }
+                        if test_container.read().unwrap().is_none() {
+                            let _ = ack_tx.send(Ok(()));
                             break;
                         }
-                        barrier.wait();
+                        let _ = ack_tx.send(Ok(()));
+
+                        match cmd_rx.recv() {
+                            Ok(WorkerCommand::Begin) => {},
+                            Ok(WorkerCommand::Stop) | Err(_) => break,
+                            Ok(_unexpected) => continue,
+                        }
+                        let _ = ack_tx.send(Ok(()));
+
+                        match cmd_rx.recv() {
+                            Ok(WorkerCommand::Finish) => {},
+                            Ok(WorkerCommand::Stop) | Err(_) => break,
+                            Ok(_unexpected) => continue,
+                        }
+                        let _ = ack_tx.send(Ok(()));
+
                         mem_schannel.send(None).unwrap(); // done communicating results
                     }
                 })
@@ -637,11 +1138,13 @@ impl Testbed {
         kind: TraceKind,
         uart: &UART,
         test_container: Arc<RwLock<Option<Test>>>,
-        barrier: Arc<Barrier>,
+        cmd_rx: crossbeam_channel::Receiver<WorkerCommand>,
+        ack_tx: crossbeam_channel::Sender<Result<()>>,
         schannel: SyncSender<Option<TraceData>>,
+        cancellation: CancellationToken,
     ) -> JoinHandle<()> {
         let name = format!("test-{}", kind);
-        let mut uart = self.pin_mapping.get_uart(uart)
+        let uart = self.pin_mapping.get_uart(uart, &UartConfig::default())
             .expect("Could not obtain UART for tracing.");
 
         thread::Builder::new()
@@ -654,32 +1157,53 @@ impl Testbed {
                 let mut trace_data = None;
 
                 loop {
-                    // Wait for next test.
-                    barrier.wait();
-
-                    if let Some(ref test) = *test_container.read().unwrap() {
-                        // Prepare for testing.
-                        // Break out allocating the space in the buffer prior to actually running testing
-                        // to minimize any jitter between the barrier and the collection starting.
-                        let prepared_buffer = trace::prepare(&mut buffer, &mut uart)
-                            .unwrap();
-
-                        barrier.wait();
-                        let t_stop_at = Instant::now() + test.max_runtime();
-                        trace_data = match trace::collect(&kind, &mut uart, prepared_buffer, t_stop_at) {
-                            Ok(trace_data) => Some(trace_data),
-                            Err(e) => {
-                                println!("trace-{}: tracing for {} failed: {}", name, kind, e);
-                                None
-                            },
-                        };
-                    } else {
-                        // No more tests to run.
+                    match cmd_rx.recv() {
+                        Ok(WorkerCommand::Ready) => {},
+                        Ok(WorkerCommand::Stop) | Err(_) => break,
+                        Ok(_unexpected) => continue,
+                    }
+
+                    if cancellation.is_cancelled() {
+                        println!("trace-{}: cancellation requested; winding down", &name);
+                        let _ = ack_tx.send(Ok(()));
                         break;
                     }
 
-                    // Post-testing wait.
-                    barrier.wait();
+                    let test = match test_container.read().unwrap().clone() {
+                        Some(test) => test,
+                        None => { let _ = ack_tx.send(Ok(())); break; },
+                    };
+
+                    // Prepare for testing, allocating the buffer up front so there's as little
+                    // jitter as possible between the Begin ack and collection actually starting.
+                    let uart_config = trace::UartConfig::new(115_200)
+                        .with_parity(rppal::uart::Parity::Even);
+                    let prepared_buffer = trace::prepare(&mut buffer, &mut uart, &uart_config)
+                        .unwrap();
+                    let _ = ack_tx.send(Ok(()));
+
+                    match cmd_rx.recv() {
+                        Ok(WorkerCommand::Begin) => {},
+                        Ok(WorkerCommand::Stop) | Err(_) => break,
+                        Ok(_unexpected) => continue,
+                    }
+
+                    let t_stop_at = Instant::now() + test.max_runtime();
+                    trace_data = match trace::collect(&kind, &mut uart, prepared_buffer, t_stop_at) {
+                        Ok(trace_data) => Some(trace_data),
+                        Err(e) => {
+                            println!("trace-{}: tracing for {} failed: {}", name, kind, e);
+                            None
+                        },
+                    };
+                    let _ = ack_tx.send(Ok(()));
+
+                    match cmd_rx.recv() {
+                        Ok(WorkerCommand::Finish) => {},
+                        Ok(WorkerCommand::Stop) | Err(_) => break,
+                        Ok(_unexpected) => continue,
+                    }
+                    let _ = ack_tx.send(Ok(()));
 
                     // Send data back.
                     schannel.send(trace_data).expect("failed to send trace data to main thread");
@@ -727,6 +1251,112 @@ impl Display for Testbed {
     }
 }
 
+/// How many observations in a row a [`TestbedPool`] worker will tolerate its own testbed's
+/// execution failing before concluding the testbed itself is unhealthy and giving up on it.
+const POOL_FAILURE_BUDGET: usize = 3;
+
+/** Distributes a suite of tests across several [`Testbed`]s, running them concurrently.
+
+Each `Testbed` gets its own dedicated worker thread that pulls tests, one at a time, off a queue
+shared by every testbed in the pool -- a testbed that finishes early just pulls the next test
+before a slower one does, rather than sitting idle on a pre-assigned share. This also gives
+graceful degradation for free: a worker that concludes its testbed is unhealthy (see
+[`POOL_FAILURE_BUDGET`]) simply stops pulling from the queue, and whatever it would have run next
+is picked up by one of the others instead.
+
+A given `Testbed` is only ever driven by its one dedicated worker, so the no-reentrancy
+requirement of [`Testbed::execute_streaming`] (and the coordination threads/channels underneath
+it) holds without any extra locking here.
+ */
+#[derive(Debug)]
+pub struct TestbedPool {
+    testbeds: Vec<Testbed>,
+}
+
+impl TestbedPool {
+    /// Create a new `TestbedPool` that distributes work across `testbeds`. A testbed's position in
+    /// this `Vec` is the id it's reported under by [`TestbedPool::execute`].
+    pub fn new(testbeds: Vec<Testbed>) -> TestbedPool {
+        TestbedPool { testbeds }
+    }
+
+    /// Return the number of testbeds in the pool.
+    pub fn len(&self) -> usize {
+        self.testbeds.len()
+    }
+
+    /** Run `tests` across every testbed in the pool, handing each finished [`Evaluation`] to
+    `sink` along with the id (index into the `Vec` passed to [`TestbedPool::new`]) of the testbed
+    that produced it.
+
+    Tests are drawn from one shared queue, so a testbed is only ever idle when there's truly no
+    work left for it, not because of a static split of `tests` up front. `sink` is called from
+    whichever worker thread finished the test, so it must be safe to call concurrently; it's given
+    the `Evaluation` itself rather than a channel of them; because [`Evaluation`] borrows from the
+    [`Observation`] it was judged from, which in turn borrows from the testbed that produced it,
+    there's no owned, 'static form of it to hand back over an actual channel.
+
+    # Examples
+    ```
+    let evaluator = StandardEvaluator::new();
+    let results = Mutex::new(Vec::new());
+    pool.execute(tests, &RunOptions::new(), &evaluator, |testbed_id, evaluation| {
+        results.lock().unwrap().push((testbed_id, evaluation.status()));
+    });
+    ```
+     */
+    pub fn execute<E, F>(&mut self, tests: Vec<Test>, options: &RunOptions, evaluator: &E, sink: F)
+    where
+        E: Evaluator + Sync,
+        F: for<'t> Fn(usize, Evaluation<'t>) + Send + Sync,
+    {
+        let (work_tx, work_rx) = crossbeam_channel::unbounded::<Test>();
+        for test in tests {
+            // Only fails if every receiver has already hung up, which can't happen before the
+            // threads below are even spawned.
+            work_tx.send(test).unwrap();
+        }
+        drop(work_tx);
+
+        thread::scope(|scope| {
+            for (id, testbed) in self.testbeds.iter_mut().enumerate() {
+                let work_rx = work_rx.clone();
+                let sink = &sink;
+
+                scope.spawn(move || {
+                    let mut consecutive_failures = 0usize;
+
+                    loop {
+                        // Check the budget before pulling, so a test this worker won't run stays
+                        // on the queue for the rest of the pool.
+                        if consecutive_failures >= POOL_FAILURE_BUDGET {
+                            println!("pool: testbed {} looks unhealthy; giving up remaining tests to the rest of the pool", id);
+                            break;
+                        }
+
+                        let test = match work_rx.recv() {
+                            Ok(test) => test,
+                            Err(_) => break,
+                        };
+
+                        let mut one_test = std::iter::once(test);
+                        testbed.execute_streaming(&mut one_test, options, |observation| {
+                            if observation.execution_result().is_err() {
+                                consecutive_failures += 1;
+                            } else {
+                                consecutive_failures = 0;
+                            }
+
+                            let evaluation = evaluator.evaluate(&observation);
+                            sink(id, evaluation);
+                        });
+                    }
+                });
+            }
+        });
+    }
+}
+
 /// Aggregated collection of test execution data.
 #[derive(Debug)]
 pub struct Observation<'a> {
@@ -737,7 +1367,8 @@ pub struct Observation<'a> {
     traces: Vec<SerialTrace>,
     trace_info: Vec<&'a TraceKind>,
     trace_data: Vec<Option<TraceData>>,
-    energy_metrics: HashMap<String, Vec<(Instant, f32)>>,
+    energy_metrics: HashMap<String, Vec<Sample>>,
+    memory_traces: Vec<MemoryTrace>,
 }
 
 impl<'a> Observation<'a> {
@@ -749,7 +1380,8 @@ impl<'a> Observation<'a> {
         traces: Vec<SerialTrace>,
         trace_info: Vec<&'a TraceKind>,
         trace_data: Vec<Option<TraceData>>,
-        energy_metrics: HashMap<String, Vec<(Instant, f32)>>
+        energy_metrics: HashMap<String, Vec<Sample>>,
+        memory_traces: Vec<MemoryTrace>,
     ) -> Observation<'a> {
         Observation {
             test,
@@ -760,6 +1392,7 @@ impl<'a> Observation<'a> {
             trace_info,
             trace_data,
             energy_metrics,
+            memory_traces,
         }
     }
 
@@ -777,9 +1410,16 @@ impl<'a> Observation<'a> {
             trace_info: Vec::new(),
             trace_data: Vec::new(),
             energy_metrics: HashMap::new(),
+            memory_traces: Vec::new(),
         }
     }
 
+    /// Build an `Observation` for a test that was skipped entirely because it's ignored and the
+    /// run's [`RunOptions`] didn't ask for it -- no device interaction happened.
+    fn skipped(test: Test) -> Observation<'a> {
+        Observation::failed(test, None, TestbedError::Skipped)
+    }
+
     /// Return the test that the `Observation` is for.
     pub fn source_test(&self) -> &Test {
         &self.test
@@ -806,7 +1446,12 @@ impl<'a> Observation<'a> {
     }
 
     /// Return data from all energy meters active during the test.
-    pub fn energy_metrics(&self) -> &HashMap<String, Vec<(Instant, f32)>> {
+    pub fn energy_metrics(&self) -> &HashMap<String, Vec<Sample>> {
         &self.energy_metrics
     }
+
+    /// Return the memory counter updates observed during the test.
+    pub fn memory_traces(&self) -> &Vec<MemoryTrace> {
+        &self.memory_traces
+    }
 }
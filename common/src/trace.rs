@@ -1,9 +1,12 @@
 //! Interpret execution trace information emitted from a DUT.
 
-use std::convert::From;
+use std::collections::HashMap;
+use std::convert::{From, TryFrom};
 use std::fmt;
 use std::time::{Duration, Instant};
 
+use flexbed_shared::mem::CounterId;
+
 use rppal::uart;
 use rppal::uart::Uart;
 
@@ -54,10 +57,10 @@ impl fmt::Display for TraceKind {
 pub enum TraceData {
     /// Raw tracing data, given as a sequence of bytes.
     Raw(Vec<u8>),
-    /// Control flow tracing data.
-    ControlFlow(Vec<SerialTrace>),
-    /// Memory usage data.
-    Memory(Vec<SerialTrace>),
+    /// Control flow tracing data: the events each process reported, in arrival order.
+    ControlFlow(Vec<ControlFlowEvent>),
+    /// Memory usage data: decoded counter samples.
+    Memory(Vec<MemorySample>),
     /// Performance benchmarking data.
     Performance(PerformanceData),
 }
@@ -89,6 +92,7 @@ impl<'a> Display<'a> {
         f: &mut fmt::Formatter) -> fmt::Result
     {
         let no_waypoints = data.no_waypoints as usize;
+        let counter_kind = metadata.counter_kind();
 
         for period in &data.metrics {
             // Show redundant metadata.
@@ -97,22 +101,70 @@ impl<'a> Display<'a> {
 
             // Headers
             let rate_text = format!("rate ({}/s)", metadata.unit());
-            write!(f, "|   waypoint   |   t_end (s)   | duration (s) | {:^20} |\n", rate_text)?;
+            write!(f, "|   waypoint   |   t_end (s)   | duration (s) | {:^20} |", rate_text)?;
+            if let Some(counter_kind) = counter_kind {
+                write!(f, " {:^14} | {:^14} |", format!("{} (/s)", counter_kind), format!("{}/byte", counter_kind))?;
+            }
+            write!(f, "\n")?;
+
             // A row for each datapoint.
             for i in 0..no_waypoints {
                 let duration: f64 = period.end_time(i) - period.start_time();
                 let data_rate: f64 = (period.data_size() as f64) / duration;
 
-                write!(f, "| {:^12} | {:13.06} | {:12.06} | {:20.06} |\n",
-                       metadata.waypoint_no(i).as_ref().map_or("???", |w| &w.label),
+                write!(f, "| {:^12} | {:13.06} | {:12.06} | {:20.06} |",
+                       metadata.waypoint_no(i).map_or("???", |w| w.label.as_str()),
                        period.end_time(i),
                        duration,
                        data_rate)?;
+
+                if counter_kind.is_some() {
+                    match period.counter_value(i) {
+                        Some(counter) => write!(f, " {:14.06} | {:14.06} |",
+                                                 (counter as f64) / duration,
+                                                 (counter as f64) / (period.data_size() as f64))?,
+                        None => write!(f, " {:^14} | {:^14} |", "-", "-")?,
+                    }
+                }
+
+                write!(f, "\n")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn display_memory(data: &[MemorySample], f: &mut fmt::Formatter) -> fmt::Result {
+        let mut by_process: HashMap<u32, Vec<&MemorySample>> = HashMap::new();
+        for sample in data {
+            by_process.entry(counter_process(&sample.counter)).or_insert_with(Vec::new).push(sample);
+        }
+
+        let mut processes: Vec<&u32> = by_process.keys().collect();
+        processes.sort();
+
+        for process in processes {
+            write!(f, "Process {}:\n", process)?;
+            write!(f, "| {:^35} | {:^10} |\n", "counter", "value")?;
+            for sample in &by_process[process] {
+                write!(f, "| {:35} | {:10} |\n", sample.counter.to_string(), sample.value)?;
             }
         }
 
         Ok(())
     }
+
+    fn display_control_flow(data: &[ControlFlowEvent], f: &mut fmt::Formatter) -> fmt::Result {
+        let mut timelines: Vec<(u32, Vec<u32>)> = reconstruct_control_flow(data).into_iter().collect();
+        timelines.sort_by_key(|(process, _events)| *process);
+
+        for (process, events) in timelines {
+            write!(f, "Process {}: {}\n", process,
+                   events.iter().map(u32::to_string).collect::<Vec<_>>().join(" -> "))?;
+        }
+
+        Ok(())
+    }
 }
 
 impl<'a> fmt::Display for Display<'a> {
@@ -125,12 +177,12 @@ impl<'a> fmt::Display for Display<'a> {
             },
 
             TraceKind::ControlFlow => match self.data {
-                TraceData::ControlFlow(_data) => unimplemented!(),
+                TraceData::ControlFlow(data) => Display::display_control_flow(data, f),
                 _ => Display::panic_mismatch(),
             },
 
             TraceKind::Memory => match self.data {
-                TraceData::Memory(_data) => unimplemented!(),
+                TraceData::Memory(data) => Display::display_memory(data, f),
                 _ => Display::panic_mismatch(),
             },
 
@@ -216,6 +268,90 @@ where
     traces
 }
 
+/// Returns the process a [`CounterId`] belongs to, regardless of variant.
+fn counter_process(id: &CounterId) -> u32 {
+    use CounterId::*;
+    match id {
+        PCB(pid) | UpcallQueue(pid) | GrantPointerTable(pid) | CustomGrant(pid) => *pid,
+        Grant(pid, _grant_no) => *pid,
+    }
+}
+
+/// Returns a [`CounterId`]'s value: its sole field, or for [`CounterId::Grant`], the second
+/// (`val`, as [`flexbed_shared::mem::CounterId::serialize`] names it).
+fn counter_value(id: &CounterId) -> u32 {
+    use CounterId::*;
+    match id {
+        PCB(val) | UpcallQueue(val) | GrantPointerTable(val) | CustomGrant(val) => *val,
+        Grant(_pid, val) => *val,
+    }
+}
+
+/// One memory counter update decoded from a [`TraceKind::Memory`] trace.
+#[derive(Clone, Debug)]
+pub struct MemorySample {
+    time: Instant,
+    counter: CounterId,
+    value: u32,
+}
+
+impl MemorySample {
+    /// Returns the time this sample was decoded.
+    pub fn get_time(&self) -> Instant {
+        self.time
+    }
+
+    /// Returns which counter this sample reports on.
+    pub fn counter(&self) -> &CounterId {
+        &self.counter
+    }
+
+    /// Returns the counter's value.
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+}
+
+/// One control flow event decoded from a [`TraceKind::ControlFlow`] trace: a process reporting
+/// that event `event` occurred.
+#[derive(Clone, Debug)]
+pub struct ControlFlowEvent {
+    time: Instant,
+    process: u32,
+    event: u32,
+}
+
+impl ControlFlowEvent {
+    /// Returns the time this event was decoded.
+    pub fn get_time(&self) -> Instant {
+        self.time
+    }
+
+    /// Returns the process that reported this event.
+    pub fn process(&self) -> u32 {
+        self.process
+    }
+
+    /// Returns the reported event id.
+    pub fn event(&self) -> u32 {
+        self.event
+    }
+}
+
+/// Groups a sequence of [`ControlFlowEvent`]s by the process that reported them, keeping each
+/// process's events in the order they arrived.
+pub fn reconstruct_control_flow<'a, T>(events: T) -> HashMap<u32, Vec<u32>>
+where
+    T: IntoIterator<Item = &'a ControlFlowEvent>,
+{
+    let mut timelines: HashMap<u32, Vec<u32>> = HashMap::new();
+    for event in events {
+        timelines.entry(event.process).or_insert_with(Vec::new).push(event.event);
+    }
+
+    timelines
+}
+
 /// Information to interpret a waypoint.
 #[derive(Clone, Debug)]
 pub struct WaypointMetadata {
@@ -223,43 +359,70 @@ pub struct WaypointMetadata {
     pub label: String,
 }
 
-const MAX_WAYPOINT_LABELS: usize = 8;
+/// Which hardware performance counter a period's per-waypoint counter word (see
+/// [`PeriodMetric::counter_value`]) represents. Purely interpretive, like [`BenchmarkMetadata`]'s
+/// `unit`: the wire format only signals whether a counter word is present, not which counter it
+/// is, so the caller declares that up front via [`BenchmarkMetadata::with_counter_kind`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CounterKind {
+    /// CPU cycles elapsed.
+    Cycles,
+    /// Retired instructions.
+    Instructions,
+    /// Cache misses.
+    CacheMisses,
+}
+
+impl fmt::Display for CounterKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CounterKind::Cycles => write!(f, "cycles"),
+            CounterKind::Instructions => write!(f, "instructions"),
+            CounterKind::CacheMisses => write!(f, "cache misses"),
+        }
+    }
+}
 
 /// Information to interpret performance tracking data.
 #[derive(Clone, Debug)]
 pub struct BenchmarkMetadata {
     unit: String,
-    waypoints: [Option<WaypointMetadata>; MAX_WAYPOINT_LABELS],
+    waypoints: Vec<WaypointMetadata>,
+    counter_kind: Option<CounterKind>,
 }
 
 impl BenchmarkMetadata {
-    /// Create a new `BenchmarkMetadata`.
+    /// Create a new `BenchmarkMetadata`. Any number of waypoints is accepted; this used to cap
+    /// out at 8 (a fixed-size array), but a serialized trace (see [`TraceData::write_to`]) now
+    /// references waypoint labels by [`StringId`] instead of inlining them, so there's no longer
+    /// a reason to bound how many a benchmark can declare.
     pub fn new(unit: &str, waypoints: &[WaypointMetadata]) -> BenchmarkMetadata {
-        let waypoints = {
-            let waypoints_iter = waypoints.iter();
-            let mut waypoints_dest = [None, None, None, None,
-                                      None, None, None, None];
-
-            for (wp_dst, wp_src) in waypoints_dest.iter_mut().zip(waypoints_iter) {
-                *wp_dst = Some(wp_src.clone());
-            }
-
-            waypoints_dest
-        };
-
         BenchmarkMetadata {
             unit: unit.to_string(),
-            waypoints,
+            waypoints: waypoints.to_vec(),
+            counter_kind: None,
         }
     }
 
+    /// Declares that this benchmark's DUT firmware also reports a hardware performance counter
+    /// alongside each waypoint's timestamp (see [`PeriodMetric::counter_value`]), and that it
+    /// should be interpreted as `kind`.
+    pub fn with_counter_kind(mut self, kind: CounterKind) -> BenchmarkMetadata {
+        self.counter_kind = Some(kind);
+        self
+    }
+
     fn unit(&self) -> &str {
         &self.unit
     }
 
     /// Return metadata about the specified waypoint.
-    fn waypoint_no(&self, no: usize) -> &Option<WaypointMetadata> {
-        &self.waypoints[no]
+    fn waypoint_no(&self, no: usize) -> Option<&WaypointMetadata> {
+        self.waypoints.get(no)
+    }
+
+    fn counter_kind(&self) -> Option<CounterKind> {
+        self.counter_kind
     }
 }
 
@@ -286,25 +449,37 @@ impl PerformanceData {
 #[derive(Clone, Debug)]
 pub struct PeriodMetric {
     t_start: f64,
-    t_ends: [f64; MAX_WAYPOINT_LABELS],
+    t_ends: Vec<f64>,
     data_size: u32,
+    /// Parallel to `t_ends`: the hardware counter reading (see [`CounterKind`]) at each waypoint,
+    /// if the DUT firmware reported one. Empty when it didn't.
+    counters: Vec<u64>,
 }
 
 impl PeriodMetric {
-    /// Create a new metric.
+    /// Create a new metric, with no per-waypoint counter readings.
     pub fn new<T>(t_start: f64, data_size: u32, waypoint_t_ends: T) -> PeriodMetric
     where
         T: IntoIterator<Item = f64>,
     {
-        let mut t_ends: [f64; MAX_WAYPOINT_LABELS] = [0.0; MAX_WAYPOINT_LABELS];
-        for (src, dst) in waypoint_t_ends.into_iter().zip(&mut t_ends) {
-            *dst = src;
-        }
-
         PeriodMetric {
             t_start,
-            t_ends,
+            t_ends: waypoint_t_ends.into_iter().collect(),
             data_size,
+            counters: Vec::new(),
+        }
+    }
+
+    /// Create a new metric that also carries a hardware counter reading per waypoint (see
+    /// [`BenchmarkMetadata::with_counter_kind`]).
+    pub fn with_counters<T, C>(t_start: f64, data_size: u32, waypoint_t_ends: T, counters: C) -> PeriodMetric
+    where
+        T: IntoIterator<Item = f64>,
+        C: IntoIterator<Item = u64>,
+    {
+        PeriodMetric {
+            counters: counters.into_iter().collect(),
+            ..PeriodMetric::new(t_start, data_size, waypoint_t_ends)
         }
     }
 
@@ -322,14 +497,25 @@ impl PeriodMetric {
     pub fn data_size(&self) -> u32 {
         self.data_size
     }
+
+    /// Returns the hardware counter reading (see [`CounterKind`]) at a waypoint, if the DUT
+    /// firmware reported one.
+    pub fn counter_value(&self, waypoint_no: usize) -> Option<u64> {
+        self.counters.get(waypoint_no).copied()
+    }
 }
 
 mod parsing {
+    use std::time::Instant;
+
     use nom::bits::complete as bits;
     use nom::bits::bits as adapt_bit_parser;
     use nom::bytes::complete as bytes;
+    use nom::error::ParseError;
     use nom::{combinator, multi, sequence};
 
+    use flexbed_shared::mem::CounterId;
+
     use crate::parsing_support::{
         BitError,
         ByteError,
@@ -338,18 +524,70 @@ mod parsing {
         little_u64,
     };
 
-    use super::{PerformanceData, PeriodMetric};
+    use super::{ControlFlowEvent, MemorySample, PerformanceData, PeriodMetric};
+
+    /// Set in [`benchmark_init`]'s header flags nibble when each period's stat containers carry
+    /// an extra hardware counter word (see [`super::CounterKind`]) after their timestamp.
+    const COUNTER_PRESENT_FLAG: u8 = 0b1000;
 
     /// Initialization data parser.
     ///
-    /// Returns a tuple: (no. of stat containers, counter frequency).
-    fn benchmark_init<'a>(data: &'a [u8]) -> ByteResult<'a, (u8, u32)> {
-        sequence::pair::<_, _, _, ByteError<'a>, _, _>(
-            adapt_bit_parser::<_, _, BitError<'a>, _, _>(
-                sequence::preceded(
-                    bits::tag(0b0000, 4usize),
-                    bits::take(4usize))),
-            little_u32)
+    /// Returns a tuple: (no. of stat containers, counter frequency, whether a counter word
+    /// follows each stat container's timestamp).
+    pub(crate) fn benchmark_init<'a>(data: &'a [u8]) -> ByteResult<'a, (u8, u32, bool)> {
+        let (data, (flags, no_containers)): (_, (u8, u8)) = adapt_bit_parser::<_, _, BitError<'a>, _, _>(
+            sequence::pair(bits::take(4usize), bits::take(4usize)))
+            (data)?;
+        let (data, freq) = little_u32(data)?;
+
+        Ok((data, (no_containers, freq, flags & COUNTER_PRESENT_FLAG != 0)))
+    }
+
+    /// Parses one stat container: a waypoint's timestamp and accumulated data size, plus its
+    /// hardware counter word when `has_counter` says one is present.
+    fn stat_container<'a>(has_counter: bool) -> impl Fn(&'a [u8]) -> ByteResult<'a, (u64, u32, Option<u64>)> {
+        move |data: &'a [u8]| {
+            let (data, t_end) = little_u64(data)?;
+            let (data, size) = little_u32(data)?;
+
+            if has_counter {
+                let (data, counter) = little_u64(data)?;
+                Ok((data, (t_end, size, Some(counter))))
+            } else {
+                Ok((data, (t_end, size, None)))
+            }
+        }
+    }
+
+    /// Parses exactly one period's worth of stat containers.
+    ///
+    /// Factored out of [`benchmark_period_metrics`] so [`super::collect_streaming`] can apply it
+    /// one record at a time to whatever's been read so far, rather than requiring the whole
+    /// capture to be in hand up front the way [`benchmark_data`] does.
+    pub(crate) fn benchmark_one_period_metric<'a>(
+        counter_freq: u32,
+        no_containers: u8,
+        has_counter: bool,
+        data: &'a [u8]
+    ) -> ByteResult<'a, PeriodMetric>
+    {
+        combinator::map(
+            // pair: <header> + <N stat containers>
+            sequence::pair(
+                // preceded: <header tag> + <64-bit timestamp>
+                sequence::preceded(bytes::tag([0b1000_0000]), little_u64),
+                // count: exactly `no_containers` stat containers
+                multi::count(stat_container(has_counter), no_containers as usize)),
+
+            move |(t_start, stats): (u64, Vec<(u64, u32, Option<u64>)>)| {
+                PeriodMetric::with_counters(
+                    (t_start as f64) / (counter_freq as f64),
+                    // Just take the first size for now, for simplicity's sake.
+                    // Later on, this may vary from waypoint to waypoint.
+                    stats[0].1,
+                    stats.iter().map(|(t_end, _ds, _c)| (*t_end as f64) / (counter_freq as f64)),
+                    stats.iter().filter_map(|(_t_end, _ds, c)| *c))
+            })
             (data)
     }
 
@@ -357,6 +595,7 @@ mod parsing {
     fn benchmark_period_metrics<'a>(
         counter_freq: u32,
         no_containers: u8,
+        has_counter: bool,
         data: &'a [u8]
     ) -> ByteResult<'a, PerformanceData>
     {
@@ -364,33 +603,196 @@ mod parsing {
         // for passing result and data from one parser to another.
 
         combinator::map(
-            multi::many0(combinator::map(
-                // pair: <header> + <N stat containers>
-                sequence::pair(
-                    // preceded: <header tag> + <64-bit timestamp>
-                    sequence::preceded(bytes::tag([0b1000_0000]), little_u64),
-                    // count: exactly `no_containers` stat containers
-                    multi::count(
-                        // pair: <64-bit timestamp> + <32-bit accumulated data size>
-                        sequence::pair(little_u64, little_u32),
-                        no_containers as usize)),
-
-                |(t_start, stats): (u64, Vec<(u64, u32)>)| {
-                    PeriodMetric::new(
-                        (t_start as f64) / (counter_freq as f64),
-                        // Just take the first size for now, for simplicity's sake.
-                        // Later on, this may vary from waypoint to waypoint.
-                        stats[0].1,
-                        stats.iter().map(|(t_end, _ds)| (*t_end as f64) / (counter_freq as f64)))
-                })),
+            multi::many0(move |d| benchmark_one_period_metric(counter_freq, no_containers, has_counter, d)),
             |metrics| PerformanceData::new(no_containers, metrics))
             (data)
     }
 
     /// Benchmark data complete parser.
     pub fn benchmark_data<'a>(data: &'a [u8]) -> ByteResult<PerformanceData> {
-        let (data, (no_stats, freq)) = benchmark_init(data)?;
-        benchmark_period_metrics(freq, no_stats, data)
+        let (data, (no_stats, freq, has_counter)) = benchmark_init(data)?;
+        benchmark_period_metrics(freq, no_stats, has_counter, data)
+    }
+
+    /// Decodes one [`CounterId`] from its wire representation (see
+    /// [`flexbed_shared::mem::CounterId::serialize`]): a tag byte (`u8::from(CounterId) ^ 0x80`)
+    /// followed by one `u32` (`PCB`/`UpcallQueue`/`GrantPointerTable`/`CustomGrant`) or two
+    /// (`Grant`).
+    pub(crate) fn counter_id<'a>(data: &'a [u8]) -> ByteResult<'a, CounterId> {
+        if data.is_empty() {
+            return Err(nom::Err::Error(ByteError::from_error_kind(data, nom::error::ErrorKind::Eof)));
+        }
+
+        let tag = data[0] ^ 0b1000_0000;
+        let data = &data[1..];
+
+        match tag {
+            1 => combinator::map(little_u32, CounterId::PCB)(data),
+            2 => combinator::map(little_u32, CounterId::UpcallQueue)(data),
+            3 => combinator::map(little_u32, CounterId::GrantPointerTable)(data),
+            4 => combinator::map(
+                sequence::pair(little_u32, little_u32),
+                |(grant_no, val)| CounterId::Grant(grant_no, val))
+                (data),
+            5 => combinator::map(little_u32, CounterId::CustomGrant)(data),
+            _ => Err(nom::Err::Error(ByteError::from_error_kind(data, nom::error::ErrorKind::Tag))),
+        }
+    }
+
+    /// Parses one [`MemorySample`], stamping it with `time`.
+    pub(crate) fn memory_one_sample<'a>(time: Instant, data: &'a [u8]) -> ByteResult<'a, MemorySample> {
+        combinator::map(counter_id, move |counter| {
+            let value = super::counter_value(&counter);
+            MemorySample { time, counter, value }
+        })(data)
+    }
+
+    /** Decodes as many back-to-back [`MemorySample`]s as `data` holds.
+
+    The wire format carries no timestamp of its own (unlike the performance trace protocol, which
+    embeds one derived from the DUT's own counter frequency), so every sample decoded from one
+    call is stamped with the same `time` -- the moment the whole capture was read, not the moment
+    each individual sample arrived.
+     */
+    pub fn memory_data<'a>(time: Instant, data: &'a [u8]) -> ByteResult<'a, Vec<MemorySample>> {
+        multi::many0(move |d| memory_one_sample(time, d))(data)
+    }
+
+    /// Parses one [`ControlFlowEvent`] -- a process id followed by the event id it reported --
+    /// stamping it with `time`.
+    pub(crate) fn control_flow_one_event<'a>(time: Instant, data: &'a [u8]) -> ByteResult<'a, ControlFlowEvent> {
+        combinator::map(
+            sequence::pair(little_u32, little_u32),
+            move |(process, event)| ControlFlowEvent { time, process, event })
+            (data)
+    }
+
+    /// Decodes as many back-to-back [`ControlFlowEvent`]s as `data` holds. As with
+    /// [`memory_data`], every event from one call is stamped with the same `time`.
+    pub fn control_flow_data<'a>(time: Instant, data: &'a [u8]) -> ByteResult<'a, Vec<ControlFlowEvent>> {
+        multi::many0(move |d| control_flow_one_event(time, d))(data)
+    }
+}
+
+/// Number of data bits carried per UART frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DataBits {
+    Five,
+    Six,
+    Seven,
+    Eight,
+}
+
+impl From<DataBits> for u8 {
+    fn from(bits: DataBits) -> u8 {
+        match bits {
+            DataBits::Five => 5,
+            DataBits::Six => 6,
+            DataBits::Seven => 7,
+            DataBits::Eight => 8,
+        }
+    }
+}
+
+impl TryFrom<u8> for DataBits {
+    type Error = std::io::Error;
+
+    fn try_from(bits: u8) -> std::io::Result<DataBits> {
+        match bits {
+            5 => Ok(DataBits::Five),
+            6 => Ok(DataBits::Six),
+            7 => Ok(DataBits::Seven),
+            8 => Ok(DataBits::Eight),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid UART data bit count")),
+        }
+    }
+}
+
+/** Line settings for a UART channel collecting trace data.
+
+Mirrors the configuration surface of a typical embedded HAL UART driver -- baud rate, frame shape
+(data bits, parity, stop bits), and the minimum-read-length/timeout pair
+[`rppal::uart::Uart::set_read_mode`] takes -- so a testbed wiring up several trace channels can
+describe each one's serial parameters instead of every channel sharing whatever the UART happened
+to be constructed with. [`prepare()`] applies a `UartConfig` to the UART it is given; hang on to
+the one used for a given channel and pass it to [`TraceData::write_to`] so a saved trace stays
+linked to the settings it was captured under.
+ */
+#[derive(Clone, Debug)]
+pub struct UartConfig {
+    baud_rate: u32,
+    data_bits: DataBits,
+    parity: uart::Parity,
+    stop_bits: u8,
+    read_min_length: u8,
+    read_timeout: Duration,
+}
+
+impl UartConfig {
+    /// Creates a config at `baud_rate`, defaulting to 8 data bits, no parity, 1 stop bit, and the
+    /// `(0, 100ms)` read mode `prepare()` previously hardcoded.
+    pub fn new(baud_rate: u32) -> UartConfig {
+        UartConfig {
+            baud_rate,
+            data_bits: DataBits::Eight,
+            parity: uart::Parity::None,
+            stop_bits: 1,
+            read_min_length: 0,
+            read_timeout: Duration::from_millis(100),
+        }
+    }
+
+    pub fn with_data_bits(mut self, data_bits: DataBits) -> UartConfig {
+        self.data_bits = data_bits;
+        self
+    }
+
+    pub fn with_parity(mut self, parity: uart::Parity) -> UartConfig {
+        self.parity = parity;
+        self
+    }
+
+    pub fn with_stop_bits(mut self, stop_bits: u8) -> UartConfig {
+        self.stop_bits = stop_bits;
+        self
+    }
+
+    /// Sets the minimum number of bytes a read will block for and the timeout it gives up after
+    /// (see [`rppal::uart::Uart::set_read_mode`]).
+    pub fn with_read_mode(mut self, min_length: u8, timeout: Duration) -> UartConfig {
+        self.read_min_length = min_length;
+        self.read_timeout = timeout;
+        self
+    }
+
+    pub fn baud_rate(&self) -> u32 {
+        self.baud_rate
+    }
+
+    pub fn data_bits(&self) -> DataBits {
+        self.data_bits
+    }
+
+    pub fn parity(&self) -> uart::Parity {
+        self.parity.clone()
+    }
+
+    pub fn stop_bits(&self) -> u8 {
+        self.stop_bits
+    }
+
+    /** Applies this configuration's line settings to `uart`.
+
+    Data bits and stop bits are fixed when an [`rppal::uart::Uart`] is constructed (see
+    [`rppal::uart::Uart::with_path`]) and cannot be changed on an already-open UART, so only baud
+    rate, parity, and read mode take effect here -- the rest is still recorded on the `UartConfig`
+    so the value that prepared a channel stays available for [`TraceData::write_to`].
+     */
+    fn apply(&self, uart: &mut Uart) -> io::Result<()> {
+        uart.set_baud_rate(self.baud_rate)?;
+        uart.set_parity(self.parity.clone())?;
+        uart.set_read_mode(self.read_min_length, self.read_timeout)?;
+        Ok(())
     }
 }
 
@@ -403,8 +805,9 @@ const SERIAL_BUFFER_SIZE: usize = 64 * 1024;
 /// This ensures that `prepare()` has executed prior to the call to `collect()`.
 pub struct PreparedBuffer<'a>(&'a mut Vec<u8>);
 
-/// Prepare the a buffer and the UART for serial data collection.
-pub fn prepare<'a>(buffer: &'a mut Vec<u8>, uart: &mut Uart) -> io::Result<PreparedBuffer<'a>> {
+/// Prepare the a buffer and the UART for serial data collection, applying `config`'s line
+/// settings to `uart`.
+pub fn prepare<'a>(buffer: &'a mut Vec<u8>, uart: &mut Uart, config: &UartConfig) -> io::Result<PreparedBuffer<'a>> {
     buffer.clear();
     // Just use a constant size for now.
     // We have to push data into the buffer to make it possible to
@@ -412,7 +815,7 @@ pub fn prepare<'a>(buffer: &'a mut Vec<u8>, uart: &mut Uart) -> io::Result<Prepa
     buffer.reserve(SERIAL_BUFFER_SIZE);
     while buffer.len() < SERIAL_BUFFER_SIZE { buffer.push(0); }
 
-    uart.set_read_mode(0, Duration::from_millis(100))?;
+    config.apply(uart)?;
     uart.flush(uart::Queue::Input)?;
 
     Ok(PreparedBuffer(buffer))
@@ -444,6 +847,621 @@ pub fn collect(kind: &TraceKind, uart: &mut Uart, buffer: PreparedBuffer, until:
             })
             .map_err(|e| format!("parsing error: {:?}", e)),
 
+        TraceKind::Memory => parsing::memory_data(Instant::now(), &buffer[0..bytes_read])
+            .map(|(unparsed, data)| {
+                println!("tracing left {} bytes unparsed", unparsed.len());
+                TraceData::Memory(data)
+            })
+            .map_err(|e| format!("parsing error: {:?}", e)),
+
+        TraceKind::ControlFlow => parsing::control_flow_data(Instant::now(), &buffer[0..bytes_read])
+            .map(|(unparsed, data)| {
+                println!("tracing left {} bytes unparsed", unparsed.len());
+                TraceData::ControlFlow(data)
+            })
+            .map_err(|e| format!("parsing error: {:?}", e)),
+
         _ => unimplemented!()
     }
 }
+
+/// Bytes [`collect_streaming`] had to discard to make room in its ring buffer, plus the largest
+/// amount of unparsed data the ring ever held at once (useful for sizing a future run's buffer).
+#[derive(Copy, Clone, Debug, Default)]
+pub struct StreamStats {
+    dropped_bytes: u64,
+    high_water_mark: usize,
+}
+
+impl StreamStats {
+    /// Bytes that arrived but had to be discarded because the ring buffer had no room left for
+    /// them when a too-slow consumer (or a too-small `buffer_size`) let it fill up.
+    pub fn dropped_bytes(&self) -> u64 {
+        self.dropped_bytes
+    }
+
+    /// The largest amount of unparsed data the ring buffer held at once over the run.
+    pub fn high_water_mark(&self) -> usize {
+        self.high_water_mark
+    }
+}
+
+/// One fully-decoded unit of trace data, as emitted incrementally by [`collect_streaming`].
+#[derive(Clone, Debug)]
+pub enum TraceRecord {
+    /// One decoded performance measurement period.
+    Performance(PeriodMetric),
+    /// One decoded memory counter sample.
+    Memory(MemorySample),
+    /// One decoded control flow event.
+    ControlFlow(ControlFlowEvent),
+    /// One raw read, for [`TraceKind::Raw`], which has no structured parser of its own.
+    Serial(SerialTrace),
+}
+
+/// Read chunk size [`collect_streaming`] uses per `uart.read()` call.
+const STREAM_READ_CHUNK: usize = 4 * 1024;
+
+/** Stream tracing data from `uart`, parsing and dispatching each complete record to `sink` as soon
+as it's decoded, rather than (as [`collect`] does) reading until `until` and only parsing
+afterwards out of one fixed-size buffer that silently truncates once full.
+
+Modeled on the eBPF perf-ring-buffer consumer pattern: reads accumulate into a ring of at most
+`buffer_size` bytes, the relevant parser runs over whatever's currently buffered, and each record
+it fully decodes is removed from the ring and handed to `sink` immediately; an unparsed trailing
+remainder (a record that's only partially arrived) is kept for the next read. If an incoming read
+would grow the ring past `buffer_size`, the oldest unparsed bytes are dropped to make room, and the
+amount dropped is tallied in the returned [`StreamStats`] instead of being silently lost.
+
+[`TraceKind::Performance`], [`TraceKind::Memory`], and [`TraceKind::ControlFlow`] all have a
+record-level parser applied incrementally this way; [`TraceKind::Raw`] has no structure to exploit,
+so it's emitted as a [`TraceRecord::Serial`] per read, the same granularity [`reconstruct_serial`]
+works from.
+ */
+pub fn collect_streaming(
+    kind: &TraceKind,
+    uart: &mut Uart,
+    until: Instant,
+    buffer_size: usize,
+    mut sink: impl FnMut(TraceRecord),
+) -> Result<StreamStats> {
+    let mut ring: Vec<u8> = Vec::with_capacity(buffer_size);
+    let mut read_chunk = vec![0u8; STREAM_READ_CHUNK];
+    let mut stats = StreamStats::default();
+    let mut perf_header: Option<(u8, u32, bool)> = None;
+
+    while Instant::now() < until {
+        let read = uart.read(&mut read_chunk).map_err(|e| e.to_string())?;
+        if read == 0 {
+            continue;
+        }
+
+        let incoming = &read_chunk[..read];
+        if incoming.len() >= buffer_size {
+            // This read alone doesn't fit; the whole ring (all older than this read) and the
+            // read's own leading overflow are both dropped.
+            let keep_from = incoming.len() - buffer_size;
+            stats.dropped_bytes += (ring.len() + keep_from) as u64;
+            ring.clear();
+            ring.extend_from_slice(&incoming[keep_from..]);
+        } else {
+            if ring.len() + incoming.len() > buffer_size {
+                let drop_n = ring.len() + incoming.len() - buffer_size;
+                ring.drain(..drop_n);
+                stats.dropped_bytes += drop_n as u64;
+            }
+            ring.extend_from_slice(incoming);
+        }
+        stats.high_water_mark = stats.high_water_mark.max(ring.len());
+
+        match kind {
+            TraceKind::Performance(_) => {
+                if perf_header.is_none() {
+                    if let Ok((rest, header)) = parsing::benchmark_init(&ring) {
+                        let consumed = ring.len() - rest.len();
+                        ring.drain(..consumed);
+                        perf_header = Some(header);
+                    }
+                }
+
+                if let Some((no_containers, freq, has_counter)) = perf_header {
+                    while let Ok((rest, metric)) = parsing::benchmark_one_period_metric(freq, no_containers, has_counter, &ring) {
+                        let consumed = ring.len() - rest.len();
+                        ring.drain(..consumed);
+                        sink(TraceRecord::Performance(metric));
+                    }
+                }
+            },
+
+            TraceKind::Memory => {
+                while let Ok((rest, sample)) = parsing::memory_one_sample(Instant::now(), &ring) {
+                    let consumed = ring.len() - rest.len();
+                    ring.drain(..consumed);
+                    sink(TraceRecord::Memory(sample));
+                }
+            },
+
+            TraceKind::ControlFlow => {
+                while let Ok((rest, event)) = parsing::control_flow_one_event(Instant::now(), &ring) {
+                    let consumed = ring.len() - rest.len();
+                    ring.drain(..consumed);
+                    sink(TraceRecord::ControlFlow(event));
+                }
+            },
+
+            TraceKind::Raw if !ring.is_empty() => {
+                sink(TraceRecord::Serial(SerialTrace::new(Instant::now(), &ring)));
+                ring.clear();
+            },
+
+            TraceKind::Raw => (),
+        }
+    }
+
+    Ok(stats)
+}
+
+/// Magic bytes opening a file written by [`TraceData::write_to`]; checked by
+/// [`TraceData::read_from`] before anything else so a file from somewhere else is rejected
+/// quickly rather than partway into parsing.
+const FORMAT_MAGIC: [u8; 4] = *b"CWTR";
+
+/// On-disk format version [`TraceData::write_to`] writes. [`TraceData::read_from`] rejects
+/// anything else, so this is bumped whenever the record layout below changes incompatibly.
+const FORMAT_VERSION: u32 = 3;
+
+/// Discriminant written in a serialized trace's header identifying which [`TraceData`] variant
+/// follows -- a reader has no [`TraceKind`] of its own to go on, unlike [`collect`]/
+/// [`collect_streaming`], which are always called with one.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u8)]
+enum FormatKind {
+    Raw = 0,
+    ControlFlow = 1,
+    Memory = 2,
+    Performance = 3,
+}
+
+impl FormatKind {
+    fn from_tag(tag: u8) -> std::io::Result<FormatKind> {
+        match tag {
+            0 => Ok(FormatKind::Raw),
+            1 => Ok(FormatKind::ControlFlow),
+            2 => Ok(FormatKind::Memory),
+            3 => Ok(FormatKind::Performance),
+            other => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("unrecognized trace kind tag {}", other))),
+        }
+    }
+}
+
+/// Identifies one string in a serialized trace's string table (see [`TraceData::write_to`]).
+/// Every distinct waypoint label and unit string is written once; everywhere else it's referenced
+/// by the `StringId` assigned to it, rather than inlined again.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+struct StringId(u32);
+
+/// Write-side string interner backing a serialized trace's string table: assigns each distinct
+/// string a [`StringId`] in the order it's first seen.
+#[derive(Default)]
+struct StringTable {
+    strings: Vec<String>,
+    ids: HashMap<String, StringId>,
+}
+
+impl StringTable {
+    fn intern(&mut self, s: &str) -> StringId {
+        if let Some(id) = self.ids.get(s) {
+            return *id;
+        }
+
+        let id = StringId(self.strings.len() as u32);
+        self.strings.push(s.to_string());
+        self.ids.insert(s.to_string(), id);
+
+        id
+    }
+
+    fn write_to(&self, out: &mut impl std::io::Write) -> std::io::Result<()> {
+        out.write_all(&(self.strings.len() as u32).to_le_bytes())?;
+        for s in &self.strings {
+            let bytes = s.as_bytes();
+            out.write_all(&(bytes.len() as u32).to_le_bytes())?;
+            out.write_all(bytes)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a string table off the front of `data`, returning the interned strings (indexable by
+    /// the `u32` inside a [`StringId`]) and whatever of `data` follows the table.
+    fn read_from(data: &[u8]) -> std::io::Result<(Vec<String>, &[u8])> {
+        let mut data = data;
+        let count = read_u32(&mut data)?;
+
+        let mut strings = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let len = read_u32(&mut data)? as usize;
+            if data.len() < len {
+                return Err(unexpected_eof());
+            }
+
+            let (s_bytes, rest) = data.split_at(len);
+            let s = String::from_utf8(s_bytes.to_vec())
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            strings.push(s);
+            data = rest;
+        }
+
+        Ok((strings, data))
+    }
+}
+
+fn unexpected_eof() -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::UnexpectedEof,
+        "trace file ended before a section it declared finished")
+}
+
+/// Wire tag for an [`rppal::uart::Parity`], written out by hand rather than cast since its
+/// discriminant values are not part of rppal's public API.
+fn parity_tag(parity: &uart::Parity) -> u8 {
+    use uart::Parity::*;
+    match parity {
+        None => 0,
+        Even => 1,
+        Odd => 2,
+        Mark => 3,
+        Space => 4,
+    }
+}
+
+fn parity_from_tag(tag: u8) -> std::io::Result<uart::Parity> {
+    use uart::Parity::*;
+    match tag {
+        0 => Ok(None),
+        1 => Ok(Even),
+        2 => Ok(Odd),
+        3 => Ok(Mark),
+        4 => Ok(Space),
+        other => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("unrecognized UART parity tag {}", other))),
+    }
+}
+
+fn read_u32(data: &mut &[u8]) -> std::io::Result<u32> {
+    if data.len() < 4 {
+        return Err(unexpected_eof());
+    }
+
+    let (bytes, rest) = data.split_at(4);
+    *data = rest;
+    Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_u64(data: &mut &[u8]) -> std::io::Result<u64> {
+    if data.len() < 8 {
+        return Err(unexpected_eof());
+    }
+
+    let (bytes, rest) = data.split_at(8);
+    *data = rest;
+    Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_f64(data: &mut &[u8]) -> std::io::Result<f64> {
+    if data.len() < 8 {
+        return Err(unexpected_eof());
+    }
+
+    let (bytes, rest) = data.split_at(8);
+    *data = rest;
+    Ok(f64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+impl TraceData {
+    /** Serialize this trace (collected under `info`) to `out`, in a self-describing format
+    inspired by rustc's measureme profiler: a fixed header (magic bytes, a format version, and a
+    tag identifying which variant follows), a string table (every distinct waypoint label and unit
+    string, written once and referenced by id from then on), then a records section.
+
+    A file written this way can be read back with [`TraceData::read_from`] without the original
+    [`super::test::Test`]/[`TraceKind`] the trace was collected under -- unlike the in-memory
+    `TraceData`, which on its own can't be interpreted without the matching `TraceKind` alongside
+    it (see [`TraceData::summary`]).
+
+    Note that arrival times are not preserved: `Instant` is a monotonic,
+    process-local clock with no meaning once saved to disk, so [`TraceKind::ControlFlow`] and
+    [`TraceKind::Memory`] traces read back via [`TraceData::read_from`] carry a fresh `Instant`
+    instead of their original one. [`PeriodMetric`]'s timestamps are unaffected, since they were
+    already plain relative `f64` seconds rather than an `Instant`.
+
+    `uart_config` is the [`UartConfig`] the channel was [`prepare`]d with, if any (a `Raw` trace
+    collected outside [`prepare`]/[`collect`] may not have one) -- recording it keeps a saved trace
+    linked to the line settings it was captured under, so it can be read back without having to
+    remember or guess them.
+     */
+    pub fn write_to(
+        &self,
+        info: &TraceKind,
+        uart_config: Option<&UartConfig>,
+        out: &mut impl std::io::Write,
+    ) -> std::io::Result<()> {
+        out.write_all(&FORMAT_MAGIC)?;
+        out.write_all(&FORMAT_VERSION.to_le_bytes())?;
+
+        let kind = match (info, self) {
+            (TraceKind::Raw, TraceData::Raw(_)) => FormatKind::Raw,
+            (TraceKind::ControlFlow, TraceData::ControlFlow(_)) => FormatKind::ControlFlow,
+            (TraceKind::Memory, TraceData::Memory(_)) => FormatKind::Memory,
+            (TraceKind::Performance(_), TraceData::Performance(_)) => FormatKind::Performance,
+            _ => return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "TraceKind does not match TraceData")),
+        };
+        out.write_all(&[kind as u8])?;
+
+        match uart_config {
+            Some(config) => {
+                out.write_all(&[1])?;
+                out.write_all(&config.baud_rate.to_le_bytes())?;
+                out.write_all(&[u8::from(config.data_bits)])?;
+                out.write_all(&[parity_tag(&config.parity)])?;
+                out.write_all(&[config.stop_bits])?;
+                out.write_all(&[config.read_min_length])?;
+                out.write_all(&(config.read_timeout.as_micros() as u64).to_le_bytes())?;
+            },
+            None => out.write_all(&[0])?,
+        }
+
+        let mut strings = StringTable::default();
+        let unit_id = match info {
+            TraceKind::Performance(metadata) => Some(strings.intern(metadata.unit())),
+            _ => None,
+        };
+        let waypoint_ids: Vec<StringId> = match info {
+            TraceKind::Performance(metadata) =>
+                metadata.waypoints.iter().map(|w| strings.intern(&w.label)).collect(),
+            _ => Vec::new(),
+        };
+
+        strings.write_to(out)?;
+
+        match self {
+            TraceData::Raw(data) => {
+                out.write_all(&(data.len() as u32).to_le_bytes())?;
+                out.write_all(data)?;
+            },
+
+            TraceData::ControlFlow(events) => {
+                out.write_all(&(events.len() as u32).to_le_bytes())?;
+                for event in events {
+                    out.write_all(&event.process.to_le_bytes())?;
+                    out.write_all(&event.event.to_le_bytes())?;
+                }
+            },
+
+            TraceData::Memory(samples) => {
+                out.write_all(&(samples.len() as u32).to_le_bytes())?;
+                for sample in samples {
+                    // `value` is not written separately -- it is just `counter`'s own embedded
+                    // field (or second field, for `Grant`), so it is re-derived on read rather
+                    // than duplicated on the wire.
+                    let mut buf = [0u8; 9];
+                    let written = sample.counter.serialize(&mut buf);
+                    out.write_all(&buf[..written])?;
+                }
+            },
+
+            TraceData::Performance(data) => {
+                out.write_all(&[data.no_waypoints])?;
+
+                let unit_id = unit_id.expect("a Performance TraceKind always interns a unit string");
+                out.write_all(&unit_id.0.to_le_bytes())?;
+
+                out.write_all(&(waypoint_ids.len() as u32).to_le_bytes())?;
+                for id in &waypoint_ids {
+                    out.write_all(&id.0.to_le_bytes())?;
+                }
+
+                let counter_kind = match info {
+                    TraceKind::Performance(metadata) => metadata.counter_kind(),
+                    _ => None,
+                };
+                out.write_all(&[counter_kind.map_or(0, |k| k as u8 + 1)])?;
+
+                out.write_all(&(data.metrics.len() as u32).to_le_bytes())?;
+                for metric in &data.metrics {
+                    out.write_all(&metric.t_start.to_le_bytes())?;
+                    out.write_all(&metric.data_size.to_le_bytes())?;
+
+                    out.write_all(&(metric.t_ends.len() as u32).to_le_bytes())?;
+                    for t_end in &metric.t_ends {
+                        out.write_all(&t_end.to_le_bytes())?;
+                    }
+
+                    out.write_all(&(metric.counters.len() as u32).to_le_bytes())?;
+                    for counter in &metric.counters {
+                        out.write_all(&counter.to_le_bytes())?;
+                    }
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Reconstructs a `TraceData` (and the [`TraceKind`] -- including [`BenchmarkMetadata`], for
+    /// a performance trace -- it was collected under, and the [`UartConfig`] it was collected
+    /// with, if one was recorded) from bytes written by [`TraceData::write_to`]. Fails without
+    /// panicking on a bad magic number, an unsupported format version, an out-of-range
+    /// [`StringId`], or data that ends before a section it declared the length of does.
+    pub fn read_from(data: &[u8]) -> std::io::Result<(TraceKind, TraceData, Option<UartConfig>)> {
+        let mut data = data;
+
+        if data.len() < FORMAT_MAGIC.len() || data[..FORMAT_MAGIC.len()] != FORMAT_MAGIC[..] {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "not a Clockwise trace file (bad magic number)"));
+        }
+        data = &data[FORMAT_MAGIC.len()..];
+
+        let version = read_u32(&mut data)?;
+        if version != FORMAT_VERSION {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("unsupported trace format version {} (expected {})", version, FORMAT_VERSION)));
+        }
+
+        if data.is_empty() {
+            return Err(unexpected_eof());
+        }
+        let kind = FormatKind::from_tag(data[0])?;
+        data = &data[1..];
+
+        if data.is_empty() {
+            return Err(unexpected_eof());
+        }
+        let has_uart_config = data[0];
+        data = &data[1..];
+        let uart_config = match has_uart_config {
+            0 => None,
+            1 => {
+                let baud_rate = read_u32(&mut data)?;
+                if data.is_empty() {
+                    return Err(unexpected_eof());
+                }
+                let data_bits = DataBits::try_from(data[0])?;
+                data = &data[1..];
+
+                if data.is_empty() {
+                    return Err(unexpected_eof());
+                }
+                let parity = parity_from_tag(data[0])?;
+                data = &data[1..];
+
+                if data.len() < 2 {
+                    return Err(unexpected_eof());
+                }
+                let stop_bits = data[0];
+                let read_min_length = data[1];
+                data = &data[2..];
+
+                let read_timeout = Duration::from_micros(read_u64(&mut data)?);
+
+                Some(UartConfig { baud_rate, data_bits, parity, stop_bits, read_min_length, read_timeout })
+            },
+            other => return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("unrecognized UART config presence tag {}", other))),
+        };
+
+        let (strings, rest) = StringTable::read_from(data)?;
+        data = rest;
+        let lookup = |id: u32| strings.get(id as usize).map(String::as_str)
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "StringId out of range"));
+
+        match kind {
+            FormatKind::Raw => {
+                let len = read_u32(&mut data)? as usize;
+                if data.len() < len {
+                    return Err(unexpected_eof());
+                }
+
+                Ok((TraceKind::Raw, TraceData::Raw(data[..len].to_vec()), uart_config))
+            },
+
+            FormatKind::ControlFlow => {
+                let count = read_u32(&mut data)?;
+                let mut events = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    let process = read_u32(&mut data)?;
+                    let event = read_u32(&mut data)?;
+                    events.push(ControlFlowEvent { time: Instant::now(), process, event });
+                }
+
+                Ok((TraceKind::ControlFlow, TraceData::ControlFlow(events), uart_config))
+            },
+
+            FormatKind::Memory => {
+                let count = read_u32(&mut data)?;
+                let mut samples = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    let (rest, counter) = parsing::counter_id(data).map_err(|_| std::io::Error::new(
+                        std::io::ErrorKind::InvalidData, "malformed CounterId in memory trace"))?;
+                    data = rest;
+
+                    let value = counter_value(&counter);
+                    samples.push(MemorySample { time: Instant::now(), counter, value });
+                }
+
+                Ok((TraceKind::Memory, TraceData::Memory(samples), uart_config))
+            },
+
+            FormatKind::Performance => {
+                if data.is_empty() {
+                    return Err(unexpected_eof());
+                }
+                let no_waypoints = data[0];
+                data = &data[1..];
+
+                let unit = lookup(read_u32(&mut data)?)?.to_string();
+
+                let waypoint_count = read_u32(&mut data)?;
+                let mut waypoints = Vec::with_capacity(waypoint_count as usize);
+                for _ in 0..waypoint_count {
+                    let label = lookup(read_u32(&mut data)?)?.to_string();
+                    waypoints.push(WaypointMetadata { label });
+                }
+
+                if data.is_empty() {
+                    return Err(unexpected_eof());
+                }
+                let counter_kind = match data[0] {
+                    0 => None,
+                    1 => Some(CounterKind::Cycles),
+                    2 => Some(CounterKind::Instructions),
+                    3 => Some(CounterKind::CacheMisses),
+                    other => return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("unrecognized counter kind tag {}", other))),
+                };
+                data = &data[1..];
+
+                let metric_count = read_u32(&mut data)?;
+                let mut metrics = Vec::with_capacity(metric_count as usize);
+                for _ in 0..metric_count {
+                    let t_start = read_f64(&mut data)?;
+                    let data_size = read_u32(&mut data)?;
+
+                    let t_end_count = read_u32(&mut data)?;
+                    let mut t_ends = Vec::with_capacity(t_end_count as usize);
+                    for _ in 0..t_end_count {
+                        t_ends.push(read_f64(&mut data)?);
+                    }
+
+                    let counter_count = read_u32(&mut data)?;
+                    let mut counters = Vec::with_capacity(counter_count as usize);
+                    for _ in 0..counter_count {
+                        counters.push(read_u64(&mut data)?);
+                    }
+
+                    metrics.push(PeriodMetric::with_counters(t_start, data_size, t_ends, counters));
+                }
+
+                let mut metadata = BenchmarkMetadata::new(&unit, &waypoints);
+                if let Some(counter_kind) = counter_kind {
+                    metadata = metadata.with_counter_kind(counter_kind);
+                }
+
+                Ok((
+                    TraceKind::Performance(metadata),
+                    TraceData::Performance(PerformanceData::new(no_waypoints, metrics)),
+                    uart_config,
+                ))
+            },
+        }
+    }
+}
@@ -21,3 +21,138 @@ pub fn deserialize_u32(buffer: &[u8]) -> Result<u32, ()> {
            | (buffer[3] as u32) << 24)
     }
 }
+
+/// Place a 32-bit unsigned integer into a buffer as a LEB128 varint, returning the number of
+/// bytes written (at most 5). Takes fewer bytes than `serialize_u32` for small values.
+pub fn serialize_varint(mut n: u32, buffer: &mut [u8]) -> Result<usize, ()> {
+    let mut written = 0;
+    loop {
+        let byte = buffer.get_mut(written).ok_or(())?;
+        *byte = (n & 0x7F) as u8;
+        n >>= 7;
+
+        if n != 0 {
+            *byte |= 0x80;
+            written += 1;
+        } else {
+            written += 1;
+            break;
+        }
+    }
+
+    Ok(written)
+}
+
+/// Extract a LEB128 varint-encoded 32-bit unsigned integer from a buffer, returning the value and
+/// the number of bytes consumed.
+pub fn deserialize_varint(buffer: &[u8]) -> Result<(u32, usize), ()> {
+    let mut value: u32 = 0;
+    let mut consumed = 0;
+
+    loop {
+        let byte = *buffer.get(consumed).ok_or(())?;
+        value |= ((byte & 0x7F) as u32) << (7 * consumed);
+        consumed += 1;
+
+        if byte & 0x80 == 0 {
+            break;
+        } else if consumed >= 5 {
+            // A u32 cannot need more than 5 groups of 7 bits.
+            return Err(());
+        }
+    }
+
+    Ok((value, consumed))
+}
+
+/// Place a 32-bit signed integer into a buffer as a zigzag-encoded LEB128 varint.
+pub fn serialize_varint_signed(n: i32, buffer: &mut [u8]) -> Result<usize, ()> {
+    let zigzagged = ((n << 1) ^ (n >> 31)) as u32;
+    serialize_varint(zigzagged, buffer)
+}
+
+/// Extract a zigzag-encoded LEB128 varint-encoded 32-bit signed integer from a buffer, returning
+/// the value and the number of bytes consumed.
+pub fn deserialize_varint_signed(buffer: &[u8]) -> Result<(i32, usize), ()> {
+    let (zigzagged, consumed) = deserialize_varint(buffer)?;
+    let value = ((zigzagged >> 1) ^ (zigzagged & 1).wrapping_neg()) as i32;
+
+    Ok((value, consumed))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn u32_round_trips() {
+        let mut buffer = [0u8; 4];
+        serialize_u32(0xDEADBEEF, &mut buffer);
+        assert_eq!(deserialize_u32(&buffer).unwrap(), 0xDEADBEEF);
+    }
+
+    #[test]
+    pub fn deserialize_u32_rejects_short_buffer() {
+        assert_eq!(deserialize_u32(&[1, 2, 3]), Err(()));
+    }
+
+    #[test]
+    pub fn varint_fits_single_byte_below_128() {
+        let mut buffer = [0u8; 5];
+        let written = serialize_varint(127, &mut buffer).unwrap();
+        assert_eq!(written, 1);
+        assert_eq!(buffer[0], 127);
+    }
+
+    #[test]
+    pub fn varint_continues_past_127() {
+        let mut buffer = [0u8; 5];
+        let written = serialize_varint(128, &mut buffer).unwrap();
+        assert_eq!(written, 2);
+        assert_eq!(buffer[0], 0x80); // low 7 bits (0) with continuation bit set
+        assert_eq!(buffer[1], 0x01); // remaining bits
+    }
+
+    #[test]
+    pub fn varint_round_trips_across_value_range() {
+        for n in [0u32, 1, 127, 128, 16_384, u32::MAX] {
+            let mut buffer = [0u8; 5];
+            let written = serialize_varint(n, &mut buffer).unwrap();
+            let (value, consumed) = deserialize_varint(&buffer).unwrap();
+            assert_eq!(value, n);
+            assert_eq!(consumed, written);
+        }
+    }
+
+    #[test]
+    pub fn deserialize_varint_rejects_never_ending_continuation() {
+        let buffer = [0x80, 0x80, 0x80, 0x80, 0x80];
+        assert_eq!(deserialize_varint(&buffer), Err(()));
+    }
+
+    #[test]
+    pub fn serialize_varint_rejects_buffer_too_small() {
+        let mut buffer = [0u8; 1];
+        assert_eq!(serialize_varint(u32::MAX, &mut buffer), Err(()));
+    }
+
+    #[test]
+    pub fn signed_varint_round_trips_negative_and_positive() {
+        for n in [0i32, 1, -1, 63, -64, i32::MAX, i32::MIN] {
+            let mut buffer = [0u8; 5];
+            let written = serialize_varint_signed(n, &mut buffer).unwrap();
+            let (value, consumed) = deserialize_varint_signed(&buffer).unwrap();
+            assert_eq!(value, n);
+            assert_eq!(consumed, written);
+        }
+    }
+
+    #[test]
+    pub fn signed_varint_small_negative_fits_one_byte() {
+        // Zigzag maps -1 to 1, which fits in a single continuation-free byte.
+        let mut buffer = [0u8; 5];
+        let written = serialize_varint_signed(-1, &mut buffer).unwrap();
+        assert_eq!(written, 1);
+        assert_eq!(buffer[0], 1);
+    }
+}
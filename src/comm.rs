@@ -4,6 +4,8 @@ use std::convert::TryFrom;
 use std::fmt;
 use std::fmt::Display;
 
+use serde::{Deserialize, Serialize};
+
 /// Direction of information flow.
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Direction {
@@ -51,7 +53,7 @@ impl TryFrom<&str> for Class {
 
 /// A signal value.
 #[allow(dead_code)]
-#[derive(Copy, Clone, Eq, Debug, PartialEq)]
+#[derive(Copy, Clone, Eq, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Signal {
     /// Digital; true for high, false for low
     Digital(bool),
@@ -13,4 +13,26 @@ pub trait EnergyMetering: Debug + Send {
     fn cooldown_duration(&self) -> Duration {
         Duration::from_millis(0)
     }
+
+    /** Begin integrating [`current`](Self::current)/[`power`](Self::power) readings over time.
+
+    Resets any previously accumulated [`energy`](Self::energy)/[`charge`](Self::charge) totals.
+    Meters that don't support integration are free to leave this a no-op.
+     */
+    fn start_accumulation(&self) {  }
+
+    /// Stop integrating readings started by [`start_accumulation`](Self::start_accumulation).
+    fn stop_accumulation(&self) {  }
+
+    /// Returns the total energy consumed in millijoules since the last [`start_accumulation`](Self::start_accumulation)
+    /// call. Meters that don't support integration return 0.0.
+    fn energy(&self) -> f32 {
+        0.0
+    }
+
+    /// Returns the total charge consumed in milliamp-hours since the last [`start_accumulation`](Self::start_accumulation)
+    /// call. Meters that don't support integration return 0.0.
+    fn charge(&self) -> f32 {
+        0.0
+    }
 }
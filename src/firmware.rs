@@ -0,0 +1,242 @@
+/*! Provisioning the device under test with a known firmware image before a test run.
+
+Tests are typically written against a specific firmware image; this module drives the
+reset/boot-select sequence over the testbed's control pins, transfers the image, and verifies
+completion so a run starts from a known state. See [`crate::io::Mapping::flash_device`].
+ */
+
+use std::fmt;
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::thread;
+use std::time::Duration;
+
+use rppal::gpio::{Gpio, Level};
+use rppal::uart::{Parity, Uart};
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// How long to hold BOOT/RESET asserted before releasing, and how long to let the bootloader
+/// settle afterward.
+const RESET_PULSE: Duration = Duration::from_millis(100);
+const BOOTLOADER_SETTLE: Duration = Duration::from_millis(500);
+
+/// Errors related to flashing firmware onto the device under test.
+#[derive(Debug)]
+pub enum Error {
+    /// Error driving the boot-select/reset pins.
+    Gpio(rppal::gpio::Error),
+    /// Error reading the firmware image off disk.
+    Io(std::io::Error),
+    /// Error communicating with a serial bootloader.
+    Uart(rppal::uart::Error),
+    /// An external flashing tool (e.g. `dfu-util`, `openocd`) exited unsuccessfully.
+    Tool(Output),
+    /// The device did not acknowledge the image in the way the method expects.
+    Verification(String),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Gpio(ref e) => Some(e),
+            Error::Io(ref e) => Some(e),
+            Error::Uart(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Gpio(ref e) => write!(f, "error driving boot/reset pins: {}", e),
+            Error::Io(ref e) => write!(f, "error reading firmware image: {}", e),
+            Error::Uart(ref e) => write!(f, "serial bootloader communication error: {}", e),
+            Error::Tool(ref output) => write!(f, "flashing tool exited with {}", output.status),
+            Error::Verification(ref msg) => write!(f, "could not verify flash completion: {}", msg),
+        }
+    }
+}
+
+impl From<rppal::gpio::Error> for Error {
+    fn from(e: rppal::gpio::Error) -> Self {
+        Error::Gpio(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<rppal::uart::Error> for Error {
+    fn from(e: rppal::uart::Error) -> Self {
+        Error::Uart(e)
+    }
+}
+
+/** Method used to provision the device under test with a firmware image.
+
+Each variant carries the host pin numbers used to drive the boot-select/reset sequence, plus
+whatever is needed to actually transfer the image for that method.
+ */
+#[derive(Clone, Debug)]
+pub enum FlashMethod {
+    /// DFU over USB, driven by shelling out to `dfu-util`.
+    Dfu {
+        /// Host pin that selects the bootloader when held low across reset.
+        boot_pin: u8,
+        /// Host pin wired to the target's reset line (active low).
+        reset_pin: u8,
+        /// Path to the `dfu-util` binary.
+        dfu_util_path: PathBuf,
+        /// `dfu-util` alt-setting/interface spec, e.g. "0".
+        alt: String,
+    },
+    /// SWD, driven by shelling out to `openocd`.
+    Swd {
+        /// Host pin wired to the target's reset line (active low).
+        reset_pin: u8,
+        /// Path to the `openocd` binary.
+        openocd_path: PathBuf,
+        /// openocd interface/target configuration file.
+        config_path: PathBuf,
+    },
+    /// Vendor serial bootloader reached over UART (e.g. the STM32 USART bootloader).
+    SerialBootloader {
+        /// Host pin that selects the bootloader when held high across reset.
+        boot_pin: u8,
+        /// Host pin wired to the target's reset line (active low).
+        reset_pin: u8,
+        /// Serial device the bootloader answers on.
+        uart_path: PathBuf,
+        /// Baud rate the bootloader expects.
+        baud_rate: u32,
+        /// Block size used to stream the image, in bytes.
+        block_size: usize,
+    },
+}
+
+/// Assert BOOT (if given) and pulse RESET so the target comes up in its bootloader, then let the
+/// bootloader settle before any transfer begins.
+fn enter_bootloader(boot_pin: Option<(u8, Level)>, reset_pin: u8) -> Result<()> {
+    let gpio = Gpio::new()?;
+
+    let mut boot = boot_pin.map(|(pin_no, level)| -> Result<_> {
+        let mut pin = gpio.get(pin_no)?.into_output();
+        pin.write(level);
+        Ok(pin)
+    }).transpose()?;
+
+    let mut reset = gpio.get(reset_pin)?.into_output();
+    reset.write(Level::Low);
+    thread::sleep(RESET_PULSE);
+    reset.write(Level::High);
+    thread::sleep(BOOTLOADER_SETTLE);
+
+    // Held for the duration of the bootloader session; released once flashing completes and the
+    // target is reset into the application below.
+    if let Some(ref mut boot) = boot {
+        boot.set_reset_on_drop(false);
+    }
+    reset.set_reset_on_drop(false);
+
+    Ok(())
+}
+
+/// Pulse RESET with BOOT released/low so the target comes up running the freshly flashed
+/// application.
+fn reset_into_application(reset_pin: u8) -> Result<()> {
+    let gpio = Gpio::new()?;
+    let mut reset = gpio.get(reset_pin)?.into_output();
+    reset.write(Level::Low);
+    thread::sleep(RESET_PULSE);
+    reset.write(Level::High);
+
+    Ok(())
+}
+
+fn flash_dfu(image: &Path, boot_pin: u8, reset_pin: u8, dfu_util_path: &Path, alt: &str) -> Result<()> {
+    enter_bootloader(Some((boot_pin, Level::Low)), reset_pin)?;
+
+    let output = Command::new(dfu_util_path)
+        .args(&["-a", alt, "-D"])
+        .arg(image)
+        .output()?;
+
+    reset_into_application(reset_pin)?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(Error::Tool(output))
+    }
+}
+
+fn flash_swd(image: &Path, reset_pin: u8, openocd_path: &Path, config_path: &Path) -> Result<()> {
+    enter_bootloader(None, reset_pin)?;
+
+    let program_cmd = format!("program {} verify reset exit", image.display());
+    let output = Command::new(openocd_path)
+        .arg("-f").arg(config_path)
+        .args(&["-c", program_cmd.as_str()])
+        .output()?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(Error::Tool(output))
+    }
+}
+
+fn flash_serial_bootloader(image: &Path,
+                           boot_pin: u8,
+                           reset_pin: u8,
+                           uart_path: &Path,
+                           baud_rate: u32,
+                           block_size: usize) -> Result<()> {
+    enter_bootloader(Some((boot_pin, Level::High)), reset_pin)?;
+
+    let uart_path_str = uart_path.to_str()
+        .ok_or_else(|| Error::Verification("UART device path is not valid UTF-8".to_string()))?;
+    let mut uart = Uart::with_path(uart_path_str, baud_rate, Parity::None, 8, 1)?;
+    uart.set_write_mode(true)?;
+    uart.set_read_mode(1, Duration::from_millis(500))?;
+
+    let image_bytes = fs::read(image)?;
+    for (i, block) in image_bytes.chunks(block_size).enumerate() {
+        uart.write(block)?;
+
+        // Vendor bootloaders typically ACK each block with a single status byte before
+        // accepting the next one.
+        let mut ack = [0u8; 1];
+        let n = uart.read(&mut ack)?;
+        if n == 0 || ack[0] != 0 {
+            return Err(Error::Verification(format!(
+                "block starting at offset {} was not acknowledged",
+                i * block_size)));
+        }
+    }
+
+    reset_into_application(reset_pin)?;
+
+    Ok(())
+}
+
+/// Flash `image` onto the device under test using the given method.
+pub fn flash(image: &Path, method: &FlashMethod) -> Result<()> {
+    match method {
+        FlashMethod::Dfu { boot_pin, reset_pin, dfu_util_path, alt } =>
+            flash_dfu(image, *boot_pin, *reset_pin, dfu_util_path, alt),
+
+        FlashMethod::Swd { reset_pin, openocd_path, config_path } =>
+            flash_swd(image, *reset_pin, openocd_path, config_path),
+
+        FlashMethod::SerialBootloader { boot_pin, reset_pin, uart_path, baud_rate, block_size } =>
+            flash_serial_bootloader(image, *boot_pin, *reset_pin, uart_path, *baud_rate, *block_size),
+    }
+}
@@ -0,0 +1,88 @@
+//! Hardware-free [`EnergyMetering`], for use alongside [`crate::sw::emulated::Emulated`] when
+//! there's no board (and so no real current sensor) attached.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::facility::EnergyMetering;
+
+/// Synthetic current/power source: a fixed idle baseline, bumped above baseline for a short
+/// window every time [`EmulatedEnergyMeter::note_activity`] is called. `energy`/`charge` sample
+/// the current reading at the moment they're asked rather than truly integrating over time (there
+/// being no real ADC to poll in the background, unlike [`crate::hw::INA219`]'s accumulator
+/// thread), so they're an approximation suited to development/CI runs, not calibration-grade
+/// figures.
+#[derive(Debug)]
+pub struct EmulatedEnergyMeter {
+    baseline_ma: f32,
+    active_ma: f32,
+    supply_voltage: f32,
+    activity_window: Duration,
+    last_activity: Mutex<Option<Instant>>,
+    accumulating_since: Mutex<Option<Instant>>,
+}
+
+impl EmulatedEnergyMeter {
+    /** Create a new emulated meter.
+
+    `baseline_ma` is the synthetic idle current draw; `active_ma` is added on top of it for
+    `activity_window` after each [`note_activity`](Self::note_activity) call; `supply_voltage`
+    converts current into a power reading.
+     */
+    pub fn new(baseline_ma: f32, active_ma: f32, supply_voltage: f32) -> EmulatedEnergyMeter {
+        EmulatedEnergyMeter {
+            baseline_ma,
+            active_ma,
+            supply_voltage,
+            activity_window: Duration::from_millis(50),
+            last_activity: Mutex::new(None),
+            accumulating_since: Mutex::new(None),
+        }
+    }
+
+    /// Record a unit of emulated device activity (e.g. an app being loaded, a trace event being
+    /// raised), bumping the synthetic current/power reading above baseline for a short window.
+    pub fn note_activity(&self) {
+        *self.last_activity.lock().unwrap() = Some(Instant::now());
+    }
+
+    fn is_active(&self) -> bool {
+        self.last_activity.lock().unwrap()
+            .map(|t| t.elapsed() < self.activity_window)
+            .unwrap_or(false)
+    }
+}
+
+impl EnergyMetering for EmulatedEnergyMeter {
+    fn current(&self) -> f32 {
+        if self.is_active() {
+            self.baseline_ma + self.active_ma
+        } else {
+            self.baseline_ma
+        }
+    }
+
+    fn power(&self) -> f32 {
+        self.current() * self.supply_voltage
+    }
+
+    fn start_accumulation(&self) {
+        *self.accumulating_since.lock().unwrap() = Some(Instant::now());
+    }
+
+    fn stop_accumulation(&self) {
+        *self.accumulating_since.lock().unwrap() = None;
+    }
+
+    fn energy(&self) -> f32 {
+        self.accumulating_since.lock().unwrap()
+            .map(|t0| self.power() * t0.elapsed().as_secs_f32())
+            .unwrap_or(0.0)
+    }
+
+    fn charge(&self) -> f32 {
+        self.accumulating_since.lock().unwrap()
+            .map(|t0| self.current() * (t0.elapsed().as_secs_f32() / 3600.0))
+            .unwrap_or(0.0)
+    }
+}
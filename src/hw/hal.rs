@@ -1,11 +1,52 @@
 use std::fmt::Debug;
+use std::thread;
+use std::time::{Duration, Instant};
 
+/// An analog-to-digital converter capable of sampling one or more channels.
 pub trait ADC: Debug + Send {
     /// Retrieve an ADC channel.
     fn get_channel(&self, channel_no: u8) -> ADCChannel;
 
-    /// Sample a channel's analog signal.
+    /// Sample a channel's raw analog signal.
     fn sample(&self, channel_no: u8) -> u32;
+
+    /// Configure how long the ADC should take per conversion.
+    fn set_sample_time(&mut self, sample_time: Duration);
+
+    /// Returns the currently configured conversion time.
+    fn get_sample_time(&self) -> Duration;
+
+    /// Returns the ADC's resolution, in bits, e.g. 8 for an 8-bit converter.
+    fn resolution_bits(&self) -> u32;
+
+    /// Returns the reference voltage used to calibrate raw samples into volts.
+    fn reference_voltage(&self) -> f32;
+
+    /// Convert a raw sample into a calibrated voltage using the ADC's resolution and reference voltage.
+    fn to_voltage(&self, raw: u32) -> f32 {
+        let max_value = (1u32 << self.resolution_bits()) - 1;
+        (raw as f32 / max_value as f32) * self.reference_voltage()
+    }
+
+    /** Continuously sample a channel for the given duration.
+
+    `on_sample` is invoked with the raw value and the time it was taken, spaced roughly
+    [`ADC::get_sample_time`] apart.
+     */
+    fn sample_continuous(&self,
+                         channel_no: u8,
+                         duration: Duration,
+                         on_sample: &mut dyn FnMut(Instant, u32))
+    {
+        let start = Instant::now();
+        let sample_time = self.get_sample_time();
+
+        while start.elapsed() < duration {
+            let now = Instant::now();
+            on_sample(now, self.sample(channel_no));
+            thread::sleep(sample_time);
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -25,4 +66,14 @@ impl<'a> ADCChannel<'a> {
     fn sample(&self) -> u32 {
         self.adc.sample(self.channel)
     }
+
+    /// Sample the channel and convert the result to a calibrated voltage.
+    fn voltage(&self) -> f32 {
+        self.adc.to_voltage(self.sample())
+    }
+
+    /// Continuously sample the channel for the given duration.
+    fn sample_continuous(&self, duration: Duration, on_sample: &mut dyn FnMut(Instant, u32)) {
+        self.adc.sample_continuous(self.channel, duration, on_sample)
+    }
 }
@@ -1,7 +1,9 @@
 use std::cell::{RefCell, RefMut};
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
 use rppal::i2c::I2c;
 
@@ -20,23 +22,40 @@ mod register {
 // 4mV per value when reading bus voltage.
 const BUS_VOLTAGE_LSB: f32 = 0.004;
 
+/// Running totals accumulated by a background sampling thread; see [`INA219::start_accumulation`].
+#[derive(Debug, Default)]
+struct Accumulation {
+    energy_mj: f32,
+    charge_mah: f32,
+}
+
 /// Driver for the TI INA219 current sensor.
 #[derive(Debug)]
 pub struct INA219 {
     address: u8,
-    i2c: Mutex<RefCell<I2c>>,
+    i2c: Arc<Mutex<RefCell<I2c>>>,
+    current_lsb: f32,
+    accumulation: Arc<Mutex<Accumulation>>,
+    accumulating: Arc<AtomicBool>,
+    sampler: Mutex<Option<JoinHandle<()>>>,
 }
 
 impl INA219 {
-    const CURRENT_LSB: f32 = 0.0305;
-
-    /// Create a new INA219 driver.
-    pub fn new(i2c: I2c, address: u8) -> Result<INA219, String> {
+    /// Create a new INA219 driver, calibrated for the testbed's expected current draw and shunt resistor.
+    ///
+    /// `max_expected_current` is the largest current the shunt is expected to see, in amperes.
+    /// `r_shunt` is the shunt resistor's resistance, in ohms.
+    pub fn new(i2c: I2c, address: u8, max_expected_current: f32, r_shunt: f32) -> Result<INA219, String> {
         let ina = INA219 {
             address,
-            i2c: Mutex::new(RefCell::new(i2c)),
+            i2c: Arc::new(Mutex::new(RefCell::new(i2c))),
+            // In milliamps per bit, so current() and power() read out in mA and mW.
+            current_lsb: max_expected_current / 2f32.powi(15) * 1000.0,
+            accumulation: Arc::new(Mutex::new(Accumulation::default())),
+            accumulating: Arc::new(AtomicBool::new(false)),
+            sampler: Mutex::new(None),
         };
-        ina.init()?;
+        ina.init(max_expected_current, r_shunt)?;
 
         Ok(ina)
     }
@@ -54,13 +73,23 @@ impl INA219 {
     /// Return the current current draw in milliamps.
     #[allow(unused)]
     pub fn current(&self) -> Result<f32, String> {
-        Ok(self.read(register::CURRENT)? as f32 * INA219::CURRENT_LSB)
+        Ok(self.read(register::CURRENT)? as f32 * self.current_lsb)
     }
 
     /// Return the current power measurement in milliwatts.
     #[allow(unused)]
     pub fn power(&self) -> Result<f32, String> {
-        Ok(self.read(register::POWER)? as f32 * 20.0f32 * INA219::CURRENT_LSB)
+        Ok(self.read(register::POWER)? as f32 * 20.0f32 * self.current_lsb)
+    }
+
+    fn read_from(i2c: &Mutex<RefCell<I2c>>, reg_addr: u8) -> Result<u16, String> {
+        let mut out = [0xff; 2];
+        let i2c_cell = i2c.lock()
+            .map_err(|e| format!("failed to lock I2C interface: {}", e))?;
+        i2c_cell.borrow_mut().write_read(&[reg_addr], &mut out)
+            .map_err(|e| format!("failed to perform write-read: {}", e))?;
+
+        Ok(((out[0] as u16) << 8) | (out[1] as u16))
     }
 
     /// Return the bus voltage in volts.
@@ -70,7 +99,7 @@ impl INA219 {
         Ok(((raw >> 3) as f32) * BUS_VOLTAGE_LSB)
     }
 
-    fn init(&self) -> Result<(), String> {
+    fn init(&self, max_expected_current: f32, r_shunt: f32) -> Result<(), String> {
         self.with_i2c(|mut i2c| {
             i2c.set_slave_address(self.address as u16)
                 .map_err(|e| format!("failed to set peripheral address: {}", e))
@@ -89,8 +118,7 @@ impl INA219 {
         let config = 0b0_0_1_11_0011_0011_111;
         self.write(register::CONFIGURATION, config)?;
 
-        // expecting 1A with .1 ohm resistor
-        let cal = calculate_calibration(1f32, 0.1);
+        let cal = calculate_calibration(max_expected_current, r_shunt);
         self.write(register::CALIBRATION, cal)?;
         println!("Calibration: {}", cal);
 
@@ -98,12 +126,7 @@ impl INA219 {
     }
 
     fn read(&self, reg_addr: u8) -> Result<u16, String> {
-        let mut out = [0xff; 2];
-        self.with_i2c(|i2c| {
-            i2c.write_read(&[reg_addr], &mut out)
-                .map_err(|e| format!("failed to perform write-read: {}", e))?;
-            Ok(((out[0] as u16) << 8) | (out[1] as u16))
-        })
+        Self::read_from(&self.i2c, reg_addr)
     }
 
     fn write(&self, reg_addr: u8, value: u16) -> Result<(), String> {
@@ -150,4 +173,59 @@ impl EnergyMetering for INA219 {
     fn cooldown_duration(&self) -> Duration {
         Duration::from_micros(532)
     }
+
+    fn start_accumulation(&self) {
+        *self.accumulation.lock().unwrap() = Accumulation::default();
+        self.accumulating.store(true, Ordering::SeqCst);
+
+        let interval = self.cooldown_duration();
+        let current_lsb = self.current_lsb;
+        let i2c = self.i2c.clone();
+        let accumulation = self.accumulation.clone();
+        let accumulating = self.accumulating.clone();
+
+        let handle = thread::Builder::new()
+            .name("ina219-accumulator".to_string())
+            .spawn(move || {
+                let mut last_tick = Instant::now();
+                while accumulating.load(Ordering::SeqCst) {
+                    thread::sleep(interval);
+
+                    let now = Instant::now();
+                    let dt = now.duration_since(last_tick);
+                    last_tick = now;
+
+                    let power_mw = match Self::read_from(&i2c, register::POWER) {
+                        Ok(raw) => raw as f32 * 20.0f32 * current_lsb,
+                        Err(_) => continue,
+                    };
+                    let current_ma = match Self::read_from(&i2c, register::CURRENT) {
+                        Ok(raw) => raw as f32 * current_lsb,
+                        Err(_) => continue,
+                    };
+
+                    let mut acc = accumulation.lock().unwrap();
+                    acc.energy_mj += power_mw * dt.as_secs_f32();
+                    acc.charge_mah += current_ma * (dt.as_secs_f32() / 3600.0);
+                }
+            })
+            .expect("failed to spawn INA219 accumulator thread");
+
+        *self.sampler.lock().unwrap() = Some(handle);
+    }
+
+    fn stop_accumulation(&self) {
+        self.accumulating.store(false, Ordering::SeqCst);
+        if let Some(handle) = self.sampler.lock().unwrap().take() {
+            let _ = handle.join();
+        }
+    }
+
+    fn energy(&self) -> f32 {
+        self.accumulation.lock().unwrap().energy_mj
+    }
+
+    fn charge(&self) -> f32 {
+        self.accumulation.lock().unwrap().charge_mah
+    }
 }
@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use super::hal::{ADC, ADCChannel};
+
+use rppal::spi::Spi;
+
+/// MCP3008 has a 10-bit resolution ADC.
+const RESOLUTION_BITS: u32 = 10;
+
+/// Driver for the MCP3008 8-channel SPI ADC.
+#[derive(Debug)]
+pub struct MCP3008 {
+    spi: Spi,
+    sample_time: Duration,
+    reference_voltage: f32,
+}
+
+impl MCP3008 {
+    /// Create a new instance of the driver.
+    ///
+    /// `reference_voltage` should be the chip's VREF, used to calibrate raw samples to volts.
+    pub fn new(spi: Spi, reference_voltage: f32) -> MCP3008 {
+        MCP3008 {
+            spi,
+            // Datasheet: conversion completes within one clock cycle of the last address bit
+            // being clocked in; at the 1MHz bus speed this testbed configures, that's a few us.
+            // Default conservatively.
+            sample_time: Duration::from_micros(10),
+            reference_voltage,
+        }
+    }
+}
+
+impl ADC for MCP3008 {
+    fn get_channel(&self, channel_no: u8) -> ADCChannel {
+        ADCChannel::new(self, channel_no)
+    }
+
+    fn sample(&self, channel_no: u8) -> u32 {
+        // Start bit, single-ended mode, then the 3-bit channel number, clocked in MSB-first
+        // alongside two don't-care bytes; the chip clocks back a leading null bit, 10 data
+        // bits, and trailing padding across the second and third bytes.
+        let command = [
+            0b0000_0001,
+            0b1000_0000 | ((channel_no & 0b0000_0111) << 4),
+            0b0000_0000,
+        ];
+        let mut response = [0u8; 3];
+        self.spi.transfer(&mut response, &command).unwrap();
+
+        (((response[1] & 0b0000_0011) as u32) << 8) | response[2] as u32
+    }
+
+    fn set_sample_time(&mut self, sample_time: Duration) {
+        self.sample_time = sample_time;
+    }
+
+    fn get_sample_time(&self) -> Duration {
+        self.sample_time
+    }
+
+    fn resolution_bits(&self) -> u32 {
+        RESOLUTION_BITS
+    }
+
+    fn reference_voltage(&self) -> f32 {
+        self.reference_voltage
+    }
+}
@@ -2,6 +2,16 @@
 
 use crate::facility::EnergyMetering;
 
+pub mod emulated;
+pub mod hal;
 pub mod ina219;
+pub mod mcp3008;
+pub mod pcf8591;
+pub mod shunt_monitor;
 
+pub use emulated::EmulatedEnergyMeter;
+pub use hal::ADC;
 pub use ina219::INA219;
+pub use mcp3008::MCP3008;
+pub use pcf8591::PCF8591;
+pub use shunt_monitor::ShuntMonitor;
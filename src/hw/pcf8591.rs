@@ -1,18 +1,31 @@
+use std::time::Duration;
+
 use super::hal::{ADC, ADCChannel};
 
 use rppal::i2c::I2c;
 
+/// PCF8591 has an 8-bit resolution ADC.
+const RESOLUTION_BITS: u32 = 8;
+
 /// Driver for the Adafruit PCF8591 ADC/DAC board.
 #[derive(Debug)]
 pub struct PCF8591 {
-    i2c: I2c
+    i2c: I2c,
+    sample_time: Duration,
+    reference_voltage: f32,
 }
 
 impl PCF8591 {
     /// Create a new instance of the driver.
-    pub fn new(i2c: I2c) -> PCF8591 {
+    ///
+    /// `reference_voltage` should be the board's VCC/VREF, used to calibrate raw samples to volts.
+    pub fn new(i2c: I2c, reference_voltage: f32) -> PCF8591 {
         PCF8591 {
             i2c,
+            // Datasheet: one conversion takes one clock cycle after the address byte is ACKed,
+            // which at typical I2C bus speeds works out to less than 100us. Default conservatively.
+            sample_time: Duration::from_micros(100),
+            reference_voltage,
         }
     }
 }
@@ -21,4 +34,32 @@ impl ADC for PCF8591 {
     fn get_channel(&self, channel_no: u8) -> ADCChannel {
         ADCChannel::new(self, channel_no)
     }
+
+    fn sample(&self, channel_no: u8) -> u32 {
+        // Select the channel to convert; the PCF8591 returns the *previous* conversion on
+        // this same transaction, so the first byte read back is discarded.
+        let control_byte = [channel_no & 0b0000_0011];
+        self.i2c.write(&control_byte).unwrap();
+
+        let mut reading = [0u8; 2];
+        self.i2c.read(&mut reading).unwrap();
+
+        reading[1] as u32
+    }
+
+    fn set_sample_time(&mut self, sample_time: Duration) {
+        self.sample_time = sample_time;
+    }
+
+    fn get_sample_time(&self) -> Duration {
+        self.sample_time
+    }
+
+    fn resolution_bits(&self) -> u32 {
+        RESOLUTION_BITS
+    }
+
+    fn reference_voltage(&self) -> f32 {
+        self.reference_voltage
+    }
 }
@@ -0,0 +1,277 @@
+//! Generic driver for the TI INA2xx family of I2C current/power shunt monitors.
+//!
+//! [`INA219`](super::INA219) already has its own hand-written driver; this module covers the
+//! rest of the family (INA226, INA260, ...) through one [`ShuntMonitor`] instead of a new
+//! hand-rolled [`EnergyMetering`] impl per chip. The INA2xx chips share a shunt-voltage/
+//! bus-voltage/power/current register layout and differ mainly in register scaling constants and
+//! whether a calibration register write is needed, so [`ShuntMonitor`] is parameterized by a
+//! [`ShuntMonitorKind`] plus those scaling constants rather than by a generic bus trait: this
+//! crate has no `embedded-hal` dependency (and no manifest to add one to), so the bus itself
+//! stays the same concrete `rppal::i2c::I2c` the rest of `hw` already uses.
+
+use std::cell::{RefCell, RefMut};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use rppal::i2c::I2c;
+
+use crate::facility::EnergyMetering;
+
+#[allow(unused)]
+mod register {
+    pub const CONFIGURATION: u8 = 0x00;
+    pub const SHUNT_VOLTAGE: u8 = 0x01;
+    pub const BUS_VOLTAGE: u8   = 0x02;
+    pub const POWER: u8         = 0x03;
+    pub const CURRENT: u8       = 0x04;
+    pub const CALIBRATION: u8   = 0x05;
+}
+
+/// Which INA2xx-family chip a [`ShuntMonitor`] is configured for; determines the reset-time
+/// configuration-register contents, the register scaling constants, and whether a calibration
+/// register write applies.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ShuntMonitorKind {
+    /// TI INA226: +/-36V bus range, 1.25mV bus-voltage LSB, requires calibration like the INA219.
+    Ina226,
+    /// TI INA260: fixed internal 2mOhm shunt, no calibration register, fixed current/power LSBs.
+    Ina260,
+}
+
+/// Running totals accumulated by a background sampling thread; see [`ShuntMonitor::start_accumulation`].
+#[derive(Debug, Default)]
+struct Accumulation {
+    energy_mj: f32,
+    charge_mah: f32,
+}
+
+/// Driver for an INA2xx-family current/power shunt monitor other than the INA219; see the module
+/// documentation for why the INA219 keeps its own driver.
+#[derive(Debug)]
+pub struct ShuntMonitor {
+    kind: ShuntMonitorKind,
+    address: u8,
+    i2c: Arc<Mutex<RefCell<I2c>>>,
+    current_lsb: f32,
+    power_lsb: f32,
+    bus_voltage_lsb: f32,
+    accumulation: Arc<Mutex<Accumulation>>,
+    accumulating: Arc<AtomicBool>,
+    sampler: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl ShuntMonitor {
+    /** Configure an INA226.
+
+    `max_expected_current` (amps) and `r_shunt` (ohms) calibrate the current/power LSBs and are
+    written to the device's calibration register, same as the INA219.
+     */
+    pub fn ina226(i2c: I2c, address: u8, max_expected_current: f32, r_shunt: f32) -> Result<ShuntMonitor, String> {
+        let current_lsb = max_expected_current / 2f32.powi(15);
+        let monitor = ShuntMonitor::new(
+            ShuntMonitorKind::Ina226,
+            i2c,
+            address,
+            current_lsb * 1000.0, // mA per bit
+            current_lsb * 25.0 * 1000.0, // INA226 power LSB is 25x the current LSB, per the datasheet; mW per bit.
+            0.00125);
+        // Averaging x1, bus+shunt conversion time 1.1ms, continuous shunt+bus mode.
+        monitor.init(Some(calculate_calibration(current_lsb, r_shunt)), 0b0_100_000_100_100_111)?;
+
+        Ok(monitor)
+    }
+
+    /** Configure an INA260.
+
+    The INA260 has a fixed internal 2mOhm shunt, so there's no calibration register or
+    user-supplied shunt resistance: current/power LSBs (1.25mA, 10mW) are fixed by the datasheet.
+     */
+    pub fn ina260(i2c: I2c, address: u8) -> Result<ShuntMonitor, String> {
+        let monitor = ShuntMonitor::new(
+            ShuntMonitorKind::Ina260,
+            i2c,
+            address,
+            1.25,
+            10.0,
+            1.25e-3);
+        // Averaging x1, current+voltage conversion time 1.1ms, continuous mode.
+        monitor.init(None, 0b0_110_000_100_100_111)?;
+
+        Ok(monitor)
+    }
+
+    fn new(kind: ShuntMonitorKind,
+           i2c: I2c,
+           address: u8,
+           current_lsb: f32,
+           power_lsb: f32,
+           bus_voltage_lsb: f32) -> ShuntMonitor {
+        ShuntMonitor {
+            kind,
+            address,
+            i2c: Arc::new(Mutex::new(RefCell::new(i2c))),
+            current_lsb,
+            power_lsb,
+            bus_voltage_lsb,
+            accumulation: Arc::new(Mutex::new(Accumulation::default())),
+            accumulating: Arc::new(AtomicBool::new(false)),
+            sampler: Mutex::new(None),
+        }
+    }
+
+    /// Returns which chip variant this driver is configured for.
+    pub fn kind(&self) -> ShuntMonitorKind {
+        self.kind
+    }
+
+    fn init(&self, calibration: Option<u16>, config: u16) -> Result<(), String> {
+        self.with_i2c(|mut i2c| {
+            i2c.set_slave_address(self.address as u16)
+                .map_err(|e| format!("failed to set peripheral address: {}", e))
+        })?;
+
+        // Reset, then apply the configuration; need >=40us after reset before writing again.
+        self.write(register::CONFIGURATION, 1u16 << 15)?;
+        thread::sleep(Duration::from_micros(40));
+        self.write(register::CONFIGURATION, config)?;
+
+        if let Some(cal) = calibration {
+            self.write(register::CALIBRATION, cal)?;
+        }
+
+        Ok(())
+    }
+
+    /// Return the current current draw in milliamps.
+    pub fn current(&self) -> Result<f32, String> {
+        Ok(self.read(register::CURRENT)? as f32 * self.current_lsb)
+    }
+
+    /// Return the current power measurement in milliwatts.
+    pub fn power(&self) -> Result<f32, String> {
+        Ok(self.read(register::POWER)? as f32 * self.power_lsb)
+    }
+
+    /// Return the bus voltage in volts.
+    #[allow(unused)]
+    pub fn bus_voltage(&self) -> Result<f32, String> {
+        let raw = self.read(register::BUS_VOLTAGE)?;
+        Ok((raw as f32) * self.bus_voltage_lsb)
+    }
+
+    fn read_from(i2c: &Mutex<RefCell<I2c>>, reg_addr: u8) -> Result<u16, String> {
+        let mut out = [0xff; 2];
+        let i2c_cell = i2c.lock()
+            .map_err(|e| format!("failed to lock I2C interface: {}", e))?;
+        i2c_cell.borrow_mut().write_read(&[reg_addr], &mut out)
+            .map_err(|e| format!("failed to perform write-read: {}", e))?;
+
+        Ok(((out[0] as u16) << 8) | (out[1] as u16))
+    }
+
+    fn read(&self, reg_addr: u8) -> Result<u16, String> {
+        Self::read_from(&self.i2c, reg_addr)
+    }
+
+    fn write(&self, reg_addr: u8, value: u16) -> Result<(), String> {
+        let buf = [
+            reg_addr,
+            (value >> 8) as u8,
+            (value & 0xFF) as u8,
+        ];
+        self.with_i2c(|mut i2c| {
+            i2c.write(&buf)
+                .map(|_bytes_written| ())
+                .map_err(|e| format!("failed to write {:X} register: {}", reg_addr, e))
+        })
+    }
+
+    fn with_i2c<F, T>(&self, op: F) -> Result<T, String>
+    where
+        F: FnOnce(RefMut<'_, I2c>) -> Result<T, String>
+    {
+        let i2c_cell = self.i2c.lock()
+            .map_err(|e| format!("failed to lock I2C interface: {}", e))?;
+
+        op(i2c_cell.borrow_mut())
+    }
+}
+
+// Calculate the calibration value for the calibration register.
+// current_lsb is in amperes, r_shunt is resistance in ohms.
+fn calculate_calibration(current_lsb: f32, r_shunt: f32) -> u16 {
+    (0.04096f32 / (current_lsb * r_shunt)) as u16
+}
+
+impl EnergyMetering for ShuntMonitor {
+    fn current(&self) -> f32 {
+        self.current().unwrap()
+    }
+
+    fn power(&self) -> f32 {
+        self.power().unwrap()
+    }
+
+    fn cooldown_duration(&self) -> Duration {
+        Duration::from_micros(1100)
+    }
+
+    fn start_accumulation(&self) {
+        *self.accumulation.lock().unwrap() = Accumulation::default();
+        self.accumulating.store(true, Ordering::SeqCst);
+
+        let interval = self.cooldown_duration();
+        let current_lsb = self.current_lsb;
+        let power_lsb = self.power_lsb;
+        let i2c = self.i2c.clone();
+        let accumulation = self.accumulation.clone();
+        let accumulating = self.accumulating.clone();
+
+        let handle = thread::Builder::new()
+            .name("shunt-monitor-accumulator".to_string())
+            .spawn(move || {
+                let mut last_tick = Instant::now();
+                while accumulating.load(Ordering::SeqCst) {
+                    thread::sleep(interval);
+
+                    let now = Instant::now();
+                    let dt = now.duration_since(last_tick);
+                    last_tick = now;
+
+                    let power_mw = match Self::read_from(&i2c, register::POWER) {
+                        Ok(raw) => raw as f32 * power_lsb,
+                        Err(_) => continue,
+                    };
+                    let current_ma = match Self::read_from(&i2c, register::CURRENT) {
+                        Ok(raw) => raw as f32 * current_lsb,
+                        Err(_) => continue,
+                    };
+
+                    let mut acc = accumulation.lock().unwrap();
+                    acc.energy_mj += power_mw * dt.as_secs_f32();
+                    acc.charge_mah += current_ma * (dt.as_secs_f32() / 3600.0);
+                }
+            })
+            .expect("failed to spawn shunt monitor accumulator thread");
+
+        *self.sampler.lock().unwrap() = Some(handle);
+    }
+
+    fn stop_accumulation(&self) {
+        self.accumulating.store(false, Ordering::SeqCst);
+        if let Some(handle) = self.sampler.lock().unwrap().take() {
+            let _ = handle.join();
+        }
+    }
+
+    fn energy(&self) -> f32 {
+        self.accumulation.lock().unwrap().energy_mj
+    }
+
+    fn charge(&self) -> f32 {
+        self.accumulation.lock().unwrap().charge_mah
+    }
+}
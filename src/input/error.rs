@@ -49,3 +49,9 @@ impl From<std::io::Error> for Error {
         Error::IO(e)
     }
 }
+
+impl From<crate::sw::error::Error> for Error {
+    fn from(e: crate::sw::error::Error) -> Self {
+        Error::Driver(e.to_string())
+    }
+}
@@ -79,7 +79,8 @@ impl TestbedConfigReader for HardCodedTestbed {
 
         // Energy metering
         let ina219: Box<dyn EnergyMetering> = Box::new(
-            INA219::new(mapping.get_i2c().unwrap(), 0x40).unwrap());
+            // expecting 1A with .1 ohm resistor
+            INA219::new(mapping.get_i2c().unwrap(), 0x40, 1f32, 0.1).unwrap());
         let energy_meters: HashMap<String, Box<dyn EnergyMetering>> = (vec![
             ("system".to_string(), ina219)
         ]).into_iter()
@@ -90,12 +91,17 @@ impl TestbedConfigReader for HardCodedTestbed {
             "hail",
             Path::new("/usr/local/bin/tockloader"),
             Path::new("/home/ubuntu/work/tock"),
-            Path::new("/home/ubuntu/work/apps/tock"));
+            Path::new("/home/ubuntu/work/apps/tock"),
+            None)?;
+
+        // No external ADCs wired up for this hard-coded testbed.
+        let analog_channels: Vec<(String, Box<dyn crate::hw::hal::ADC>, u8)> = Vec::new();
 
         let testbed = Testbed::new(
             mapping,
             Box::new(platform),
-            energy_meters);
+            energy_meters,
+            analog_channels);
 
         Ok(testbed)
     }
@@ -157,6 +163,14 @@ impl HardCodedTests {
             ],
         }
     }
+
+    /// Consume this adapter, returning its tests directly.
+    ///
+    /// Used by [`crate::opts::TestFormatRegistry`]'s `"hard-coded"` factory, which needs an owned
+    /// `Vec<Test>` rather than a borrowed iterator.
+    pub fn into_tests(self) -> Vec<Test> {
+        self.tests
+    }
 }
 
 impl TestConfigAdapter for HardCodedTests {
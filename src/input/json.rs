@@ -3,6 +3,7 @@ use std::convert::TryFrom;
 use std::fs::File;
 use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use serde::Deserialize;
 use serde_json;
@@ -13,9 +14,11 @@ use crate::comm::{Direction,
 use crate::device::Device;
 use crate::facility::EnergyMetering;
 use crate::hw;
+use crate::hw::hal::ADC;
 use crate::io::Mapping;
 use crate::sw::{Platform, PlatformSupport};
 use crate::sw::platform;
+use crate::testing::criteria::{Criterion, GPIOCriterion, EnergyCriterion, EnergyStat, Timing, TraceCondition, TraceCriterion, SerialTraceCondition, SerialTraceCriterion};
 use crate::testing::testbed::Testbed;
 
 use super::{Result,
@@ -66,6 +69,33 @@ impl JSONTestbedParser {
         Ok(meters)
     }
 
+    fn parse_analog(&self, mapping: &Mapping, json: &JSONValue) -> Result<Vec<(String, Box<dyn ADC>, u8)>> {
+        let mut channels = Vec::new();
+        let json_channels = json["channels"].as_array()
+            .ok_or(Error::Format("Analog 'channels' must be an array.".to_string()))?;
+        for json_channel in json_channels {
+            let name = json_channel["name"].as_str()
+                .ok_or(Error::Format("Analog channel is missing a name.".to_string()))?;
+            let driver = json_channel["driver-id"].as_str()
+                .ok_or(Error::Format("Analog channel is missing a 'driver-id'.".to_string()))?;
+            let props = json_channel["driver-props"].as_object()
+                .ok_or(Error::Format(format!("Analog channel '{}' is missing 'driver-props'.", name)))?;
+            let channel_no = json_channel["channel"].as_u64()
+                .ok_or(Error::Format(format!("Analog channel '{}' is missing a 'channel' number.", name)))?
+                as u8;
+
+            let adc: Box<dyn ADC> = match driver {
+                "pcf8591" => Ok(Box::<hw::PCF8591>::new(hw::PCF8591::from_json(mapping, serde_json::Value::Object(props.clone()))?)),
+                "mcp3008" => Ok(Box::<hw::MCP3008>::new(hw::MCP3008::from_json(mapping, serde_json::Value::Object(props.clone()))?)),
+                _ => Err(Error::Unsupported),
+            }?;
+
+            channels.push((name.to_string(), adc, channel_no));
+        }
+
+        Ok(channels)
+    }
+
     fn parse_platform(&self, platform_json: &JSONValue) -> Result<Box<dyn PlatformSupport>> {
         let platform_id = platform_json["id"].as_str()
             .ok_or(Error::Format("Platform missing 'id' string.".to_string()))?;
@@ -76,6 +106,178 @@ impl JSONTestbedParser {
 
         Ok(platform)
     }
+
+    /** Parse a `"criteria"` array into the [`Criterion`]s a [`crate::testing::test::Test`] expects
+    of the device under test.
+
+    Lets a test's expected behavior be authored declaratively rather than compiled in, the same
+    way `parse_energy`/`parse_platform` let the rest of the testbed be. Supported `"type"`s are
+    `"gpio"`, `"energy"`, `"trace"`, and `"serial-trace"`; an unrecognized type is rejected the
+    same way an unrecognized `driver-id` is in `parse_energy`.
+     */
+    pub fn parse_criteria(&self, json: &JSONValue) -> Result<Vec<Criterion>> {
+        let json_criteria = json.as_array()
+            .ok_or(Error::Format("'criteria' must be an array.".to_string()))?;
+
+        let mut criteria = Vec::with_capacity(json_criteria.len());
+        for json_criterion in json_criteria {
+            let kind = json_criterion["type"].as_str()
+                .ok_or(Error::Format("Criterion is missing a 'type'.".to_string()))?;
+
+            let criterion = match kind {
+                "gpio" => Criterion::GPIO(self.parse_gpio_criterion(json_criterion)?),
+                "energy" => Criterion::Energy(self.parse_energy_criterion(json_criterion)?),
+                "trace" => Criterion::Trace(self.parse_trace_criterion(json_criterion)?),
+                "serial-trace" => Criterion::SerialTrace(self.parse_serial_trace_criterion(json_criterion)?),
+                _ => return Err(Error::Unsupported),
+            };
+
+            criteria.push(criterion);
+        }
+
+        Ok(criteria)
+    }
+
+    fn parse_gpio_criterion(&self, json: &JSONValue) -> Result<GPIOCriterion> {
+        let pin = json["pin"].as_u64()
+            .ok_or(Error::Format("GPIO criterion is missing a 'pin' number.".to_string()))?
+            as u8;
+        let mode = json["mode"].as_str().unwrap_or("any");
+
+        let criterion = match mode {
+            "any" => GPIOCriterion::Any(pin),
+
+            "edge-count" => GPIOCriterion::EdgeCount {
+                pin,
+                min: json["min"].as_u64(),
+                max: json["max"].as_u64(),
+            },
+
+            "frequency" => GPIOCriterion::Frequency {
+                pin,
+                min_hz: json["min-hz"].as_f64().map(|v| v as f32),
+                max_hz: json["max-hz"].as_f64().map(|v| v as f32),
+                window: Duration::from_millis(
+                    json["window-ms"].as_u64()
+                        .ok_or(Error::Format("GPIO 'frequency' criterion is missing a 'window-ms'.".to_string()))?),
+            },
+
+            "duty-cycle" => GPIOCriterion::DutyCycle {
+                pin,
+                min: json["min"].as_f64().map(|v| v as f32),
+                max: json["max"].as_f64().map(|v| v as f32),
+            },
+
+            other => return Err(Error::Format(format!("GPIO criterion has unrecognized 'mode' '{}'.", other))),
+        };
+
+        Ok(criterion)
+    }
+
+    fn parse_energy_criterion(&self, json: &JSONValue) -> Result<EnergyCriterion> {
+        let meter = json["meter"].as_str()
+            .ok_or(Error::Format("Energy criterion is missing a 'meter' name.".to_string()))?;
+        let stat = match json["stat"].as_str()
+            .ok_or(Error::Format("Energy criterion is missing a 'stat'.".to_string()))?
+        {
+            "total" => EnergyStat::Total,
+            "average" => EnergyStat::Average,
+            "max" => EnergyStat::Max,
+            "min" => EnergyStat::Min,
+            other => return Err(Error::Format(format!("Energy criterion has unrecognized 'stat' '{}'.", other))),
+        };
+
+        let mut criterion = EnergyCriterion::new(meter, stat);
+        if let Some(min) = json["min"].as_f64() {
+            criterion = criterion.with_min(min as f32);
+        }
+        if let Some(max) = json["max"].as_f64() {
+            criterion = criterion.with_max(max as f32);
+        }
+
+        Ok(criterion)
+    }
+
+    fn parse_trace_criterion(&self, json: &JSONValue) -> Result<TraceCriterion> {
+        let json_conditions = json["conditions"].as_array()
+            .ok_or(Error::Format("Trace criterion is missing a 'conditions' array.".to_string()))?;
+
+        let mut conditions = Vec::with_capacity(json_conditions.len());
+        for json_condition in json_conditions {
+            let id = json_condition["id"].as_u64()
+                .ok_or(Error::Format("Trace condition is missing an 'id'.".to_string()))?
+                as u16;
+
+            let mut condition = TraceCondition::new(id);
+            if let Some(extra) = json_condition["extra"].as_u64() {
+                condition = condition.with_extra_data(extra as u16);
+            }
+            if json_condition["timing"].is_object() {
+                let timing_json = &json_condition["timing"];
+                let offset_ms = timing_json["offset_ms"].as_u64()
+                    .ok_or(Error::Format("Trace condition timing is missing an 'offset_ms'.".to_string()))?;
+                let tolerance_ms = timing_json["tolerance_ms"].as_u64()
+                    .ok_or(Error::Format("Trace condition timing is missing a 'tolerance_ms'.".to_string()))?;
+                let offset = Duration::from_millis(offset_ms);
+                let tolerance = Duration::from_millis(tolerance_ms);
+
+                let timing = match timing_json["kind"].as_str()
+                    .ok_or(Error::Format("Trace condition timing is missing a 'kind'.".to_string()))?
+                {
+                    "absolute" => Timing::Absolute(offset),
+                    "relative" => Timing::Relative(offset),
+                    other => return Err(Error::Format(format!("Trace condition timing has unrecognized 'kind' '{}'.", other))),
+                };
+
+                condition = condition.with_timing(timing, tolerance);
+            }
+
+            conditions.push(condition);
+        }
+
+        Ok(TraceCriterion::new(&conditions))
+    }
+
+    fn parse_serial_trace_criterion(&self, json: &JSONValue) -> Result<SerialTraceCriterion> {
+        let json_conditions = json["conditions"].as_array()
+            .ok_or(Error::Format("Serial trace criterion is missing a 'conditions' array.".to_string()))?;
+
+        let mut conditions = Vec::with_capacity(json_conditions.len());
+        for json_condition in json_conditions {
+            let json_pattern = json_condition["pattern"].as_array()
+                .ok_or(Error::Format("Serial trace condition is missing a 'pattern' byte array.".to_string()))?;
+            let pattern: Vec<u8> = json_pattern.iter()
+                .map(|b| b.as_u64()
+                     .ok_or(Error::Format("Serial trace condition 'pattern' must contain only bytes.".to_string()))
+                     .map(|b| b as u8))
+                .collect::<Result<Vec<u8>>>()?;
+
+            let mut condition = SerialTraceCondition::new(&pattern);
+            if json_condition["timing"].is_object() {
+                let timing_json = &json_condition["timing"];
+                let offset_ms = timing_json["offset_ms"].as_u64()
+                    .ok_or(Error::Format("Serial trace condition timing is missing an 'offset_ms'.".to_string()))?;
+                let tolerance_ms = timing_json["tolerance_ms"].as_u64()
+                    .ok_or(Error::Format("Serial trace condition timing is missing a 'tolerance_ms'.".to_string()))?;
+                let offset = Duration::from_millis(offset_ms);
+                let tolerance = Duration::from_millis(tolerance_ms);
+
+                let timing = match timing_json["kind"].as_str()
+                    .ok_or(Error::Format("Serial trace condition timing is missing a 'kind'.".to_string()))?
+                {
+                    "absolute" => Timing::Absolute(offset),
+                    "relative" => Timing::Relative(offset),
+                    other => return Err(Error::Format(format!("Serial trace condition timing has unrecognized 'kind' '{}'.", other))),
+                };
+
+                condition = condition.with_timing(timing, tolerance);
+            }
+
+            conditions.push(condition);
+        }
+
+        Ok(SerialTraceCriterion::new(&conditions))
+    }
 }
 
 impl TestbedConfigReader for JSONTestbedParser {
@@ -107,11 +309,14 @@ impl TestbedConfigReader for JSONTestbedParser {
         let energy_meters = self.parse_energy(&mapping, &json["energy"])?;
         // Software platform support.
         let platform_support = self.parse_platform(&json["platform"])?;
+        // Analog sampling.
+        let analog_channels = self.parse_analog(&mapping, &json["analog"])?;
 
         let testbed = Testbed::new(
             mapping,
             platform_support,
-            energy_meters);
+            energy_meters,
+            analog_channels);
 
         Ok(testbed)
     }
@@ -168,12 +373,36 @@ impl JSONHardware for hw::INA219 {
             .ok_or(Error::Format("INA219: missing 'i2c-address' property.".to_string()))
             .and_then(|addr| u8::try_from(addr)
                                  .map_err(|_e| Error::Format("INA219: 'i2c-address' is not valid.".to_string())))?;
+        let max_current = json["max-current-amps"].as_f64()
+            .ok_or(Error::Format("INA219: missing 'max-current-amps' property.".to_string()))? as f32;
+        let r_shunt = json["shunt-resistance-ohms"].as_f64()
+            .ok_or(Error::Format("INA219: missing 'shunt-resistance-ohms' property.".to_string()))? as f32;
 
-        hw::INA219::new(i2c, address)
+        hw::INA219::new(i2c, address, max_current, r_shunt)
             .map_err(|e| Error::Driver(e.to_string()))
     }
 }
 
+impl JSONHardware for hw::PCF8591 {
+    fn from_json(mapping: &Mapping, json: JSONValue) -> Result<Self> {
+        let i2c = mapping.get_i2c()?;
+        let reference_voltage = json["reference-voltage"].as_f64()
+            .ok_or(Error::Format("PCF8591: missing 'reference-voltage' property.".to_string()))? as f32;
+
+        Ok(hw::PCF8591::new(i2c, reference_voltage))
+    }
+}
+
+impl JSONHardware for hw::MCP3008 {
+    fn from_json(mapping: &Mapping, json: JSONValue) -> Result<Self> {
+        let spi = mapping.get_spi()?;
+        let reference_voltage = json["reference-voltage"].as_f64()
+            .ok_or(Error::Format("MCP3008: missing 'reference-voltage' property.".to_string()))? as f32;
+
+        Ok(hw::MCP3008::new(spi, reference_voltage))
+    }
+}
+
 trait JSONPlatform: Sized {
     fn from_json(props: &JSONValue) -> Result<Self> {
         Err(Error::Unsupported)
@@ -189,6 +418,8 @@ struct TockPlatformConfig {
     #[serde(alias = "application-path")]
     app_path: String,
     board: String,
+    #[serde(alias = "tool-config-path", default)]
+    tool_config_path: Option<String>,
 }
 
 impl JSONPlatform for platform::Tock {
@@ -199,7 +430,9 @@ impl JSONPlatform for platform::Tock {
             config.board.as_str(),
             Path::new(&config.tockloader_path),
             Path::new(&config.repo_path),
-            Path::new(&config.app_path));
+            Path::new(&config.app_path),
+            config.tool_config_path.as_deref().map(Path::new))
+            .map_err(|e| Error::Driver(e.to_string()))?;
 
         Ok(tock_support)
     }
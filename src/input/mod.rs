@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::iter::Iterator;
 
@@ -7,6 +8,7 @@ use crate::testing::test::Test;
 pub mod error;
 pub mod hard_code;
 pub mod json;
+pub mod toml;
 
 type Result<T> = std::result::Result<T, error::Error>;
 
@@ -14,6 +16,18 @@ type Result<T> = std::result::Result<T, error::Error>;
 pub trait TestbedConfigReader: Debug {
     /// Create a configured testbed ready to run tests.
     fn create(&self) -> Result<Testbed>;
+
+    /** Create a configured testbed, applying `overrides` (site-local `key=value` pairs; see
+    [`crate::opts`]) on top of the reader's own config.
+
+    This lets a single structured testbed config be retargeted to a different board at a
+    deployment site (a different `tockloader`/kernel/application path, say) without hand-editing
+    or recompiling it. Readers with nothing overridable can rely on the default, which just
+    ignores `overrides` and defers to [`TestbedConfigReader::create`].
+     */
+    fn create_with_overrides(&self, _overrides: &HashMap<String, String>) -> Result<Testbed> {
+        self.create()
+    }
 }
 
 /// Data adapter producing tests read from an input source.
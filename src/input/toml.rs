@@ -0,0 +1,347 @@
+//! TOML testbed configuration, as an alternative to [`super::json`].
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use toml::Value as TOMLValue;
+
+use crate::comm::{Direction,
+                  Class as SignalClass};
+use crate::device::Device;
+use crate::facility::EnergyMetering;
+use crate::hw;
+use crate::hw::hal::ADC;
+use crate::io::Mapping;
+use crate::sw::{Platform, PlatformSupport};
+use crate::sw::platform;
+use crate::testing::testbed::Testbed;
+
+use super::{Result,
+            TestbedConfigReader};
+use super::error::Error;
+
+const CONFIG_VERSION: i64 = 1;
+
+#[derive(Debug)]
+pub struct TOMLTestbedParser {
+    config_path: PathBuf,
+}
+
+impl TOMLTestbedParser {
+    pub fn new(config_path: &Path) -> TOMLTestbedParser {
+        TOMLTestbedParser {
+            config_path: config_path.to_path_buf(),
+        }
+    }
+
+    fn parse_gpio(&self, toml: &TOMLValue) -> Result<IOConfig> {
+        let io_table = toml.get("io")
+            .ok_or(Error::Format("Missing 'io' table.".to_string()))?
+            .clone();
+        io_table.try_into()
+            .map_err(|e| Error::Format(format!("IO configuration parsing failed: {}", e)))
+    }
+
+    fn parse_energy(&self, mapping: &Mapping, toml: &TOMLValue) -> Result<HashMap<String, Box<dyn EnergyMetering>>> {
+        let mut meters = HashMap::new();
+        let toml_meters = toml.get("meters")
+            .and_then(TOMLValue::as_array)
+            .ok_or(Error::Format("Energy 'meters' must be an array.".to_string()))?;
+        for toml_meter in toml_meters {
+            let name = toml_meter.get("name").and_then(TOMLValue::as_str)
+                .ok_or(Error::Format("Energy meter is missing a name.".to_string()))?;
+            let driver = toml_meter.get("driver-id").and_then(TOMLValue::as_str)
+                .ok_or(Error::Format("Energy meter is missing a 'driver-id'.".to_string()))?;
+            let props = toml_meter.get("driver-props")
+                .ok_or(Error::Format(format!("Energy meter '{}' is missing 'driver-props'.", name)))?;
+
+            let meter: Box<dyn EnergyMetering> = match driver {
+                "ina219" => Ok(Box::<hw::INA219>::new(hw::INA219::from_toml(mapping, props)?)),
+                "ina226" => Ok(Box::new(self.parse_ina226(mapping, name, props)?) as Box<dyn EnergyMetering>),
+                "ina260" => Ok(Box::new(self.parse_ina260(mapping, name, props)?) as Box<dyn EnergyMetering>),
+                "emulated" => Ok(Box::new(self.parse_emulated_meter(name, props)?) as Box<dyn EnergyMetering>),
+                _ => Err(Error::Unsupported),
+            }?;
+
+            meters.insert(name.to_string(), meter);
+        }
+
+        Ok(meters)
+    }
+
+    fn parse_ina226(&self, mapping: &Mapping, name: &str, props: &TOMLValue) -> Result<hw::ShuntMonitor> {
+        let i2c = mapping.get_i2c()?;
+        let address = props.get("i2c-address").and_then(TOMLValue::as_integer)
+            .ok_or(Error::Format(format!("Energy meter '{}': missing 'i2c-address' property.", name)))
+            .and_then(|addr| u8::try_from(addr)
+                                 .map_err(|_e| Error::Format(format!("Energy meter '{}': 'i2c-address' is not valid.", name))))?;
+        let max_current = props.get("max-current-amps").and_then(TOMLValue::as_float)
+            .ok_or(Error::Format(format!("Energy meter '{}': missing 'max-current-amps' property.", name)))? as f32;
+        let r_shunt = props.get("shunt-resistance-ohms").and_then(TOMLValue::as_float)
+            .ok_or(Error::Format(format!("Energy meter '{}': missing 'shunt-resistance-ohms' property.", name)))? as f32;
+
+        hw::ShuntMonitor::ina226(i2c, address, max_current, r_shunt)
+            .map_err(Error::Driver)
+    }
+
+    fn parse_ina260(&self, mapping: &Mapping, name: &str, props: &TOMLValue) -> Result<hw::ShuntMonitor> {
+        let i2c = mapping.get_i2c()?;
+        let address = props.get("i2c-address").and_then(TOMLValue::as_integer)
+            .ok_or(Error::Format(format!("Energy meter '{}': missing 'i2c-address' property.", name)))
+            .and_then(|addr| u8::try_from(addr)
+                                 .map_err(|_e| Error::Format(format!("Energy meter '{}': 'i2c-address' is not valid.", name))))?;
+
+        hw::ShuntMonitor::ina260(i2c, address)
+            .map_err(Error::Driver)
+    }
+
+    /// No real driver properties needed: a hardware-free meter for device-free (CI/development)
+    /// runs, with fixed settings: 20mA baseline, 15mA more while active, and a 3.3V supply.
+    fn parse_emulated_meter(&self, _name: &str, _props: &TOMLValue) -> Result<hw::EmulatedEnergyMeter> {
+        Ok(hw::EmulatedEnergyMeter::new(20.0, 15.0, 3.3))
+    }
+
+    fn parse_analog(&self, mapping: &Mapping, toml: &TOMLValue) -> Result<Vec<(String, Box<dyn ADC>, u8)>> {
+        let mut channels = Vec::new();
+        let toml_channels = toml.get("channels")
+            .and_then(TOMLValue::as_array)
+            .ok_or(Error::Format("Analog 'channels' must be an array.".to_string()))?;
+        for toml_channel in toml_channels {
+            let name = toml_channel.get("name").and_then(TOMLValue::as_str)
+                .ok_or(Error::Format("Analog channel is missing a name.".to_string()))?;
+            let driver = toml_channel.get("driver-id").and_then(TOMLValue::as_str)
+                .ok_or(Error::Format("Analog channel is missing a 'driver-id'.".to_string()))?;
+            let props = toml_channel.get("driver-props")
+                .ok_or(Error::Format(format!("Analog channel '{}' is missing 'driver-props'.", name)))?;
+            let channel_no = toml_channel.get("channel").and_then(TOMLValue::as_integer)
+                .ok_or(Error::Format(format!("Analog channel '{}' is missing a 'channel' number.", name)))?
+                as u8;
+
+            let adc: Box<dyn ADC> = match driver {
+                "pcf8591" => Ok(Box::<hw::PCF8591>::new(hw::PCF8591::from_toml(mapping, props)?)),
+                "mcp3008" => Ok(Box::<hw::MCP3008>::new(hw::MCP3008::from_toml(mapping, props)?)),
+                _ => Err(Error::Unsupported),
+            }?;
+
+            channels.push((name.to_string(), adc, channel_no));
+        }
+
+        Ok(channels)
+    }
+
+    fn parse_platform(&self, platform_toml: &TOMLValue) -> Result<Box<dyn PlatformSupport>> {
+        let platform_id = platform_toml.get("id").and_then(TOMLValue::as_str)
+            .ok_or(Error::Format("Platform missing 'id' string.".to_string()))?;
+        let platform: Box<dyn PlatformSupport> = match platform_id {
+            "tock" => Box::<platform::Tock>::new(platform::Tock::from_toml(platform_toml)?),
+            "emulated" => Box::<crate::sw::emulated::Emulated>::new(crate::sw::emulated::Emulated::new()),
+            _ => return Err(Error::Unsupported),
+        };
+
+        Ok(platform)
+    }
+}
+
+impl TOMLTestbedParser {
+    /// Reads and version-checks the config file, returning its parsed contents.
+    fn read(&self) -> Result<TOMLValue> {
+        let mut text = String::new();
+        let mut file = File::open(self.config_path.as_path())?;
+        file.read_to_string(&mut text)?;
+
+        let toml: TOMLValue = text.parse()
+            .map_err(|e| Error::Format(format!("TOML parsing failure: {}", e)))?;
+
+        // Check file version.
+        toml.get("_version").and_then(TOMLValue::as_integer)
+            .ok_or(Error::Format("Missing '_version' specifier.".to_string()))
+            .and_then(|ver| if ver == CONFIG_VERSION {
+                Ok(())
+            } else {
+                let msg = format!(
+                    "Configuration not compatible (provided: {}, required: {}).",
+                    ver,
+                    CONFIG_VERSION);
+                Err(Error::Format(msg))
+            })?;
+
+        Ok(toml)
+    }
+
+    /// Builds a [`Testbed`] from an already-read and version-checked config document.
+    fn build(&self, toml: &TOMLValue) -> Result<Testbed> {
+        // Host and target I/O.
+        let mapping = self.parse_gpio(toml)?
+            .create_mapping()?;
+        // Energy metering.
+        let energy_meters = self.parse_energy(
+            &mapping,
+            toml.get("energy").ok_or(Error::Format("Missing 'energy' table.".to_string()))?)?;
+        // Software platform support.
+        let platform_support = self.parse_platform(
+            toml.get("platform").ok_or(Error::Format("Missing 'platform' table.".to_string()))?)?;
+        // Analog sampling.
+        let analog_channels = self.parse_analog(
+            &mapping,
+            toml.get("analog").ok_or(Error::Format("Missing 'analog' table.".to_string()))?)?;
+
+        let testbed = Testbed::new(
+            mapping,
+            platform_support,
+            energy_meters,
+            analog_channels);
+
+        Ok(testbed)
+    }
+
+    /** Applies any `platform.*` override (site-local `key=value` pairs; see [`crate::opts`]) onto
+    a clone of the config's `platform` table, overwriting the matching TOML key
+    (`platform.repo-path` overwrites the `repo-path` key of the `[platform]` table, etc.) with the
+    override's value.
+     */
+    fn apply_platform_overrides(&self, toml: &TOMLValue, overrides: &HashMap<String, String>) -> Result<TOMLValue> {
+        let mut toml = toml.clone();
+        let platform = toml.get_mut("platform")
+            .ok_or(Error::Format("Missing 'platform' table.".to_string()))?;
+        let table = platform.as_table_mut()
+            .ok_or(Error::Format("'platform' is not a table.".to_string()))?;
+
+        for (key, value) in overrides {
+            if let Some(toml_key) = key.strip_prefix("platform.") {
+                table.insert(toml_key.to_string(), TOMLValue::String(value.clone()));
+            }
+        }
+
+        Ok(toml)
+    }
+}
+
+impl TestbedConfigReader for TOMLTestbedParser {
+    fn create(&self) -> Result<Testbed> {
+        let toml = self.read()?;
+        self.build(&toml)
+    }
+
+    fn create_with_overrides(&self, overrides: &HashMap<String, String>) -> Result<Testbed> {
+        let toml = self.read()?;
+        let toml = self.apply_platform_overrides(&toml, overrides)?;
+        self.build(&toml)
+    }
+}
+
+#[derive(Deserialize)]
+struct IOConfig {
+    gpio: Vec<PinConfig>,
+    #[serde(rename = "trace-pins")]
+    trace_pins: Vec<u8>,
+}
+
+impl IOConfig {
+    fn create_mapping(&self) -> Result<Mapping> {
+        let mut device_io: Vec<(u8, (Direction, SignalClass))> = Vec::new();
+        for pcfg in &self.gpio {
+            let dir = Direction::try_from(pcfg.direction.as_str())
+                .map_err(|e| Error::Format(e.to_string()))?;
+            let sig = SignalClass::try_from(pcfg.signal.as_str())
+                .map_err(|e| Error::Format(e.to_string()))?;
+
+            device_io.push((pcfg.dpin, (dir, sig)));
+        }
+
+        let device = Device::new(&device_io);
+        let pin_conns: Vec<_> = self.gpio.iter().map(|pcfg| (pcfg.tpin, pcfg.dpin))
+            .collect();
+        let it_trace_pins = self.trace_pins.iter();
+
+        let mapping = Mapping::new(&device, &pin_conns, it_trace_pins)
+            .map_err(|e| Error::Format(format!("IO mapping error: {}", e)))?;
+
+        Ok(mapping)
+    }
+}
+
+#[derive(Deserialize)]
+struct PinConfig {
+    dpin: u8,
+    tpin: u8,
+    direction: String,
+    signal: String,
+}
+
+trait TOMLHardware: Sized {
+    fn from_toml(mapping: &Mapping, toml: &TOMLValue) -> Result<Self>;
+}
+
+impl TOMLHardware for hw::INA219 {
+    fn from_toml(mapping: &Mapping, toml: &TOMLValue) -> Result<Self> {
+        let i2c = mapping.get_i2c()?;
+        let address = toml.get("i2c-address").and_then(TOMLValue::as_integer)
+            .ok_or(Error::Format("INA219: missing 'i2c-address' property.".to_string()))
+            .and_then(|addr| u8::try_from(addr)
+                                 .map_err(|_e| Error::Format("INA219: 'i2c-address' is not valid.".to_string())))?;
+        let max_current = toml.get("max-current-amps").and_then(TOMLValue::as_float)
+            .ok_or(Error::Format("INA219: missing 'max-current-amps' property.".to_string()))? as f32;
+        let r_shunt = toml.get("shunt-resistance-ohms").and_then(TOMLValue::as_float)
+            .ok_or(Error::Format("INA219: missing 'shunt-resistance-ohms' property.".to_string()))? as f32;
+
+        hw::INA219::new(i2c, address, max_current, r_shunt)
+            .map_err(|e| Error::Driver(e.to_string()))
+    }
+}
+
+impl TOMLHardware for hw::PCF8591 {
+    fn from_toml(mapping: &Mapping, toml: &TOMLValue) -> Result<Self> {
+        let i2c = mapping.get_i2c()?;
+        let reference_voltage = toml.get("reference-voltage").and_then(TOMLValue::as_float)
+            .ok_or(Error::Format("PCF8591: missing 'reference-voltage' property.".to_string()))? as f32;
+
+        Ok(hw::PCF8591::new(i2c, reference_voltage))
+    }
+}
+
+impl TOMLHardware for hw::MCP3008 {
+    fn from_toml(mapping: &Mapping, toml: &TOMLValue) -> Result<Self> {
+        let spi = mapping.get_spi()?;
+        let reference_voltage = toml.get("reference-voltage").and_then(TOMLValue::as_float)
+            .ok_or(Error::Format("MCP3008: missing 'reference-voltage' property.".to_string()))? as f32;
+
+        Ok(hw::MCP3008::new(spi, reference_voltage))
+    }
+}
+
+trait TOMLPlatform: Sized {
+    fn from_toml(props: &TOMLValue) -> Result<Self>;
+}
+
+#[derive(Deserialize)]
+struct TockPlatformConfig {
+    #[serde(alias = "tockloader-path")]
+    tockloader_path: String,
+    #[serde(alias = "repo-path")]
+    repo_path: String,
+    #[serde(alias = "application-path")]
+    app_path: String,
+    board: String,
+    #[serde(alias = "tool-config-path", default)]
+    tool_config_path: Option<String>,
+}
+
+impl TOMLPlatform for platform::Tock {
+    fn from_toml(props: &TOMLValue) -> Result<Self> {
+        let config: TockPlatformConfig = props.clone().try_into()
+            .map_err(|e| Error::Format(format!("Tock support: deserialization error: {}", e)))?;
+        let tock_support = platform::Tock::new(
+            config.board.as_str(),
+            Path::new(&config.tockloader_path),
+            Path::new(&config.repo_path),
+            Path::new(&config.app_path),
+            config.tool_config_path.as_deref().map(Path::new))
+            .map_err(|e| Error::Driver(e.to_string()))?;
+
+        Ok(tock_support)
+    }
+}
@@ -8,15 +8,20 @@ use std::convert::From;
 use std::fmt;
 use std::fmt::Display;
 use std::iter::{Iterator, IntoIterator};
+use std::path::Path;
 
 use rppal::gpio;
 use rppal::gpio::{Gpio, InputPin, OutputPin};
 use rppal::i2c;
 use rppal::i2c::I2c;
+use rppal::spi;
+use rppal::spi::{Bus, Mode, SlaveSelect, Spi};
 
 use crate::comm::Direction;
 use crate::device;
 use crate::device::Device;
+use crate::firmware;
+use crate::firmware::FlashMethod;
 
 type Result<T> = std::result::Result<T, Error>;
 
@@ -38,6 +43,12 @@ pub enum Error {
     I2CUnavailable,
     /// I2C initialization error
     I2C(i2c::Error),
+    /// Mapping does not allow SPI
+    SPIUnavailable,
+    /// SPI initialization error
+    SPI(spi::Error),
+    /// Error flashing firmware onto the device under test.
+    Firmware(firmware::Error),
 }
 
 impl std::error::Error for Error {
@@ -45,6 +56,7 @@ impl std::error::Error for Error {
         match *self {
             Error::Device(ref dev_error) => Some(dev_error),
             Error::Gpio(ref gpio_error) => Some(gpio_error),
+            Error::Firmware(ref firmware_error) => Some(firmware_error),
             _ => None,
         }
     }
@@ -58,6 +70,9 @@ impl Display for Error {
             Error::UndefinedPin(pin_no) => write!(f, "target pin {} not mapped", pin_no),
             Error::I2CUnavailable => write!(f, "I2C pins (2, 3) are mapped"),
             Error::I2C(ref e) => write!(f, "could obtain I2C interface: {}", e),
+            Error::SPIUnavailable => write!(f, "SPI0 pins (7, 8, 9, 10, 11) are mapped"),
+            Error::SPI(ref e) => write!(f, "could obtain SPI interface: {}", e),
+            Error::Firmware(ref e) => write!(f, "could not flash device: {}", e),
         }
     }
 }
@@ -74,12 +89,81 @@ impl From<i2c::Error> for Error {
     }
 }
 
+impl From<spi::Error> for Error {
+    fn from(e: spi::Error) -> Self {
+        Error::SPI(e)
+    }
+}
+
 impl From<device::Error> for Error {
     fn from(e: device::Error) -> Self {
         Error::Device(e)
     }
 }
 
+impl From<firmware::Error> for Error {
+    fn from(e: firmware::Error) -> Self {
+        Error::Firmware(e)
+    }
+}
+
+/// Pull resistor configuration for a host pin configured as an input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pull {
+    Off,
+    Down,
+    Up,
+}
+
+/// Output drive strength for a host pin configured as an output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Drive {
+    Low,
+    Medium,
+    High,
+    Max,
+}
+
+/// Output slew rate for a host pin configured as an output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlewRate {
+    Slow,
+    Fast,
+}
+
+/** Electrical configuration for a host pin, consulted when it is materialized into a live GPIO pin.
+
+Left unset (`None`) fields keep the underlying GPIO implementation's default. A [`Pull`] keeps a
+floating DUT output from producing spurious edge captures. [`Drive`] and [`SlewRate`] are stored
+but not applied to any pin.
+ */
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PinConfig {
+    pull: Option<Pull>,
+    #[allow(dead_code)]
+    drive: Option<Drive>,
+    #[allow(dead_code)]
+    slew_rate: Option<SlewRate>,
+}
+
+impl PinConfig {
+    pub fn new() -> PinConfig {
+        PinConfig::default()
+    }
+
+    pub fn with_pull(self, pull: Pull) -> PinConfig {
+        PinConfig { pull: Some(pull), ..self }
+    }
+
+    pub fn with_drive(self, drive: Drive) -> PinConfig {
+        PinConfig { drive: Some(drive), ..self }
+    }
+
+    pub fn with_slew_rate(self, slew_rate: SlewRate) -> PinConfig {
+        PinConfig { slew_rate: Some(slew_rate), ..self }
+    }
+}
+
 /** Interface to I/O between the testbed and the device under test.
 
 `Mapping` defines the interface between the testbed and the device under test.
@@ -90,6 +174,7 @@ pub struct Mapping {
     device: Device,
     numbering: HashMap<u8, u8>,
     trace_pins: Vec<u8>,
+    pin_configs: HashMap<u8, PinConfig>,
 }
 
 impl Mapping {
@@ -126,9 +211,22 @@ impl Mapping {
             device: device.clone(),
             numbering,
             trace_pins,
+            pin_configs: HashMap::new(),
         })
     }
 
+    /** Record electrical configuration (pull resistor, drive strength, slew rate) for a host pin.
+
+    `host_pin` need not be mapped yet. Only the pull setting is applied, to pins materialized as
+    host inputs by [`get_gpio_outputs`](Mapping::get_gpio_outputs);
+    [`get_gpio_inputs`](Mapping::get_gpio_inputs) does not consult the configuration, and drive
+    strength and slew rate are not applied to any pin.
+     */
+    pub fn with_pin_config(mut self, host_pin: u8, config: PinConfig) -> Mapping {
+        self.pin_configs.insert(host_pin, config);
+        self
+    }
+
     /// Returns the host-target pin mapping.
     pub fn get_mapping(&self) -> &HashMap<u8, u8> {
         &self.numbering
@@ -148,7 +246,12 @@ impl Mapping {
 
         for (h_pin, t_pin) in input_numbering {
             let pin = gpio.get(h_pin)?;
-            inputs.push((t_pin, pin.into_output()));
+            let output = pin.into_output();
+            // NOTE: rppal doesn't expose per-pin drive strength/slew rate control on the BCM28xx
+            // GPIO hardware this testbed targets, so `PinConfig::drive`/`slew_rate` aren't wired up
+            // to anything yet; they're captured for API parity with DUT-side boards (e.g. RP2040)
+            // whose HAL does support them.
+            inputs.push((t_pin, output));
         }
 
         Ok(DeviceInputs::new(inputs))
@@ -168,7 +271,15 @@ impl Mapping {
 
         for (h_pin, t_pin) in output_numbering {
             let pin = gpio.get(h_pin)?;
-            outputs.push((t_pin, pin.into_input()));
+            let mut input = pin.into_input();
+            if let Some(pull) = self.pin_configs.get(&h_pin).and_then(|config| config.pull) {
+                input.set_pullupdown(match pull {
+                    Pull::Off => gpio::Pull::Off,
+                    Pull::Down => gpio::Pull::Down,
+                    Pull::Up => gpio::Pull::Up,
+                });
+            }
+            outputs.push((t_pin, input));
         }
 
         Ok(DeviceOutputs::new(outputs))
@@ -199,6 +310,31 @@ impl Mapping {
             Ok(I2c::new()?)
         }
     }
+
+    /** Configures and returns the SPI0 interface.
+
+    # Errors
+    - If the I/O mapping has mapped the pins used for the SPI0 bus, this function returns `Error::SPIUnavailable`.
+    - If the underlying implementation encounters an error initializing SPI, this function returns `Error::SPI`.
+     */
+    pub fn get_spi(&self) -> Result<Spi> {
+        let spi_pins_mapped = (7..=11).any(|pin_no| self.numbering.contains_key(&pin_no));
+        if spi_pins_mapped {
+            Err(Error::SPIUnavailable)
+        } else {
+            Ok(Spi::new(Bus::Spi0, SlaveSelect::Ss0, 1_000_000, Mode::Mode0)?)
+        }
+    }
+
+    /** Provision the device under test with a firmware image before a test run.
+
+    Drives the reset/boot-select sequence described by `method` over its configured control
+    pins, transfers `image`, and resets the target into the freshly flashed application.
+     */
+    pub fn flash_device(&self, image: &Path, method: FlashMethod) -> Result<()> {
+        firmware::flash(image, &method)
+            .map_err(Error::from)
+    }
 }
 
 impl Display for Mapping {
@@ -259,6 +395,30 @@ impl<T> Pins<T> {
     }
 }
 
+impl Pins<OutputPin> {
+    /** Begin driving `pin_no` with a software PWM waveform.
+
+    `duty_cycle` is in the range `0.0..=1.0`. This hands off to rppal's own software PWM
+    implementation (`OutputPin::set_pwm_frequency`), which runs the waveform on a background
+    thread and stops it automatically when the pin is dropped or reconfigured, so callers don't
+    need to track or join anything themselves.
+     */
+    pub fn start_pwm(&mut self, pin_no: u8, frequency_hz: f64, duty_cycle: f64) -> Result<()> {
+        let pin = self.get_pin_mut(pin_no)?;
+        pin.set_pwm_frequency(frequency_hz, duty_cycle)?;
+
+        Ok(())
+    }
+
+    /// Stop driving `pin_no` with PWM, returning it to a static output level.
+    pub fn stop_pwm(&mut self, pin_no: u8) -> Result<()> {
+        let pin = self.get_pin_mut(pin_no)?;
+        pin.clear_pwm()?;
+
+        Ok(())
+    }
+}
+
 /** An iterator over mutable pins.
 
 This iterator allows the pins that are iterated over to change state
@@ -2,9 +2,12 @@
 
 use std::process;
 
+use json;
+
 mod comm;
 mod device;
 mod facility;
+mod firmware;
 mod hw;
 mod input;
 mod io;
@@ -12,11 +15,44 @@ mod opts;
 mod sw;
 mod testing;
 
+use crate::opts::MessageFormat;
+use crate::testing::evaluation::Evaluation;
 use crate::testing::test::{
     Operation,
     Test,
 };
 
+/// Print a fatal error according to `format`: free-form text for [`MessageFormat::Human`], or one
+/// `{"kind":"error",...}` JSON object for [`MessageFormat::Json`].
+fn print_error(format: MessageFormat, message: &str) {
+    match format {
+        MessageFormat::Human => println!("{}", message),
+        MessageFormat::Json => println!("{}", json::object! { kind: "error", message: message }),
+    }
+}
+
+/// Print a test outcome according to `format`: the current [`Display`](std::fmt::Display) output
+/// for [`MessageFormat::Human`], or one `{"kind":"result",...}` JSON object for [`MessageFormat::Json`].
+fn print_result(format: MessageFormat, evaluation: &Evaluation) {
+    match format {
+        MessageFormat::Human => println!("{}", evaluation),
+        MessageFormat::Json => {
+            let status = evaluation.outcome().to_string().to_lowercase();
+            let duration_ms = evaluation.get_exec_result().as_ref()
+                .map(|exec| exec.duration().as_millis() as u64)
+                .unwrap_or(0);
+
+            let obj = json::object! {
+                kind: "result",
+                test: evaluation.get_test().get_id(),
+                status: status,
+                duration_ms: duration_ms,
+            };
+            println!("{}", obj);
+        },
+    }
+}
+
 fn main() {
     let result = opts::parse();
     if let Err(ref e) = result {
@@ -28,26 +64,24 @@ fn main() {
         process::exit(1);
     }
     let configuration = result.unwrap();
+    let message_format = configuration.get_message_format();
 
-    let result = configuration.get_testbed_reader().create();
+    let result = configuration.get_testbed_reader().create_with_overrides(configuration.get_overrides());
     if let Err(ref e) = result {
-        println!("Failed to initialize testbed.\n{}", e);
+        print_error(message_format, &format!("Failed to initialize testbed.\n{}", e));
         process::exit(1);
     }
     let testbed = result.unwrap();
     print!("{}\n", testbed);
 
-    let tests: Vec<Test> = configuration.get_test_adapter().tests()
-        .into_iter()
-        .map(|r| r.unwrap().clone())
-        .collect();
+    let tests: Vec<Test> = configuration.get_tests().to_vec();
 
     let res = testbed.execute(&tests);
     if let Ok(results) = res {
         for r in results {
-            println!("{}", r);
+            print_result(message_format, &r);
         }
     } else {
-        println!("Error running tests: {}", res.unwrap_err());
+        print_error(message_format, &format!("Error running tests: {}", res.unwrap_err()));
     }
 }
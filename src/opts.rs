@@ -1,20 +1,46 @@
 //! Runtime configuration options.
 
+use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::env;
 use std::fmt;
 use std::fmt::Display;
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use getopts::Options;
+use json;
+use serde::Deserialize;
 
 use crate::input::{
     TestbedConfigReader,
     // TestConfigAdapter,
 };
+use crate::input::hard_code::HardCodedTests;
 use crate::input::json::JSONTestbedParser;
+use crate::input::toml::TOMLTestbedParser;
+use crate::testing::test::{Mode, Test};
 
 type Result<T> = std::result::Result<T, Error>;
 
+/// The name of the project-local config file, discovered the same way rustfmt locates
+/// `rustfmt.toml`: walking upward from the current directory until one is found.
+const PROJECT_CONFIG_FILE_NAME: &str = "clockwise.toml";
+
+/// Override keys a site-local `--overrides` file is allowed to set.
+///
+/// These name deployment-site facts (network identity, clock source, ...) that field-deployed
+/// boards commonly need to tweak without touching the structured testbed config.
+const KNOWN_OVERRIDE_KEYS: &[&str] = &[
+    "network.address",
+    "network.mac",
+    "clock.source",
+    "platform.tockloader-path",
+    "platform.repo-path",
+    "platform.application-path",
+    "platform.board",
+];
+
 #[derive(Clone, Debug)]
 pub enum Error {
     /// An option is missing its required argument.
@@ -25,6 +51,15 @@ pub enum Error {
     Help(String),
     /// User passed an invalid option.
     Invalid(String),
+    /// An `--overrides` file could not be read.
+    IO(String),
+    /// An `--overrides` file line could not be parsed as `key=value`.
+    OverrideFormat(String),
+    /// An `--overrides` file set a key this program doesn't recognize.
+    UnknownOverrideKey(String),
+    /// A `clockwise.toml` project config (discovered or given via `--config`) could not be read
+    /// or did not parse as valid TOML for its expected shape.
+    Config(String),
 }
 
 impl std::error::Error for Error {
@@ -43,6 +78,10 @@ impl Display for Error {
             ArgumentMissing(arg) => write!(f, "missing argument for '{}' option", arg),
             Help(ref help_msg) => write!(f, "Program help:\n{}", help_msg),
             Invalid(ref opt) => write!(f, "Invalid option: {}", opt),
+            IO(ref msg) => write!(f, "could not read overrides file: {}", msg),
+            OverrideFormat(ref line) => write!(f, "malformed override line: '{}'", line),
+            UnknownOverrideKey(ref key) => write!(f, "unknown override key: '{}'", key),
+            Config(ref msg) => write!(f, "invalid project config: {}", msg),
             _ => write!(f, ""),
         }
     }
@@ -54,32 +93,299 @@ impl From<getopts::Fail> for Error {
     }
 }
 
+/// How results and diagnostics are printed, following rustc's `ErrorOutputType` split.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MessageFormat {
+    /// The current free-form [`Display`]-based output.
+    Human,
+    /// One self-contained JSON object per line, so CI systems can consume results without
+    /// scraping human-readable text.
+    Json,
+}
+
+impl TryFrom<&str> for MessageFormat {
+    type Error = String;
+
+    fn try_from(s: &str) -> std::result::Result<Self, Self::Error> {
+        match s {
+            "human" => Ok(MessageFormat::Human),
+            "json" => Ok(MessageFormat::Json),
+            _ => Err(format!("'{}' is not a valid message format", s)),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Configuration {
     testbed_reader: Box<dyn TestbedConfigReader>,
-    // test_reader: Box<dyn TestConfigAdapter>,
+    overrides: HashMap<String, String>,
+    message_format: MessageFormat,
+    tests: Vec<Test>,
+    target: Option<String>,
+    filters: Vec<String>,
+    mode: Option<Mode>,
 }
 
 impl Configuration {
-    fn new(testbed_reader: Box<dyn TestbedConfigReader>) -> Configuration {
+    fn new(testbed_reader: Box<dyn TestbedConfigReader>,
+           overrides: HashMap<String, String>,
+           message_format: MessageFormat,
+           tests: Vec<Test>,
+           target: Option<String>,
+           filters: Vec<String>,
+           mode: Option<Mode>) -> Configuration {
         Configuration {
             testbed_reader,
+            overrides,
+            message_format,
+            tests,
+            target,
+            filters,
+            mode,
         }
     }
 
     pub fn get_testbed_reader(&self) -> &dyn TestbedConfigReader {
         self.testbed_reader.as_ref()
     }
+
+    /// Returns the site-local key/value overrides layered on top of the testbed config, if any
+    /// were given via `--overrides`.
+    pub fn get_overrides(&self) -> &HashMap<String, String> {
+        &self.overrides
+    }
+
+    /// Returns how results and diagnostics should be printed.
+    pub fn get_message_format(&self) -> MessageFormat {
+        self.message_format
+    }
+
+    /** Returns the tests selected by `--test-format`/`[tests].format` (`hard-coded` by default),
+    already narrowed down by [`Configuration::get_filters`] and [`Configuration::get_mode`].
+     */
+    pub fn get_tests(&self) -> &[Test] {
+        &self.tests
+    }
+
+    /// Returns the positional name filters given after the testbed config path; a test is kept
+    /// only if its id contains at least one of these as a substring (all tests pass if empty).
+    pub fn get_filters(&self) -> &[String] {
+        &self.filters
+    }
+
+    /// Returns the `--mode` a test's declared [`Mode`] must match to be kept, if one was given.
+    pub fn get_mode(&self) -> Option<Mode> {
+        self.mode
+    }
+
+    /** Returns the board selected by `--target`, if given.
+
+    Like rustc's `--target`, this disambiguates among multiple boards a testbed config could
+    describe; unlike rustc it's carried to the testbed reader as a `platform.board` entry in
+    [`Configuration::get_overrides`] (see [`resolve_target`]) rather than as a value the reader
+    takes directly, reusing the override mechanism every other site-local setting already goes
+    through.
+     */
+    pub fn get_target(&self) -> Option<&str> {
+        self.target.as_deref()
+    }
+}
+
+/// A factory producing a configured [`TestbedConfigReader`] for one testbed format.
+type TestbedFactory = Box<dyn Fn(&Path) -> Box<dyn TestbedConfigReader>>;
+
+/** Maps a format name (as given to `--testbed-format` or a `clockwise.toml` `[testbed].format`)
+to the factory that builds a [`TestbedConfigReader`] for it.
+
+Pre-populated with this crate's built-in formats (`json`, `toml`), and
+[`register`](TestbedFormatRegistry::register) adds or replaces a format. [`parse`] builds its own
+registry holding only these built-in formats, so formats registered on any other instance are not
+available to it.
+ */
+pub struct TestbedFormatRegistry {
+    factories: HashMap<String, TestbedFactory>,
+}
+
+impl TestbedFormatRegistry {
+    fn with_defaults() -> TestbedFormatRegistry {
+        let mut registry = TestbedFormatRegistry { factories: HashMap::new() };
+        registry.register("json", |path| Box::new(JSONTestbedParser::new(path)));
+        registry.register("toml", |path| Box::new(TOMLTestbedParser::new(path)));
+
+        registry
+    }
+
+    /// Register `factory` under `name`, overwriting any factory already registered under it.
+    pub fn register<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn(&Path) -> Box<dyn TestbedConfigReader> + 'static,
+    {
+        self.factories.insert(name.to_string(), Box::new(factory));
+    }
+
+    fn build(&self, name: &str, path: &Path) -> Result<Box<dyn TestbedConfigReader>> {
+        self.factories.get(name)
+            .map(|factory| factory(path))
+            .ok_or_else(|| Error::Invalid(format!(
+                "'{}' is not a testbed format (known formats: {})", name, self.known_names())))
+    }
+
+    fn known_names(&self) -> String {
+        let mut names: Vec<&str> = self.factories.keys().map(String::as_str).collect();
+        names.sort();
+        names.join(", ")
+    }
+}
+
+/// A factory producing the tests for one test format.
+type TestFactory = Box<dyn Fn() -> Vec<Test>>;
+
+/** Maps a format name (as given to `--test-format` or a `clockwise.toml` `[tests].format`) to the
+factory that builds its tests.
+
+Pre-populated with this crate's built-in `hard-coded` format (see
+[`HardCodedTests`](crate::input::hard_code::HardCodedTests)); see
+[`TestbedFormatRegistry`] for the equivalent registry for testbeds.
+ */
+pub struct TestFormatRegistry {
+    factories: HashMap<String, TestFactory>,
+}
+
+impl TestFormatRegistry {
+    fn with_defaults() -> TestFormatRegistry {
+        let mut registry = TestFormatRegistry { factories: HashMap::new() };
+        registry.register("hard-coded", || HardCodedTests::new().into_tests());
+
+        registry
+    }
+
+    /// Register `factory` under `name`, overwriting any factory already registered under it.
+    pub fn register<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn() -> Vec<Test> + 'static,
+    {
+        self.factories.insert(name.to_string(), Box::new(factory));
+    }
+
+    fn build(&self, name: &str) -> Result<Vec<Test>> {
+        self.factories.get(name)
+            .map(|factory| factory())
+            .ok_or_else(|| Error::Invalid(format!(
+                "'{}' is not a test format (known formats: {})", name, self.known_names())))
+    }
+
+    fn known_names(&self) -> String {
+        let mut names: Vec<&str> = self.factories.keys().map(String::as_str).collect();
+        names.sort();
+        names.join(", ")
+    }
+}
+
+/// The `[testbed]`/`[tests]` tables of a `clockwise.toml` project config.
+#[derive(Debug, Default, Deserialize)]
+struct ProjectConfig {
+    testbed: Option<ProjectConfigTable>,
+    tests: Option<ProjectConfigTable>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProjectConfigTable {
+    format: Option<String>,
+    path: Option<String>,
+}
+
+/** Walk upward from the current directory looking for a [`PROJECT_CONFIG_FILE_NAME`], the same
+way rustfmt's `get_toml_path` locates `rustfmt.toml`: check the current directory, then each parent
+in turn, stopping at the first match (or the filesystem root).
+ */
+fn find_project_config() -> Option<PathBuf> {
+    let mut dir = env::current_dir().ok()?;
+    loop {
+        let candidate = dir.join(PROJECT_CONFIG_FILE_NAME);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+
+        if !dir.pop() {
+            return None;
+        }
+    }
+}
+
+/// Read and parse a `clockwise.toml` project config from `path`.
+fn load_project_config(path: &Path) -> Result<ProjectConfig> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| Error::Config(format!("{}: {}", path.display(), e)))?;
+
+    toml::from_str(&text)
+        .map_err(|e| Error::Config(format!("{}: {}", path.display(), e)))
+}
+
+/** Resolve a `--target` value to the board id the testbed reader should be overridden with,
+following rustc's `TargetTriple` split between a plain triple and a `TargetJson` spec file: a value
+ending in `.json` is read as a target spec naming the board under a `"board"` key, anything else is
+taken as a literal board id.
+ */
+fn resolve_target(value: &str) -> Result<String> {
+    if value.ends_with(".json") {
+        let text = fs::read_to_string(value)
+            .map_err(|e| Error::Invalid(format!("could not read target spec '{}': {}", value, e)))?;
+        let spec = json::parse(&text)
+            .map_err(|e| Error::Invalid(format!("'{}' is not valid JSON: {}", value, e)))?;
+
+        spec["board"].as_str()
+            .map(str::to_string)
+            .ok_or_else(|| Error::Invalid(format!("target spec '{}' has no string 'board' field", value)))
+    } else {
+        Ok(value.to_string())
+    }
 }
 
 fn create_options() -> Options {
     let mut opts = Options::new();
-    opts.optopt("b", "testbed-format", "select a testbed input format", "FORMAT");
+    opts.optopt("b", "testbed-format", "select a testbed input format (json, toml)", "FORMAT");
+    opts.optopt("", "test-format", "select a test input format (hard-coded)", "FORMAT");
+    opts.optopt("", "overrides", "key=value overrides layered on top of the testbed config", "PATH");
+    opts.optopt("", "message-format", "how to print results/diagnostics (human, json)", "FORMAT");
+    opts.optopt("", "config", "use this clockwise.toml instead of discovering one", "PATH");
+    opts.optopt("", "target", "select a board (or a *.json target spec naming one)", "TRIPLE");
+    opts.optopt("", "mode", "only run tests declared with this mode (pass, fail)", "MODE");
     opts.optflag("h", "help", "show help");
 
     opts
 }
 
+/** Parse a `key=value` override file.
+
+One `key=value` pair per line; blank lines and lines starting with `#` are ignored. Keys not found
+in [`KNOWN_OVERRIDE_KEYS`] are rejected, since they could never apply to anything.
+ */
+fn parse_overrides(path: &Path) -> Result<HashMap<String, String>> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| Error::IO(e.to_string()))?;
+
+    let mut overrides = HashMap::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (key, value) = line.split_once('=')
+            .ok_or(Error::OverrideFormat(line.to_string()))?;
+        let key = key.trim();
+        let value = value.trim();
+
+        if !KNOWN_OVERRIDE_KEYS.contains(&key) {
+            return Err(Error::UnknownOverrideKey(key.to_string()));
+        }
+
+        overrides.insert(key.to_string(), value.to_string());
+    }
+
+    Ok(overrides)
+}
+
 pub fn parse() -> Result<Configuration> {
     let opts = create_options();
 
@@ -90,31 +396,103 @@ pub fn parse() -> Result<Configuration> {
         let brief = format!("Usage: {} [ options ] <testbed config>", &cli_args[0]);
         Err(Error::Help(opts.usage(&brief)))
     } else {
-        // Free arguments.
+        // A forced `--config` always wins over discovery; otherwise walk upward from the
+        // current directory looking for a project config to overlay on top of the built-in
+        // defaults below.
+        let project_config = if matches.opt_present("config") {
+            let config_path = matches.opt_str("config")
+                .ok_or(Error::ArgumentMissing("config"))?;
+            Some(load_project_config(Path::new(&config_path))?)
+        } else {
+            match find_project_config() {
+                Some(ref path) => Some(load_project_config(path)?),
+                None => None,
+            }
+        };
+        let testbed_table = project_config.as_ref().and_then(|c| c.testbed.as_ref());
+        let tests_table = project_config.as_ref().and_then(|c| c.tests.as_ref());
+
+        // Resolve the testbed path: built-in default is none (a path must come from somewhere),
+        // overlaid by the project config's `[testbed].path`, overlaid by an explicit free
+        // argument on the command line. Everything after that first free argument is a
+        // compiletest-style substring filter over test names (see `Configuration::get_filters`).
         let testbed_config = matches.free.get(0)
+            .cloned()
+            .or_else(|| testbed_table.and_then(|t| t.path.clone()))
             .ok_or(Error::ArgumentMissing("testbed config"))?;
+        let filters: Vec<String> = matches.free.iter().skip(1).cloned().collect();
 
-        // Other provided arguments.
-        let testbed_reader = if matches.opt_present("testbed-format") {
-            let format = matches.opt_str("testbed-format")
-                .ok_or(Error::ArgumentMissing("testbed-format"))?;
-            match format.as_str() {
-                "json" => {
-                    let json_path = Path::new(testbed_config);
-                    Ok(Box::new(JSONTestbedParser::new(json_path)))
-                },
-
-                _ => {
-                    let msg = format!("{} is not a testbed format", format);
-                    Err(Error::Invalid(msg))
-                }
-            }
+        // Resolve the testbed format the same way: built-in default is JSON, overlaid by the
+        // project config's `[testbed].format`, overlaid by `--testbed-format`.
+        let testbed_format = if matches.opt_present("testbed-format") {
+            Some(matches.opt_str("testbed-format")
+                 .ok_or(Error::ArgumentMissing("testbed-format"))?)
+        } else {
+            testbed_table.and_then(|t| t.format.clone())
+        };
+        let testbed_format = testbed_format.as_deref().unwrap_or("json");
+
+        let testbed_registry = TestbedFormatRegistry::with_defaults();
+        let testbed_reader = testbed_registry.build(testbed_format, Path::new(&testbed_config))?;
+
+        // Resolve the test format: built-in default is `hard-coded`, overlaid by the project
+        // config's `[tests].format`, overlaid by `--test-format`.
+        let test_format = if matches.opt_present("test-format") {
+            Some(matches.opt_str("test-format")
+                 .ok_or(Error::ArgumentMissing("test-format"))?)
+        } else {
+            tests_table.and_then(|t| t.format.clone())
+        };
+        let test_format = test_format.as_deref().unwrap_or("hard-coded");
+
+        let test_registry = TestFormatRegistry::with_defaults();
+        let tests = test_registry.build(test_format)?;
+
+        let mode = if matches.opt_present("mode") {
+            let value = matches.opt_str("mode")
+                .ok_or(Error::ArgumentMissing("mode"))?;
+            Some(Mode::try_from(value.as_str()).map_err(Error::Invalid)?)
+        } else {
+            None
+        };
+
+        // Narrow the tests down to those matching every active filter: a name filter (if any
+        // were given) and the declared mode (if `--mode` was given).
+        let tests: Vec<Test> = tests.into_iter()
+            .filter(|t| filters.is_empty() || filters.iter().any(|f| t.get_id().contains(f.as_str())))
+            .filter(|t| mode.map_or(true, |m| t.get_mode() == m))
+            .collect();
+
+        let mut overrides = if matches.opt_present("overrides") {
+            let overrides_path = matches.opt_str("overrides")
+                .ok_or(Error::ArgumentMissing("overrides"))?;
+            parse_overrides(Path::new(&overrides_path))?
+        } else {
+            HashMap::new()
+        };
+
+        // `--target` always wins over an `--overrides` file's `platform.board`, the same way
+        // every other explicit CLI option wins over a file-based setting.
+        let target = if matches.opt_present("target") {
+            let value = matches.opt_str("target")
+                .ok_or(Error::ArgumentMissing("target"))?;
+            let board = resolve_target(&value)?;
+            overrides.insert("platform.board".to_string(), board.clone());
+
+            Some(board)
+        } else {
+            None
+        };
+
+        let message_format = if matches.opt_present("message-format") {
+            let format = matches.opt_str("message-format")
+                .ok_or(Error::ArgumentMissing("message-format"))?;
+            MessageFormat::try_from(format.as_str())
+                .map_err(Error::Invalid)?
         } else {
-            // Default to the JSON testbed reader.
-            let json_path = Path::new(testbed_config);
-            Ok(Box::new(JSONTestbedParser::new(json_path)))
-        }?;
+            MessageFormat::Human
+        };
 
-        Ok(Configuration::new(testbed_reader))
+        Ok(Configuration::new(testbed_reader, overrides, message_format, tests, target, filters, mode))
     }
 }
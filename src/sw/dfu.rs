@@ -0,0 +1,149 @@
+/*! USB DFU (Device Firmware Upgrade) based flashing.
+
+Where [`super::platform::Tock`] loads applications by shelling out to `tockloader`, [`DfuLoader`]
+talks the USB DFU class protocol directly, for boards that expose a DFU interface and don't need
+(or have) a `tockloader` install. This crate has no USB dependency of its own, so the actual
+control transfers are left to a caller-supplied [`DfuTransport`] (backed by, say, `rusb` in
+whatever binary embeds the testbed); this module owns the download state machine on top of it.
+ */
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fs;
+use std::thread;
+use std::time::Duration;
+
+use super::application::Application;
+use super::error::Error;
+use super::Platform;
+use super::PlatformSupport;
+use super::Result;
+
+/// Block size used to chunk the application image for `DFU_DNLOAD` transfers, matching the
+/// convention most DFU tooling (e.g. `dfu-util`) defaults to.
+const BLOCK_SIZE: usize = 2048;
+
+/// DFU device state, as reported by `DFU_GETSTATUS` (USB DFU class spec, section 6.1.2).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DfuState {
+    AppIdle,
+    AppDetach,
+    DfuIdle,
+    DfuDnloadSync,
+    DfuDnbusy,
+    DfuDnloadIdle,
+    DfuManifestSync,
+    DfuManifest,
+    DfuManifestWaitReset,
+    DfuUploadIdle,
+    DfuError,
+}
+
+/// A `DFU_GETSTATUS` response: the device's current state and how long the host should wait
+/// before polling again.
+#[derive(Clone, Copy, Debug)]
+pub struct DfuStatus {
+    pub state: DfuState,
+    pub poll_timeout: Duration,
+}
+
+/** Raw USB control-transfer access to a DFU-capable device.
+
+This is the seam a real USB backend plugs in through; [`DfuLoader`] drives the download state
+machine (erase, block streaming, status polling, manifestation) entirely in terms of these three
+requests and never touches a USB handle itself.
+ */
+pub trait DfuTransport: std::fmt::Debug {
+    /// Issue a full-chip erase. Not part of the base DFU class spec proper, but supported by
+    /// essentially every DFU-capable microcontroller bootloader as a vendor extension.
+    fn erase(&self) -> std::result::Result<(), String>;
+
+    /// Issue a `DFU_DNLOAD` (class request 1) transfer for `block_num` with `data` as the
+    /// payload. An empty `data` is the final, manifestation-triggering block.
+    fn download(&self, block_num: u16, data: &[u8]) -> std::result::Result<(), String>;
+
+    /// Issue a `DFU_GETSTATUS` (class request 3) transfer and parse the result.
+    fn get_status(&self) -> std::result::Result<DfuStatus, String>;
+}
+
+/// Testbed support for boards flashed directly over a USB DFU interface.
+#[derive(Debug)]
+pub struct DfuLoader {
+    platform: Platform,
+    transport: Box<dyn DfuTransport>,
+    loaded_apps: RefCell<HashSet<String>>,
+}
+
+impl DfuLoader {
+    /// Create a new loader that reports as `platform` and talks to the device through
+    /// `transport`.
+    pub fn new(platform: Platform, transport: Box<dyn DfuTransport>) -> DfuLoader {
+        DfuLoader {
+            platform,
+            transport,
+            loaded_apps: RefCell::new(HashSet::new()),
+        }
+    }
+
+    /// Poll `DFU_GETSTATUS` until the device leaves `dfuDNBUSY`, honoring each response's
+    /// reported `bwPollTimeout` between attempts.
+    fn await_idle(&self) -> Result<()> {
+        loop {
+            let status = self.transport.get_status().map_err(Error::Dfu)?;
+            match status.state {
+                DfuState::DfuDnbusy => thread::sleep(status.poll_timeout),
+                DfuState::DfuError => return Err(Error::Dfu("device reported dfuERROR".to_string())),
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Send one `DFU_DNLOAD` block and wait for the device to finish processing it.
+    fn download_block(&self, block_num: u16, data: &[u8]) -> Result<()> {
+        self.transport.download(block_num, data).map_err(Error::Dfu)?;
+        self.await_idle()
+    }
+}
+
+impl PlatformSupport for DfuLoader {
+    fn platform(&self) -> Platform {
+        self.platform
+    }
+
+    fn load(&self, app: &Application) -> Result<()> {
+        let path = app.get_for(self.platform)?;
+        let image = fs::read(path)?;
+
+        self.transport.erase().map_err(Error::Dfu)?;
+        self.await_idle()?;
+
+        let mut block_num: u16 = 0;
+        for chunk in image.chunks(BLOCK_SIZE) {
+            self.download_block(block_num, chunk)?;
+            block_num += 1;
+        }
+        // A zero-length final block triggers manifestation and the device's reset.
+        self.download_block(block_num, &[])?;
+
+        self.loaded_apps.borrow_mut()
+            .insert(app.get_id().to_string());
+
+        Ok(())
+    }
+
+    fn unload(&self, app_id: &str) -> Result<()> {
+        let was_present = self.loaded_apps.borrow_mut().remove(app_id);
+        if was_present {
+            self.transport.erase().map_err(Error::Dfu)?;
+            self.await_idle()?;
+        }
+
+        Ok(())
+    }
+
+    fn loaded_software(&self) -> HashSet<String> {
+        self.loaded_apps.borrow().iter()
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,70 @@
+/*! A hardware-free [`PlatformSupport`] for CI/development runs without a board attached.
+
+`Emulated` stands in for a real target's loader: `load`/`unload` install/remove an application
+image into an in-process "flash" (just the set of app ids; the bytes are not kept) instead of programming a
+device, and `loaded_software` reports what's currently "installed" the same way [`super::platform::Tock`]
+does.
+
+What this module deliberately does NOT attempt: the rest of the testbed's tracing pipeline
+(`Test::execute`/`Test::observe` in [`crate::testing::test`]) talks to `rppal::gpio` pins directly
+to record [`crate::comm::Signal`] responses, not through `PlatformSupport` — there is no
+memory-mapped peripheral bus or CPU step loop in this tree to emulate, and no event types named
+`KernelWork`/`ProcessSuspended`/`InterruptServiced` exist here (the current pipeline's `Trace`
+records host GPIO trigger pins, not kernel-level events). Running an existing `Test`/`Criterion`
+definition fully device-free would additionally require an emulated GPIO backend behind
+[`crate::io::Mapping`], which is out of scope here. What `Emulated` and [`super::super::hw::EmulatedEnergyMeter`]
+give you is the software-loading and energy-metering half of that story, usable standalone or as
+the seed of a future emulated GPIO layer.
+ */
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fs;
+
+use super::application::Application;
+use super::error::Error;
+use super::Platform;
+use super::PlatformSupport;
+use super::Result;
+
+/// In-process stand-in for a target device: "flashing" an app just remembers its id.
+#[derive(Debug, Default)]
+pub struct Emulated {
+    flash: RefCell<HashSet<String>>,
+}
+
+impl Emulated {
+    /// Create a new, empty emulated target.
+    pub fn new() -> Emulated {
+        Emulated::default()
+    }
+}
+
+impl PlatformSupport for Emulated {
+    fn platform(&self) -> Platform {
+        Platform::Emulated
+    }
+
+    fn load(&self, app: &Application) -> Result<()> {
+        let path = app.get_for(self.platform())?;
+        // Read the image so a missing/unreadable file is still caught, same as a real loader
+        // would fail to flash a nonexistent image; the bytes themselves aren't retained.
+        fs::read(path)?;
+
+        self.flash.borrow_mut()
+            .insert(app.get_id().to_string());
+
+        Ok(())
+    }
+
+    fn unload(&self, app_id: &str) -> Result<()> {
+        self.flash.borrow_mut().remove(app_id);
+        Ok(())
+    }
+
+    fn loaded_software(&self) -> HashSet<String> {
+        self.flash.borrow().iter()
+            .cloned()
+            .collect()
+    }
+}
@@ -16,6 +16,16 @@ pub enum Error {
     Other(String),
     /// Application not defined for platform.
     UndefinedApp(String, Platform),
+    /// Problem opening or attaching to a debug probe.
+    Probe(String),
+    /// Problem programming an image onto the target through a debug probe.
+    Flash(String),
+    /// Problem transferring a DFU block or reading back device status ([`super::dfu`]).
+    Dfu(String),
+    /// A captured trace message's header named a `Spec` version (first field) other than the
+    /// `SPEC_VERSION` the host was built against (second field); see
+    /// [`super::instrument::TraceData::deserialize_with`].
+    SpecVersionMismatch(u32, u32),
 }
 
 impl error::Error for Error {
@@ -34,6 +44,11 @@ impl Display for Error {
             Error::Tool(ref output) => write!(f, "could not load software (status: {})", output.status),
             Error::Other(ref msg) => write!(f, "unexpected error: {}", msg),
             Error::UndefinedApp(ref name, platform) => write!(f, "no '{}' app defined for {}", name, platform),
+            Error::Probe(ref msg) => write!(f, "debug probe error: {}", msg),
+            Error::Flash(ref msg) => write!(f, "flashing error: {}", msg),
+            Error::Dfu(ref msg) => write!(f, "DFU error: {}", msg),
+            Error::SpecVersionMismatch(found, expected) =>
+                write!(f, "trace capture was written against spec version {}, but this build expects version {}", found, expected),
         }
     }
 }
@@ -1,13 +1,15 @@
 //! Platform instrumentation support.
 
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Display;
 use std::fs::File;
+use std::hash::{Hash, Hasher};
 use std::path::Path;
 
+use super::error::Error;
 use super::Result;
-// use error::Error;
 
 use json;
 use json::JsonValue;
@@ -40,13 +42,29 @@ impl Spec {
         }
     }
 
+    /// Fingerprint the requested trace point set, independent of the order they're given in.
+    ///
+    /// Used to compare what's being requested against what was last persisted as deployed, so a
+    /// platform can skip a rebuild/flash cycle when nothing has actually changed (see
+    /// `PlatformSupport::deployed_fingerprint`).
+    pub fn fingerprint<'a, T>(trace_points: T) -> String
+    where
+        T: IntoIterator<Item = &'a str>
+    {
+        let mut points: Vec<&str> = trace_points.into_iter().collect();
+        points.sort();
+
+        let mut hasher = DefaultHasher::new();
+        points.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+
     #[allow(dead_code)]
     pub fn trace_point_value(&self, name: &str) -> Option<u16> {
         self.name_value.get(name)
             .map(|val| *val)
     }
 
-    #[allow(dead_code)]
     pub fn trace_point_name(&self, value: u16) -> Option<&String> {
         self.value_name.get(&value)
     }
@@ -94,3 +112,82 @@ impl Display for Spec {
         Ok(())
     }
 }
+
+/** A trace message read off the device, resolved against a [`Spec`]'s trace-point table.
+
+Unlike a fixed set of hardcoded message kinds, a `TraceData` is only ever produced by
+[`TraceData::deserialize_with`], which looks the message's id up in the `Spec` it's given: an id
+the `Spec` recognizes decodes as [`TraceData::Point`], and one it doesn't (e.g. a trace point added
+to target firmware the host hasn't regenerated its `Spec` from yet) decodes as
+[`TraceData::Custom`] instead of failing. This lets new trace message kinds appear on the target
+and be captured on the host without this enum itself ever needing new variants.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum TraceData {
+    /// A message whose id this capture's `Spec` resolves to a named trace point.
+    Point { id: u16, name: String, payload: Vec<u8> },
+    /// A message whose id isn't in this capture's `Spec`.
+    Custom { id: u16, payload: Vec<u8> },
+}
+
+impl TraceData {
+    /// Returns the message's trace point id, regardless of whether it was resolved to a name.
+    pub fn id(&self) -> u16 {
+        match self {
+            TraceData::Point { id, .. } => *id,
+            TraceData::Custom { id, .. } => *id,
+        }
+    }
+
+    /** Decode one trace message from the front of `buffer`, returning it along with the number
+    of bytes consumed so the caller can decode the next message from what follows.
+
+    The wire format is self-describing: a 4-byte little-endian `Spec` version (checked against
+    [`SPEC_VERSION`] so a capture written by an incompatible build is rejected up front with
+    [`Error::SpecVersionMismatch`] rather than silently misdecoded), a 2-byte little-endian id
+    field (masked down to `spec.id_bit_length()` bits), a 1-byte payload length, then the payload
+    itself.
+     */
+    pub fn deserialize_with(buffer: &[u8], spec: &Spec) -> Result<(TraceData, usize)> {
+        const HEADER_LEN: usize = 7;
+        if buffer.len() < HEADER_LEN {
+            return Err(Error::Other(format!(
+                "trace message buffer too short ({} byte(s), need at least {})",
+                buffer.len(), HEADER_LEN)));
+        }
+
+        let version = u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]);
+        if version != SPEC_VERSION {
+            return Err(Error::SpecVersionMismatch(version, SPEC_VERSION));
+        }
+
+        let raw_id = u16::from_le_bytes([buffer[4], buffer[5]]);
+        let id = raw_id & id_mask(spec.id_bit_length());
+
+        let payload_len = buffer[6] as usize;
+        let payload_end = HEADER_LEN + payload_len;
+        if buffer.len() < payload_end {
+            return Err(Error::Other(format!(
+                "trace message declares a {}-byte payload but only {} byte(s) remain",
+                payload_len, buffer.len() - HEADER_LEN)));
+        }
+        let payload = buffer[HEADER_LEN..payload_end].to_vec();
+
+        let data = match spec.trace_point_name(id) {
+            Some(name) => TraceData::Point { id, name: name.clone(), payload },
+            None => TraceData::Custom { id, payload },
+        };
+
+        Ok((data, payload_end))
+    }
+}
+
+/// Returns the mask covering the low `len` bits of a trace point id field.
+fn id_mask(len: u8) -> u16 {
+    let mut mask = 0;
+    for n in 0..len {
+        mask |= 1 << n;
+    }
+
+    mask
+}
@@ -2,15 +2,21 @@
  */
 
 pub mod application;
+pub mod dfu;
+pub mod emulated;
 pub mod error;
 pub mod instrument;
 pub mod platform;
+pub mod probe;
+pub mod update;
 
 use std::collections::HashSet;
 use std::convert::From;
 use std::convert::TryFrom;
 use std::fmt;
 use std::fmt::{Debug, Display};
+use std::path::Path;
+use std::process::Command;
 
 use application::Application;
 use instrument::Spec;
@@ -22,6 +28,11 @@ type Result<T> = std::result::Result<T, Error>;
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum Platform {
     Tock,
+    /// A bare-metal or non-Tock target delivered as a prebuilt ELF startup kernel rather than a
+    /// `.tab` app; see [`probe::ProbeRsFlasher`].
+    BareElf,
+    /// No physical target at all; see [`emulated::Emulated`].
+    Emulated,
 }
 
 impl TryFrom<&str> for Platform {
@@ -31,6 +42,8 @@ impl TryFrom<&str> for Platform {
         use Platform::*;
         match s {
             "tock" => Ok(Tock),
+            "bare-elf" => Ok(BareElf),
+            "emulated" => Ok(Emulated),
             _ => Err(format!("'{}' is not a valid platform", s)),
         }
     }
@@ -40,6 +53,8 @@ impl Display for Platform {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Platform::Tock => write!(f, "Tock OS"),
+            Platform::BareElf => write!(f, "bare ELF"),
+            Platform::Emulated => write!(f, "emulated"),
         }
     }
 }
@@ -48,6 +63,8 @@ impl From<Platform> for String {
     fn from(platform: Platform) -> String {
         match platform {
             Platform::Tock => "Tock OS".to_string(),
+            Platform::BareElf => "bare ELF".to_string(),
+            Platform::Emulated => "emulated".to_string(),
         }
     }
 }
@@ -71,4 +88,58 @@ pub trait PlatformSupport: Debug {
         let _ = trace_points;
         Err(Error::Unsupported)
     }
+
+    /// Query the configuration fingerprint currently deployed on the target, if determinable.
+    ///
+    /// Platforms that can't query the device for this (most can't) should persist the
+    /// fingerprint of what they last deployed themselves and return that; `None` means "unknown,
+    /// assume a rebuild is needed".
+    fn deployed_fingerprint(&self) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// Build a [`Spec`] out of the requested trace points.
+    ///
+    /// Shared by every platform's `reconfigure` so assigning trace points their `Spec` values
+    /// happens the same way regardless of how (or whether) the platform rebuilds from source.
+    fn build_spec<'a, T>(&self, trace_points: T) -> Spec
+    where
+        T: IntoIterator<Item = &'a str>,
+        Self: Sized,
+    {
+        Spec::new(trace_points)
+    }
+
+    /// Find the files under `search_paths` referencing each of `trace_points` and touch them, so
+    /// a subsequent `make` picks them up as changed. Shared across source-based platforms (e.g.
+    /// [`platform::Tock`]) that instrument their build by touching the files a trace point
+    /// appears in rather than threading the `Spec` through the build system directly.
+    fn touch_source<'a, T>(&self,
+                           grep_path: &Path,
+                           touch_path: &Path,
+                           search_paths: &[&Path],
+                           trace_points: T) -> Result<()>
+    where
+        T: IntoIterator<Item = &'a String>,
+        Self: Sized,
+    {
+        for trace_point_name in trace_points {
+            let mut grep_args = vec!["-l".to_string(), "-r".to_string(), trace_point_name.clone()];
+            grep_args.extend(search_paths.iter().map(|p| p.to_str().unwrap().to_string()));
+
+            let grep_output = Command::new(grep_path)
+                .args(&grep_args)
+                .output()
+                .map(|output| String::from_utf8(output.stdout).unwrap().trim().to_string())?;
+
+            for path in grep_output.lines() {
+                println!("Touching '{}'.", path);
+                Command::new(touch_path)
+                    .args(&[path])
+                    .output()?;
+            }
+        }
+
+        Ok(())
+    }
 }
@@ -1,8 +1,9 @@
 //! Multi-platform support interfaces.
 
 use std::cell::RefCell;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::env;
+use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Output};
 
@@ -13,6 +14,64 @@ use super::Platform;
 use super::PlatformSupport;
 use super::Result;
 
+/// Tool locations and extra build environment variables for a [`Tock`] instance, loaded from a
+/// `key=value` file so non-FHS toolchain layouts don't require recompiling the crate.
+///
+/// Recognized keys: `make=`, `grep=`, `touch=` (paths to those tools), and any number of
+/// `env.NAME=value` pairs, which are added to the build environment on top of the host's own.
+/// Lines starting with `#` and blank lines are ignored. Any key not recognized is rejected so
+/// typos in the config file don't silently do nothing.
+#[derive(Clone, Debug)]
+struct ToolConfig {
+    make_path: PathBuf,
+    grep_path: PathBuf,
+    touch_path: PathBuf,
+    env: HashMap<String, String>,
+}
+
+impl Default for ToolConfig {
+    fn default() -> ToolConfig {
+        ToolConfig {
+            make_path: PathBuf::from("/usr/bin/make"),
+            grep_path: PathBuf::from("/usr/bin/grep"),
+            touch_path: PathBuf::from("/usr/bin/touch"),
+            env: HashMap::new(),
+        }
+    }
+}
+
+impl ToolConfig {
+    fn load(config_path: &Path) -> Result<ToolConfig> {
+        let text = fs::read_to_string(config_path)?;
+
+        let mut config = ToolConfig::default();
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line.split_once('=')
+                .ok_or_else(|| Error::Other(format!(
+                    "{}:{}: expected 'key=value', got '{}'", config_path.display(), line_no + 1, line)))?;
+            let (key, value) = (key.trim(), value.trim());
+
+            match key {
+                "make" => config.make_path = PathBuf::from(value),
+                "grep" => config.grep_path = PathBuf::from(value),
+                "touch" => config.touch_path = PathBuf::from(value),
+                _ if key.starts_with("env.") => {
+                    config.env.insert(key["env.".len()..].to_string(), value.to_string());
+                },
+                _ => return Err(Error::Other(format!(
+                    "{}:{}: unrecognized key '{}'", config_path.display(), line_no + 1, key))),
+            }
+        }
+
+        Ok(config)
+    }
+}
+
 /// Testbed support for the Tock OS platform.
 #[derive(Clone, Debug)]
 pub struct Tock {
@@ -25,64 +84,48 @@ pub struct Tock {
     enabled_trace_points: RefCell<HashSet<String>>,
     source_path: PathBuf,
     app_path: PathBuf,
+    tools: ToolConfig,
 }
 
 impl Tock {
     /// Create a new Tock platform instance.
+    ///
+    /// `tool_config_path`, if given, points to a `key=value` file overriding the tool paths
+    /// `make_command`/`touch_source` shell out to and adding extra build environment variables;
+    /// see [`ToolConfig`]. Without one, the conventional FHS tool locations are used and the
+    /// build environment is passed through unmodified.
     pub fn new(board: &str,
                tockloader_path: &Path,
                source_path: &Path,
-               app_path: &Path) -> Tock {
-        Tock {
+               app_path: &Path,
+               tool_config_path: Option<&Path>) -> Result<Tock> {
+        let tools = match tool_config_path {
+            Some(path) => ToolConfig::load(path)?,
+            None => ToolConfig::default(),
+        };
+
+        Ok(Tock {
             board: board.to_string(),
             tockloader_path: tockloader_path.to_path_buf(),
             loaded_apps: RefCell::new(HashSet::new()),
             enabled_trace_points: RefCell::new(HashSet::new()),
             source_path: source_path.to_path_buf(),
             app_path: app_path.to_path_buf(),
-        }
-    }
-
-    /// Touch files containing the listed trace points to get `make` to rebuild them.
-    fn touch_source<'a, T>(&self, trace_points: T) -> Result<()>
-    where
-        T: IntoIterator<Item = &'a String>
-    {
-        let kernel_path = self.source_path.clone().join("kernel/src");
-        let capsules_path = self.source_path.clone().join("capsules/src");
-        for trace_point_name in trace_points {
-            // Find file with the trace point.
-            let grep_output = Command::new("/usr/bin/grep")
-                .args(&["-l",
-                        "-r",
-                        &trace_point_name,
-                        kernel_path.to_str().unwrap(),
-                        capsules_path.to_str().unwrap()])
-                .output()
-                .map(|output| String::from_utf8(output.stdout).unwrap().trim().to_string())?;
-
-            for path in grep_output.lines() {
-                println!("Touching '{}'.", path);
-                Command::new("/usr/bin/touch")
-                    .args(&[path])
-                    .output()?;
-            }
-        }
-
-        Ok(())
+            tools,
+        })
     }
 
     /// Retrieve a `make` command.
     fn make_command(&self) -> Command {
-        // NOTICE: forcing use of the Hail board configuration.
         let make_work_dir = self.source_path.clone()
-            .join("boards/hail");
+            .join("boards")
+            .join(&self.board);
 
-        // Assuming make is in /usr/bin.
-        let mut command = Command::new("/usr/bin/make");
+        let mut command = Command::new(&self.tools.make_path);
         command
             .args(&["-C", make_work_dir.to_str().unwrap()])
-            .envs(env::vars());
+            .envs(env::vars())
+            .envs(self.tools.env.clone());
 
         command
     }
@@ -137,6 +180,17 @@ impl Tock {
             .output()
             .map_err(|io_err| Error::IO(io_err))
     }
+
+    /// Where the fingerprint of the last-deployed trace point set is persisted, so it survives
+    /// across testbed restarts.
+    fn fingerprint_path(&self) -> PathBuf {
+        self.app_path.join(".tock_spec_fingerprint")
+    }
+
+    fn write_deployed_fingerprint(&self, fingerprint: &str) -> Result<()> {
+        fs::write(self.fingerprint_path(), fingerprint)?;
+        Ok(())
+    }
 }
 
 impl PlatformSupport for Tock {
@@ -194,17 +248,30 @@ impl PlatformSupport for Tock {
     }
 
     fn reconfigure(&self, trace_points: &Vec<String>) -> Result<Spec> {
-        // Do not rebuild if the desired points are already enabled.
         let trace_points: HashSet<String> = trace_points.into_iter()
             .cloned()
             .collect();
+        let requested_fingerprint = Spec::fingerprint(trace_points.iter().map(|s| s.as_str()));
+
+        // Do not rebuild if the desired points are already enabled: either this instance already
+        // rebuilt for them this run, or (surviving a testbed restart) the persisted record of
+        // what was last flashed already matches.
         let already_enabled = self.enabled_trace_points.borrow()
-            .is_superset(&trace_points);
+            .is_superset(&trace_points)
+            || self.deployed_fingerprint()?.as_deref() == Some(requested_fingerprint.as_str());
+
         if !already_enabled {
             println!("Triggering rebuild of Tock. Need new trace points enabled.");
-            self.touch_source(&trace_points)?;
-
-            let spec = Spec::new(trace_points.iter().map(|s| s.as_ref()));
+            let kernel_path = self.source_path.join("kernel/src");
+            let capsules_path = self.source_path.join("capsules/src");
+            PlatformSupport::touch_source(
+                self,
+                &self.tools.grep_path,
+                &self.tools.touch_path,
+                &[kernel_path.as_path(), capsules_path.as_path()],
+                &trace_points)?;
+
+            let spec = self.build_spec(trace_points.iter().map(|s| s.as_ref()));
             // let output = self.build_instrumented(&spec)?;
             // let stdout = String::from_utf8(output.stdout.clone())
             //     .unwrap_or("<<Could not process stdout output.>>".to_string());
@@ -218,10 +285,23 @@ impl PlatformSupport for Tock {
             //     self.program()?;
             //     Ok(spec)
             // }
+            self.write_deployed_fingerprint(&requested_fingerprint)?;
+            *self.enabled_trace_points.borrow_mut() = trace_points;
+
             Ok(spec)
         } else {
             println!("Using currently deployed build of Tock.");
-            Ok(Spec::new(self.enabled_trace_points.borrow().iter().map(|s| s.as_ref())))
+            Ok(self.build_spec(self.enabled_trace_points.borrow().iter().map(|s| s.as_ref())))
+        }
+    }
+
+    fn deployed_fingerprint(&self) -> Result<Option<String>> {
+        // No protocol to read this back from the device itself, so this reads the local record
+        // of what this instance (or a prior run) last flashed.
+        match fs::read_to_string(self.fingerprint_path()) {
+            Ok(fingerprint) => Ok(Some(fingerprint)),
+            Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(Error::IO(e)),
         }
     }
 }
@@ -0,0 +1,163 @@
+//! In-process firmware flashing over a debug probe (via `probe-rs`), as an alternative to
+//! shelling out to target-specific loader tools (`tockloader`, `openocd`, etc.).
+
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::time::Duration;
+
+use probe_rs::{Permissions, Probe, Session};
+use probe_rs::flashing::{self, Format};
+
+use super::application::Application;
+use super::error::Error;
+use super::Platform;
+use super::PlatformSupport;
+use super::Result;
+
+/// How to leave the core once flashing completes.
+#[derive(Clone, Copy, Debug)]
+pub enum PostFlashState {
+    /// Reset the core and let it run.
+    Run,
+    /// Reset the core and leave it halted.
+    Halt,
+}
+
+/** Flashes images onto an ARM Cortex-M target over a debug probe.
+
+Where [`super::platform::Tock`] loads applications by shelling out to `tockloader`, `ProbeRsFlasher`
+attaches to the target in-process via `probe-rs`: halt the core, program the image through the flash
+loader, then reset-and-run (or reset-and-halt). This removes the dependency on target-specific CLI
+tooling, and `probe_serial` lets the harness pick among several connected probes.
+
+Constructed with [`Platform::BareElf`], this is also the testbed's support for bare-metal/non-Tock
+targets delivered as a prebuilt ELF startup kernel rather than a `.tab` app: `load` programs the ELF
+straight from `Application::get_for`, and since there is no `tockloader`-style registry to query,
+`loaded_software` reports the content hash of the last image this flasher itself flashed.
+ */
+#[derive(Clone, Debug)]
+pub struct ProbeRsFlasher {
+    platform: Platform,
+    chip: String,
+    probe_serial: Option<String>,
+    post_flash: PostFlashState,
+    // Content hash of the last image successfully flashed, in lieu of querying the device for
+    // what's currently loaded (see `loaded_software`).
+    loaded_image: RefCell<Option<String>>,
+}
+
+impl ProbeRsFlasher {
+    /** Create a new flasher targeting the named chip (as recognized by probe-rs) for the given
+    platform.
+
+    `probe_serial` selects among multiple connected probes by serial number; pass `None` to attach
+    to whichever probe is attached.
+     */
+    pub fn new(platform: Platform, chip: &str, probe_serial: Option<&str>) -> ProbeRsFlasher {
+        ProbeRsFlasher {
+            platform,
+            chip: chip.to_string(),
+            probe_serial: probe_serial.map(|s| s.to_string()),
+            post_flash: PostFlashState::Run,
+            loaded_image: RefCell::new(None),
+        }
+    }
+
+    /// Leave the core halted after flashing instead of letting it run.
+    #[allow(dead_code)]
+    pub fn with_post_flash_halt(self) -> Self {
+        Self {
+            post_flash: PostFlashState::Halt,
+            ..self
+        }
+    }
+
+    /// Open the configured probe (or the first attached probe listed, if none was named) and attach to the
+    /// configured chip.
+    fn attach(&self) -> Result<Session> {
+        let probe = match &self.probe_serial {
+            Some(serial) => {
+                let info = Probe::list_all()
+                    .into_iter()
+                    .find(|info| info.serial_number.as_deref() == Some(serial.as_str()))
+                    .ok_or_else(|| Error::Probe(format!("no probe with serial '{}' attached", serial)))?;
+                info.open()
+                    .map_err(|e| Error::Probe(e.to_string()))?
+            },
+            None => {
+                let info = Probe::list_all()
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| Error::Probe("no debug probes attached".to_string()))?;
+                info.open()
+                    .map_err(|e| Error::Probe(e.to_string()))?
+            },
+        };
+
+        probe.attach(&self.chip, Permissions::default())
+            .map_err(|e| Error::Probe(e.to_string()))
+    }
+
+    /// Flash an image file onto the target, then reset according to `post_flash`.
+    fn flash(&self, image_path: &Path) -> Result<()> {
+        let image_bytes = fs::read(image_path)?;
+
+        let mut session = self.attach()?;
+
+        session.core(0)
+            .and_then(|mut core| core.halt(Duration::from_millis(500)))
+            .map_err(|e| Error::Probe(e.to_string()))?;
+
+        let format = match image_path.extension().and_then(|ext| ext.to_str()) {
+            Some("bin") => Format::Bin(Default::default()),
+            _ => Format::Elf,
+        };
+        flashing::download_file(&mut session, image_path, format)
+            .map_err(|e| Error::Flash(e.to_string()))?;
+
+        let mut core = session.core(0)
+            .map_err(|e| Error::Probe(e.to_string()))?;
+        match self.post_flash {
+            PostFlashState::Run =>
+                core.reset().map_err(|e| Error::Probe(e.to_string()))?,
+            PostFlashState::Halt =>
+                core.reset_and_halt(Duration::from_millis(500))
+                    .map(|_| ())
+                    .map_err(|e| Error::Probe(e.to_string()))?,
+        }
+
+        let mut hasher = DefaultHasher::new();
+        image_bytes.hash(&mut hasher);
+        *self.loaded_image.borrow_mut() = Some(format!("{:016x}", hasher.finish()));
+
+        Ok(())
+    }
+}
+
+impl PlatformSupport for ProbeRsFlasher {
+    fn platform(&self) -> Platform {
+        self.platform
+    }
+
+    fn load(&self, app: &Application) -> Result<()> {
+        let image_path = app.get_for(self.platform)?;
+        self.flash(image_path)
+    }
+
+    fn unload(&self, _app_id: &str) -> Result<()> {
+        Err(Error::Other(
+            "ProbeRsFlasher has no notion of selectively unloading applications; reflash instead".to_string()))
+    }
+
+    fn loaded_software(&self) -> HashSet<String> {
+        // No protocol to query the device for what's currently loaded, so this reports the
+        // content hash of the last image this flasher itself successfully flashed.
+        self.loaded_image.borrow().iter()
+            .cloned()
+            .collect()
+    }
+}
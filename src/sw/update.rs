@@ -0,0 +1,86 @@
+//! A/B (dual-bank) firmware update flow with post-swap verification and rollback.
+//!
+//! Models an update mechanism built on a dual-bank bootloader: a new image is written into a
+//! DFU/update partition, a swap is requested, and the device reboots into the new bank. The new
+//! image must actively confirm itself (via [`FirmwareUpdater::mark_booted`]) before the bootloader
+//! will keep it there on the following reset; an image that never confirms itself gets rolled back
+//! automatically. [`verify_update`] drives that confirmation from trace-point evidence so an OTA
+//! test exercises the real swap-and-verify path instead of assuming a flash always "took".
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::testing::criteria::TraceCondition;
+use crate::testing::trace::Trace;
+
+use super::Result;
+
+/// State of a pending or confirmed firmware update, as reported by the bootloader.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum UpdateState {
+    /// No update is pending, and none has been confirmed booted since the last full reflash.
+    None,
+    /// The bootloader swapped in the new image, but it has not confirmed itself booted yet.
+    ///
+    /// A device reset while still in this state causes the bootloader to roll back to the
+    /// previous bank.
+    Swapped,
+    /// The new image confirmed itself booted; the bootloader will keep it on future resets.
+    Booted,
+}
+
+/** Drives a dual-bank bootloader's write/swap/verify protocol for a device under test.
+
+Implementors speak whatever transport exposes the device's update partition and bootloader status
+(USB DFU, a UART command channel, a memory-mapped status word, ...); this trait only names the steps
+of the swap-and-verify flow so a testbed can exercise it the same way regardless of transport.
+ */
+pub trait FirmwareUpdater: std::fmt::Debug {
+    /** Write `image` into the update partition and request a bootloader swap.
+
+    Returns once the write and swap request are accepted; the swap itself only takes effect once
+    the device reboots, leaving the update in [`UpdateState::Swapped`].
+     */
+    fn update(&self, image: &Path) -> Result<()>;
+
+    /// Read back the bootloader's current update-state indicator.
+    fn get_update_state(&self) -> Result<UpdateState>;
+
+    /** Confirm the freshly swapped image passed its self-test.
+
+    Tells the bootloader to keep the new image on future resets instead of rolling back to the
+    previous bank. Calling this when no update is pending is a no-op as far as the bootloader is
+    concerned.
+     */
+    fn mark_booted(&self) -> Result<()>;
+}
+
+/** Confirm or roll back a pending update based on whether its self-test checkpoint trace fired.
+
+Scans `traces` (as reconstructed for the post-reboot execution, offset from `t0`) for one matching
+`checkpoint` within `deadline`. If found, the new image is confirmed via
+[`FirmwareUpdater::mark_booted`] and this returns `Ok(UpdateState::Booted)`. If not, the update is
+left unconfirmed — the bootloader rolls back to the previous bank on the device's next reset — and
+this returns the updater's own read of the current state (ordinarily `UpdateState::Swapped`).
+ */
+pub fn verify_update(
+    updater: &dyn FirmwareUpdater,
+    checkpoint: &TraceCondition,
+    traces: &[Trace],
+    t0: Instant,
+    deadline: Duration,
+) -> Result<UpdateState> {
+    let reached_checkpoint = traces.iter()
+        .any(|trace| {
+            trace.get_id() == checkpoint.get_id()
+                && checkpoint.get_extra_data().map_or(true, |extra| trace.get_extra() == extra)
+                && trace.get_offset(t0) <= deadline
+        });
+
+    if reached_checkpoint {
+        updater.mark_booted()?;
+        Ok(UpdateState::Booted)
+    } else {
+        updater.get_update_state()
+    }
+}
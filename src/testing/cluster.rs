@@ -0,0 +1,301 @@
+/*! Distribute a test suite across several networked [`Testbed`] agents.
+
+A [`TestbedAgent`] wraps a local `Testbed` with a TCP listener: a connected controller streams it
+jobs, the agent runs each one on its own hardware exactly as `Testbed::execute` would, and results
+stream back. A [`TestbedCluster`] on the controller side connects to a set of agents and hands out
+tests work-stealing style, so a faster or less-loaded board simply pulls its next job sooner
+instead of sitting idle waiting for a fixed share; a dropped agent connection re-queues its
+in-flight test for whichever agent asks next.
+ */
+
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::thread::JoinHandle;
+
+use serde::{Deserialize, Serialize};
+
+use super::Error;
+use super::criteria::Criterion;
+use super::evaluation::{Evaluation, Status};
+use super::test::{Mode, Operation, Test};
+use super::testbed::Testbed;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/** Wire-format mirror of a [`Test`].
+
+Identical data to a `Test`, but with its actions flattened out of their local min-heap ordering
+(`BinaryHeap<Reverse<Operation>>` doesn't round-trip through serde) so the whole thing can be
+sent over the wire and turned back into a `Test` on the other end.
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WireTest {
+    id: String,
+    app_ids: Vec<String>,
+    trace_points: Vec<String>,
+    actions: Vec<Operation>,
+    criteria: Vec<Criterion>,
+    mode: Mode,
+}
+
+impl From<&Test> for WireTest {
+    fn from(test: &Test) -> WireTest {
+        WireTest {
+            id: test.get_id().to_string(),
+            app_ids: test.get_app_ids().iter().cloned().collect(),
+            trace_points: test.get_trace_points().iter().cloned().collect(),
+            actions: test.get_actions().copied().collect(),
+            criteria: test.get_criteria().clone(),
+            mode: test.get_mode(),
+        }
+    }
+}
+
+impl From<WireTest> for Test {
+    fn from(wire: WireTest) -> Test {
+        Test::new(&wire.id,
+                  wire.app_ids.iter().map(|id| id.as_str()),
+                  wire.trace_points.iter().map(|tp| tp.as_str()),
+                  wire.actions.iter(),
+                  wire.criteria.iter(),
+                  wire.mode == Mode::Pass)
+    }
+}
+
+/** Wire-format summary of an [`Evaluation`].
+
+An `Evaluation` itself can't be serialized as-is -- it's built on `Instant`s that mean nothing off
+the machine that recorded them. This carries the outcome plus the same report a human operator
+would see locally (via `Evaluation`'s `Display` impl), with every timestamp already rendered as an
+offset from test start.
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WireEvaluation {
+    /// Identifier of the test this is an evaluation of.
+    pub test_id: String,
+    /// Overall outcome of the evaluation.
+    pub outcome: Status,
+    /// Human-readable report, identical to what [`Evaluation`]'s `Display` impl would print.
+    pub report: String,
+}
+
+impl From<&Evaluation> for WireEvaluation {
+    fn from(eval: &Evaluation) -> WireEvaluation {
+        WireEvaluation {
+            test_id: eval.get_test().get_id().to_string(),
+            outcome: eval.outcome(),
+            report: eval.to_string(),
+        }
+    }
+}
+
+/// Messages exchanged between a [`TestbedCluster`] and a [`TestbedAgent`] over a single
+/// connection, one JSON object per line.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+enum Message {
+    /// Controller -> agent: run this test next.
+    Job(WireTest),
+    /// Agent -> controller: result of the most recently assigned job.
+    Result(WireEvaluation),
+    /// Controller -> agent: no more tests; the agent may disconnect.
+    Done,
+}
+
+fn send_message(stream: &mut TcpStream, message: &Message) -> Result<()> {
+    let mut line = serde_json::to_string(message)
+        .map_err(|e| Error::Cluster(format!("failed to encode message: {}", e)))?;
+    line.push('\n');
+
+    stream.write_all(line.as_bytes())
+        .map_err(|e| Error::Cluster(format!("failed to send message: {}", e)))
+}
+
+/// Returns the next message on the connection, or `None` if the peer closed it.
+fn recv_message(reader: &mut BufReader<TcpStream>) -> Result<Option<Message>> {
+    let mut line = String::new();
+    let read = reader.read_line(&mut line)
+        .map_err(|e| Error::Cluster(format!("failed to receive message: {}", e)))?;
+    if read == 0 {
+        return Ok(None);
+    }
+
+    serde_json::from_str(line.trim_end())
+        .map(Some)
+        .map_err(|e| Error::Cluster(format!("failed to decode message: {}", e)))
+}
+
+/// Runs tests dispatched by a [`TestbedCluster`] on this node's local `Testbed`.
+pub struct TestbedAgent {
+    testbed: Testbed,
+}
+
+impl TestbedAgent {
+    /// Wrap a local `Testbed` for remote dispatch.
+    pub fn new(testbed: Testbed) -> TestbedAgent {
+        TestbedAgent { testbed }
+    }
+
+    /** Accept one controller connection on `addr` and run jobs until it sends [`Message::Done`]
+    or disconnects.
+
+    Serves a single controller per call; call again to accept the next one.
+     */
+    pub fn serve<A: ToSocketAddrs>(&self, addr: A) -> Result<()> {
+        let listener = TcpListener::bind(addr)
+            .map_err(|e| Error::Cluster(format!("failed to bind agent socket: {}", e)))?;
+        let (stream, peer) = listener.accept()
+            .map_err(|e| Error::Cluster(format!("failed to accept controller connection: {}", e)))?;
+        println!("agent: controller connected from {}", peer);
+
+        let mut writer = stream.try_clone()
+            .map_err(|e| Error::Cluster(format!("failed to clone agent socket: {}", e)))?;
+        let mut reader = BufReader::new(stream);
+
+        loop {
+            match recv_message(&mut reader)? {
+                Some(Message::Job(wire_test)) => {
+                    let test: Test = wire_test.into();
+                    println!("agent: running '{}'", test.get_id());
+
+                    let results = self.testbed.execute(&[test])?;
+                    for result in &results {
+                        send_message(&mut writer, &Message::Result(WireEvaluation::from(result)))?;
+                    }
+                },
+                Some(Message::Result(_)) => {
+                    // Controllers never send a Result; ignore rather than tearing down the
+                    // connection over a malformed peer.
+                },
+                Some(Message::Done) | None => {
+                    println!("agent: controller is done; disconnecting");
+                    break;
+                },
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// One agent connection owned by a [`TestbedCluster`].
+struct AgentConnection {
+    addr: String,
+    stream: TcpStream,
+}
+
+/** Distributes a test suite across several [`TestbedAgent`]s and collects their results.
+
+Work is handed out work-stealing style: every agent pulls its next test off one shared queue as
+soon as it finishes its last one, rather than being handed a fixed share up front.
+ */
+pub struct TestbedCluster {
+    agents: Vec<AgentConnection>,
+}
+
+impl TestbedCluster {
+    /** Connect to every agent address given.
+
+    Fails if any single agent can't be reached -- a cluster doesn't start silently short-handed.
+     */
+    pub fn connect<A: ToSocketAddrs + ToString>(addrs: &[A]) -> Result<TestbedCluster> {
+        let mut agents = Vec::new();
+        for addr in addrs {
+            let stream = TcpStream::connect(addr)
+                .map_err(|e| Error::Cluster(format!("failed to connect to agent '{}': {}", addr.to_string(), e)))?;
+            agents.push(AgentConnection { addr: addr.to_string(), stream });
+        }
+
+        Ok(TestbedCluster { agents })
+    }
+
+    /** Run `tests` across every connected agent and return the results collected from all of them.
+
+    Dispatch is work-stealing: a shared queue holds the not-yet-assigned tests, and each agent's
+    worker thread pops the next one as soon as it's free, so a faster board simply ends up running
+    more tests. If an agent's connection drops while a test is in flight, that test is pushed back
+    onto the queue for another agent to pick up instead of being lost; the disconnected agent's
+    thread then exits without rejoining the pool. If every agent disconnects before the queue is
+    drained, the remaining tests are left unrun and a warning is logged -- the results collected so
+    far are still returned rather than discarded.
+     */
+    pub fn run<'a, T>(&mut self, tests: T) -> Result<Vec<WireEvaluation>>
+    where
+        T: IntoIterator<Item = &'a Test>,
+    {
+        let queue: Arc<Mutex<VecDeque<Test>>> = Arc::new(Mutex::new(tests.into_iter().cloned().collect()));
+        let results: Arc<Mutex<Vec<WireEvaluation>>> = Arc::new(Mutex::new(Vec::new()));
+
+        let mut workers: Vec<JoinHandle<()>> = Vec::new();
+        for agent in &self.agents {
+            let addr = agent.addr.clone();
+            let mut writer = match agent.stream.try_clone() {
+                Ok(stream) => stream,
+                Err(e) => {
+                    println!("cluster: could not use agent '{}': {}", addr, e);
+                    continue;
+                },
+            };
+            let mut reader = match writer.try_clone() {
+                Ok(stream) => BufReader::new(stream),
+                Err(e) => {
+                    println!("cluster: could not use agent '{}': {}", addr, e);
+                    continue;
+                },
+            };
+            let queue = Arc::clone(&queue);
+            let results = Arc::clone(&results);
+
+            workers.push(thread::spawn(move || {
+                loop {
+                    let test = match queue.lock().unwrap().pop_front() {
+                        Some(test) => test,
+                        None => break,
+                    };
+                    let test_id = test.get_id().to_string();
+
+                    if let Err(e) = send_message(&mut writer, &Message::Job(WireTest::from(&test))) {
+                        println!("cluster: agent '{}' unreachable ({}); requeuing '{}'", addr, e, test_id);
+                        queue.lock().unwrap().push_back(test);
+                        break;
+                    }
+
+                    match recv_message(&mut reader) {
+                        Ok(Some(Message::Result(evaluation))) => {
+                            results.lock().unwrap().push(evaluation);
+                        },
+                        Ok(other) => {
+                            println!("cluster: agent '{}' sent an unexpected reply ({:?}); requeuing '{}'",
+                                     addr, other, test_id);
+                            queue.lock().unwrap().push_back(test);
+                            break;
+                        },
+                        Err(e) => {
+                            println!("cluster: lost agent '{}' ({}); requeuing '{}'", addr, e, test_id);
+                            queue.lock().unwrap().push_back(test);
+                            break;
+                        },
+                    }
+                }
+            }));
+        }
+
+        for worker in workers {
+            if worker.join().is_err() {
+                println!("cluster: a dispatch thread panicked");
+            }
+        }
+
+        let remaining = queue.lock().unwrap().len();
+        if remaining > 0 {
+            println!("cluster: {} test(s) could not be run; all agents that could have run them disconnected",
+                     remaining);
+        }
+
+        Ok(Arc::try_unwrap(results)
+            .map(|results| results.into_inner().unwrap())
+            .unwrap_or_else(|results| results.lock().unwrap().clone()))
+    }
+}
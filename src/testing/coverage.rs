@@ -0,0 +1,478 @@
+/*! Firmware source-line coverage from a firmware ELF's DWARF line-number table.
+
+Given a set of program-counter addresses observed on the device (however they were collected --
+see the module-level caveat below) and the firmware image that produced them, [`LineTable`] maps
+each address back to a `(file, line, column)` and [`CoverageReport`] tallies which lines were hit.
+
+This only handles DWARF versions 2 through 4, which covers the default output of `arm-none-eabi-gcc`
+and similar embedded toolchains; DWARF 5's split directory/file-name tables (and 64-bit DWARF, ie. a
+`0xffffffff` initial length escape) aren't recognized and are reported as [`super::Error::Coverage`].
+Non-statement rows (`is_stmt` false, typically inlined or compiler-generated code) are dropped when
+the line-number program is run, so an address inside such a row's range is attributed to the
+statement row that precedes it.
+
+Nothing in the current trace pipeline actually produces raw addresses yet -- [`super::trace::Trace`]
+carries GPIO-trigger `(id, extra)` pairs, not program-counter samples -- so there is no
+`Evaluation::coverage()` here. Once a source of real PC samples exists (e.g. an ITM/SWO data trace
+packet stream), build a [`LineTable`] from the firmware ELF once per test run and feed its samples to
+[`CoverageReport::from_samples`].
+ */
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use super::error::Error;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// One row of a DWARF line-number program's matrix: `address` and everything up to (but not
+/// including) the next row's address is attributed to `(file, line, column)`, unless this row is
+/// an `end_sequence` marker closing off the preceding range.
+#[derive(Clone, Debug)]
+struct LineRow {
+    address: u64,
+    file: usize,
+    line: u32,
+    column: u32,
+    end_sequence: bool,
+}
+
+/// A sorted address -> `(file, line, column)` table built from a firmware ELF's `.debug_line`
+/// section.
+#[derive(Debug)]
+pub struct LineTable {
+    files: Vec<String>,
+    rows: Vec<LineRow>,
+}
+
+impl LineTable {
+    /// Loads the ELF image at `path` and builds a `LineTable` from its `.debug_line` section.
+    pub fn from_elf(path: &Path) -> Result<LineTable> {
+        let image = fs::read(path)
+            .map_err(|e| Error::Coverage(format!("couldn't read '{}': {}", path.display(), e)))?;
+        let debug_line = find_section(&image, ".debug_line")?;
+        parse_debug_line(debug_line)
+    }
+
+    /// Finds the row covering `address`, returning `None` if it falls before the first mapped
+    /// row, past its sequence's `end_sequence` row, or on an address with nothing attributed to
+    /// it (ie. should be counted as unattributed).
+    pub fn lookup(&self, address: u64) -> Option<(&str, u32, u32)> {
+        let idx = match self.rows.binary_search_by_key(&address, |r| r.address) {
+            Ok(i) => i,
+            Err(0) => return None,
+            Err(i) => i - 1,
+        };
+
+        let row = &self.rows[idx];
+        if row.end_sequence {
+            return None;
+        }
+
+        self.files.get(row.file).map(|file| (file.as_str(), row.line, row.column))
+    }
+
+    /// Every distinct `(file, line)` pair this table can attribute an address to, used as the
+    /// denominator for [`CoverageReport::ratio`].
+    fn line_universe(&self) -> HashMap<&str, HashSet<u32>> {
+        let mut universe: HashMap<&str, HashSet<u32>> = HashMap::new();
+        for row in &self.rows {
+            if row.end_sequence {
+                continue;
+            }
+            if let Some(file) = self.files.get(row.file) {
+                universe.entry(file.as_str()).or_insert_with(HashSet::new).insert(row.line);
+            }
+        }
+
+        universe
+    }
+}
+
+/// Firmware line coverage derived from attributing a set of observed addresses against a
+/// [`LineTable`].
+#[derive(Clone, Debug, Default)]
+pub struct CoverageReport {
+    hits: HashMap<String, HashMap<u32, u64>>,
+    unattributed: u64,
+    total_lines: usize,
+}
+
+impl CoverageReport {
+    /// Attributes each address in `samples` against `table`, counting one hit per covering line;
+    /// an address outside every mapped range is tallied in [`CoverageReport::unattributed`]
+    /// instead.
+    pub fn from_samples(table: &LineTable, samples: &[u64]) -> CoverageReport {
+        let universe = table.line_universe();
+        let total_lines = universe.values().map(|lines| lines.len()).sum();
+
+        let mut hits: HashMap<String, HashMap<u32, u64>> = HashMap::new();
+        let mut unattributed = 0u64;
+        for &address in samples {
+            match table.lookup(address) {
+                Some((file, line, _column)) => {
+                    *hits.entry(file.to_string()).or_insert_with(HashMap::new)
+                        .entry(line).or_insert(0) += 1;
+                },
+                None => unattributed += 1,
+            }
+        }
+
+        CoverageReport { hits, unattributed, total_lines }
+    }
+
+    /// Per-file line number -> hit count.
+    pub fn hits(&self) -> &HashMap<String, HashMap<u32, u64>> {
+        &self.hits
+    }
+
+    /// Number of samples that couldn't be attributed to any line.
+    pub fn unattributed(&self) -> u64 {
+        self.unattributed
+    }
+
+    /// Fraction of the firmware's instrumentable lines that were hit at least once, or `0.0` if
+    /// the line table is empty.
+    pub fn ratio(&self) -> f32 {
+        if self.total_lines == 0 {
+            return 0.0;
+        }
+
+        let covered: usize = self.hits.values().map(|lines| lines.len()).sum();
+        covered as f32 / self.total_lines as f32
+    }
+
+    /// Renders this report as an LCOV `.info` document (one `SF:`/`DA:`/`end_of_record` block per
+    /// file with at least one hit), for ingestion by standard coverage tooling.
+    pub fn to_lcov(&self) -> String {
+        let mut out = String::new();
+
+        let mut files: Vec<&String> = self.hits.keys().collect();
+        files.sort();
+        for file in files {
+            let lines = &self.hits[file];
+            out.push_str(&format!("SF:{}\n", file));
+
+            let mut line_nos: Vec<&u32> = lines.keys().collect();
+            line_nos.sort();
+            for line in line_nos {
+                out.push_str(&format!("DA:{},{}\n", line, lines[line]));
+            }
+
+            out.push_str("end_of_record\n");
+        }
+
+        out
+    }
+}
+
+/// Finds `name` in the ELF image's section header table and returns its contents.
+fn find_section<'a>(image: &'a [u8], name: &str) -> Result<&'a [u8]> {
+    if image.len() < 20 || &image[0..4] != b"\x7fELF" {
+        return Err(Error::Coverage("not an ELF image".to_string()));
+    }
+    if image[5] != 1 {
+        return Err(Error::Coverage("only little-endian ELF images are supported".to_string()));
+    }
+    let is_64 = match image[4] {
+        1 => false,
+        2 => true,
+        _ => return Err(Error::Coverage("unrecognized ELF class".to_string())),
+    };
+
+    let (shoff, shentsize, shnum, shstrndx) = if is_64 {
+        (read_u64(image, 0x28)?, read_u16(image, 0x3A)?, read_u16(image, 0x3C)?, read_u16(image, 0x3E)?)
+    } else {
+        (read_u32(image, 0x20)? as u64, read_u16(image, 0x2E)?, read_u16(image, 0x30)?, read_u16(image, 0x32)?)
+    };
+
+    let section = |index: u16| -> Result<(u32, u64, u64)> {
+        let base = shoff as usize + index as usize * shentsize as usize;
+        if is_64 {
+            Ok((read_u32(image, base)?, read_u64(image, base + 0x18)?, read_u64(image, base + 0x20)?))
+        } else {
+            Ok((read_u32(image, base)?, read_u32(image, base + 0x10)? as u64, read_u32(image, base + 0x14)? as u64))
+        }
+    };
+
+    let (_, shstrtab_offset, _) = section(shstrndx)?;
+
+    for i in 0..shnum {
+        let (name_offset, offset, size) = section(i)?;
+        let candidate = read_cstr(image, shstrtab_offset as usize + name_offset as usize)?;
+        if candidate == name {
+            let start = offset as usize;
+            let end = start + size as usize;
+            return image.get(start..end)
+                .ok_or_else(|| Error::Coverage(format!("'{}' section extends past end of file", name)));
+        }
+    }
+
+    Err(Error::Coverage(format!("no '{}' section", name)))
+}
+
+/// Parses a `.debug_line` section's line-number programs (there may be more than one compilation
+/// unit's worth back to back) into a single sorted [`LineTable`].
+fn parse_debug_line(section: &[u8]) -> Result<LineTable> {
+    let mut files = vec!["<unknown>".to_string()];
+    let mut rows = Vec::new();
+    let mut unit_start = 0usize;
+
+    while unit_start < section.len() {
+        let mut cursor = unit_start;
+        let unit_length = read_u32(section, cursor)? as usize;
+        cursor += 4;
+        if unit_length == 0xffff_ffff {
+            return Err(Error::Coverage("64-bit DWARF is not supported".to_string()));
+        }
+        let unit_end = cursor + unit_length;
+
+        let version = read_u16(section, cursor)?;
+        cursor += 2;
+        if version < 2 || version > 4 {
+            return Err(Error::Coverage(format!("unsupported DWARF line program version {}", version)));
+        }
+
+        let header_length = read_u32(section, cursor)? as usize;
+        cursor += 4;
+        let program_start = cursor + header_length;
+
+        let minimum_instruction_length = section[cursor];
+        cursor += 1;
+        if version >= 4 {
+            cursor += 1; // maximum_operations_per_instruction; VLIW targets aren't handled.
+        }
+        let default_is_stmt = section[cursor] != 0;
+        cursor += 1;
+        let line_base = section[cursor] as i8;
+        cursor += 1;
+        let line_range = section[cursor];
+        cursor += 1;
+        let opcode_base = section[cursor];
+        cursor += 1;
+
+        let mut standard_opcode_lengths = vec![0u8; opcode_base as usize - 1];
+        for len in &mut standard_opcode_lengths {
+            *len = section[cursor];
+            cursor += 1;
+        }
+
+        // include_directories: sequence of NUL-terminated strings, ended by an empty one.
+        loop {
+            let dir = read_cstr(section, cursor)?;
+            cursor += dir.len() + 1;
+            if dir.is_empty() {
+                break;
+            }
+        }
+
+        // file_names: (name, dir index, mtime, length) tuples, ended by an empty name. This unit's
+        // 1-based file indices are offset past the files earlier units already added.
+        let file_base = files.len() - 1;
+        loop {
+            let name = read_cstr(section, cursor)?;
+            cursor += name.len() + 1;
+            if name.is_empty() {
+                break;
+            }
+            let (_dir, used) = read_uleb128(section, cursor)?;
+            cursor += used;
+            let (_mtime, used) = read_uleb128(section, cursor)?;
+            cursor += used;
+            let (_length, used) = read_uleb128(section, cursor)?;
+            cursor += used;
+            files.push(name.to_string());
+        }
+
+        cursor = program_start;
+
+        let mut address = 0u64;
+        let mut file = 1usize;
+        let mut line = 1i64;
+        let mut column = 0u32;
+        let mut is_stmt = default_is_stmt;
+
+        let mut append_row = |address: u64, file: usize, line: i64, column: u32, end_sequence: bool| {
+            rows.push(LineRow {
+                address,
+                file: file_base + file,
+                line: line.max(0) as u32,
+                column,
+                end_sequence,
+            });
+        };
+
+        while cursor < unit_end {
+            let opcode = section[cursor];
+            cursor += 1;
+
+            if opcode == 0 {
+                let (length, used) = read_uleb128(section, cursor)?;
+                let extended_start = cursor + used;
+                cursor = extended_start;
+                let sub_opcode = section[cursor];
+                let operand_len = length as usize - 1;
+
+                match sub_opcode {
+                    1 => {
+                        // DW_LNE_end_sequence
+                        append_row(address, file, line, column, true);
+                        address = 0;
+                        file = 1;
+                        line = 1;
+                        column = 0;
+                        is_stmt = default_is_stmt;
+                    },
+                    2 => {
+                        // DW_LNE_set_address
+                        address = match operand_len {
+                            4 => read_u32(section, cursor + 1)? as u64,
+                            8 => read_u64(section, cursor + 1)?,
+                            _ => return Err(Error::Coverage("unexpected address size in .debug_line".to_string())),
+                        };
+                    },
+                    _ => {}, // DW_LNE_define_file and vendor extensions aren't needed for lookup.
+                }
+
+                cursor = extended_start + length as usize;
+            } else if opcode < opcode_base {
+                match opcode {
+                    1 => {
+                        // DW_LNS_copy
+                        if is_stmt {
+                            append_row(address, file, line, column, false);
+                        }
+                    },
+                    2 => {
+                        let (advance, used) = read_uleb128(section, cursor)?;
+                        cursor += used;
+                        address += advance * minimum_instruction_length as u64;
+                    },
+                    3 => {
+                        let (advance, used) = read_sleb128(section, cursor)?;
+                        cursor += used;
+                        line += advance;
+                    },
+                    4 => {
+                        let (index, used) = read_uleb128(section, cursor)?;
+                        cursor += used;
+                        file = index as usize;
+                    },
+                    5 => {
+                        let (col, used) = read_uleb128(section, cursor)?;
+                        cursor += used;
+                        column = col as u32;
+                    },
+                    6 => is_stmt = !is_stmt,
+                    7 => {}, // DW_LNS_set_basic_block
+                    8 => {
+                        // DW_LNS_const_add_pc: advance address as the special opcode 255 would,
+                        // without emitting a row.
+                        let adjusted = 255 - opcode_base;
+                        address += (adjusted / line_range) as u64 * minimum_instruction_length as u64;
+                    },
+                    9 => {
+                        address += read_u16(section, cursor)? as u64;
+                        cursor += 2;
+                    },
+                    10 | 11 => {}, // DW_LNS_set_prologue_end / DW_LNS_set_epilogue_begin
+                    12 => {
+                        let (_isa, used) = read_uleb128(section, cursor)?;
+                        cursor += used;
+                    },
+                    other => {
+                        // Unknown standard opcode (vendor extension): skip its declared operands.
+                        for _ in 0..standard_opcode_lengths[other as usize - 1] {
+                            let (_operand, used) = read_uleb128(section, cursor)?;
+                            cursor += used;
+                        }
+                    },
+                }
+            } else {
+                let adjusted = opcode - opcode_base;
+                address += (adjusted / line_range) as u64 * minimum_instruction_length as u64;
+                line += line_base as i64 + (adjusted % line_range) as i64;
+                if is_stmt {
+                    append_row(address, file, line, column, false);
+                }
+            }
+        }
+
+        unit_start = unit_end;
+    }
+
+    rows.sort_by_key(|r| r.address);
+
+    Ok(LineTable { files, rows })
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16> {
+    let bytes: [u8; 2] = data.get(offset..offset + 2)
+        .ok_or_else(|| Error::Coverage("unexpected end of section".to_string()))?
+        .try_into().unwrap();
+    Ok(u16::from_le_bytes(bytes))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    let bytes: [u8; 4] = data.get(offset..offset + 4)
+        .ok_or_else(|| Error::Coverage("unexpected end of section".to_string()))?
+        .try_into().unwrap();
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<u64> {
+    let bytes: [u8; 8] = data.get(offset..offset + 8)
+        .ok_or_else(|| Error::Coverage("unexpected end of section".to_string()))?
+        .try_into().unwrap();
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Reads a NUL-terminated string starting at `offset`, not including the terminator.
+fn read_cstr(data: &[u8], offset: usize) -> Result<&str> {
+    let end = data[offset..].iter().position(|&b| b == 0)
+        .ok_or_else(|| Error::Coverage("unterminated string in section".to_string()))?;
+    std::str::from_utf8(&data[offset..offset + end])
+        .map_err(|_| Error::Coverage("non-UTF-8 string in section".to_string()))
+}
+
+/// Reads an unsigned LEB128 value, returning it along with the number of bytes it occupied.
+fn read_uleb128(data: &[u8], offset: usize) -> Result<(u64, usize)> {
+    let mut result = 0u64;
+    let mut shift = 0;
+    let mut i = offset;
+    loop {
+        let byte = *data.get(i).ok_or_else(|| Error::Coverage("unexpected end of section".to_string()))?;
+        result |= ((byte & 0x7f) as u64) << shift;
+        i += 1;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+
+    Ok((result, i - offset))
+}
+
+/// Reads a signed LEB128 value, returning it along with the number of bytes it occupied.
+fn read_sleb128(data: &[u8], offset: usize) -> Result<(i64, usize)> {
+    let mut result = 0i64;
+    let mut shift = 0;
+    let mut i = offset;
+    let mut byte;
+    loop {
+        byte = *data.get(i).ok_or_else(|| Error::Coverage("unexpected end of section".to_string()))?;
+        result |= ((byte & 0x7f) as i64) << shift;
+        i += 1;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+
+    if shift < 64 && byte & 0x40 != 0 {
+        result |= -1i64 << shift;
+    }
+
+    Ok((result, i - offset))
+}
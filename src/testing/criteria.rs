@@ -5,14 +5,16 @@ use std::fmt;
 use std::fmt::Display;
 use std::time::{Duration, Instant};
 
-use super::trace::Trace;
+use serde::{Deserialize, Serialize};
+
+use super::trace::{SerialTrace, Trace};
 
 /** Defined response to look for from the device under test.
 
 Criterion are used by [`super::test::Test`]s to determine how to inspect the output from a device under test.
  */
 #[allow(unused)]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Criterion {
     /// GPIO activity.
     GPIO(GPIOCriterion),
@@ -20,6 +22,10 @@ pub enum Criterion {
     Energy(EnergyCriterion),
     /// GPIO-based activity tracing.
     Trace(TraceCriterion),
+    /// Analog signal level, as read from an external ADC.
+    Analog(AnalogCriterion),
+    /// Expected byte patterns in a device's decoded UART output.
+    SerialTrace(SerialTraceCriterion),
 }
 
 impl Display for Criterion {
@@ -28,28 +34,171 @@ impl Display for Criterion {
             Criterion::GPIO(ref c) => write!(f, "GPIO activity: {}", c),
             Criterion::Energy(ref c) => write!(f, "Energy: {}", c),
             Criterion::Trace(ref c) => write!(f, "Trace: {}", c),
+            Criterion::Analog(ref c) => write!(f, "Analog: {}", c),
+            Criterion::SerialTrace(ref c) => write!(f, "Serial trace: {}", c),
         }
     }
 }
 
 /// Trackable GPIO activity.
 #[allow(unused)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum GPIOCriterion {
     /// Any and all activity on a GPIO pin.
     Any(u8),
+    /// Number of level transitions observed on a pin.
+    EdgeCount {
+        pin: u8,
+        min: Option<u64>,
+        max: Option<u64>,
+    },
+    /// Frequency of rising edges on a pin, measured over a time window from the start of the test.
+    Frequency {
+        pin: u8,
+        min_hz: Option<f32>,
+        max_hz: Option<f32>,
+        window: Duration,
+    },
+    /// Fraction of observed time a pin spends high.
+    DutyCycle {
+        pin: u8,
+        min: Option<f32>,
+        max: Option<f32>,
+    },
+}
+
+impl GPIOCriterion {
+    /// Returns the device pin number the criterion tracks.
+    pub fn get_pin(&self) -> u8 {
+        match self {
+            GPIOCriterion::Any(pin) => *pin,
+            GPIOCriterion::EdgeCount { pin, .. } => *pin,
+            GPIOCriterion::Frequency { pin, .. } => *pin,
+            GPIOCriterion::DutyCycle { pin, .. } => *pin,
+        }
+    }
+
+    /** Returns true if the given value violates the criterion.
+
+    If there is no part of the criterion that can be violated this function returns None.
+     */
+    pub fn violated(&self, value: f32) -> Option<bool> {
+        match self {
+            GPIOCriterion::Any(_) => None,
+
+            GPIOCriterion::EdgeCount { min, max, .. } => {
+                if min.is_none() && max.is_none() {
+                    None
+                } else {
+                    let count = value as u64;
+                    let b = min.map(|min| count < min).unwrap_or(false)
+                        || max.map(|max| count > max).unwrap_or(false);
+
+                    Some(b)
+                }
+            },
+
+            GPIOCriterion::Frequency { min_hz, max_hz, .. } => {
+                if min_hz.is_none() && max_hz.is_none() {
+                    None
+                } else {
+                    let b = min_hz.map(|min| value < min).unwrap_or(false)
+                        || max_hz.map(|max| value > max).unwrap_or(false);
+
+                    Some(b)
+                }
+            },
+
+            GPIOCriterion::DutyCycle { min, max, .. } => {
+                if min.is_none() && max.is_none() {
+                    None
+                } else {
+                    let b = min.map(|min| value < min).unwrap_or(false)
+                        || max.map(|max| value > max).unwrap_or(false);
+
+                    Some(b)
+                }
+            },
+        }
+    }
 }
 
 impl Display for GPIOCriterion {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             GPIOCriterion::Any(pin_no) => write!(f, "any output on device pin {}", pin_no),
+
+            GPIOCriterion::EdgeCount { pin, min, max } => {
+                write!(f, "device pin {} edge count (min: {}, max: {})",
+                       pin,
+                       min.map(|v| v.to_string()).unwrap_or("-".to_string()),
+                       max.map(|v| v.to_string()).unwrap_or("-".to_string()))
+            },
+
+            GPIOCriterion::Frequency { pin, min_hz, max_hz, window } => {
+                write!(f, "device pin {} frequency over {:?} (min: {}, max: {})",
+                       pin,
+                       window,
+                       min_hz.map(|v| format!("{:.2}Hz", v)).unwrap_or("-".to_string()),
+                       max_hz.map(|v| format!("{:.2}Hz", v)).unwrap_or("-".to_string()))
+            },
+
+            GPIOCriterion::DutyCycle { pin, min, max } => {
+                write!(f, "device pin {} duty cycle (min: {}, max: {})",
+                       pin,
+                       min.map(|v| format!("{:.2}%", v * 100.0)).unwrap_or("-".to_string()),
+                       max.map(|v| format!("{:.2}%", v * 100.0)).unwrap_or("-".to_string()))
+            },
         }
     }
 }
 
+/** Scopes criterion evaluation to specific parts of a test execution.
+
+`included` and `excluded` are each a set of `(start, end)` offsets from the start of the test. A
+point in time is allowed if it falls within some included window (or no included windows were
+given at all, meaning the whole execution is eligible) and does not fall within any excluded
+window. This lets a criterion ignore startup/teardown transients while still asserting on a
+steady-state region, without the caller having to split the execution into separate tests.
+ */
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Windows {
+    included: Vec<(Duration, Duration)>,
+    excluded: Vec<(Duration, Duration)>,
+}
+
+impl Windows {
+    /// No restriction: every point in the execution is eligible.
+    pub fn all() -> Windows {
+        Windows::default()
+    }
+
+    /// Add a window, relative to the start of the test, during which the criterion applies.
+    pub fn with_included(mut self, start: Duration, end: Duration) -> Self {
+        self.included.push((start, end));
+        self
+    }
+
+    /// Add a window, relative to the start of the test, during which the criterion does not apply.
+    ///
+    /// Takes precedence over any included window it overlaps.
+    pub fn with_excluded(mut self, start: Duration, end: Duration) -> Self {
+        self.excluded.push((start, end));
+        self
+    }
+
+    /// Returns true if `t`, an offset from the start of the test, is eligible for evaluation.
+    pub fn allows(&self, t: Duration) -> bool {
+        let in_included = self.included.is_empty()
+            || self.included.iter().any(|(start, end)| *start <= t && t <= *end);
+        let in_excluded = self.excluded.iter().any(|(start, end)| *start <= t && t <= *end);
+
+        in_included && !in_excluded
+    }
+}
+
 /// Timing requirement.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 pub enum Timing {
     /// Point in time relative to the start of the test.
     Absolute(Duration),
@@ -78,12 +227,81 @@ impl Display for Timing {
     }
 }
 
+/** Requires a burst of repeated matching trace events on a regular cadence.
+
+After a [`TraceCondition`]'s first matching event, the aligner greedily consumes subsequent events
+with the same ID/extra data whose spacing from the previous consumed event falls within
+`cadence`±`tolerance`, stopping at the first gap that doesn't fit (or at `max_count`, if given).
+The condition is satisfied only if at least `min_count` events were consumed this way. This is what
+lets a test assert "pin N toggled at ~100Hz at least 10 times" instead of a single edge.
+ */
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct BurstSpec {
+    cadence: Duration,
+    tolerance: Duration,
+    min_count: u32,
+    max_count: Option<u32>,
+}
+
+impl BurstSpec {
+    /// Require at least `min_count` events spaced `cadence`±`tolerance` apart.
+    #[allow(dead_code)]
+    pub fn new(cadence: Duration, tolerance: Duration, min_count: u32) -> BurstSpec {
+        BurstSpec {
+            cadence,
+            tolerance,
+            min_count,
+            max_count: None,
+        }
+    }
+
+    /// Stop consuming events once `max_count` have been collected.
+    #[allow(dead_code)]
+    pub fn with_max_count(self, max_count: u32) -> Self {
+        Self {
+            max_count: Some(max_count),
+            ..self
+        }
+    }
+
+    /// Returns the nominal spacing between consecutive events.
+    fn get_cadence(&self) -> Duration {
+        self.cadence
+    }
+
+    /// Returns the allowed deviation from the nominal cadence.
+    fn get_tolerance(&self) -> Duration {
+        self.tolerance
+    }
+
+    /// Returns the minimum number of events that must be collected.
+    fn get_min_count(&self) -> u32 {
+        self.min_count
+    }
+
+    /// Returns the maximum number of events to collect, if bounded.
+    fn get_max_count(&self) -> Option<u32> {
+        self.max_count
+    }
+}
+
+impl Display for BurstSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "burst of {}", self.min_count)?;
+        if let Some(max_count) = self.max_count {
+            write!(f, "-{}", max_count)?;
+        }
+        write!(f, "+ events @ {:?}±{:?}", self.cadence, self.tolerance)
+    }
+}
+
 /// Component condition of a [`TraceCriterion`].
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 pub struct TraceCondition {
     id: u16,
     extra: Option<u16>,
     timing: Option<(Timing, Duration)>,
+    burst: Option<BurstSpec>,
 }
 
 impl TraceCondition {
@@ -93,6 +311,7 @@ impl TraceCondition {
             id,
             extra: None,
             timing: None,
+            burst: None,
         }
     }
 
@@ -123,9 +342,6 @@ impl TraceCondition {
     }
 
     /// Construct a trace condition with the specified extra data.
-    ///
-    /// This is a convenience function for test that may later be removed.
-    #[allow(dead_code)]
     pub fn with_extra_data(self, extra: u16) -> Self {
         Self {
             extra: Some(extra),
@@ -134,9 +350,6 @@ impl TraceCondition {
     }
 
     /// Construct a trace condition with the specified timing.
-    ///
-    /// This is a convenience function for test that may later be removed.
-    #[allow(dead_code)]
     pub fn with_timing(self, time: Timing, tolerance: Duration) -> Self {
         Self {
             timing: Some((time, tolerance)),
@@ -144,6 +357,20 @@ impl TraceCondition {
         }
     }
 
+    /// Construct a trace condition that requires a burst of repeated matching events.
+    #[allow(dead_code)]
+    pub fn with_burst(self, burst: BurstSpec) -> Self {
+        Self {
+            burst: Some(burst),
+            ..self
+        }
+    }
+
+    /// If provided, returns the burst requirement to satisfy the trace condition.
+    fn get_burst(&self) -> Option<BurstSpec> {
+        self.burst
+    }
+
     /// Returns true if the provided trace's ID and extra data satisfy the condition.
     ///
     /// Because the required timing of the condition is dependent on whether the timing is relative to the
@@ -166,14 +393,86 @@ impl Display for TraceCondition {
             write!(f, ", {} (tol: {:?})", timing, self.get_tolerance().unwrap())?;
         }
 
+        if let Some(burst) = self.get_burst() {
+            write!(f, ", {}", burst)?;
+        }
+
         Ok(())
     }
 }
 
-/// Trace criterion specification details.
+/** Why a [`TraceCondition`] could not be matched against the closest candidate [`Trace`].
+
+Returned as part of an [`AlignmentFailure`] so a test author can tell a genuinely missing event
+apart from one that merely missed its timing window or didn't sustain a long enough burst.
+ */
 #[derive(Clone, Debug)]
+pub enum MismatchReason {
+    /// The candidate event's ID/extra data didn't match what the condition required.
+    #[allow(dead_code)]
+    IdOrExtra,
+    /// The candidate event's timing missed the condition's requirement by `offset`, at least
+    /// the condition's `tolerance`.
+    Timing {
+        /// How far the candidate event's time was from the condition's required time point.
+        offset: Duration,
+        /// The condition's allowed tolerance.
+        tolerance: Duration,
+    },
+    /// A [`BurstSpec`] attached to the condition didn't see enough cadence-aligned events
+    /// follow the first match.
+    BurstTooShort {
+        /// The number of events actually collected into the burst.
+        collected: u32,
+        /// The minimum the condition's [`BurstSpec`] required.
+        required: u32,
+    },
+}
+
+impl Display for MismatchReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MismatchReason::IdOrExtra => write!(f, "no event with a matching ID/extra data was found"),
+            MismatchReason::Timing { offset, tolerance } =>
+                write!(f, "closest candidate missed timing by {:?} (tolerance: {:?})", offset, tolerance),
+            MismatchReason::BurstTooShort { collected, required } =>
+                write!(f, "burst only collected {} of the required {} events", collected, required),
+        }
+    }
+}
+
+/** Diagnostic report produced when [`TraceCriterion::align`] fails to find a full match.
+
+Identifies the furthest [`TraceCondition`] alignment reached and, where a plausible candidate
+event existed for it, why that candidate fell short. This is meant to answer "which condition
+broke, and by how much" without a test author having to re-derive it from raw trace dumps.
+ */
+#[derive(Clone, Debug)]
+pub struct AlignmentFailure {
+    /// Index into the criterion's condition list of the furthest condition reached.
+    pub condition_index: usize,
+    /// The closest candidate event found for that condition, if any shared its ID/extra data.
+    pub closest_event: Option<Trace>,
+    /// Why `closest_event` didn't satisfy the condition. `None` if no candidate existed at all.
+    pub reason: Option<MismatchReason>,
+}
+
+impl Display for AlignmentFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "alignment failed at condition {}", self.condition_index)?;
+        match (&self.closest_event, &self.reason) {
+            (Some(event), Some(reason)) => write!(f, ": {} ({})", reason, event),
+            _ => write!(f, ": no candidate event found"),
+        }
+    }
+}
+
+/// Trace criterion specification details.
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TraceCriterion {
     conditions: Vec<TraceCondition>,
+    windows: Windows,
+    deglitch_window: Option<Duration>,
 }
 
 impl TraceCriterion {
@@ -186,15 +485,101 @@ impl TraceCriterion {
             conditions: conditions.into_iter()
                 .copied()
                 .collect(),
+            windows: Windows::all(),
+            deglitch_window: None,
         }
     }
 
-    /// Returns the [`Trace`]s satisfying the criterion.
-    pub fn align<'a>(&self, t0: Instant, traces: &'a [Trace]) -> Option<Vec<&'a Trace>> {
-        TraceCriterion::rec_align(t0,
-                                  t0,
-                                  self.conditions.as_slice(),
-                                  traces)
+    /// Restrict the criterion to the given evaluation windows.
+    #[allow(dead_code)]
+    pub fn with_windows(self, windows: Windows) -> Self {
+        Self {
+            windows,
+            ..self
+        }
+    }
+
+    /** Collapse closely-spaced, same-ID/extra events into one before alignment is attempted.
+
+    GPIO-derived traces can carry spurious, closely-spaced transitions (glitches) that derail
+    alignment by shadowing the event a [`TraceCondition`] actually meant to match. Opting in
+    groups consecutive events sharing an ID/extra whose timestamps fall within `window` of each
+    other and collapses each group to a single representative event at the group's *median* time
+    (not the first, to avoid bias from leading glitches).
+     */
+    #[allow(dead_code)]
+    pub fn with_deglitch(self, window: Duration) -> Self {
+        Self {
+            deglitch_window: Some(window),
+            ..self
+        }
+    }
+
+    /** Returns the [`Trace`]s satisfying the criterion, or an [`AlignmentFailure`] diagnosing
+    the furthest condition reached.
+
+    Events outside the criterion's [`Windows`] are skipped before alignment is attempted, so a
+    condition cannot be satisfied by, e.g., a startup transient the windows were meant to exclude.
+     */
+    pub fn align(&self, t0: Instant, traces: &[Trace]) -> Result<Vec<Trace>, AlignmentFailure> {
+        let deglitched;
+        let events: &[Trace] = match self.deglitch_window {
+            Some(window) => {
+                deglitched = TraceCriterion::deglitch(traces, window);
+                &deglitched
+            },
+            None => traces,
+        };
+
+        match TraceCriterion::dp_align(t0, self.conditions.as_slice(), events, &self.windows) {
+            Some(matches) => Ok(matches),
+            None => Err(TraceCriterion::diagnose_failure(t0, self.conditions.as_slice(), events, &self.windows)),
+        }
+    }
+
+    /// Collapse groups of consecutive same-ID/extra events within `window` of each other into one
+    /// representative event at the group's median timestamp. See [`Self::with_deglitch`].
+    fn deglitch(traces: &[Trace], window: Duration) -> Vec<Trace> {
+        let mut sorted: Vec<Trace> = traces.to_vec();
+        sorted.sort_by_key(|t| t.get_time());
+
+        let mut collapsed = Vec::new();
+        let mut group: Vec<Trace> = Vec::new();
+
+        for trace in sorted {
+            let fits_group = group.last()
+                .map(|last: &Trace| trace.get_id() == last.get_id()
+                     && trace.get_extra() == last.get_extra()
+                     && trace.get_time().duration_since(last.get_time()) <= window)
+                .unwrap_or(true);
+
+            if fits_group {
+                group.push(trace);
+            } else {
+                collapsed.push(TraceCriterion::median_event(group));
+                group = vec![trace];
+            }
+        }
+        if !group.is_empty() {
+            collapsed.push(TraceCriterion::median_event(group));
+        }
+
+        collapsed
+    }
+
+    /// Returns the group's representative event, its timestamp replaced with the group's median.
+    fn median_event(mut group: Vec<Trace>) -> Trace {
+        group.sort_by_key(|t| t.get_time());
+        let mid = group.len() / 2;
+        let median_time = if group.len() % 2 == 1 {
+            group[mid].get_time()
+        } else {
+            let earlier = group[mid - 1].get_time();
+            let later = group[mid].get_time();
+            earlier + (later - earlier) / 2
+        };
+
+        group[mid].with_time(median_time)
     }
 
 
@@ -202,70 +587,205 @@ impl TraceCriterion {
 
     # Algorithm overview
 
-    Advances through:
-    - ordering of trace conditions
-    - sequence of trace events captured during the test
+    Builds a `conditions.len() + 1` by `events.len() + 1` table bottom-up, where cell `(i, j)`
+    holds the match (if any) for satisfying `conditions[i..]` by searching `events[j..]`. Because
+    the search always resumes right after whichever event satisfied `conditions[i - 1]`, `j`
+    alone determines the reference time (`Timing::Relative` is anchored to `events[j - 1]`'s
+    timestamp, or `t0` when `j` is `0`) — so no separate "reference time" dimension is needed.
 
-    For each trace condition, advances through the trace events to find a matching trace event.
-    Upon finding a matching trace condition, the function advances to the next trace condition.
-    If a trace condition fails to find a matching trace event, then we back out to the previous trace condition.
-    The previous trace condition seeks another matching trace event.
-    If a trace condition advances to the last trace event and does not find a match, then the function returns false.
+    Each cell is computed once, by trying `events[j..]` in order against `conditions[i]` with that
+    same reference time (consuming a burst's worth of events if the condition carries a
+    [`BurstSpec`]) and taking the first one whose `table[i + 1][_]` continuation holds. Skipped
+    events therefore never move the reference time. That bounds the whole table at
+    `O(conditions.len() * events.len()^2)` event checks, instead of backtracking recursion's
+    exponential worst case.
      */
-    fn rec_align<'a>(t0: Instant,
-                     tp: Instant,
-                     conditions: &[TraceCondition],
-                     events: &'a [Trace]) -> Option<Vec<&'a Trace>>
+    fn dp_align(t0: Instant,
+                conditions: &[TraceCondition],
+                events: &[Trace],
+                windows: &Windows) -> Option<Vec<Trace>>
     {
-        let mut matches = Vec::new();
-
-        if conditions.len() > 0 {
-            let condition = conditions[0];
-            for (event, idx) in events.iter().zip(0..) {
-                // Check the timing of the trace event as that cannot be determined
-                // within the context of the TraceCondition alone, especially if the
-                // timing is relative to other conditions.
-                if condition.satisfied_by(event) {
-                    let timing_matches: bool = {
-                        if let Some(timing) = condition.get_offset() {
-                            // println!("Checking timing for trace event.");
-                            // Calculate the time point test the trace condition
-                            // specifies the trace should occur at.
-                            let t_req = match timing {
-                                Timing::Absolute(d) => t0 + d,
-                                Timing::Relative(d) => tp + d,
-                            };
-                            // Difference between the actual event occurrence time and the specification's time point.
-                            let since = t_req.max(event.get_time()) - t_req.min(event.get_time());
-                            // println!("  req. offset: {:?}, tolerance: {:?}", since, condition.get_tolerance().unwrap());
-                            // println!("  since time offset: {:?}", since);
-                            since < condition.get_tolerance().unwrap()
-                        } else {
-                            true
-                        }
-                    };
-                    // If the rest of the events in the condition chain are satisfied, then
-                    // the criterion is satisfied. If not, we continue skimming over events.
-                    if timing_matches {
-                        let rest = TraceCriterion::rec_align(t0,
-                                                             event.get_time(),
-                                                             &conditions[1..],
-                                                             &events[idx+1..]);
-                        if let Some(rest) = rest {
-                            matches.push(event);
-                            matches.extend(rest.into_iter());
-                            return Some(matches);
-                        }
+        let n_conditions = conditions.len();
+        let n_events = events.len();
+
+        let mut table: Vec<Vec<Option<Vec<Trace>>>> = vec![vec![None; n_events + 1]; n_conditions + 1];
+        for cell in table[n_conditions].iter_mut() {
+            *cell = Some(Vec::new());
+        }
+
+        for i in (0..n_conditions).rev() {
+            let condition = conditions[i];
+
+            for j in (0..=n_events).rev() {
+                let tp = if j == 0 { t0 } else { events[j - 1].get_time() };
+
+                let via_match = (j..n_events).find_map(|k| {
+                    TraceCriterion::try_match(t0, tp, &condition, k, events, windows)
+                        .and_then(|(consumed, next_j)| {
+                            table[i + 1][next_j].as_ref().map(|rest| {
+                                let mut full = consumed;
+                                full.extend(rest.iter().cloned());
+                                full
+                            })
+                        })
+                });
+
+                table[i][j] = via_match;
+            }
+        }
+
+        table[0][0].take()
+    }
+
+    /** Try to use `events[j]` to satisfy `condition`, given the reference time `tp` that
+    `Timing::Relative` is anchored to.
+
+    On success, returns the events consumed (more than one if `condition` carries a
+    [`BurstSpec`]) along with the index the search should resume from.
+     */
+    fn try_match(t0: Instant,
+                 tp: Instant,
+                 condition: &TraceCondition,
+                 j: usize,
+                 events: &[Trace],
+                 windows: &Windows) -> Option<(Vec<Trace>, usize)>
+    {
+        let event = &events[j];
+        if !windows.allows(event.get_offset(t0)) || !condition.satisfied_by(event) {
+            return None;
+        }
+
+        if let Some(timing) = condition.get_offset() {
+            let t_req = match timing {
+                Timing::Absolute(d) => t0 + d,
+                Timing::Relative(d) => tp + d,
+            };
+            let since = t_req.max(event.get_time()) - t_req.min(event.get_time());
+            if since >= condition.get_tolerance().unwrap() {
+                return None;
+            }
+        }
+
+        match condition.get_burst() {
+            None => Some((vec![event.clone()], j + 1)),
+            Some(burst) => {
+                let (consumed, next_idx) = TraceCriterion::collect_burst(t0, condition, burst, j, events, windows);
+                if consumed.len() as u32 >= burst.get_min_count() {
+                    Some((consumed, next_idx))
+                } else {
+                    None
+                }
+            },
+        }
+    }
+
+    /// Greedily consume a cadence-aligned run of events matching `condition`, starting at
+    /// `events[j]`, up to `burst`'s maximum count (if any). Does not check `burst`'s minimum
+    /// count — callers decide what to do with a short run.
+    fn collect_burst(t0: Instant,
+                      condition: &TraceCondition,
+                      burst: BurstSpec,
+                      j: usize,
+                      events: &[Trace],
+                      windows: &Windows) -> (Vec<Trace>, usize)
+    {
+        let mut consumed = vec![events[j].clone()];
+        let mut prev_time = events[j].get_time();
+        let mut next_idx = j + 1;
+
+        while burst.get_max_count().map_or(true, |max| (consumed.len() as u32) < max) {
+            match events.get(next_idx) {
+                Some(next_event)
+                    if windows.allows(next_event.get_offset(t0))
+                    && condition.satisfied_by(next_event) =>
+                {
+                    let since_prev = prev_time.max(next_event.get_time())
+                        - prev_time.min(next_event.get_time());
+                    let cadence_error = since_prev.max(burst.get_cadence())
+                        - since_prev.min(burst.get_cadence());
+
+                    if cadence_error <= burst.get_tolerance() {
+                        prev_time = next_event.get_time();
+                        consumed.push(next_event.clone());
+                        next_idx += 1;
+                    } else {
+                        break;
                     }
+                },
+                _ => break,
+            }
+        }
+
+        (consumed, next_idx)
+    }
+
+    /** Walk the conditions forward greedily to explain why [`Self::dp_align`] failed.
+
+    For each condition in turn, finds the closest (in search order) event sharing its ID/extra
+    data and reports why it fell short — missing entirely, missing its timing window, or (for a
+    burst condition) not sustaining enough cadence-aligned events. Stops at the first condition
+    that can't be explained this way, since [`Self::dp_align`] already established the whole
+    chain can't be satisfied.
+     */
+    fn diagnose_failure(t0: Instant,
+                        conditions: &[TraceCondition],
+                        events: &[Trace],
+                        windows: &Windows) -> AlignmentFailure
+    {
+        let mut tp = t0;
+        let mut search_from = 0usize;
+
+        for (i, condition) in conditions.iter().enumerate() {
+            let candidate = events[search_from..].iter().enumerate()
+                .find(|(_, event)| windows.allows(event.get_offset(t0)) && condition.satisfied_by(event));
+            let (rel_idx, candidate) = match candidate {
+                Some(found) => found,
+                None => return AlignmentFailure { condition_index: i, closest_event: None, reason: None },
+            };
+            let idx = search_from + rel_idx;
+
+            if let Some(timing) = condition.get_offset() {
+                let t_req = match timing {
+                    Timing::Absolute(d) => t0 + d,
+                    Timing::Relative(d) => tp + d,
+                };
+                let offset = t_req.max(candidate.get_time()) - t_req.min(candidate.get_time());
+                let tolerance = condition.get_tolerance().unwrap();
+                if offset >= tolerance {
+                    return AlignmentFailure {
+                        condition_index: i,
+                        closest_event: Some(candidate.clone()),
+                        reason: Some(MismatchReason::Timing { offset, tolerance }),
+                    };
                 }
             }
 
-            // No more events to match. Game over.
-            None
-        } else {
-            // No more conditions to try to match. We're finished.
-            Some(Vec::new())
+            match condition.get_burst() {
+                Some(burst) => {
+                    let (consumed, next_idx) = TraceCriterion::collect_burst(t0, condition, burst, idx, events, windows);
+                    if (consumed.len() as u32) < burst.get_min_count() {
+                        return AlignmentFailure {
+                            condition_index: i,
+                            closest_event: Some(candidate.clone()),
+                            reason: Some(MismatchReason::BurstTooShort {
+                                collected: consumed.len() as u32,
+                                required: burst.get_min_count(),
+                            }),
+                        };
+                    }
+                    tp = consumed.last().unwrap().get_time();
+                    search_from = next_idx;
+                },
+                None => {
+                    tp = candidate.get_time();
+                    search_from = idx + 1;
+                },
+            }
         }
+
+        // dp_align found no full match, so some earlier condition should have explained that
+        // above; fall back to an uninformative report rather than claiming false success.
+        AlignmentFailure { condition_index: conditions.len(), closest_event: None, reason: None }
     }
 }
 
@@ -293,29 +813,48 @@ impl Display for TraceCriterion {
 }
 
 /// Energy criterion specification details.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EnergyCriterion {
     meter: String,
     stat: EnergyStat,
     min: Option<f32>,
     max: Option<f32>,
+    windows: Windows,
 }
 
 /// Energy-specific criterion of interest.
 impl EnergyCriterion {
     /// Create a new EnergyCriterion.
-    #[allow(dead_code)]
     pub fn new(meter: &str, stat: EnergyStat) -> Self {
         Self {
             meter: meter.to_string(),
             stat,
             min: None,
             max: None,
+            windows: Windows::all(),
         }
     }
 
+    /** Restrict the criterion to the given evaluation windows.
+
+    Stored for when energy samples carry their own timestamps; until then, [`Self::violated`] still
+    evaluates over the whole sample set, since there's no per-sample time to test a window against.
+     */
+    #[allow(dead_code)]
+    pub fn with_windows(self, windows: Windows) -> Self {
+        Self {
+            windows,
+            ..self
+        }
+    }
+
+    /// Returns the criterion's evaluation windows.
+    #[allow(dead_code)]
+    pub fn get_windows(&self) -> &Windows {
+        &self.windows
+    }
+
     /// Specify a minimum value for the criterion.
-    #[allow(unused)]
     pub fn with_min(self, min: f32) -> Self {
         Self {
             min: Some(min),
@@ -324,7 +863,6 @@ impl EnergyCriterion {
     }
 
     /// Specify a maximum value for the energy criterion.
-    #[allow(unused)]
     pub fn with_max(self, max: f32) -> Self {
         Self {
             max: Some(max),
@@ -378,7 +916,7 @@ impl Display for EnergyCriterion {
 
 /// Trackable energy usage statistics.
 #[allow(unused)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum EnergyStat {
     /// Track total energy consumption.
     Total,
@@ -400,3 +938,322 @@ impl Display for EnergyStat {
         }
     }
 }
+
+/// Analog criterion specification details.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AnalogCriterion {
+    channel: String,
+    stat: AnalogStat,
+    min: Option<f32>,
+    max: Option<f32>,
+}
+
+/// Analog-specific criterion of interest.
+impl AnalogCriterion {
+    /// Create a new AnalogCriterion.
+    #[allow(dead_code)]
+    pub fn new(channel: &str, stat: AnalogStat) -> Self {
+        Self {
+            channel: channel.to_string(),
+            stat,
+            min: None,
+            max: None,
+        }
+    }
+
+    /// Specify a minimum value for the criterion.
+    #[allow(unused)]
+    pub fn with_min(self, min: f32) -> Self {
+        Self {
+            min: Some(min),
+            ..self
+        }
+    }
+
+    /// Specify a maximum value for the criterion.
+    #[allow(unused)]
+    pub fn with_max(self, max: f32) -> Self {
+        Self {
+            max: Some(max),
+            ..self
+        }
+    }
+
+    /// Returns the name of the target ADC channel.
+    pub fn get_channel(&self) -> &str {
+        &self.channel
+    }
+
+    /// Returns the analog statistic.
+    pub fn get_stat(&self) -> AnalogStat {
+        self.stat
+    }
+
+    /** Returns true if the given value violates the criterion.
+
+    If there is no part of the criterion that can be violated this function returns None.
+     */
+    pub fn violated(&self, value: f32) -> Option<bool> {
+        if self.min.is_none() && self.max.is_none() {
+            None
+        } else {
+            let b = self.min.map(|min| value < min)
+                .unwrap_or(false)
+                ||
+                self.max.map(|max| value > max)
+                .unwrap_or(false);
+
+            Some(b)
+        }
+    }
+}
+
+impl Display for AnalogCriterion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "'{}' {} ", self.channel, self.stat)?;
+        write!(f, "(min: {},", self.min.map(|x| format!("{:.3}V", x)).unwrap_or("-".to_string()))?;
+        write!(f, " max: {})", self.max.map(|x| format!("{:.3}V", x)).unwrap_or("-".to_string()))?;
+
+        Ok(())
+    }
+}
+
+/// Trackable statistics over a window of analog samples.
+#[allow(unused)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum AnalogStat {
+    /// The most recently taken sample.
+    Sample,
+    /// Average of all samples taken over the test.
+    Mean,
+    /// Minimum sample value observed.
+    Min,
+    /// Maximum sample value observed.
+    Max,
+    /// Difference between the maximum and minimum sample values observed.
+    PeakToPeak,
+}
+
+impl Display for AnalogStat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnalogStat::Sample => write!(f, "sample"),
+            AnalogStat::Mean => write!(f, "mean"),
+            AnalogStat::Min => write!(f, "min"),
+            AnalogStat::Max => write!(f, "max"),
+            AnalogStat::PeakToPeak => write!(f, "peak-to-peak"),
+        }
+    }
+}
+
+/// Component condition of a [`SerialTraceCriterion`]: a byte pattern expected to appear in a
+/// device's decoded UART output.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SerialTraceCondition {
+    pattern: Vec<u8>,
+    timing: Option<(Timing, Duration)>,
+}
+
+impl SerialTraceCondition {
+    /// Create a new condition requiring `pattern` to appear somewhere in the serial output.
+    pub fn new(pattern: &[u8]) -> SerialTraceCondition {
+        SerialTraceCondition {
+            pattern: pattern.to_vec(),
+            timing: None,
+        }
+    }
+
+    /// Returns the byte pattern that would satisfy the condition.
+    pub fn get_pattern(&self) -> &[u8] {
+        &self.pattern
+    }
+
+    /// If provided, returns the necessary time offset to satisfy the condition.
+    pub fn get_offset(&self) -> Option<Timing> {
+        self.timing.as_ref()
+            .map(|(timing, _tolerance)| *timing)
+    }
+
+    /// If provided, returns the timing tolerance to satisfy the condition.
+    pub fn get_tolerance(&self) -> Option<Duration> {
+        self.timing.as_ref()
+            .map(|(_timing, tolerance)| *tolerance)
+    }
+
+    /// Construct a condition with the specified timing.
+    pub fn with_timing(self, time: Timing, tolerance: Duration) -> Self {
+        Self {
+            timing: Some((time, tolerance)),
+            ..self
+        }
+    }
+}
+
+impl Display for SerialTraceCondition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "pattern {:?}", String::from_utf8_lossy(&self.pattern))?;
+
+        if let Some(timing) = self.get_offset() {
+            write!(f, " @ {:?}±{:?} from {}",
+                   timing.get_offset(),
+                   self.get_tolerance().unwrap(),
+                   match timing {
+                       Timing::Absolute(_) => "test start",
+                       Timing::Relative(_) => "previous match",
+                   })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Why a [`SerialTraceCondition`] could not be satisfied by a test's captured serial output.
+#[derive(Clone, Debug)]
+pub enum SerialMismatchReason {
+    /// The pattern did not appear anywhere in the output searched.
+    NotFound,
+    /// The pattern was found, but too far from the condition's required time point.
+    Timing {
+        /// How far the match's time was from the condition's required time point.
+        offset: Duration,
+        /// The condition's allowed tolerance.
+        tolerance: Duration,
+    },
+}
+
+impl Display for SerialMismatchReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SerialMismatchReason::NotFound => write!(f, "pattern not found"),
+            SerialMismatchReason::Timing { offset, tolerance } =>
+                write!(f, "match missed timing by {:?} (tolerance: {:?})", offset, tolerance),
+        }
+    }
+}
+
+/// Diagnostic report produced when [`SerialTraceCriterion::align`] fails to satisfy every
+/// condition.
+#[derive(Clone, Debug)]
+pub struct SerialAlignmentFailure {
+    /// Index into the criterion's condition list of the condition that could not be satisfied.
+    pub condition_index: usize,
+    /// Why the condition could not be satisfied.
+    pub reason: SerialMismatchReason,
+}
+
+impl Display for SerialAlignmentFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "alignment failed at condition {} ({})", self.condition_index, self.reason)
+    }
+}
+
+/// One [`SerialTraceCondition`] satisfied by [`SerialTraceCriterion::align`], with where in the
+/// captured output it was found.
+#[derive(Clone, Debug)]
+pub struct SerialMatch {
+    /// Index into the criterion's condition list of the condition this satisfies.
+    pub condition_index: usize,
+    /// Offset from `t0` at which the pattern was found.
+    pub offset: Duration,
+}
+
+/** A set of byte patterns expected (in order) in a device's decoded UART output.
+
+Mirrors [`TraceCriterion`]'s role for GPIO-derived traces, but a [`SerialTraceCondition`] is
+satisfied by locating its byte pattern as a substring of the accumulated serial stream rather than
+matching a discretely-identified event. `conditions` are matched strictly in order: each
+condition's search starts right after the previous condition's match ended (or at the start of
+the stream, for the first condition), and the time the previous match's first byte was read (or
+`t0`, for the first condition) is the next [`Timing::Relative`] condition's reference point.
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SerialTraceCriterion {
+    conditions: Vec<SerialTraceCondition>,
+}
+
+impl SerialTraceCriterion {
+    /// Create a new serial trace criterion.
+    pub fn new<'a, T>(conditions: T) -> SerialTraceCriterion
+    where
+        T: IntoIterator<Item = &'a SerialTraceCondition>
+    {
+        SerialTraceCriterion {
+            conditions: conditions.into_iter().cloned().collect(),
+        }
+    }
+
+    /** Returns the matches satisfying every condition, in order, or a [`SerialAlignmentFailure`]
+    diagnosing the first condition that could not be satisfied.
+     */
+    pub fn align(&self, t0: Instant, traces: &[SerialTrace]) -> Result<Vec<SerialMatch>, SerialAlignmentFailure> {
+        let stream = SerialTraceCriterion::flatten(traces);
+
+        let mut matches = Vec::with_capacity(self.conditions.len());
+        let mut search_from = 0usize;
+        let mut tp = t0;
+
+        for (i, condition) in self.conditions.iter().enumerate() {
+            let found = SerialTraceCriterion::find_pattern(&stream, search_from, condition.get_pattern());
+            let (pos, match_time) = match found {
+                Some(found) => found,
+                None => return Err(SerialAlignmentFailure {
+                    condition_index: i,
+                    reason: SerialMismatchReason::NotFound,
+                }),
+            };
+
+            if let Some(timing) = condition.get_offset() {
+                let t_req = match timing {
+                    Timing::Absolute(d) => t0 + d,
+                    Timing::Relative(d) => tp + d,
+                };
+                let offset = t_req.max(match_time) - t_req.min(match_time);
+                let tolerance = condition.get_tolerance().unwrap();
+                if offset >= tolerance {
+                    return Err(SerialAlignmentFailure {
+                        condition_index: i,
+                        reason: SerialMismatchReason::Timing { offset, tolerance },
+                    });
+                }
+            }
+
+            matches.push(SerialMatch {
+                condition_index: i,
+                offset: if match_time > t0 { match_time - t0 } else { Duration::from_millis(0) },
+            });
+            tp = match_time;
+            search_from = pos + condition.get_pattern().len();
+        }
+
+        Ok(matches)
+    }
+
+    /// Flattens `traces` into one byte stream, each byte paired with the time its chunk was read.
+    fn flatten(traces: &[SerialTrace]) -> Vec<(u8, Instant)> {
+        traces.iter()
+            .flat_map(|chunk| chunk.get_data().iter().map(move |&b| (b, chunk.get_time())))
+            .collect()
+    }
+
+    /// Finds the first occurrence of `pattern` in `stream` at or after `search_from`, returning
+    /// its starting index and the time its first byte was read.
+    fn find_pattern(stream: &[(u8, Instant)], search_from: usize, pattern: &[u8]) -> Option<(usize, Instant)> {
+        if pattern.is_empty() || search_from + pattern.len() > stream.len() {
+            return None;
+        }
+
+        (search_from..=stream.len() - pattern.len())
+            .find(|&i| stream[i..i + pattern.len()].iter().map(|(b, _)| b).eq(pattern.iter()))
+            .map(|i| (i, stream[i].1))
+    }
+}
+
+impl Display for SerialTraceCriterion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for condition in &self.conditions {
+            write!(f, "\n   → {}", condition)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,204 @@
+//! Interactive breakpoint/step debugging for trace reconstruction.
+//!
+//! [`Debugger`] turns [`super::trace::reconstruct`]'s otherwise fire-and-forget walk over GPIO
+//! responses into something a user can pause mid-run: set a breakpoint on a named trace point (as
+//! known to [`crate::sw::instrument::Spec`]), and execution stops and hands control to a small
+//! command loop as soon as that trace point fires. From there a user can inspect the trace that
+//! triggered the stop, the last known level of any GPIO pin, and the most recent energy reading
+//! reported for a meter, before stepping or continuing.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+use crate::comm::Signal;
+use crate::sw::instrument::Spec;
+
+use super::trace::Trace;
+
+/// A parsed debugger command.
+#[derive(Clone, Debug, PartialEq)]
+enum Command {
+    /// Stop the next time the named trace point fires.
+    Break(String),
+    /// Resume until the next reconstructed trace, breakpoint or not.
+    Step,
+    /// Resume until the next breakpoint is hit.
+    Continue,
+    /// Repeat the last command `N` times.
+    Repeat(u32),
+    /// Print the last known level of a GPIO pin.
+    Read(u8),
+}
+
+impl Command {
+    fn parse(line: &str) -> Option<Command> {
+        let mut words = line.split_whitespace();
+        match words.next()? {
+            "break" | "b" => words.next().map(|name| Command::Break(name.to_string())),
+            "step" | "s" => Some(Command::Step),
+            "continue" | "c" => Some(Command::Continue),
+            "repeat" | "r" => words.next().and_then(|n| n.parse().ok()).map(Command::Repeat),
+            "read" => words.next().and_then(|pin| pin.parse().ok()).map(Command::Read),
+            _ => None,
+        }
+    }
+}
+
+/// Whether reconstruction should keep running freely or stop at the very next trace.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mode {
+    Running,
+    Stepping,
+}
+
+/** Drives an interactive monitor loop over trace reconstruction.
+
+Feed it GPIO [`Response`](super::test::Response)s as they're consumed (via [`Debugger::observe_gpio`])
+and each reconstructed [`Trace`] (via [`Debugger::check`]) to let it track live state and decide when
+to stop.
+ */
+#[derive(Debug)]
+pub struct Debugger {
+    breakpoints: Vec<String>,
+    mode: Mode,
+    last_command: Option<Command>,
+    gpio_levels: HashMap<u8, Signal>,
+    energy_readings: HashMap<String, f32>,
+}
+
+impl Debugger {
+    /// Create a new `Debugger` with no breakpoints set, starting in free-running mode.
+    pub fn new() -> Debugger {
+        Debugger {
+            breakpoints: Vec::new(),
+            mode: Mode::Running,
+            last_command: None,
+            gpio_levels: HashMap::new(),
+            energy_readings: HashMap::new(),
+        }
+    }
+
+    /// Set a breakpoint on a trace point, by the name it was given in the build [`Spec`].
+    pub fn break_on(&mut self, trace_point: &str) {
+        self.breakpoints.push(trace_point.to_string());
+    }
+
+    /// Record the most recently observed level of a GPIO pin, for `read <pin>` to report.
+    pub fn observe_gpio(&mut self, pin_no: u8, level: Signal) {
+        self.gpio_levels.insert(pin_no, level);
+    }
+
+    /// Record the most recent energy reading for a meter, printed alongside the trace whenever
+    /// the debugger stops.
+    pub fn observe_energy(&mut self, meter: &str, value_mw: f32) {
+        self.energy_readings.insert(meter.to_string(), value_mw);
+    }
+
+    /** Check whether reconstruction should stop for the trace that was just built.
+
+    Stops unconditionally in stepping mode, or when the trace's point name (per `spec`) matches a
+    breakpoint. When it stops, runs the command loop until a `step` or `continue` resumes execution.
+     */
+    pub fn check(&mut self, trace: &Trace, spec: &Spec) {
+        let point_name = spec.trace_point_name(trace.get_id())
+            .map(|s| s.as_str())
+            .unwrap_or("<unknown>");
+
+        let should_stop = self.mode == Mode::Stepping
+            || self.breakpoints.iter().any(|bp| bp == point_name);
+
+        if !should_stop {
+            return;
+        }
+
+        println!("--- stopped at trace point '{}' ---", point_name);
+        println!("{}", trace);
+        for (meter, value_mw) in &self.energy_readings {
+            println!("  meter '{}': {:.2} mW", meter, value_mw);
+        }
+
+        self.command_loop();
+    }
+
+    /// Read, parse, and run commands from stdin until one resumes execution (`step`/`continue`).
+    fn command_loop(&mut self) {
+        let stdin = io::stdin();
+        loop {
+            print!("(debug) ");
+            let _ = io::stdout().flush();
+
+            let mut line = String::new();
+            if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+                // EOF on stdin; nothing left to do but resume.
+                self.mode = Mode::Running;
+                return;
+            }
+
+            let line = line.trim();
+            let command = if line.is_empty() {
+                self.last_command.clone()
+            } else {
+                Command::parse(line)
+            };
+
+            let command = match command {
+                Some(command) => command,
+                None => {
+                    println!("unrecognized command: '{}'", line);
+                    continue;
+                },
+            };
+
+            if self.run_command(command.clone()) {
+                self.last_command = Some(command);
+                return;
+            }
+            self.last_command = Some(command);
+        }
+    }
+
+    /// Execute a single command. Returns `true` if it resumes reconstruction.
+    fn run_command(&mut self, command: Command) -> bool {
+        match command {
+            Command::Break(trace_point) => {
+                println!("breaking on '{}'", trace_point);
+                self.breakpoints.push(trace_point);
+                false
+            },
+            Command::Step => {
+                self.mode = Mode::Stepping;
+                true
+            },
+            Command::Continue => {
+                self.mode = Mode::Running;
+                true
+            },
+            Command::Repeat(n) => {
+                let repeated = self.last_command.clone();
+                match repeated {
+                    Some(command) if command != Command::Repeat(n) => {
+                        let mut resumed = false;
+                        for _ in 0..n {
+                            resumed = self.run_command(command.clone());
+                            if resumed {
+                                break;
+                            }
+                        }
+                        resumed
+                    },
+                    _ => {
+                        println!("nothing to repeat");
+                        false
+                    },
+                }
+            },
+            Command::Read(pin_no) => {
+                match self.gpio_levels.get(&pin_no) {
+                    Some(level) => println!("pin {}: {:?}", pin_no, level),
+                    None => println!("pin {}: no reading yet", pin_no),
+                }
+                false
+            },
+        }
+    }
+}
@@ -23,12 +23,21 @@ pub enum Error {
     Threading(std::io::Error),
     /// Energy meter does not exist.
     NoSuchMeter(String),
+    /// Analog-to-digital channel does not exist.
+    NoSuchChannel(String),
     /// Platform configuration not provided.
     NoPlatformConfig(String),
     /// No applications provided when tests require one.
     NoApplications,
     /// Error originating from interacting with software ([`sw::error::Error`]).
     Software(sw::error::Error),
+    /// One or more testbed data sources (or the device driver) panicked while running a test;
+    /// carries a description of each caught fault.
+    ThreadFault(Vec<String>),
+    /// Error communicating with a remote testbed agent/controller ([`super::cluster`]).
+    Cluster(String),
+    /// Firmware ELF/DWARF could not be parsed for coverage ([`super::coverage`]).
+    Coverage(String),
 }
 
 impl error::Error for Error {
@@ -76,9 +85,13 @@ impl Display for Error {
             Error::Comm(ref e) => write!(f, "thread communication error: {}", e),
             Error::Threading(ref e) => write!(f, "thread spawning error: {}", e),
             Error::NoSuchMeter(ref id) => write!(f, "the meter '{}' does not exist", id),
+            Error::NoSuchChannel(ref id) => write!(f, "the analog channel '{}' does not exist", id),
             Error::NoPlatformConfig(ref name) => write!(f, "config for '{}' required but missing", name),
             Error::NoApplications => write!(f, "no applications defined but at least one expected"),
             Error::Software(ref e) => write!(f, "software interaction error: {}", e),
+            Error::ThreadFault(ref faults) => write!(f, "worker thread fault(s): {}", faults.join("; ")),
+            Error::Cluster(ref msg) => write!(f, "cluster communication error: {}", msg),
+            Error::Coverage(ref msg) => write!(f, "firmware coverage error: {}", msg),
         }
     }
 }
@@ -5,6 +5,9 @@ use std::fmt;
 use std::fmt::Display;
 use std::time::Duration;
 
+use serde::{Deserialize, Serialize};
+
+use crate::comm::Signal;
 use crate::sw::instrument::Spec;
 
 use super::{Error, Result};
@@ -12,17 +15,19 @@ use super::criteria::{
     Criterion,
     GPIOCriterion,
     EnergyStat,
+    AnalogStat,
 };
+use super::histogram::{Bucketing, Histogram};
 use super::test::{
     Execution,
     Response,
     Test
 };
-use super::trace::Trace;
+use super::trace::{SerialTrace, Trace};
 
 /// Summary of an `Evaluation`.
 #[allow(dead_code)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 pub enum Status {
     /// Execution finished without error.
     Complete,
@@ -45,6 +50,16 @@ impl Display for Status {
     }
 }
 
+/// One criterion's outcome, as produced by [`Evaluation::criteria_results`].
+pub struct CriterionResult<'a> {
+    /// The criterion that was evaluated.
+    pub criterion: &'a Criterion,
+    /// Its outcome.
+    pub status: Status,
+    /// Human-readable detail, if the criterion produced one.
+    pub message: Option<String>,
+}
+
 /// In-depth information about a test execution.
 #[derive(Debug)]
 pub struct Evaluation {
@@ -53,7 +68,9 @@ pub struct Evaluation {
     exec_result: Result<Execution>,
     device_responses: Vec<Response>,
     traces: Vec<Trace>,
+    serial_traces: Vec<SerialTrace>,
     energy_metrics: HashMap<String, Vec<f32>>,
+    analog_samples: HashMap<String, Vec<f32>>,
 }
 
 impl Evaluation {
@@ -62,7 +79,9 @@ impl Evaluation {
                exec_result: Result<Execution>,
                device_responses: Vec<Response>,
                traces: Vec<Trace>,
-               energy_metrics: HashMap<String, Vec<f32>>) -> Evaluation
+               serial_traces: Vec<SerialTrace>,
+               energy_metrics: HashMap<String, Vec<f32>>,
+               analog_samples: HashMap<String, Vec<f32>>) -> Evaluation
     {
         Evaluation {
             test: test.clone(),
@@ -70,7 +89,9 @@ impl Evaluation {
             exec_result,
             device_responses,
             traces,
+            serial_traces,
             energy_metrics,
+            analog_samples,
         }
     }
 
@@ -81,7 +102,9 @@ impl Evaluation {
             exec_result: Err(error),
             device_responses: Vec::new(),
             traces: Vec::new(),
+            serial_traces: Vec::new(),
             energy_metrics: HashMap::new(),
+            analog_samples: HashMap::new(),
         }
     }
 
@@ -90,6 +113,38 @@ impl Evaluation {
         &self.exec_result
     }
 
+    /// Returns the test this is an evaluation of.
+    pub fn get_test(&self) -> &Test {
+        &self.test
+    }
+
+    /** Summarizes the samples collected for `meter_id` into a [`Histogram`], or `None` if that
+    meter wasn't metered during this evaluation.
+     */
+    pub fn energy_histogram(&self, meter_id: &str, bucketing: Bucketing) -> Option<Histogram> {
+        let samples = self.energy_metrics.get(meter_id)?;
+
+        let mut histogram = Histogram::new(bucketing);
+        for sample in samples {
+            histogram.add_sample(*sample);
+        }
+
+        Some(histogram)
+    }
+
+    /// Summarizes the time between consecutive device responses into a [`Histogram`].
+    pub fn timing_histogram(&self, bucketing: Bucketing) -> Histogram {
+        let mut times: Vec<_> = self.device_responses.iter().map(|r| r.get_time()).collect();
+        times.sort();
+
+        let mut histogram = Histogram::new(bucketing);
+        for pair in times.windows(2) {
+            histogram.add_sample((pair[1] - pair[0]).as_secs_f32());
+        }
+
+        histogram
+    }
+
     /// Overall outcome of the evaluation.
     pub fn outcome(&self) -> Status {
         if self.exec_result.is_err() {
@@ -99,12 +154,122 @@ impl Evaluation {
         }
     }
 
+    /// Evaluates every criterion on the underlying `Test`, in declaration order. See
+    /// [`super::report`] for a machine-readable serialization of these alongside the rest of an
+    /// `Evaluation`.
+    pub fn criteria_results(&self) -> Vec<CriterionResult> {
+        self.test.get_criteria().iter()
+            .map(|criterion| {
+                let (status, message) = self.evaluate(criterion);
+                CriterionResult { criterion, status, message }
+            })
+            .collect()
+    }
+
+    /// Length of the execution, or `None` if it failed outright (see [`Evaluation::outcome`]).
+    pub fn duration(&self) -> Option<Duration> {
+        self.exec_result.as_ref().ok().map(Execution::duration)
+    }
+
+    /// Device GPIO responses observed during the execution, each paired with its offset from
+    /// execution start. Empty if the execution failed outright.
+    pub fn responses_with_offsets(&self) -> Vec<(&Response, Duration)> {
+        match self.exec_result.as_ref() {
+            Ok(execution) => self.device_responses.iter()
+                .map(|r| (r, r.get_offset(*execution.get_start())))
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// GPIO-derived/SWO traces observed during the execution, each paired with its offset from
+    /// execution start. Empty if the execution failed outright.
+    pub fn traces_with_offsets(&self) -> Vec<(&Trace, Duration)> {
+        match self.exec_result.as_ref() {
+            Ok(execution) => self.traces.iter()
+                .map(|t| (t, t.get_offset(*execution.get_start())))
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Number of samples collected for each metered energy source.
+    pub fn energy_summary(&self) -> Vec<(&str, usize)> {
+        self.energy_metrics.iter().map(|(meter, samples)| (meter.as_str(), samples.len())).collect()
+    }
+
     // Come up with an evaluation for the given criterion.
     fn evaluate(&self, criterion: &Criterion) -> (Status, Option<String>) {
         match criterion {
             Criterion::GPIO(criterion) => {
                 match criterion {
                     GPIOCriterion::Any(_pin) => (Status::Complete, None),
+
+                    GPIOCriterion::EdgeCount { pin, .. } => {
+                        let count = self.device_responses.iter()
+                            .filter(|r| r.get_pin() == *pin)
+                            .count();
+
+                        let status = match criterion.violated(count as f32) {
+                            Some(true) => Status::Fail,
+                            Some(false) => Status::Pass,
+                            None => Status::Complete,
+                        };
+
+                        (status, Some(format!("{} edge(s) observed", count)))
+                    },
+
+                    GPIOCriterion::Frequency { pin, window, .. } => {
+                        let execution_start = *self.exec_result.as_ref()
+                            .expect("Attempted to evaluate criterion when execution result failed")
+                            .get_start();
+
+                        let rising_edges = self.device_responses.iter()
+                            .filter(|r| r.get_pin() == *pin
+                                    && r.get_output() == Signal::Digital(true)
+                                    && r.get_offset(execution_start) <= *window)
+                            .count();
+                        let freq_hz = rising_edges as f32 / window.as_secs_f32();
+
+                        let status = match criterion.violated(freq_hz) {
+                            Some(true) => Status::Fail,
+                            Some(false) => Status::Pass,
+                            None => Status::Complete,
+                        };
+
+                        (status, Some(format!("{:.2}Hz observed", freq_hz)))
+                    },
+
+                    GPIOCriterion::DutyCycle { pin, .. } => {
+                        let execution = self.exec_result.as_ref()
+                            .expect("Attempted to evaluate criterion when execution result failed");
+                        let execution_end = *execution.get_start() + execution.duration();
+
+                        let mut transitions: Vec<&Response> = self.device_responses.iter()
+                            .filter(|r| r.get_pin() == *pin)
+                            .collect();
+                        transitions.sort_by_key(|r| r.get_time());
+
+                        let total_time = execution.duration().as_secs_f32();
+                        let high_time: f32 = transitions.iter().enumerate()
+                            .filter(|(_i, r)| r.get_output() == Signal::Digital(true))
+                            .map(|(i, r)| {
+                                let until = transitions.get(i + 1)
+                                    .map(|next| next.get_time())
+                                    .unwrap_or(execution_end);
+                                (until - r.get_time()).as_secs_f32()
+                            })
+                            .sum();
+                        let duty = if total_time > 0.0 { high_time / total_time } else { 0.0 };
+
+                        let status = match criterion.violated(duty) {
+                            Some(true) => Status::Fail,
+                            Some(false) => Status::Pass,
+                            None => Status::Complete,
+                        };
+
+                        (status, Some(format!("{:.2}% duty cycle", duty * 100.0)))
+                    },
                 }
             },
 
@@ -208,6 +373,60 @@ impl Evaluation {
             },
 
             Criterion::Trace(_trace_criterion) => (Status::Complete, None),
+
+            Criterion::SerialTrace(criterion) => {
+                let t0 = *self.exec_result.as_ref()
+                    .expect("Attempted to evaluate criterion when execution result failed")
+                    .get_start();
+
+                match criterion.align(t0, &self.serial_traces) {
+                    Ok(matches) => (Status::Pass, Some(format!("{} pattern(s) matched", matches.len()))),
+                    Err(failure) => (Status::Fail, Some(format!("{}", failure))),
+                }
+            },
+
+            Criterion::Analog(criterion) => {
+                // Should exist in map because criterion stated it should be sampled.
+                let samples = self.analog_samples.get(criterion.get_channel())
+                    .unwrap();
+
+                let value = match criterion.get_stat() {
+                    AnalogStat::Sample => samples.last().copied().unwrap_or(0f32),
+
+                    AnalogStat::Mean => if samples.len() > 0 {
+                        samples.iter().sum::<f32>() / samples.len() as f32
+                    } else {
+                        0f32
+                    },
+
+                    AnalogStat::Min => samples.iter()
+                        .copied()
+                        .fold(f32::MAX, |curr, n| if n < curr { n } else { curr }),
+
+                    AnalogStat::Max => samples.iter()
+                        .copied()
+                        .fold(f32::MIN, |curr, n| if n > curr { n } else { curr }),
+
+                    AnalogStat::PeakToPeak => {
+                        let min = samples.iter()
+                            .copied()
+                            .fold(f32::MAX, |curr, n| if n < curr { n } else { curr });
+                        let max = samples.iter()
+                            .copied()
+                            .fold(f32::MIN, |curr, n| if n > curr { n } else { curr });
+
+                        max - min
+                    },
+                };
+
+                let status = match criterion.violated(value) {
+                    Some(true) => Status::Fail,
+                    Some(false) => Status::Pass,
+                    None => Status::Complete,
+                };
+
+                (status, Some(format!("{:.3}V ({})", value, criterion.get_stat())))
+            },
         }
     }
 }
@@ -236,6 +455,13 @@ impl Display for Evaluation {
                 }
             }
 
+            if self.analog_samples.len() > 0 {
+                write!(f, "  Analog sampling:\n")?;
+                for (channel_id, samples) in &self.analog_samples {
+                    write!(f, "    {:<10} ({} samples)\n", channel_id, samples.len())?;
+                }
+            }
+
             if self.traces.len() > 0 {
                 write!(f, "  Traces:\n")?;
                 for trace in &self.traces {
@@ -247,14 +473,20 @@ impl Display for Evaluation {
                 }
             }
 
+            if self.serial_traces.len() > 0 {
+                write!(f, "  Serial trace:\n")?;
+                for chunk in &self.serial_traces {
+                    write!(f, "    @{:?}\t{}\n", chunk.get_offset(*execution.get_start()), chunk)?;
+                }
+            }
+
             write!(f, "\n")?;
 
             // Show criteria results.
             write!(f, "=== Criteria summary:\n")?;
-            for criterion in self.test.get_criteria() {
-                let (status, opt_message) = self.evaluate(criterion);
-                write!(f, "  - {} ({})\n", criterion, status)?;
-                if let Some(ref message) = opt_message {
+            for result in self.criteria_results() {
+                write!(f, "  - {} ({})\n", result.criterion, result.status)?;
+                if let Some(ref message) = result.message {
                     write!(f, "    Message: {}\n", message)?;
                 }
             }
@@ -0,0 +1,328 @@
+/*! A small single-threaded cooperative executor for running data sources alongside a test.
+
+This replaces the old fixed `Barrier::new(5 + N)` lockstep, where every source -- whether or not
+it had anything to do for the current test -- had to be walked through the same three rendezvous
+points. Here, each source is just an entry in a `Vec`: one that has nothing to do for this test
+simply isn't polled, and adding a new kind of source is a matter of pushing another `Box<dyn
+Source>` rather than editing a magic barrier count.
+ */
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+use std::thread;
+use std::time::Duration;
+
+use super::test::{Response, Test};
+
+/// A [`Source`]'s lifecycle state, as tracked by [`SourceStatus`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SourceState {
+    /// Not currently participating in a round (either no test has started yet, or the source's
+    /// last `prepare` reported nothing to do, or it's been [`WorkerCommand::Pause`]d).
+    Idle,
+    /// Actively running as part of the current round.
+    Collecting,
+    /// The source's `prepare` or `run` returned an error (or it's been [`WorkerCommand::Cancel`]ed);
+    /// it is still offered future rounds unless cancelled, so a transient fault doesn't
+    /// permanently sideline a source.
+    Faulted,
+    /// Finished its work for the most recently run round.
+    Finished,
+}
+
+/// A point-in-time snapshot of one [`Source`]'s status, as returned by
+/// [`super::testbed::Testbed::tracing_workers`].
+#[derive(Clone, Debug)]
+pub struct SourceStatus {
+    label: String,
+    state: SourceState,
+    last_error: Option<String>,
+    tests_serviced: u64,
+}
+
+impl SourceStatus {
+    /// A freshly-registered worker that hasn't participated in a round yet.
+    pub fn idle(label: &str) -> SourceStatus {
+        SourceStatus {
+            label: label.to_string(),
+            state: SourceState::Idle,
+            last_error: None,
+            tests_serviced: 0,
+        }
+    }
+
+    /// Returns the worker's label (see [`Source::label`]).
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// Returns the worker's current lifecycle state.
+    pub fn state(&self) -> SourceState {
+        self.state
+    }
+
+    /// Returns the message from the worker's most recent fault, if any.
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// Returns the number of tests this worker has serviced (successfully or not) to completion.
+    pub fn tests_serviced(&self) -> u64 {
+        self.tests_serviced
+    }
+}
+
+/// A live command affecting a tracing worker's participation in future rounds, set through
+/// [`super::testbed::Testbed::control_tracing_worker`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WorkerCommand {
+    /// Skip this worker in future rounds without discarding it; it keeps its accumulated state.
+    Pause,
+    /// Resume including a paused worker in future rounds.
+    Resume,
+    /// Permanently stop offering this worker future rounds.
+    Cancel,
+}
+
+/// One item gathered by a [`Source`] while a test ran.
+#[derive(Debug)]
+pub enum SourceEvent {
+    /// A GPIO response observed from the device under test.
+    Response(Response),
+    /// One energy sample, tagged with the meter that produced it.
+    Energy(String, f32),
+    /// One analog sample, tagged with the channel that produced it.
+    Analog(String, f32),
+}
+
+/** A data source that can participate in running a test.
+
+Mirrors the phases the old barrier protocol encoded implicitly through wait counts: `prepare`
+configures the source for the upcoming test and reports whether it has anything to do, `run` does
+that work, and `collect` drains whatever was gathered. A source whose `prepare` returns `false` is
+dropped from the round entirely instead of being carried through idle rendezvous points.
+
+Methods return boxed futures rather than being declared `async fn` so that `Source` stays
+object-safe -- `run_round` holds its sources as `Box<dyn Source>`, since the set of sources for a
+test is only known at runtime.
+ */
+pub trait Source {
+    /// Short label used in diagnostics and worker control (e.g. "observer", "metering"); also
+    /// the key [`WorkerCommand`]s and [`SourceStatus`] snapshots are addressed by.
+    fn label(&self) -> &str;
+
+    /// Prepare to watch `test`; returns whether this source has anything to do for it, or an
+    /// error describing why preparation failed.
+    fn prepare<'a>(&'a mut self, test: &'a Test) -> Pin<Box<dyn Future<Output = Result<bool, String>> + 'a>>;
+
+    /// Run for the duration of `test`'s execution, or return an error describing what went wrong.
+    fn run<'a>(&'a mut self, test: &'a Test) -> Pin<Box<dyn Future<Output = Result<(), String>> + 'a>>;
+
+    /// Collect and return everything gathered since the last call, resetting internal state.
+    fn collect(&mut self) -> Pin<Box<dyn Future<Output = Vec<SourceEvent>> + '_>>;
+}
+
+/** Somewhere to send [`SourceEvent`]s as a round collects them, instead of building up one big
+`Vec` that isn't handed to the caller until every active source has finished.
+
+[`BufferingSink`] is the default -- it reproduces the executor's old all-at-once behavior for
+callers that just want a `Vec<SourceEvent>` back -- but a sink that writes straight to disk can
+implement this directly to flush each source's events as soon as they're collected rather than
+holding a whole test's worth of trace/energy data in memory.
+ */
+pub trait DataSink {
+    /// Called once before any events for `test` are written.
+    fn begin_test(&mut self, test: &Test);
+
+    /// Called for each event collected from an active source, as soon as that source's `collect`
+    /// resolves.
+    fn write(&mut self, event: SourceEvent);
+
+    /// Called once after every active source's events have been written for `test`.
+    fn end_test(&mut self);
+}
+
+/// Default [`DataSink`] that buffers everything in memory, matching the executor's original
+/// all-at-once behavior.
+#[derive(Debug, Default)]
+pub struct BufferingSink {
+    events: Vec<SourceEvent>,
+}
+
+impl BufferingSink {
+    /// Creates an empty sink.
+    pub fn new() -> BufferingSink {
+        BufferingSink { events: Vec::new() }
+    }
+
+    /// Takes the events buffered since the last call.
+    pub fn take(&mut self) -> Vec<SourceEvent> {
+        std::mem::take(&mut self.events)
+    }
+}
+
+impl DataSink for BufferingSink {
+    fn begin_test(&mut self, _test: &Test) {}
+
+    fn write(&mut self, event: SourceEvent) {
+        self.events.push(event);
+    }
+
+    fn end_test(&mut self) {}
+}
+
+/** Runs one full round (prepare -> run -> collect) of `sources` against `test`, driving `drive`
+(typically the code that actually exercises the device under test) concurrently with them, and
+streaming collected events to `sink` as each source finishes rather than returning them all at
+once.
+
+`statuses` must have one entry per `sources` entry, in the same order, and is updated in place so
+a caller can expose a live snapshot (see [`super::testbed::Testbed::tracing_workers`]) of each
+source's lifecycle across calls. `commands` lets an operator [`WorkerCommand::Pause`] or
+[`WorkerCommand::Cancel`] a source by label; since sources are only ever between rounds here (not
+preempted mid-poll), a command takes effect starting with the next round rather than the one
+already running.
+
+Sources for which `prepare` returns `Ok(false)` are skipped for the rest of the round; one for
+which `prepare` or `run` returns `Err` is marked [`SourceState::Faulted`] and also skipped for the
+rest of the round, but (unless cancelled) is offered the next one normally. `throttle` bounds how
+often a round with nothing left to do re-polls while waiting, so a quiet round doesn't spin the
+CPU.
+
+A source's own internal buffering (e.g. a whole test's samples held in memory until `collect`
+resolves) isn't addressed here; streaming within a single source's `run` would need that source's
+underlying data path to support incremental reads, which the current GPIO/energy/analog APIs
+don't.
+ */
+pub fn run_round<D>(sources: &mut [Box<dyn Source>],
+                     statuses: &mut [SourceStatus],
+                     commands: &HashMap<String, WorkerCommand>,
+                     test: &Test,
+                     throttle: Duration,
+                     drive: D,
+                     sink: &mut dyn DataSink)
+where
+    D: Future<Output = ()>,
+{
+    assert_eq!(sources.len(), statuses.len(), "each source must have a matching status entry");
+
+    sink.begin_test(test);
+
+    let mut active: Vec<usize> = Vec::new();
+    for (i, (source, status)) in sources.iter_mut().zip(statuses.iter_mut()).enumerate() {
+        match commands.get(source.label()) {
+            Some(WorkerCommand::Cancel) => {
+                status.state = SourceState::Faulted;
+                status.last_error = Some("cancelled by operator".to_string());
+                continue;
+            },
+            Some(WorkerCommand::Pause) => {
+                status.state = SourceState::Idle;
+                continue;
+            },
+            Some(WorkerCommand::Resume) | None => {},
+        }
+
+        match block_on(source.prepare(test), throttle) {
+            Ok(true) => {
+                status.state = SourceState::Collecting;
+                active.push(i);
+            },
+            Ok(false) => {
+                println!("executor: '{}' idle for '{}'", source.label(), test.get_id());
+                status.state = SourceState::Idle;
+            },
+            Err(e) => {
+                println!("executor: '{}' failed to prepare for '{}': {}", source.label(), test.get_id(), e);
+                status.state = SourceState::Faulted;
+                status.last_error = Some(e);
+            },
+        }
+    }
+
+    let mut run_futures: Vec<Pin<Box<dyn Future<Output = ()> + '_>>> = Vec::new();
+    run_futures.push(Box::pin(drive));
+    for (i, (source, status)) in sources.iter_mut().zip(statuses.iter_mut()).enumerate() {
+        if !active.contains(&i) { continue; }
+
+        let label = source.label().to_string();
+        let run_future = source.run(test);
+        run_futures.push(Box::pin(async move {
+            if let Err(e) = run_future.await {
+                println!("executor: '{}' failed while running '{}': {}", label, test.get_id(), e);
+                status.state = SourceState::Faulted;
+                status.last_error = Some(e);
+            }
+        }));
+    }
+    block_on_all(run_futures, throttle);
+
+    for (i, (source, status)) in sources.iter_mut().zip(statuses.iter_mut()).enumerate() {
+        if !active.contains(&i) { continue; }
+
+        if status.state != SourceState::Faulted {
+            status.state = SourceState::Finished;
+        }
+        status.tests_serviced += 1;
+
+        for event in block_on(source.collect(), throttle) {
+            sink.write(event);
+        }
+    }
+
+    sink.end_test();
+}
+
+/// Polls `future` on the current thread until it resolves, sleeping `throttle` between polls that
+/// return `Pending`.
+fn block_on<F: Future>(future: F, throttle: Duration) -> F::Output {
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    let mut future = Box::pin(future);
+
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(value) => return value,
+            Poll::Pending => thread::sleep(throttle),
+        }
+    }
+}
+
+/// Polls every future in `futures` in a round, dropping each as it resolves, until all have
+/// resolved. Sleeps `throttle` between rounds where none of them made progress.
+fn block_on_all(mut futures: Vec<Pin<Box<dyn Future<Output = ()> + '_>>>, throttle: Duration) {
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    while !futures.is_empty() {
+        let mut made_progress = false;
+        let mut i = 0;
+        while i < futures.len() {
+            match futures[i].as_mut().poll(&mut cx) {
+                Poll::Ready(()) => {
+                    futures.remove(i);
+                    made_progress = true;
+                },
+                Poll::Pending => i += 1,
+            }
+        }
+
+        if !made_progress && !futures.is_empty() {
+            thread::sleep(throttle);
+        }
+    }
+}
+
+/// A waker that does nothing; every future here is polled again immediately (or after
+/// `throttle`) rather than scheduled via a real reactor, so there is nothing to wake.
+fn noop_waker() -> Waker {
+    fn clone(_: *const ()) -> RawWaker {
+        RawWaker::new(std::ptr::null(), &VTABLE)
+    }
+    fn no_op(_: *const ()) {}
+
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, no_op, no_op, no_op);
+    unsafe { Waker::from_raw(RawWaker::new(std::ptr::null(), &VTABLE)) }
+}
@@ -0,0 +1,119 @@
+//! Bucketed histograms summarizing sample sets, so consumers can compare distributions (e.g.
+//! power draw across test runs) without shipping or re-deriving them from raw sample vectors.
+
+use std::fmt;
+use std::fmt::Display;
+
+/// How a [`Histogram`]'s buckets are spaced.
+#[derive(Copy, Clone, Debug)]
+pub enum Bucketing {
+    /// `n_buckets` buckets evenly spaced between `min` and `max`.
+    Linear { min: f64, max: f64, n_buckets: usize },
+    /// `n_buckets` buckets: the first starts at 0, the rest start at `min` and grow exponentially
+    /// toward (but not reaching) `max`, each lower bound rounded to a whole number and at least
+    /// one above the previous.
+    Exponential { min: f64, max: f64, n_buckets: usize },
+}
+
+/// One bucket's lower bound and the count of samples that landed in it.
+#[derive(Copy, Clone, Debug)]
+pub struct Bucket {
+    /// Lowest value that belongs in this bucket.
+    pub lower_bound: f64,
+    /// Number of samples that landed in this bucket.
+    pub count: u64,
+}
+
+/** A bucketed summary of a set of samples, with precomputed sum/count so a caller can compute a
+mean or compare distributions without needing the raw samples that produced it.
+ */
+#[derive(Clone, Debug)]
+pub struct Histogram {
+    buckets: Vec<Bucket>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    /// Creates an empty histogram with buckets laid out according to `bucketing`.
+    pub fn new(bucketing: Bucketing) -> Histogram {
+        let lower_bounds: Vec<f64> = match bucketing {
+            Bucketing::Linear { min, max, n_buckets } => {
+                (0..n_buckets)
+                    .map(|i| min + (max - min) * i as f64 / n_buckets as f64)
+                    .collect()
+            },
+
+            Bucketing::Exponential { min, max, n_buckets } => {
+                let mut ranges: Vec<i64> = Vec::with_capacity(n_buckets);
+                ranges.push(0);
+                for i in 1..n_buckets {
+                    let grown = min * (max / min).powf((i - 1) as f64 / (n_buckets - 1) as f64);
+                    ranges.push(std::cmp::max(ranges[i - 1] + 1, grown.round() as i64));
+                }
+
+                ranges.into_iter().map(|r| r as f64).collect()
+            },
+        };
+
+        Histogram {
+            buckets: lower_bounds.into_iter()
+                .map(|lower_bound| Bucket { lower_bound, count: 0 })
+                .collect(),
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    /** Adds `value` to the histogram.
+
+    `value` is accumulated into the highest bucket whose lower bound is `<= value`; a value below
+    every bucket's lower bound is clamped into the first bucket.
+     */
+    pub fn add_sample(&mut self, value: f32) {
+        let value = value as f64;
+
+        let bucket_index = self.buckets.iter()
+            .rposition(|bucket| bucket.lower_bound <= value)
+            .unwrap_or(0);
+        self.buckets[bucket_index].count += 1;
+
+        self.sum += value;
+        self.count += 1;
+    }
+
+    /// Returns the histogram's buckets, in ascending order of lower bound.
+    pub fn buckets(&self) -> &[Bucket] {
+        &self.buckets
+    }
+
+    /// Total number of samples added.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Sum of every sample added.
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    /// Mean of every sample added, or `0.0` if none have been.
+    pub fn mean(&self) -> f64 {
+        if self.count > 0 {
+            self.sum / self.count as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Display for Histogram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} sample(s), mean {:.3}\n", self.count, self.mean())?;
+        for bucket in &self.buckets {
+            write!(f, "  >= {:.3}: {}\n", bucket.lower_bound, bucket.count)?;
+        }
+
+        Ok(())
+    }
+}
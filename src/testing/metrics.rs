@@ -0,0 +1,65 @@
+/*! Typed point-in-time metric samples describing what a [`super::testbed::Testbed`] is doing
+right now, meant to be scraped repeatedly by an external collector instead of only consuming the
+[`super::evaluation::Evaluation`]s `Testbed::execute` returns once a whole test has finished.
+
+There's no HTTP server or oximeter-style producer framework available in this tree (no external
+crates), so this doesn't expose a literal pull endpoint --
+[`super::testbed::Testbed::metric_samples`] is the pull surface itself; a thin HTTP handler that
+calls it on each scrape is an integration detail for whatever binary embeds this testbed.
+ */
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Where a `Testbed` currently is in running a test, reported as `testbed_executor_phase` by
+/// [`super::testbed::Testbed::metric_samples`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExecutorPhase {
+    /// Not currently running a test.
+    Idle,
+    /// Loading or unloading applications ahead of a test (see `Testbed::load_apps`).
+    Loading,
+    /// Running a test's tracing workers and device driver.
+    Running,
+}
+
+/// One typed time-series sample, tagged with at least the board it describes.
+#[derive(Clone, Debug)]
+pub struct MetricSample {
+    name: String,
+    value: f64,
+    tags: HashMap<String, String>,
+    time: Instant,
+}
+
+impl MetricSample {
+    /// Constructs a sample for `name` taken just now.
+    pub fn new(name: &str, value: f64, tags: HashMap<String, String>) -> MetricSample {
+        MetricSample {
+            name: name.to_string(),
+            value,
+            tags,
+            time: Instant::now(),
+        }
+    }
+
+    /// Returns the metric's name (e.g. `"testbed_energy_mw"`).
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the metric's value.
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    /// Returns the tags identifying what this sample describes (e.g. `board_id`, `meter_id`).
+    pub fn tags(&self) -> &HashMap<String, String> {
+        &self.tags
+    }
+
+    /// Returns when this sample was taken.
+    pub fn time(&self) -> Instant {
+        self.time
+    }
+}
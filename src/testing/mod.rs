@@ -1,12 +1,22 @@
 /*! Defining and executing tests and evaluating their results.
  */
 
+pub mod cluster;
+pub mod coverage;
 pub mod criteria;
+pub mod debugger;
 pub mod error;
 pub mod evaluation;
+pub mod executor;
+pub mod histogram;
+pub mod metrics;
+pub mod qlog;
+pub mod report;
 pub mod test;
 pub mod testbed;
+pub mod timing;
 pub mod trace;
+pub mod wheel;
 
 use error::Error;
 
@@ -0,0 +1,193 @@
+/*! Export an entire test run as a single, timestamped JSON event stream.
+
+Right now a run's data comes back as disconnected pieces -- a `Test`'s `Operation` timeline, a
+`Vec<Response>` of observed GPIO activity, `HashMap<String, Vec<f32>>` energy/analog samples, each
+with its own shape and no common serialization -- which makes cross-tool analysis (diffing runs,
+plotting power against GPIO edges) a matter of writing a bespoke parser per data kind. Modeled
+loosely on QUIC's qlog, [`QlogWriter`] is a [`DataSink`] that stamps every [`SourceEvent`] (plus,
+fed separately, a `Test`'s input timeline) with a timestamp relative to a single `t0` and writes
+it as one JSON object per event, so a whole run ends up as one array (in arrival order) a
+downstream tool can walk without knowing which collector produced which event.
+
+Energy and analog samples carry no per-reading `Instant` of their own -- `Test::meter` and
+`Test::sample_analog` just append to a `Vec<f32>` as fast as they can go -- so their timestamps
+here are an approximation: `index * APPROX_LOOP_MICROS`, the same ~545us-per-iteration estimate
+`Test::prep_meter`/`Test::prep_sample` already use to pre-size their sample buffers.
+ */
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::comm::Signal;
+
+use super::executor::{DataSink, SourceEvent};
+use super::test::{Action, Test};
+
+/// Same rough per-iteration cost `Test::prep_meter`/`Test::prep_sample` assume; reused here to
+/// approximate a timestamp for samples that don't carry their own `Instant`.
+const APPROX_LOOP_MICROS: u64 = 545;
+
+/// Category tag for a [`QlogEvent`], covering every kind of data a test run can produce.
+#[derive(Copy, Clone, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QlogCategory {
+    /// An `Operation` driven into the device under test.
+    Input,
+    /// A GPIO-level response observed from the device under test.
+    GpioResponse,
+    /// One energy sample.
+    Energy,
+    /// One analog sample.
+    Analog,
+}
+
+/// Typed payload of a [`QlogEvent`]; which variant is present is implied by its [`QlogCategory`].
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum QlogData {
+    Input { pin: u8, signal: Signal },
+    GpioResponse { pin: u8, signal: Signal },
+    Energy { meter: String, sample: f32 },
+    Analog { channel: String, sample: f32 },
+}
+
+/// One entry in a qlog-style event stream.
+#[derive(Debug, Serialize)]
+pub struct QlogEvent {
+    /// Milliseconds elapsed since the run's `t0`.
+    time: f64,
+    category: QlogCategory,
+    data: QlogData,
+}
+
+impl QlogEvent {
+    fn new(offset: Duration, category: QlogCategory, data: QlogData) -> QlogEvent {
+        QlogEvent {
+            time: offset.as_secs_f64() * 1000.0,
+            category,
+            data,
+        }
+    }
+}
+
+/** Writes a test run's full event stream as a single JSON array, one object per event, in the
+order events are written.
+
+Call [`QlogWriter::write_inputs`] once with the `Test` being run to record its input timeline,
+then use the writer as a [`DataSink`] for the executor round (see [`super::executor::run_round`])
+to capture everything the device produced in response, and [`QlogWriter::finish`] to close the
+array. Events are written in arrival order, not sorted by timestamp -- a consumer that wants one
+combined timeline should sort on `time` itself.
+
+`write`, like the rest of [`DataSink`], can't report failure directly; an I/O error during any
+write is latched and every write after it is a no-op. Check [`QlogWriter::error`] once the run is
+done.
+ */
+pub struct QlogWriter<W: Write> {
+    out: W,
+    t0: Instant,
+    wrote_any: bool,
+    energy_counts: HashMap<String, u64>,
+    analog_counts: HashMap<String, u64>,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> QlogWriter<W> {
+    /// Begin a new event stream, stamping every subsequent event relative to `t0` (typically
+    /// [`super::test::Execution::get_start`]).
+    pub fn new(mut out: W, t0: Instant) -> io::Result<QlogWriter<W>> {
+        out.write_all(b"[")?;
+        Ok(QlogWriter {
+            out,
+            t0,
+            wrote_any: false,
+            energy_counts: HashMap::new(),
+            analog_counts: HashMap::new(),
+            error: None,
+        })
+    }
+
+    /// Records `test`'s input timeline as a sequence of `input` events, timestamped by each
+    /// `Operation`'s own declared offset from `t0`. `Operation`s with no action (or an `Idle`
+    /// action, which drives nothing) have no input to record and are skipped. A recurring
+    /// `Operation` is recorded once, at its first occurrence; its repeats are not written.
+    pub fn write_inputs(&mut self, test: &Test) -> io::Result<()> {
+        for op in test.get_actions() {
+            if let Some(Action::Input(signal, pin)) = op.get_action() {
+                self.write_event(
+                    Duration::from_millis(op.get_time()),
+                    QlogCategory::Input,
+                    QlogData::Input { pin, signal },
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Closes the JSON array and returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.write_all(b"]")?;
+        Ok(self.out)
+    }
+
+    /// Returns the I/O error that stopped this writer from accepting further events, if any.
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    fn write_event(&mut self, offset: Duration, category: QlogCategory, data: QlogData) -> io::Result<()> {
+        let event = QlogEvent::new(offset, category, data);
+        let encoded = serde_json::to_string(&event)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+
+        if self.wrote_any {
+            self.out.write_all(b",")?;
+        } else {
+            self.wrote_any = true;
+        }
+
+        self.out.write_all(encoded.as_bytes())
+    }
+}
+
+impl<W: Write> DataSink for QlogWriter<W> {
+    fn begin_test(&mut self, _test: &Test) {}
+
+    fn write(&mut self, event: SourceEvent) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let (category, data, offset) = match event {
+            SourceEvent::Response(response) => (
+                QlogCategory::GpioResponse,
+                QlogData::GpioResponse { pin: response.get_pin(), signal: response.get_output() },
+                response.get_offset(self.t0),
+            ),
+            SourceEvent::Energy(meter, sample) => {
+                let count = self.energy_counts.entry(meter.clone()).or_insert(0);
+                let offset = Duration::from_micros(*count * APPROX_LOOP_MICROS);
+                *count += 1;
+
+                (QlogCategory::Energy, QlogData::Energy { meter, sample }, offset)
+            },
+            SourceEvent::Analog(channel, sample) => {
+                let count = self.analog_counts.entry(channel.clone()).or_insert(0);
+                let offset = Duration::from_micros(*count * APPROX_LOOP_MICROS);
+                *count += 1;
+
+                (QlogCategory::Analog, QlogData::Analog { channel, sample }, offset)
+            },
+        };
+
+        if let Err(e) = self.write_event(offset, category, data) {
+            self.error = Some(e);
+        }
+    }
+
+    fn end_test(&mut self) {}
+}
@@ -0,0 +1,159 @@
+/*! Structured, machine-readable summaries of a batch of [`Evaluation`]s.
+
+[`Evaluation`]'s `Display` impl is meant for a human watching a run on a terminal; neither a
+dashboard nor a CI system can parse it without re-deriving the formatting it already threw away.
+[`Report`] is implemented by each output backend -- [`JsonReport`] and [`JUnitReport`] so far -- and
+renders the same data `Evaluation` already exposes (`criteria_results`, `responses_with_offsets`,
+`traces_with_offsets`, `energy_summary`, `duration`) into one machine-readable document per batch.
+ */
+
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use super::evaluation::{Evaluation, Status};
+
+/// Renders a batch of [`Evaluation`]s into a machine-readable document.
+pub trait Report {
+    /// Renders `evaluations`, in order, into one document.
+    fn render(&self, evaluations: &[Evaluation]) -> String;
+}
+
+#[derive(Serialize)]
+struct JsonCriterion {
+    criterion: String,
+    status: String,
+    message: Option<String>,
+}
+
+#[derive(Serialize)]
+struct JsonResponse {
+    pin: u8,
+    signal: String,
+    offset_ms: f64,
+}
+
+#[derive(Serialize)]
+struct JsonTrace {
+    id: u16,
+    extra: u16,
+    offset_ms: f64,
+}
+
+#[derive(Serialize)]
+struct JsonEnergyMeter {
+    meter: String,
+    samples: usize,
+}
+
+#[derive(Serialize)]
+struct JsonEvaluation {
+    test_id: String,
+    status: String,
+    duration_ms: Option<f64>,
+    error: Option<String>,
+    criteria: Vec<JsonCriterion>,
+    responses: Vec<JsonResponse>,
+    traces: Vec<JsonTrace>,
+    energy: Vec<JsonEnergyMeter>,
+}
+
+fn to_json(eval: &Evaluation) -> JsonEvaluation {
+    JsonEvaluation {
+        test_id: eval.get_test().get_id().to_string(),
+        status: eval.outcome().to_string(),
+        duration_ms: eval.duration().map(|d| d.as_secs_f64() * 1000.0),
+        error: eval.get_exec_result().as_ref().err().map(ToString::to_string),
+        // Criteria are only evaluated for an execution that completed.
+        criteria: if eval.get_exec_result().is_ok() {
+            eval.criteria_results().into_iter()
+                .map(|result| JsonCriterion {
+                    criterion: result.criterion.to_string(),
+                    status: result.status.to_string(),
+                    message: result.message,
+                })
+                .collect()
+        } else {
+            Vec::new()
+        },
+        responses: eval.responses_with_offsets().into_iter()
+            .map(|(response, offset)| JsonResponse {
+                pin: response.get_pin(),
+                signal: response.get_output().to_string(),
+                offset_ms: offset.as_secs_f64() * 1000.0,
+            })
+            .collect(),
+        traces: eval.traces_with_offsets().into_iter()
+            .map(|(trace, offset)| JsonTrace {
+                id: trace.get_id(),
+                extra: trace.get_extra(),
+                offset_ms: offset.as_secs_f64() * 1000.0,
+            })
+            .collect(),
+        energy: eval.energy_summary().into_iter()
+            .map(|(meter, samples)| JsonEnergyMeter { meter: meter.to_string(), samples })
+            .collect(),
+    }
+}
+
+/// Emits a JSON array, one object per `Evaluation`.
+pub struct JsonReport;
+
+impl Report for JsonReport {
+    fn render(&self, evaluations: &[Evaluation]) -> String {
+        let documents: Vec<JsonEvaluation> = evaluations.iter().map(to_json).collect();
+        serde_json::to_string_pretty(&documents).expect("an Evaluation report always serializes to JSON")
+    }
+}
+
+/// Escapes text for use in XML element/attribute content.
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Emits a JUnit-style `<testsuite>` document: one `<testcase>` per `Evaluation`, with an
+/// `<error>` if the execution itself failed and one `<failure>` per violated criterion.
+pub struct JUnitReport;
+
+impl Report for JUnitReport {
+    fn render(&self, evaluations: &[Evaluation]) -> String {
+        let failures: usize = evaluations.iter()
+            .filter(|e| e.get_exec_result().is_ok()
+                    && e.criteria_results().iter().any(|r| matches!(r.status, Status::Fail)))
+            .count();
+        let errors: usize = evaluations.iter()
+            .filter(|e| matches!(e.outcome(), Status::Error))
+            .count();
+
+        let mut out = String::new();
+        write!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n").unwrap();
+        write!(out, "<testsuite name=\"iot-test\" tests=\"{}\" failures=\"{}\" errors=\"{}\">\n",
+               evaluations.len(), failures, errors).unwrap();
+
+        for eval in evaluations {
+            let duration_s = eval.duration().map_or(0.0, |d| d.as_secs_f64());
+            write!(out, "  <testcase name=\"{}\" time=\"{:.6}\">\n",
+                   escape_xml(eval.get_test().get_id()), duration_s).unwrap();
+
+            if let Err(e) = eval.get_exec_result() {
+                write!(out, "    <error message=\"{}\"/>\n", escape_xml(&e.to_string())).unwrap();
+            } else {
+                for result in eval.criteria_results() {
+                    if matches!(result.status, Status::Fail) {
+                        let message = result.message.unwrap_or_else(|| result.criterion.to_string());
+                        write!(out, "    <failure message=\"{}\">{}</failure>\n",
+                               escape_xml(&result.criterion.to_string()), escape_xml(&message)).unwrap();
+                    }
+                }
+            }
+
+            write!(out, "  </testcase>\n").unwrap();
+        }
+
+        write!(out, "</testsuite>\n").unwrap();
+        out
+    }
+}
@@ -14,34 +14,180 @@ use rppal::gpio::{
     Trigger,
 };
 use rppal::uart::Uart;
+use serde::{Deserialize, Serialize};
 
 use crate::comm::Signal;
 use crate::facility::EnergyMetering;
+use crate::hw::hal::ADC;
 use crate::io::{DeviceInputs, DeviceOutputs};
 
 use super::Error;
-use super::criteria::{
-    Criterion,
-    GPIOCriterion,
-};
+use super::criteria::Criterion;
+use super::timing;
 use super::trace::SerialTrace;
+use super::wheel::TimerWheel;
 
 type Result<T> = std::result::Result<T, Error>;
 
-/// An input to perform at a specific time.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+/// What an [`Operation`] actually does when its time comes.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Action {
+    /// Drive `0` (the `Signal`) into device pin `1` (the `u8`).
+    Input(Signal, u8),
+    /// Do nothing; occupy time without driving anything.
+    Idle(Duration),
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Action::Input(signal, pin_no) => write!(f, "input {} on P{:02}", signal, pin_no),
+            Action::Idle(length) => write!(f, "idle for {:?}", length),
+        }
+    }
+}
+
+/// How many more times a recurring [`Operation`] fires after the one it's attached to.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Recurrence {
+    /// Fire this many additional times, spaced one period apart.
+    Count(u32),
+    /// Keep firing one period apart for as long as the next occurrence's time does not exceed
+    /// this absolute offset (milliseconds from test start).
+    Until(u64),
+}
+
+/// A recurring [`Operation`]'s period and how long it keeps recurring.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+struct Repeat {
+    period_ms: u64,
+    recurrence: Recurrence,
+}
+
+impl Repeat {
+    /// Returns the next `Operation` after `from` (whose schedule this `Repeat` belongs to), or
+    /// `None` if `from` was the final occurrence.
+    fn advance(&self, from: &Operation) -> Option<Operation> {
+        let next_time = from.time + self.period_ms;
+        match self.recurrence {
+            Recurrence::Count(0) => None,
+            Recurrence::Count(remaining) => Some(Operation {
+                time: next_time,
+                repeat: Some(Repeat { recurrence: Recurrence::Count(remaining - 1), ..*self }),
+                ..*from
+            }),
+            Recurrence::Until(deadline) => {
+                if next_time > deadline {
+                    None
+                } else {
+                    Some(Operation { time: next_time, ..*from })
+                }
+            },
+        }
+    }
+
+    /// Returns the time (ms from test start) of the final occurrence of a schedule beginning at
+    /// `first_time` and recurring per this `Repeat`, without stepping through every occurrence.
+    fn final_time(&self, first_time: u64) -> u64 {
+        if self.period_ms == 0 {
+            return first_time;
+        }
+
+        match self.recurrence {
+            Recurrence::Count(additional) => first_time + self.period_ms * additional as u64,
+            Recurrence::Until(deadline) => {
+                let span = deadline.saturating_sub(first_time);
+                first_time + (span / self.period_ms) * self.period_ms
+            },
+        }
+    }
+}
+
+/** An input to perform at a specific time, built up with [`Operation::at`] and one of
+[`Operation::input`]/[`Operation::idle_sync`], optionally repeating via [`Operation::every`].
+
+```ignore
+Operation::at(0).input(Signal::Digital(true), 23);
+Operation::at(0).idle_sync(Duration::from_millis(3000));
+Operation::at(0).input(Signal::Digital(true), 23).every(Duration::from_millis(10), Recurrence::Count(99));
+```
+ */
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Operation {
-    /// Time to perform the input in milliseconds
-    pub time: u64,
-    /// Signal to apply
-    pub input: Signal,
-    /// Device pin to apply the signal to.
-    pub pin_no: u8,
+    time: u64,
+    action: Option<Action>,
+    repeat: Option<Repeat>,
+}
+
+impl Operation {
+    /// Begin building an `Operation` due at `time` (milliseconds from test start).
+    pub fn at(time: u64) -> Operation {
+        Operation {
+            time,
+            action: None,
+            repeat: None,
+        }
+    }
+
+    /// Sets this `Operation` to drive `signal` into `pin_no` when it fires.
+    pub fn input(self, signal: Signal, pin_no: u8) -> Operation {
+        Operation {
+            action: Some(Action::Input(signal, pin_no)),
+            ..self
+        }
+    }
+
+    /// Sets this `Operation` to idle for `length` when it fires, driving nothing.
+    pub fn idle_sync(self, length: Duration) -> Operation {
+        Operation {
+            action: Some(Action::Idle(length)),
+            ..self
+        }
+    }
+
+    /** Makes this `Operation` recur every `period` after its first occurrence, bounded by
+    `recurrence`. A recurring `Operation` re-inserts itself into [`Test::execute`]'s
+    [`TimerWheel`] each time it fires rather than being expanded into a fixed list up front, so a
+    waveform-style input is just as cheap to define whether it repeats ten times or ten thousand.
+     */
+    pub fn every(self, period: Duration, recurrence: Recurrence) -> Operation {
+        Operation {
+            repeat: Some(Repeat { period_ms: period.as_millis() as u64, recurrence }),
+            ..self
+        }
+    }
+
+    /// Returns the time (milliseconds from test start) this `Operation` is due.
+    pub fn get_time(&self) -> u64 {
+        self.time
+    }
+
+    /// Returns what this `Operation` does when it fires, if anything was set.
+    pub fn get_action(&self) -> Option<Action> {
+        self.action
+    }
+
+    /// Returns the time (milliseconds from test start) of this `Operation`'s final occurrence,
+    /// accounting for its full recurrence if it has one, or just its own `time` if it doesn't.
+    fn final_time(&self) -> u64 {
+        match &self.repeat {
+            Some(repeat) => repeat.final_time(self.time),
+            None => self.time,
+        }
+    }
+
+    /// Returns this `Operation`'s next occurrence, if it recurs and hasn't reached its final one.
+    fn next_occurrence(&self) -> Option<Operation> {
+        self.repeat.as_ref().and_then(|repeat| repeat.advance(self))
+    }
 }
 
 impl Display for Operation {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}\tinput: {}", self.time, self.input)
+        match &self.action {
+            Some(action) => write!(f, "{}\t{}", self.time, action),
+            None => write!(f, "{}\t(no action)", self.time),
+        }
     }
 }
 
@@ -144,6 +290,47 @@ impl Execution {
     }
 }
 
+/** Rate-limited progress logger for long-running test executions.
+
+Without this, a test that's mostly waiting (e.g. a multi-second `idle_sync`-style gap between
+[`Operation`]s) prints nothing until it finishes, which looks identical to a hung device. `tick`
+prints at most once per `interval` of elapsed time, and coalesces a line that's identical to the
+last one printed so an idle wait doesn't spam identical output every interval.
+ */
+struct ProgressLog {
+    interval: Duration,
+    next_at: Instant,
+    last_line: Option<String>,
+}
+
+impl ProgressLog {
+    fn new(interval: Duration, now: Instant) -> ProgressLog {
+        ProgressLog {
+            interval,
+            next_at: now + interval,
+            last_line: None,
+        }
+    }
+
+    /// Log the result of `line` if the interval has elapsed since the last log and the line
+    /// differs from the one last printed.
+    fn tick<F>(&mut self, now: Instant, line: F)
+    where
+        F: FnOnce() -> String,
+    {
+        if now < self.next_at {
+            return;
+        }
+        self.next_at = now + self.interval;
+
+        let line = line();
+        if self.last_line.as_deref() != Some(line.as_str()) {
+            println!("{}", line);
+            self.last_line = Some(line);
+        }
+    }
+}
+
 /** Test definition.
 
 A test mainly consists of a timeline of [`Operation`]s to perform (inputs to the device under test)
@@ -152,6 +339,32 @@ and a set of responses ([`Criterion`]) to record (outputs from the device under
 Executing a test (via [`Test::execute`]) produces an [`Execution`] that contains information about the test run.
 
  */
+/** A test's declared mode, borrowed from compiletest's `run-pass`/`compile-fail` distinction: most
+tests are expected to satisfy all of their [`Criterion`]s, but a `Fail` test documents a known-bad
+case whose criteria are expected to come back unsatisfied. `--mode` (see [`crate::opts`]) filters a
+run down to just one of these.
+ */
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Mode {
+    /// The test's criteria are expected to be satisfied.
+    Pass,
+    /// The test's criteria are expected to come back unsatisfied.
+    Fail,
+}
+
+impl std::convert::TryFrom<&str> for Mode {
+    type Error = String;
+
+    fn try_from(s: &str) -> std::result::Result<Self, Self::Error> {
+        use Mode::*;
+        match s {
+            "pass" => Ok(Pass),
+            "fail" => Ok(Fail),
+            _ => Err(format!("'{}' is not a valid mode (expected one of: pass, fail)", s)),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Test {
     id: String,
@@ -160,15 +373,46 @@ pub struct Test {
     actions: BinaryHeap<Reverse<Operation>>,
     criteria: Vec<Criterion>,
     tail_duration: Option<Duration>,
+    mode: Mode,
+    spin_threshold: Duration,
+    uart_baud: u32,
+    idle_frames: u32,
+    analog_pwm_hz: f64,
 }
 
+/// Default UART baud [`Test::trace`] assumes until [`Test::with_uart_framing`] overrides it.
+const DEFAULT_UART_BAUD: u32 = 115200;
+
+/// Default idle-frame count [`Test::trace`] assumes until [`Test::with_uart_framing`] overrides it.
+const DEFAULT_IDLE_FRAMES: u32 = 2;
+
+/// Default PWM rate [`Test::execute`] drives `Signal::Analog` inputs at until
+/// [`Test::with_analog_pwm_frequency`] overrides it.
+const DEFAULT_ANALOG_PWM_HZ: f64 = 1000.0;
+
+/// Resolution assumed for a raw `Signal::Analog` value driven by [`Test::execute`], matching the
+/// 12-bit converter a [`crate::hw::hal::ADC`] typically models on this hardware.
+const ANALOG_DRIVE_RESOLUTION_BITS: u32 = 12;
+
+/// Bucket width of the [`TimerWheel`] [`Test::execute`] drives its [`Operation`]s off of. This
+/// matches [`Operation::time`]'s own millisecond resolution, so a schedule's declared times land
+/// on exact bucket boundaries rather than being rounded down to a coarser tick.
+const WHEEL_GRANULARITY: Duration = Duration::from_millis(1);
+
+/// Bucket count for the [`TimerWheel`] [`Test::execute`] drives. Chosen so a test running for
+/// up to a little over a second needs no bucket to carry more than one lap; longer or
+/// heavier-repeating tests still work correctly, just with more than one lap recorded per entry.
+const WHEEL_BUCKET_COUNT: usize = 1024;
+
 impl Test {
-    /// Define a new test.
+    /// Define a new test. `expect_pass` sets the test's [`Mode`]: `true` for [`Mode::Pass`],
+    /// `false` for [`Mode::Fail`].
     pub fn new<'a, T, U, V, W>(id: &str,
                                app_id: T,
                                trace_points: U,
                                ops: V,
-                               criteria: W) -> Test
+                               criteria: W,
+                               expect_pass: bool) -> Test
     where
         T: IntoIterator<Item = &'a str>,
         U: IntoIterator<Item = &'a str>,
@@ -182,9 +426,63 @@ impl Test {
             actions: ops.into_iter().map(|x| Reverse(*x)).collect(),
             criteria: criteria.into_iter().cloned().collect(),
             tail_duration: Some(Duration::from_millis(5)),
+            mode: if expect_pass { Mode::Pass } else { Mode::Fail },
+            spin_threshold: timing::DEFAULT_SPIN_THRESHOLD,
+            uart_baud: DEFAULT_UART_BAUD,
+            idle_frames: DEFAULT_IDLE_FRAMES,
+            analog_pwm_hz: DEFAULT_ANALOG_PWM_HZ,
+        }
+    }
+
+    /** Sets how close to a deadline [`Test::execute`] switches from sleeping to spinning (see
+    [`super::timing::sleep_until`]). Defaults to [`timing::DEFAULT_SPIN_THRESHOLD`]; widen this on
+    a host where `std::thread::sleep` overshoots by more than that, at the cost of spinning (and
+    burning a core) for longer before each input.
+     */
+    pub fn with_spin_threshold(self, spin_threshold: Duration) -> Test {
+        Test {
+            spin_threshold,
+            ..self
         }
     }
 
+    /** Sets the UART line rate and idle-frame count [`Test::trace`] uses to tell one burst of
+    trace data from the next. Defaults to 115200 baud and 2 character frames; set this to match
+    whatever baud the test's UART connection actually runs at so the idle gap this computes lines
+    up with real frame timing.
+     */
+    pub fn with_uart_framing(self, baud: u32, idle_frames: u32) -> Test {
+        Test {
+            uart_baud: baud,
+            idle_frames,
+            ..self
+        }
+    }
+
+    /** Sets the PWM rate [`Test::execute`] drives `Signal::Analog` inputs at. Defaults to 1000 Hz;
+    raise this if the device under test needs a faster-settling approximation of a DC level than
+    software PWM at the default rate can provide.
+     */
+    pub fn with_analog_pwm_frequency(self, analog_pwm_hz: f64) -> Test {
+        Test {
+            analog_pwm_hz,
+            ..self
+        }
+    }
+
+    /** Returns the span of UART silence that [`Test::trace`] treats as the end of one burst and
+    the start of the next: `idle_frames` character frames at `uart_baud`, 10 bits per frame (8N1:
+    start + 8 data + stop bits).
+     */
+    fn idle_gap(&self) -> Duration {
+        Duration::from_secs_f64(self.idle_frames as f64 * 10.0 / self.uart_baud as f64)
+    }
+
+    /// Returns the test's declared [`Mode`].
+    pub fn get_mode(&self) -> Mode {
+        self.mode
+    }
+
     /// Returns the identifier of the test definition.
     pub fn get_id(&self) -> &str {
         &self.id
@@ -205,26 +503,81 @@ impl Test {
         &self.criteria
     }
 
-    /// Drive test outputs (inputs to the device).
+    /// Returns the test's [`Operation`]s, in no particular order (use [`Operation::time`] to
+    /// recover their schedule).
+    pub fn get_actions(&self) -> impl Iterator<Item = &Operation> {
+        self.actions.iter().map(|Reverse(op)| op)
+    }
+
+    /** Drive test outputs (inputs to the device).
+
+    [`Operation`]s are loaded into a [`TimerWheel`] up front and fired off as the wheel ticks
+    once per [`WHEEL_GRANULARITY`] of elapsed wall time (sleeping through most of each tick via
+    [`timing::sleep_until`] rather than busy-polling `Instant::now`, and letting `progress` report
+    how much longer there is to go at most once per `tick_interval`). A
+    recurring `Operation` re-schedules its own next occurrence back into the wheel as soon as it
+    fires, so the cost of a repeating input is independent of how many times it repeats.
+     */
     pub fn execute(&self, t0: Instant, pins: &mut DeviceInputs) -> Result<Execution> {
-        let timeline = self.actions.iter()
-            .map(|Reverse(op)| (t0 + Duration::from_millis(op.time), op));
-        for (t, op) in timeline {
-            while Instant::now() < t {  } // spin wait?
-            match op.input {
-                Signal::Digital(true) =>
-                    (*pins.get_pin_mut(op.pin_no)?)
-                    .set_high(),
-                Signal::Digital(false) =>
-                    (*pins.get_pin_mut(op.pin_no)?)
-                    .set_low(),
-                input => panic!("Unhandled input type: {:?}", input),
-            };
+        let mut wheel: TimerWheel<Operation> = TimerWheel::new(WHEEL_GRANULARITY, WHEEL_BUCKET_COUNT);
+        for Reverse(op) in &self.actions {
+            wheel.schedule(Duration::from_millis(op.time), *op);
+        }
+
+        let tick_interval = Duration::from_secs(1);
+        let mut progress = ProgressLog::new(tick_interval, t0);
+        let deadline = t0 + self.get_max_runtime();
+
+        loop {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+
+            for op in wheel.tick() {
+                self.fire(&op, pins, &mut wheel)?;
+            }
+
+            progress.tick(now, || format!(
+                "executor: {:?} elapsed, {:?} remaining",
+                now.duration_since(t0),
+                deadline.saturating_duration_since(now)));
+
+            let wake_at = (now + WHEEL_GRANULARITY).min(deadline);
+            timing::sleep_until(wake_at, self.spin_threshold);
         }
 
         Ok(Execution::new(t0, Instant::now()))
     }
 
+    /// Applies `op`'s `Action`, if it has one, and re-schedules its next occurrence into `wheel`
+    /// if it recurs.
+    fn fire(&self, op: &Operation, pins: &mut DeviceInputs, wheel: &mut TimerWheel<Operation>) -> Result<()> {
+        match op.get_action() {
+            Some(Action::Input(Signal::Digital(true), pin_no)) =>
+                (*pins.get_pin_mut(pin_no)?)
+                .set_high(),
+            Some(Action::Input(Signal::Digital(false), pin_no)) =>
+                (*pins.get_pin_mut(pin_no)?)
+                .set_low(),
+            Some(Action::Input(Signal::Analog(level), pin_no)) => {
+                // No true DAC on this hardware: approximate the level with software PWM,
+                // treating `level` as a raw ANALOG_DRIVE_RESOLUTION_BITS-bit duty-cycle code
+                // the same way an ADC sample is a raw code into `ADC::to_voltage`.
+                let max_level = (1u32 << ANALOG_DRIVE_RESOLUTION_BITS) - 1;
+                let duty_cycle = (level as f64 / max_level as f64).min(1.0);
+                pins.start_pwm(pin_no, self.analog_pwm_hz, duty_cycle)?;
+            },
+            Some(Action::Idle(_)) | None => {},
+        };
+
+        if let Some(next) = op.next_occurrence() {
+            wheel.schedule(Duration::from_millis(next.get_time() - op.get_time()), next);
+        }
+
+        Ok(())
+    }
+
     /// Set up to record test inputs.
     pub fn prep_observe(&self,
                         pins: &mut DeviceOutputs,
@@ -242,13 +595,10 @@ impl Test {
             });
         for criterion in gpio_criteria {
             println!("observer: watching for {}", criterion);
-            match criterion {
-                GPIOCriterion::Any(pin_no) => {
-                    pins.get_pin_mut(*pin_no)?
-                        .set_interrupt(Trigger::Both)?;
-                    interrupt_pins.push(*pin_no);
-                },
-            };
+            let pin_no = criterion.get_pin();
+            pins.get_pin_mut(pin_no)?
+                .set_interrupt(Trigger::Both)?;
+            interrupt_pins.push(pin_no);
         }
 
         // Configure interrupts on the trace pins differently if specified.
@@ -353,9 +703,15 @@ impl Test {
         Ok(has_energy_criteria)
     }
 
-    /// Perform energy metering.
-    ///
-    /// The `out` parameter should be the same `out` passed to [`Test::prep_meter`].
+    /** Perform energy metering.
+
+    The `out` parameter should be the same `out` passed to [`Test::prep_meter`].
+
+    Unlike [`Test::execute`]'s wait for its next [`Operation`], this loop has nothing to sleep
+    through: every iteration does real work (a meter read) back-to-back for the whole test, and
+    that back-to-back cadence is what the ~568µs/sample rate noted below already assumes. Routing
+    it through [`timing::sleep_until`] would throttle sampling, not reclaim idle CPU.
+     */
     pub fn meter(&self, meters: &HashMap<String, Box<dyn EnergyMetering>>, out: &mut HashMap<String, Vec<f32>>) {
         let start = Instant::now();
         let runtime = self.get_max_runtime();
@@ -377,12 +733,66 @@ impl Test {
         }
     }
 
+    /// Prepare structures for analog sampling.
+    ///
+    /// # Returns
+    /// Returns true if there are analog criteria in this test.
+    /// [`Test::sample_analog`] should be called when running the test.
+    pub fn prep_sample(&self,
+                       channels: &HashMap<String, (Box<dyn ADC>, u8)>,
+                       out: &mut HashMap<String, Vec<f32>>,
+    ) -> Result<bool> {
+        // only care about channels defined in the criteria
+        out.clear();
+
+        let approx_loop_micros = 545;
+        let max_sample_count = (self.get_max_runtime().as_micros() /
+                                approx_loop_micros as u128) + 1;
+
+        let mut has_analog_criteria = false;
+        // pre-allocate space in sample output vectors
+        for criterion in &self.criteria {
+            if let Criterion::Analog(ref analog_criterion) = criterion {
+                has_analog_criteria = true;
+                let channel_id = analog_criterion.get_channel();
+                if !channels.contains_key(channel_id) {
+                    return Err(Error::NoSuchChannel(channel_id.to_string()));
+                } else {
+                    out.entry(channel_id.to_string())
+                        .or_insert(Vec::new())
+                        .reserve_exact(max_sample_count as usize);
+                }
+            }
+        }
+
+        Ok(has_analog_criteria)
+    }
+
+    /// Perform analog sampling.
+    ///
+    /// The `out` parameter should be the same `out` passed to [`Test::prep_sample`]. See
+    /// [`Test::meter`] for why this stays a tight sampling loop rather than sleeping between reads.
+    pub fn sample_analog(&self, channels: &HashMap<String, (Box<dyn ADC>, u8)>, out: &mut HashMap<String, Vec<f32>>) {
+        let start = Instant::now();
+        let runtime = self.get_max_runtime();
+
+        loop {
+            if Instant::now() - start >= runtime { break; }
+
+            for (id, buf) in &mut *out {
+                let (adc, channel_no) = channels.get(id).unwrap();
+                buf.push(adc.to_voltage(adc.sample(*channel_no)));
+            }
+        }
+    }
+
     pub fn prep_tracing<'a>(&self,
                             uart: &mut Uart,
                             data_buffer: &'a mut Vec<u8>) -> Result<&'a mut [u8]> {
-        // Timeout is a bit arbitrary here.
-        // Don't want the thread hanging the test unnecessarily.
-        uart.set_read_mode(0, Duration::from_millis(50))?;
+        // The read timeout doubles as our idle-line detector: a read() that comes back with
+        // nothing means the line has gone quiet for a full idle gap, so `trace` can treat that
+        // as a burst boundary instead of polling for one.
+        uart.set_read_mode(0, self.idle_gap())?;
 
         data_buffer.clear();
         data_buffer.reserve_exact(1 * 1024 * 1024);
@@ -390,17 +800,39 @@ impl Test {
         Ok(data_buffer.as_mut_slice())
     }
 
+    /** Read UART trace data, grouping contiguous bytes into bursts.
+
+    A `read()` blocks for up to [`Test::idle_gap`] (set via [`Test::prep_tracing`]), so one that
+    returns zero bytes means the line has been idle that long: whatever burst is currently open
+    gets closed out and pushed, stamped with the `Instant` of its *first* byte. Without this, a
+    burst split across several `read()` calls would otherwise get a separate, later-skewed
+    timestamp per call instead of one honest arrival time for the whole thing.
+     */
     pub fn trace(&self,
                  uart: &mut Uart,
                  buffer: &mut [u8],
-                 _out: &mut Vec<SerialTrace>) -> Result<usize> {
+                 out: &mut Vec<SerialTrace>) -> Result<usize> {
         let start = Instant::now();
         let max_runtime = self.get_max_runtime();
         let mut bytes_read: usize = 0;
+        let mut burst: Option<(Instant, usize)> = None;
 
         loop {
             if Instant::now() - start >= max_runtime { break; }
-            bytes_read += uart.read(&mut buffer[bytes_read..])?;
+
+            let n = uart.read(&mut buffer[bytes_read..])?;
+            if n > 0 {
+                if burst.is_none() {
+                    burst = Some((Instant::now(), bytes_read));
+                }
+                bytes_read += n;
+            } else if let Some((burst_time, burst_begin)) = burst.take() {
+                out.push(SerialTrace::new(buffer[burst_begin..bytes_read].to_vec(), burst_time));
+            }
+        }
+
+        if let Some((burst_time, burst_begin)) = burst.take() {
+            out.push(SerialTrace::new(buffer[burst_begin..bytes_read].to_vec(), burst_time));
         }
 
         Ok(bytes_read)
@@ -411,8 +843,8 @@ impl Test {
     /// TODO: make this dependent on actions' timing, criteria timing, and another tail duration(?).
     fn get_max_runtime(&self) -> Duration {
         let duration_ms = self.actions.iter()
-            .map(|Reverse(action)| action.time)
-            .last()
+            .map(|Reverse(action)| action.final_time())
+            .max()
             .unwrap_or(0);
         let tail_ms = self.tail_duration
             .unwrap_or(Duration::from_millis(0))
@@ -429,14 +861,16 @@ impl Display for Test {
         write!(f, "|{:>10}|{:^5}|{:^20}|\n", "time (ms)", "pin", "operation")?;
         write!(f, "|----------+-----+--------------------|\n")?;
         for Reverse(ref action) in &self.actions {
-            let sig_text = match action.input {
-                Signal::Digital(true) => "digital 1".to_string(),
-                Signal::Digital(false) => "digital 0".to_string(),
-                Signal::Analog(lv) => format!("analog {:5}", lv),
+            let (pin_text, sig_text) = match action.get_action() {
+                Some(Action::Input(Signal::Digital(true), pin_no)) => (pin_no.to_string(), "digital 1".to_string()),
+                Some(Action::Input(Signal::Digital(false), pin_no)) => (pin_no.to_string(), "digital 0".to_string()),
+                Some(Action::Input(Signal::Analog(lv), pin_no)) => (pin_no.to_string(), format!("analog {:5}", lv)),
+                Some(Action::Idle(length)) => ("-".to_string(), format!("idle {:?}", length)),
+                None => ("-".to_string(), "(no action)".to_string()),
             };
             write!(f, "|{:>10}|{:^5}|{:^20}|\n",
-                   action.time,
-                   action.pin_no,
+                   action.get_time(),
+                   pin_text,
                    sig_text)?;
         }
         write!(f, "\n")?;
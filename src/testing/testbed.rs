@@ -1,30 +1,36 @@
 //! Configure and execute tests.
 
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Display;
-use std::sync::mpsc;
-use std::sync::mpsc::SyncSender;
-use std::sync::{Arc,
-                Barrier,
-                Mutex,
-                RwLock};
-use std::thread;
-use std::thread::JoinHandle;
-use std::time::Instant;
+use std::future::Future;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use crate::facility::EnergyMetering;
-use crate::io::Mapping;
+use crate::hw::hal::ADC;
+use crate::io::{DeviceOutputs, Mapping};
 use crate::sw::{PlatformSupport, Platform};
 use crate::sw::application::ApplicationSet;
 use crate::testing::test::Response;
 
 use super::Error;
 use super::evaluation::Evaluation;
-use super::test::Test;
+use super::executor::{run_round, BufferingSink, Source, SourceEvent, SourceStatus, WorkerCommand};
+use super::metrics::{ExecutorPhase, MetricSample};
+use super::test::{Execution, Test};
 
 type Result<T> = std::result::Result<T, Error>;
 
+/// How often an idle round re-polls while waiting for a source or the device driver to make
+/// progress; see [`run_round`].
+const DEFAULT_POLL_THROTTLE: Duration = Duration::from_millis(1);
+
 /// Test suite executor
 #[derive(Debug)]
 pub struct Testbed {
@@ -32,23 +38,39 @@ pub struct Testbed {
     target_platform: Platform,
     platform_support: Box<dyn PlatformSupport>,
     energy_meters: Arc<Mutex<HashMap<String, Box<dyn EnergyMetering>>>>,
-    applications: Option<ApplicationSet>
+    analog_channels: Arc<Mutex<HashMap<String, (Box<dyn ADC>, u8)>>>,
+    applications: Option<ApplicationSet>,
+    poll_throttle: Duration,
+    worker_statuses: Mutex<Vec<SourceStatus>>,
+    worker_commands: Mutex<HashMap<String, WorkerCommand>>,
+    phase: Mutex<ExecutorPhase>,
+    latest_energy: Mutex<HashMap<String, f32>>,
+    latest_analog: Mutex<HashMap<String, f32>>,
+    responses_observed: Mutex<u64>,
 }
 
+/// Labels of the tracing workers every `Testbed` runs; see [`Testbed::tracing_workers`].
+const WORKER_LABELS: [&str; 3] = ["observer", "metering", "analog"];
+
 impl Testbed {
     /// Create a new `Testbed`.
-    pub fn new<'a, T, U>(pin_mapping: Mapping,
+    pub fn new<'a, T, U, V>(pin_mapping: Mapping,
                          target_platform: Platform,
                          platform_support: T,
                          energy_meters: U,
+                         analog_channels: V,
                          applications: Option<ApplicationSet>) -> Result<Testbed>
     where
         T: IntoIterator<Item = Box<dyn PlatformSupport>>,
         U: IntoIterator<Item = (&'a str, Box<dyn EnergyMetering>)>,
+        V: IntoIterator<Item = (&'a str, Box<dyn ADC>, u8)>,
     {
         let energy_meters = energy_meters.into_iter()
             .map(|(id, meter)| (id.to_string(), meter))
             .collect();
+        let analog_channels = analog_channels.into_iter()
+            .map(|(id, adc, channel_no)| (id.to_string(), (adc, channel_no)))
+            .collect();
 
         let platform_support = platform_support.into_iter()
             .find(|p| p.platform() == target_platform)
@@ -59,12 +81,88 @@ impl Testbed {
             target_platform,
             platform_support,
             energy_meters: Arc::new(Mutex::new(energy_meters)),
+            analog_channels: Arc::new(Mutex::new(analog_channels)),
             applications,
+            poll_throttle: DEFAULT_POLL_THROTTLE,
+            worker_statuses: Mutex::new(WORKER_LABELS.iter().map(|label| SourceStatus::idle(label)).collect()),
+            worker_commands: Mutex::new(HashMap::new()),
+            phase: Mutex::new(ExecutorPhase::Idle),
+            latest_energy: Mutex::new(HashMap::new()),
+            latest_analog: Mutex::new(HashMap::new()),
+            responses_observed: Mutex::new(0),
         };
 
         Ok(testbed)
     }
 
+    /// Returns a snapshot of each tracing worker's current lifecycle state, last error (if any),
+    /// and number of tests serviced so far.
+    pub fn tracing_workers(&self) -> Vec<SourceStatus> {
+        self.worker_statuses.lock().unwrap().clone()
+    }
+
+    /** Issue a [`WorkerCommand`] to the tracing worker labelled `label`.
+
+    Takes effect starting with that worker's next round; see [`run_round`]. `label` is matched
+    against [`Source::label`] (e.g. `"observer"`, `"metering"`, `"analog"`) and a command for an
+    unrecognized label is simply never picked up.
+     */
+    pub fn control_tracing_worker(&self, label: &str, command: WorkerCommand) {
+        let mut commands = self.worker_commands.lock().unwrap();
+        match command {
+            WorkerCommand::Resume => { commands.remove(label); },
+            other => { commands.insert(label.to_string(), other); },
+        }
+    }
+
+    /** Returns a point-in-time snapshot of this testbed's state as typed metric samples, tagged
+    with `board_id`, suitable for an external collector to scrape on an interval instead of
+    waiting for `execute` to return a whole test's [`Evaluation`].
+     */
+    pub fn metric_samples(&self, board_id: &str) -> Vec<MetricSample> {
+        let make_tags = |extra: &[(&str, &str)]| {
+            let mut tags = HashMap::new();
+            tags.insert("board_id".to_string(), board_id.to_string());
+            for (k, v) in extra {
+                tags.insert(k.to_string(), v.to_string());
+            }
+            tags
+        };
+
+        let mut samples = Vec::new();
+
+        let phase_value = match *self.phase.lock().unwrap() {
+            ExecutorPhase::Idle => 0.0,
+            ExecutorPhase::Loading => 1.0,
+            ExecutorPhase::Running => 2.0,
+        };
+        samples.push(MetricSample::new("testbed_executor_phase", phase_value, make_tags(&[])));
+
+        for (meter_id, value) in self.latest_energy.lock().unwrap().iter() {
+            samples.push(MetricSample::new("testbed_energy_mw", *value as f64, make_tags(&[("meter_id", meter_id)])));
+        }
+
+        for (channel_id, value) in self.latest_analog.lock().unwrap().iter() {
+            samples.push(MetricSample::new("testbed_analog_v", *value as f64, make_tags(&[("channel_id", channel_id)])));
+        }
+
+        samples.push(MetricSample::new(
+            "testbed_gpio_responses_total",
+            *self.responses_observed.lock().unwrap() as f64,
+            make_tags(&[]),
+        ));
+
+        for status in self.worker_statuses.lock().unwrap().iter() {
+            samples.push(MetricSample::new(
+                "testbed_worker_tests_serviced_total",
+                status.tests_serviced() as f64,
+                make_tags(&[("worker", status.label())]),
+            ));
+        }
+
+        samples
+    }
+
     /** Run tests.
      *
      * Execute the given tests one after the other.
@@ -81,21 +179,20 @@ impl Testbed {
     {
         let mut test_results = Vec::new();
 
-        let barrier = Arc::new(Barrier::new(3));
-        let current_test: Arc<RwLock<Option<Test>>> = Arc::new(RwLock::new(None));
-
-        let (observer_schannel, observer_rchannel) = mpsc::sync_channel(0);
-        let watch_thread = self.launch_observer(Arc::clone(&current_test),
-                                                Arc::clone(&barrier),
-                                                observer_schannel)?;
-
-        let (energy_schannel, energy_rchannel) = mpsc::sync_channel(0);
-        let energy_thread = self.launch_metering(Arc::clone(&current_test),
-                                                 Arc::clone(&barrier),
-                                                 energy_schannel)?;
+        let outputs = self.pin_mapping.get_gpio_outputs()?;
+        let trace_pins = self.pin_mapping.get_trace_pin_nos().clone();
+        let mut sources: Vec<Box<dyn Source>> = vec![
+            Box::new(ObserverSource::new(outputs, trace_pins)),
+            Box::new(MeteringSource::new(Arc::clone(&self.energy_meters))),
+            Box::new(AnalogSource::new(Arc::clone(&self.analog_channels))),
+        ];
+        let mut statuses = self.worker_statuses.lock().unwrap();
 
         for test in tests {
+            let commands = self.worker_commands.lock().unwrap().clone();
+
             println!("executor: running '{}'", test.get_id());
+            *self.phase.lock().unwrap() = ExecutorPhase::Loading;
 
             // Reconfigure target if necessary.
             // Just always configuring when there are trace points
@@ -129,175 +226,70 @@ impl Testbed {
                 continue;
             }
 
-            *current_test.write().unwrap() = Some(test.clone());
-
             let mut inputs = self.pin_mapping.get_gpio_inputs()?;
 
-            // wait for observer, metering thread to be ready
-            barrier.wait();
+            // The device driver runs as just another task in the same round as the sources
+            // watching it, rather than being called from the executor thread in between two
+            // fixed rendezvous points; it stashes its result here since a `Future` can't return
+            // one directly to `run_round`.
+            let exec_result_cell: Rc<RefCell<Option<Result<Execution>>>> = Rc::new(RefCell::new(None));
+            let drive = {
+                let exec_result_cell = Rc::clone(&exec_result_cell);
+                async move {
+                    let result = test.execute(Instant::now(), &mut inputs);
+                    *exec_result_cell.borrow_mut() = Some(result);
+                }
+            };
 
-            // wait for test to begin
-            barrier.wait();
             println!("executor: starting test '{}'", test.get_id());
-
-            let exec_result = test.execute(Instant::now(), &mut inputs);
-
-            // release observer thread
+            *self.phase.lock().unwrap() = ExecutorPhase::Running;
+            let mut sink = BufferingSink::new();
+            let round = panic::catch_unwind(AssertUnwindSafe(|| {
+                run_round(&mut sources, &mut *statuses, &commands, test, self.poll_throttle, drive, &mut sink)
+            }));
+            *self.phase.lock().unwrap() = ExecutorPhase::Idle;
             println!("executor: test execution complete");
-            barrier.wait();
 
-            // get GPIO responses
-            let mut responses = Vec::new();
-            while let Some(response) = observer_rchannel.recv()? {
-                let response = response.remapped(self.pin_mapping.get_mapping());
-                responses.push(response);
-            }
+            match round {
+                Ok(()) => {
+                    let exec_result = exec_result_cell.borrow_mut().take()
+                        .unwrap_or_else(|| Err(Error::ThreadFault(vec!["test driver did not report a result".to_string()])));
+
+                    let mut responses = Vec::new();
+                    let mut energy_data: HashMap<String, Vec<f32>> = HashMap::new();
+                    let mut analog_data: HashMap<String, Vec<f32>> = HashMap::new();
+                    for event in sink.take() {
+                        match event {
+                            SourceEvent::Response(response) => {
+                                *self.responses_observed.lock().unwrap() += 1;
+                                responses.push(response.remapped(self.pin_mapping.get_mapping()));
+                            },
+                            SourceEvent::Energy(meter_id, sample) => {
+                                self.latest_energy.lock().unwrap().insert(meter_id.clone(), sample);
+                                energy_data.entry(meter_id).or_insert_with(Vec::new).push(sample);
+                            },
+                            SourceEvent::Analog(channel_id, sample) => {
+                                self.latest_analog.lock().unwrap().insert(channel_id.clone(), sample);
+                                analog_data.entry(channel_id).or_insert_with(Vec::new).push(sample);
+                            },
+                        }
+                    }
 
-            // get energy data
-            let mut energy_data = HashMap::new();
-            while let Some((meter_id, sample)) = energy_rchannel.recv()? {
-                energy_data.entry(meter_id)
-                    .or_insert(Vec::new())
-                    .push(sample);
+                    test_results.push(Evaluation::new(test, exec_result, responses, energy_data, analog_data));
+                },
+                Err(payload) => {
+                    let message = panic_message(&*payload);
+                    println!("executor: a source panicked while running '{}': {}", test.get_id(), message);
+                    test_results.push(Evaluation::failed(test, None, Error::ThreadFault(vec![message])));
+                },
             }
 
-            test_results.push(Evaluation::new(test, exec_result, responses, energy_data));
             println!("executor: test finished.");
         }
 
-        *current_test.write().unwrap() = None;
-        println!("executor: final wait");
-        barrier.wait();
-
-        // Not too concerned with joining these without error
-        // since testing is complete at this point. It shouldn't
-        // result in a crash either.
-        watch_thread.join().unwrap_or_else(|_e| {
-            println!("executor: failed to join with observer thread");
-        });
-        energy_thread.join().unwrap_or_else(|_e| {
-            println!("executor: failed to join with metering thread");
-        });
-
         Ok(test_results)
     }
 
-    fn launch_observer(
-        &self,
-        test_container: Arc<RwLock<Option<Test>>>,
-        barrier: Arc<Barrier>,
-        response_schannel: SyncSender<Option<Response>>,
-    ) -> Result<JoinHandle<()>> {
-        let mut outputs = self.pin_mapping.get_gpio_outputs()?;
-        let trace_pins = self.pin_mapping.get_trace_pin_nos().clone();
-
-        thread::Builder::new()
-            .name("test-observer".to_string())
-            .spawn(move || {
-                println!("observer: started.");
-
-                let mut responses = Vec::new();
-                loop {
-                    // wait for next test
-                    barrier.wait();
-
-                    // set up to watch for responses according to criteria
-                    if let Some(ref test) = *test_container.read().unwrap() {
-                        test.prep_observe(&mut outputs, &trace_pins)
-                            .unwrap(); // <-- communicate back?
-
-                        // wait for test to begin
-                        println!("observer: ready to begin test");
-                        barrier.wait();
-                        println!("observer: starting watch");
-
-                        let t0 = Instant::now();
-                        test.observe(t0, &outputs, &mut responses)
-                            .unwrap();
-
-                        // wait for output responses from dut or the end of the test
-                        // can I just wait for the barrier here or will an interrupt stop it?
-                        barrier.wait();
-
-                        println!("observer: cleaning up interrupts");
-                        for pin in &mut outputs {
-                            pin.clear_interrupt().unwrap();
-                        }
-
-                        for r in responses.drain(..) {
-                            response_schannel.send(Some(r)).unwrap();
-                        }
-                        response_schannel.send(None).unwrap();
-                    } else {
-                        // no more tests to run
-                        break;
-                    }
-                }
-
-                println!("observer: exiting");
-            })
-            .map_err(|e| Error::Threading(e))
-    }
-
-    fn launch_metering(
-        &self,
-        test_container: Arc<RwLock<Option<Test>>>,
-        barrier: Arc<Barrier>,
-        energy_schannel: SyncSender<Option<(String, f32)>>,
-    ) -> Result<JoinHandle<()>> {
-        println!("Starting energy metering thread.");
-
-        let meters = Arc::clone(&self.energy_meters);
-
-        thread::Builder::new()
-            .name("test-metering".to_string())
-            .spawn(move || {
-                println!("metering: started.");
-
-                let meters = meters.lock().unwrap();
-                let mut samples: HashMap<String, Vec<f32>> = meters.keys()
-                    .map(|meter_id| { (meter_id.clone(), Vec::new()) })
-                    .collect();
-
-                loop {
-                    // wait for next test
-                    barrier.wait();
-
-                    if let Some(ref test) = *test_container.read().unwrap() {
-                        // here, better error management across threads would be nice!
-                        let need_metering = test.prep_meter(&meters, &mut samples).unwrap();
-                        if !need_metering {
-                            println!("metering: idling; not needed for this test");
-                            barrier.wait();
-                        } else {
-                            // wait for test to begin
-                            println!("metering: ready to begin test");
-                            barrier.wait();
-
-                            test.meter(&meters, &mut samples);
-                        }
-                    } else {
-                        // no more tests to run
-                        break;
-                    }
-
-                    barrier.wait();
-
-                    // communicate results back
-                    for (meter_id, samples) in &samples {
-                        for sample in samples {
-                            // .to_string()... kinda wasteful, but it works;
-                            // perhaps better comm. types wanted?
-                            let message = Some((meter_id.to_string(), *sample));
-                            energy_schannel.send(message).unwrap();
-                        }
-                    }
-                    energy_schannel.send(None).unwrap(); // done communicating results
-                }
-            })
-            .map_err(|e| Error::Threading(e))
-    }
-
     /// Load specified applications onto the device.
     fn load_apps(&self, test: &Test) -> Result<()> {
         let app_set = self.applications.as_ref()
@@ -324,6 +316,164 @@ impl Testbed {
     }
 }
 
+/// Watches the device under test's GPIO outputs and reports the [`Response`]s observed.
+struct ObserverSource {
+    outputs: DeviceOutputs,
+    trace_pins: Vec<u8>,
+    responses: Vec<Response>,
+}
+
+impl ObserverSource {
+    fn new(outputs: DeviceOutputs, trace_pins: Vec<u8>) -> ObserverSource {
+        ObserverSource {
+            outputs,
+            trace_pins,
+            responses: Vec::new(),
+        }
+    }
+}
+
+impl Source for ObserverSource {
+    fn label(&self) -> &str {
+        "observer"
+    }
+
+    fn prepare<'a>(&'a mut self, test: &'a Test) -> Pin<Box<dyn Future<Output = std::result::Result<bool, String>> + 'a>> {
+        Box::pin(async move {
+            test.prep_observe(&mut self.outputs, &self.trace_pins)
+                .map_err(|e| e.to_string())?;
+
+            // The observer always watches, regardless of whether this test defines any GPIO
+            // criteria; a test adding one later shouldn't need this source to change.
+            Ok(true)
+        })
+    }
+
+    fn run<'a>(&'a mut self, test: &'a Test) -> Pin<Box<dyn Future<Output = std::result::Result<(), String>> + 'a>> {
+        Box::pin(async move {
+            let t0 = Instant::now();
+            test.observe(t0, &self.outputs, &mut self.responses)
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn collect(&mut self) -> Pin<Box<dyn Future<Output = Vec<SourceEvent>> + '_>> {
+        Box::pin(async move {
+            for pin in &mut self.outputs {
+                let _ = pin.clear_interrupt();
+            }
+
+            self.responses.drain(..).map(SourceEvent::Response).collect()
+        })
+    }
+}
+
+/// Samples energy meters defined on the testbed for as long as a test needs them.
+#[derive(Debug)]
+struct MeteringSource {
+    meters: Arc<Mutex<HashMap<String, Box<dyn EnergyMetering>>>>,
+    samples: HashMap<String, Vec<f32>>,
+}
+
+impl MeteringSource {
+    fn new(meters: Arc<Mutex<HashMap<String, Box<dyn EnergyMetering>>>>) -> MeteringSource {
+        MeteringSource { meters, samples: HashMap::new() }
+    }
+}
+
+impl Source for MeteringSource {
+    fn label(&self) -> &str {
+        "metering"
+    }
+
+    fn prepare<'a>(&'a mut self, test: &'a Test) -> Pin<Box<dyn Future<Output = std::result::Result<bool, String>> + 'a>> {
+        Box::pin(async move {
+            let meters = self.meters.lock().unwrap();
+            test.prep_meter(&meters, &mut self.samples).map_err(|e| e.to_string())
+        })
+    }
+
+    fn run<'a>(&'a mut self, test: &'a Test) -> Pin<Box<dyn Future<Output = std::result::Result<(), String>> + 'a>> {
+        Box::pin(async move {
+            let meters = self.meters.lock().unwrap();
+            test.meter(&meters, &mut self.samples);
+            Ok(())
+        })
+    }
+
+    fn collect(&mut self) -> Pin<Box<dyn Future<Output = Vec<SourceEvent>> + '_>> {
+        Box::pin(async move {
+            let mut events = Vec::new();
+            for (meter_id, samples) in self.samples.drain() {
+                for sample in samples {
+                    events.push(SourceEvent::Energy(meter_id.clone(), sample));
+                }
+            }
+
+            events
+        })
+    }
+}
+
+/// Samples analog channels defined on the testbed for as long as a test needs them.
+#[derive(Debug)]
+struct AnalogSource {
+    channels: Arc<Mutex<HashMap<String, (Box<dyn ADC>, u8)>>>,
+    samples: HashMap<String, Vec<f32>>,
+}
+
+impl AnalogSource {
+    fn new(channels: Arc<Mutex<HashMap<String, (Box<dyn ADC>, u8)>>>) -> AnalogSource {
+        AnalogSource { channels, samples: HashMap::new() }
+    }
+}
+
+impl Source for AnalogSource {
+    fn label(&self) -> &str {
+        "analog"
+    }
+
+    fn prepare<'a>(&'a mut self, test: &'a Test) -> Pin<Box<dyn Future<Output = std::result::Result<bool, String>> + 'a>> {
+        Box::pin(async move {
+            let channels = self.channels.lock().unwrap();
+            test.prep_sample(&channels, &mut self.samples).map_err(|e| e.to_string())
+        })
+    }
+
+    fn run<'a>(&'a mut self, test: &'a Test) -> Pin<Box<dyn Future<Output = std::result::Result<(), String>> + 'a>> {
+        Box::pin(async move {
+            let channels = self.channels.lock().unwrap();
+            test.sample_analog(&channels, &mut self.samples);
+            Ok(())
+        })
+    }
+
+    fn collect(&mut self) -> Pin<Box<dyn Future<Output = Vec<SourceEvent>> + '_>> {
+        Box::pin(async move {
+            let mut events = Vec::new();
+            for (channel_id, samples) in self.samples.drain() {
+                for sample in samples {
+                    events.push(SourceEvent::Analog(channel_id.clone(), sample));
+                }
+            }
+
+            events
+        })
+    }
+}
+
+/// Extracts a human-readable message from a caught panic's payload, falling back to a generic
+/// description for payloads that aren't a `&str` or `String` (the common case for `panic!`/`unwrap`).
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "non-string panic payload".to_string()
+    }
+}
+
 impl Display for Testbed {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Testbed\n{}", self.pin_mapping)?;
@@ -337,6 +487,15 @@ impl Display for Testbed {
             write!(f, " (unavailable)\n")?;
         }
 
+        write!(f, "\nAnalog channels:\n")?;
+        if let Ok(channels) = self.analog_channels.lock() {
+            for channel_id in channels.keys() {
+                write!(f, " - '{}'\n", channel_id)?;
+            }
+        } else {
+            write!(f, " (unavailable)\n")?;
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,37 @@
+/*! Wait for a deadline without burning a full core the whole way there.
+
+[`Test::execute`](super::test::Test::execute) used to busy-poll [`Instant::now`] until its next
+scheduled [`Operation`](super::test::Operation), which pins a core at 100% for the entire test --
+wasteful on its own, and a problem when [`Test::meter`](super::test::Test::meter) is sampling
+energy at the same time. [`sleep_until`] sleeps through all but the last `spin_threshold` of the
+wait (covering the jitter `std::thread::sleep` and the OS scheduler introduce near a wakeup), then
+spins only that final sliver to keep the microsecond-level edge placement tests depend on.
+ */
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Default spin threshold: the ~568µs a bare sample loop iteration costs in this codebase (see
+/// the measurement noted in [`super::test::Test::meter`]), so a hybrid wait spins for
+/// roughly as long as one of those iterations would have taken anyway.
+pub const DEFAULT_SPIN_THRESHOLD: Duration = Duration::from_micros(568);
+
+/** Block the current thread until `deadline`.
+
+If more than `spin_threshold` remains, sleeps for all of that slack but `spin_threshold`, then
+spins on [`Instant::now`] for whatever's left. Returns immediately if `deadline` has already
+passed.
+ */
+pub fn sleep_until(deadline: Instant, spin_threshold: Duration) {
+    let now = Instant::now();
+    if now >= deadline {
+        return;
+    }
+
+    let slack = deadline - now;
+    if slack > spin_threshold {
+        thread::sleep(slack - spin_threshold);
+    }
+
+    while Instant::now() < deadline {  }
+}
@@ -1,4 +1,4 @@
-//! Interpret GPIO-based execution trace information.
+//! Interpret execution trace information derived from GPIO activity or an SWO/ITM byte stream.
 
 use std::collections::HashMap;
 use std::fmt;
@@ -10,24 +10,37 @@ use crate::sw::instrument::Spec;
 
 use super::test::Response;
 
-/// Trace execution information derived from GPIO activity.
+/// Trace execution information derived from GPIO activity or an SWO/ITM byte stream.
 #[derive(Clone, Debug)]
 pub struct Trace {
     id: u16,
     extra: u16,
+    time: Instant,
     responses: Vec<Response>,
 }
 
 impl Trace {
-    /// Construct a new Trace.
+    /// Construct a new Trace derived from GPIO pin responses.
     fn new(id: u16, extra: u16, responses: Vec<Response>) -> Trace {
+        let time = responses[0].get_time();
         Trace {
             id,
             extra,
+            time,
             responses,
         }
     }
 
+    /// Construct a new Trace derived from an ITM software packet, which carries no GPIO responses.
+    fn from_itm_packet(id: u16, extra: u16, time: Instant) -> Trace {
+        Trace {
+            id,
+            extra,
+            time,
+            responses: Vec::new(),
+        }
+    }
+
     /// Returns the ID of the trace.
     #[allow(dead_code)]
     pub fn get_id(&self) -> u16 {
@@ -42,11 +55,13 @@ impl Trace {
 
     /** Returns the time the trace point was triggered.
 
-    This is equivalent to the time the first pin in the set of GPIO trace pins was set by the hardware under test.
+    For GPIO-derived traces, this is equivalent to the time the first pin in the set of GPIO trace pins was set
+    by the hardware under test. For SWO/ITM-derived traces, this is the time the packet carrying the trace word
+    was received.
      */
     #[allow(dead_code)]
     pub fn get_time(&self) -> Instant {
-        self.responses[0].get_time()
+        self.time
     }
 
     /** Returns the length of time between the triggering of this Trace and the provided Instant.
@@ -60,25 +75,109 @@ impl Trace {
             Duration::from_millis(0)
         }
     }
+
+    /// Returns a copy of this Trace with its recorded time replaced.
+    ///
+    /// Used by [`super::criteria::TraceCriterion::with_deglitch`] to collapse a group of
+    /// near-duplicate events down to one representative event at the group's median timestamp.
+    pub fn with_time(&self, time: Instant) -> Trace {
+        Trace {
+            time,
+            ..self.clone()
+        }
+    }
 }
 
 impl Display for Trace {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Trace - ID: {}, data: {}\nRaw responses:\n", self.id, self.extra)?;
-        for r in &self.responses {
-            write!(f, "  {}\n", r)?;
+        write!(f, "Trace - ID: {}, data: {}\n", self.id, self.extra)?;
+        if self.responses.is_empty() {
+            write!(f, "  (SWO/ITM packet, no raw GPIO responses)\n")?;
+        } else {
+            write!(f, "Raw responses:\n")?;
+            for r in &self.responses {
+                write!(f, "  {}\n", r)?;
+            }
         }
 
         Ok(())
     }
 }
 
+/// One chunk of raw UART data read while tracing a test's serial output, tagged with the time it
+/// was read.
+#[derive(Clone, Debug)]
+pub struct SerialTrace {
+    data: Vec<u8>,
+    time: Instant,
+}
+
+impl SerialTrace {
+    /// Construct a new SerialTrace from one read of raw UART data.
+    pub fn new(data: Vec<u8>, time: Instant) -> SerialTrace {
+        SerialTrace { data, time }
+    }
+
+    /// Returns the raw bytes read.
+    pub fn get_data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the time this chunk was read.
+    pub fn get_time(&self) -> Instant {
+        self.time
+    }
+
+    /** Returns the length of time between `t0` and this chunk being read.
+
+    If `t0` occurs after the chunk was read, this function returns a 0-length Duration.
+     */
+    pub fn get_offset(&self, t0: Instant) -> Duration {
+        if t0 < self.get_time() {
+            self.get_time() - t0
+        } else {
+            Duration::from_millis(0)
+        }
+    }
+}
+
+impl Display for SerialTrace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} byte(s): {}", self.data.len(), String::from_utf8_lossy(&self.data))
+    }
+}
+
 /// Derive [`Trace`]s from the provided GPIO activity.
 pub fn reconstruct<'a, T>(responses: T,
                           test_spec: &Spec,
                           pin_sig: &HashMap<u8, u16>) -> Vec<Trace>
 where
     T: IntoIterator<Item = &'a Response>
+{
+    reconstruct_impl(responses, test_spec, pin_sig, None)
+}
+
+/** Derive [`Trace`]s from the provided GPIO activity, pausing at breakpoints set on `debugger`.
+
+Identical to [`reconstruct`], except that after each [`Trace`] is built, `debugger` is given the
+chance to stop and hand control to its interactive command loop (see [`super::debugger::Debugger`]).
+ */
+pub fn reconstruct_with_debugger<'a, T>(responses: T,
+                                         test_spec: &Spec,
+                                         pin_sig: &HashMap<u8, u16>,
+                                         debugger: &mut super::debugger::Debugger) -> Vec<Trace>
+where
+    T: IntoIterator<Item = &'a Response>
+{
+    reconstruct_impl(responses, test_spec, pin_sig, Some(debugger))
+}
+
+fn reconstruct_impl<'a, T>(responses: T,
+                           test_spec: &Spec,
+                           pin_sig: &HashMap<u8, u16>,
+                           mut debugger: Option<&mut super::debugger::Debugger>) -> Vec<Trace>
+where
+    T: IntoIterator<Item = &'a Response>
 {
     let last_trace_pin = *pin_sig.iter()
         .reduce(|(pin_no_a, sig_a), (pin_no_b, sig_b)| {
@@ -97,6 +196,9 @@ where
         let mut trace_responses: Vec<Response> = Vec::new();
         while let Some(response) = response_iter.next() {
             trace_responses.push(*response);
+            if let Some(ref mut debugger) = debugger {
+                debugger.observe_gpio(response.get_pin(), response.get_output());
+            }
             if response.get_pin() == last_trace_pin {
                 break;
             }
@@ -118,12 +220,88 @@ where
             (trace_val & extra_mask(test_spec.id_bit_length())) >> test_spec.id_bit_length(),
             trace_responses);
 
+        if let Some(ref mut debugger) = debugger {
+            debugger.check(&trace, test_spec);
+        }
+
         traces.push(trace);
     }
 
     traces
 }
 
+/** Derive [`Trace`]s from an ARM Cortex-M SWO byte stream carrying ITM software packets.
+
+This is an alternative to [`reconstruct`] for devices under test that emit trace data over a single SWO line
+(via a debug probe) rather than toggling a bank of GPIO trace pins. Where the GPIO scheme packs `id`/`extra`
+across as many physical pins as are wired up, `trace_port` identifies a single ITM stimulus port carrying the
+full trace word, split into `id`/`extra` the same way as [`reconstruct`] (using `test_spec.id_bit_length()`).
+
+`bytes` is the SWO byte stream, each byte paired with the `Instant` it was received. Each ITM software packet
+begins with a header byte: bits `[1:0]` give the payload size (`1` → 1 byte, `2` → 2 bytes, `3` → 4 bytes) and
+bits `[7:3]` give the stimulus port number. Packets not addressed to `trace_port` are decoded (to stay in sync
+with the stream) and discarded. Headers whose size bits are `0` — ITM overflow, (local/global) timestamp, and
+sync packets — can't be sized this way; those bytes are skipped one at a time until a recognizable header is
+found again.
+ */
+pub fn reconstruct_swo<'a, T>(bytes: T, test_spec: &Spec, trace_port: u8) -> Vec<Trace>
+where
+    T: IntoIterator<Item = &'a (Instant, u8)>
+{
+    let mut traces = Vec::new();
+    let mut stream = bytes.into_iter();
+
+    while let Some(&(time, header)) = stream.next() {
+        let (port, size) = match itm_packet_size(header) {
+            Some(decoded) => decoded,
+            // Overflow/timestamp/sync packet (or noise); resync on the next byte.
+            None => continue,
+        };
+
+        let mut payload: u32 = 0;
+        let mut bytes_read = 0;
+        for shift in 0..size {
+            match stream.next() {
+                Some(&(_, byte)) => {
+                    payload |= (byte as u32) << (shift * 8);
+                    bytes_read += 1;
+                },
+                // Stream ended mid-packet; nothing more to decode.
+                None => break,
+            }
+        }
+        if bytes_read < size {
+            break;
+        }
+
+        if port != trace_port {
+            continue;
+        }
+
+        let trace_val = payload as u16;
+        traces.push(Trace::from_itm_packet(
+            trace_val & id_mask(test_spec.id_bit_length()),
+            (trace_val & extra_mask(test_spec.id_bit_length())) >> test_spec.id_bit_length(),
+            time));
+    }
+
+    traces
+}
+
+/// Returns the stimulus port and payload size (in bytes) encoded by an ITM packet header, or `None` if the
+/// header does not describe a software packet (e.g. overflow, timestamp, or sync).
+fn itm_packet_size(header: u8) -> Option<(u8, u32)> {
+    let size = match header & 0b0000_0011 {
+        0b01 => 1,
+        0b10 => 2,
+        0b11 => 4,
+        _ => return None,
+    };
+    let port = header >> 3;
+
+    Some((port, size))
+}
+
 /// Returns the mask of a given length for the ID bits.
 fn id_mask(len: u8) -> u16 {
     let mut mask = 0;
@@ -138,3 +316,58 @@ fn id_mask(len: u8) -> u16 {
 fn extra_mask(id_len: u8) -> u16 {
     u16::MAX ^ id_mask(id_len)
 }
+
+/// Bit-level parsing input: a byte slice paired with how many of its first byte's bits are
+/// already consumed, as `nom`'s bit-level combinators expect.
+pub type BitsInput<'a> = (&'a [u8], usize);
+
+/// Bit-level parsing result over [`BitsInput`].
+pub type BitsResult<'a, O> = nom::IResult<BitsInput<'a>, O, nom::error::Error<BitsInput<'a>>>;
+
+/** Decode a captured parallel-trace bitstream into the sequence of trace point names it encodes.
+
+This is a simpler alternative to [`reconstruct`] for a capture that's already been reduced to one
+bit per trace pin per sample (MSB-first) rather than a raw stream of pin-edge [`Response`]s:
+`input` is read `spec.id_bit_length()` bits at a time from the big-endian bit cursor, each field
+mapped through [`Spec::trace_point_name`](crate::sw::instrument::Spec::trace_point_name) and
+paired with its index among the fields decoded so far. A field that reads as `0` means "no event
+this sample" and is skipped; a field whose value has no matching trace point is a malformed
+capture and reported as a parse `Err`, not a panic, since decoding untrusted captured data
+shouldn't ever crash the caller. Running out of bits partway through a field ends decoding
+normally (the trailing partial field is presumed to be capture padding, not a malformed record).
+
+No caller in this tree constructs a `ParallelTraceCriterion` yet -- `Criterion` has no such variant
+-- so this decoder isn't reachable from `Criterion` evaluation today. It's implemented against the
+exact signature requested so that adding `ParallelTraceCriterion` later is a matter of wiring
+`Criterion::evaluate`, not revisiting this function.
+ */
+pub fn decode_parallel_trace<'a>(input: BitsInput<'a>, spec: &Spec) -> BitsResult<'a, Vec<(usize, String)>> {
+    use nom::error::ParseError;
+
+    let field_width = spec.id_bit_length() as usize;
+    let mut decoded = Vec::new();
+    let mut rest = input;
+    let mut field_index = 0usize;
+
+    loop {
+        let (next_rest, value): (BitsInput<'a>, u16) = match nom::bits::complete::take(field_width)(rest) {
+            Ok(pair) => pair,
+            // Not enough bits left for another full field: treat as the end of the capture.
+            Err(nom::Err::Error(_)) | Err(nom::Err::Incomplete(_)) => break,
+            Err(e @ nom::Err::Failure(_)) => return Err(e),
+        };
+        rest = next_rest;
+
+        if value != 0 {
+            match spec.trace_point_name(value) {
+                Some(name) => decoded.push((field_index, name.clone())),
+                None => return Err(nom::Err::Failure(
+                    nom::error::Error::from_error_kind(rest, nom::error::ErrorKind::MapOpt))),
+            }
+        }
+
+        field_index += 1;
+    }
+
+    Ok((rest, decoded))
+}
@@ -0,0 +1,114 @@
+/*! A hashed timer wheel scheduler, after neqo-common's `timer` module.
+
+Scheduling `N` entries in a `BinaryHeap` (as [`super::test::Test`] does with its
+[`super::test::Operation`] timeline) costs `O(log N)` per insertion and `O(N)` to materialize --
+fine for a timeline someone enumerated by hand, expensive for a waveform expressed as "toggle this
+pin every 2ms for an hour". A hashed timer wheel trades that for `O(1)`: time is divided into fixed
+buckets of width `granularity`, an entry due at a `delay` away lands in bucket
+`(cursor + delay / granularity) % bucket_count`, and however many times the wheel has to wrap
+around before it actually reaches that bucket is recorded as the entry's remaining "laps" rather
+than the entry being placed more precisely than the bucket array allows. Advancing the wheel one
+tick inspects only the bucket the cursor is currently on: anything with zero laps left fires,
+anything else has its lap count decremented and waits for the cursor's next time around.
+ */
+
+use std::time::Duration;
+
+/// One scheduled entry sitting in a [`TimerWheel`] bucket, waiting for its remaining laps to
+/// reach zero.
+struct Scheduled<T> {
+    laps_remaining: u32,
+    item: T,
+}
+
+/** A hashed timer wheel over `bucket_count` buckets, each spanning `granularity` of time.
+
+A caller drives this externally: [`TimerWheel::schedule`] to add an entry some `delay` from now,
+[`TimerWheel::tick`] once per `granularity` of elapsed time to collect whatever just came due. The
+wheel has no notion of wall-clock time itself -- it only knows how many ticks have passed -- so
+the caller is responsible for calling `tick` at roughly the right cadence (see
+[`super::test::Test::execute`], which pairs this with [`super::timing::sleep_until`]).
+ */
+pub struct TimerWheel<T> {
+    buckets: Vec<Vec<Scheduled<T>>>,
+    granularity: Duration,
+    cursor: usize,
+}
+
+impl<T> TimerWheel<T> {
+    /// Creates an empty wheel of `bucket_count` buckets, each spanning `granularity`.
+    ///
+    /// # Panics
+    /// Panics if `bucket_count` is zero.
+    pub fn new(granularity: Duration, bucket_count: usize) -> TimerWheel<T> {
+        assert!(bucket_count > 0, "a timer wheel needs at least one bucket");
+
+        TimerWheel {
+            buckets: (0..bucket_count).map(|_| Vec::new()).collect(),
+            granularity,
+            cursor: 0,
+        }
+    }
+
+    /// Schedules `item` to fire `delay` from the wheel's current position (i.e. after that many
+    /// more calls to [`TimerWheel::tick`]), rounding down to the nearest whole tick.
+    pub fn schedule(&mut self, delay: Duration, item: T) {
+        let granularity_nanos = self.granularity.as_nanos().max(1);
+        let ticks = (delay.as_nanos() / granularity_nanos) as u64;
+
+        // `tick` reads the cursor's bucket *before* advancing it, so the bucket reached by the
+        // `n`th call to `tick` is the one `n - 1` advances away from the cursor's current
+        // position. An entry due after `ticks` calls (0 and 1 both mean "the very next call",
+        // since there's no call numbered zero) therefore belongs `ticks.max(1) - 1` advances out.
+        let bucket_count = self.buckets.len() as u64;
+        let offset = ticks.saturating_sub(1) % bucket_count;
+        let bucket_index = ((self.cursor as u64 + offset) % bucket_count) as usize;
+        let laps_remaining = (ticks.saturating_sub(1) / bucket_count) as u32;
+
+        self.buckets[bucket_index].push(Scheduled { laps_remaining, item });
+    }
+
+    /// Advances the wheel by one tick (one `granularity`), returning every item now due.
+    ///
+    /// Items with laps left are carried over in the same bucket with their lap count
+    /// decremented; nothing is touched in any other bucket, so this costs only as much as the
+    /// current bucket's occupancy, not the wheel's total entry count.
+    pub fn tick(&mut self) -> Vec<T> {
+        let due = std::mem::take(&mut self.buckets[self.cursor]);
+
+        let mut fired = Vec::new();
+        for scheduled in due {
+            if scheduled.laps_remaining == 0 {
+                fired.push(scheduled.item);
+            } else {
+                self.buckets[self.cursor].push(Scheduled {
+                    laps_remaining: scheduled.laps_remaining - 1,
+                    item: scheduled.item,
+                });
+            }
+        }
+
+        self.cursor = (self.cursor + 1) % self.buckets.len();
+
+        fired
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn item_fires_on_exactly_the_nth_tick() {
+        for ticks in 1..=5u32 {
+            let mut wheel: TimerWheel<u32> = TimerWheel::new(Duration::from_millis(1), 4);
+            wheel.schedule(Duration::from_millis(ticks as u64), ticks);
+
+            for call in 1..ticks {
+                assert!(wheel.tick().is_empty(), "item for ticks={} fired early, on call {}", ticks, call);
+            }
+
+            assert_eq!(wheel.tick(), vec![ticks], "item for ticks={} did not fire on call {}", ticks, ticks);
+        }
+    }
+}
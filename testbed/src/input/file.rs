@@ -0,0 +1,184 @@
+//! Load tests from a directory of external JSON test vectors.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use flate2::read::GzDecoder;
+use serde_json::Value as JSONValue;
+
+use clockwise_common::comm::Signal;
+use clockwise_common::criteria::{
+    Criterion,
+    EnergyCriterion,
+    EnergyStat,
+    GPIOCriterion,
+    SerialTraceCondition,
+    SerialTraceCriterion,
+};
+use clockwise_common::input::TestProvider;
+use clockwise_common::test::{Operation, Test};
+
+use super::Result;
+use super::error::Error;
+
+/** Test adapter that loads tests from a directory of JSON test vectors.
+
+Scans `directory` for `*.json` and `*.json.gz` files at construction time. Each file describes a
+single [`Test`]: an `id`, an array of `operations` (`{time, pin, level}`, where `level` is `"high"`
+or `"low"`, or `{time, idle}` for an idle period), and an array of `criteria` (`gpio`/`energy`/
+`serial_trace`). Gzip-compressed entries are decompressed transparently before parsing. This lets a
+growing corpus of regression tests live on disk and be picked up without recompiling, the way
+generated test-vector suites are typically distributed.
+ */
+#[derive(Debug)]
+pub struct FileTestProvider {
+    tests: Vec<Test>,
+}
+
+impl FileTestProvider {
+    /// Load every `*.json`/`*.json.gz` test vector found directly inside `directory`.
+    pub fn new(directory: &Path) -> Result<FileTestProvider> {
+        let mut tests = Vec::new();
+
+        for entry in std::fs::read_dir(directory)? {
+            let path = entry?.path();
+            if !Self::is_test_vector(&path) {
+                continue;
+            }
+
+            tests.push(Self::load_one(&path)?);
+        }
+
+        Ok(FileTestProvider { tests })
+    }
+
+    /// Returns true if `path` names a `*.json` or `*.json.gz` file.
+    fn is_test_vector(path: &Path) -> bool {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => true,
+            Some("gz") => path.file_stem()
+                .map(Path::new)
+                .and_then(|stem| stem.extension())
+                .map_or(false, |ext| ext == "json"),
+            _ => false,
+        }
+    }
+
+    fn load_one(path: &Path) -> Result<Test> {
+        let text = Self::read_text(path)?;
+        let json: JSONValue = serde_json::from_str(&text)
+            .map_err(|e| Error::Format(format!("{}: {}", path.display(), e)))?;
+
+        let id = json["id"].as_str()
+            .ok_or_else(|| Error::Format(format!("{}: missing 'id' string", path.display())))?;
+
+        let ops = json["operations"].as_array()
+            .ok_or_else(|| Error::Format(format!("{}: 'operations' must be an array", path.display())))?
+            .iter()
+            .map(Self::parse_operation)
+            .collect::<Result<Vec<Operation>>>()?;
+
+        let criteria = json["criteria"].as_array()
+            .ok_or_else(|| Error::Format(format!("{}: 'criteria' must be an array", path.display())))?
+            .iter()
+            .map(Self::parse_criterion)
+            .collect::<Result<Vec<Criterion>>>()?;
+
+        Ok(Test::new(id, &[], &[], &ops, &criteria, true))
+    }
+
+    /// Read `path`'s contents as text, transparently decompressing `.gz` entries.
+    fn read_text(path: &Path) -> Result<String> {
+        let mut file = File::open(path)?;
+        let mut text = String::new();
+
+        if path.extension().map_or(false, |ext| ext == "gz") {
+            GzDecoder::new(file).read_to_string(&mut text)?;
+        } else {
+            file.read_to_string(&mut text)?;
+        }
+
+        Ok(text)
+    }
+
+    fn parse_operation(json: &JSONValue) -> Result<Operation> {
+        let time = json["time"].as_u64()
+            .ok_or_else(|| Error::Format("operation missing 'time'".to_string()))?;
+
+        if let Some(idle_ms) = json["idle"].as_u64() {
+            return Ok(Operation::at(time).idle_sync(Duration::from_millis(idle_ms)));
+        }
+
+        let pin = json["pin"].as_u64()
+            .ok_or_else(|| Error::Format("operation missing 'pin'".to_string()))? as u8;
+        let level = match json["level"].as_str() {
+            Some("high") => Signal::Digital(true),
+            Some("low") => Signal::Digital(false),
+            _ => return Err(Error::Format("operation 'level' must be 'high' or 'low'".to_string())),
+        };
+
+        Ok(Operation::at(time).input(level, pin))
+    }
+
+    fn parse_criterion(json: &JSONValue) -> Result<Criterion> {
+        match json["type"].as_str() {
+            Some("gpio") => {
+                let pin = json["pin"].as_u64()
+                    .ok_or_else(|| Error::Format("gpio criterion missing 'pin'".to_string()))? as u8;
+                Ok(Criterion::GPIO(GPIOCriterion::Any(pin)))
+            },
+            Some("energy") => {
+                let meter = json["meter"].as_str()
+                    .ok_or_else(|| Error::Format("energy criterion missing 'meter'".to_string()))?;
+                let stat = match json["stat"].as_str() {
+                    Some("total") => EnergyStat::Total,
+                    Some("average") => EnergyStat::Average,
+                    Some("max") => EnergyStat::Max,
+                    Some("min") => EnergyStat::Min,
+                    _ => return Err(Error::Format(
+                        "energy criterion 'stat' must be one of total/average/max/min".to_string())),
+                };
+
+                let mut criterion = EnergyCriterion::new(meter, stat);
+                if let Some(min) = json["min"].as_f64() {
+                    criterion = criterion.with_min(min as f32);
+                }
+                if let Some(max) = json["max"].as_f64() {
+                    criterion = criterion.with_max(max as f32);
+                }
+
+                Ok(Criterion::Energy(criterion))
+            },
+            Some("serial_trace") => {
+                let conditions = json["conditions"].as_array()
+                    .ok_or_else(|| Error::Format("serial_trace criterion missing 'conditions'".to_string()))?
+                    .iter()
+                    .map(Self::parse_serial_trace_condition)
+                    .collect::<Result<Vec<SerialTraceCondition>>>()?;
+
+                Ok(Criterion::SerialTrace(SerialTraceCriterion::new(&conditions)))
+            },
+            _ => Err(Error::Format("criterion 'type' must be one of gpio/energy/serial_trace".to_string())),
+        }
+    }
+
+    fn parse_serial_trace_condition(json: &JSONValue) -> Result<SerialTraceCondition> {
+        let data = json["data"].as_array()
+            .ok_or_else(|| Error::Format("serial_trace condition missing 'data'".to_string()))?
+            .iter()
+            .map(|byte| byte.as_u64()
+                 .map(|b| b as u8)
+                 .ok_or_else(|| Error::Format("serial_trace condition 'data' must be an array of bytes".to_string())))
+            .collect::<Result<Vec<u8>>>()?;
+
+        Ok(SerialTraceCondition::new(&data))
+    }
+}
+
+impl TestProvider for FileTestProvider {
+    fn tests(&self) -> Box<dyn Iterator<Item = Test> + '_> {
+        Box::new(self.tests.iter().cloned())
+    }
+}
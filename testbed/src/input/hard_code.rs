@@ -13,12 +13,14 @@ use clockwise_common::criteria::{
     GPIOCriterion,
     EnergyCriterion,
     EnergyStat,
+    PerformanceTraceCriterion,
+    PerformanceWaypoint,
     Timing,
     SerialTraceCondition,
     SerialTraceCriterion,
 };
 use clockwise_common::facility::EnergyMetering;
-use clockwise_common::hw::INA219;
+use clockwise_common::hw::{Chip, INA219};
 use clockwise_common::input::{TestProvider, TestbedProvider};
 use clockwise_common::io;
 use clockwise_common::io::{
@@ -90,7 +92,7 @@ impl TestbedProvider for HardCodedTestbed {
 
         // Energy metering
         let ina219: Box<dyn EnergyMetering> = Box::new(
-            INA219::new(mapping.get_i2c().unwrap(), 0x40).unwrap());
+            INA219::new(mapping.get_i2c().unwrap(), 0x40, Chip::Ina219).unwrap());
         let energy_meters: HashMap<String, Box<dyn EnergyMetering>> = (vec![
             ("system".to_string(), ina219)
         ]).into_iter()
@@ -125,7 +127,7 @@ impl TestbedProvider for HardCodedTestbed {
             mapping,
             Box::new(platform),
             energy_meters,
-            None,
+            Some(UART::PL011),
             None,
             tracing);
 
@@ -141,6 +143,14 @@ pub struct HardCodedTests {
 
 impl HardCodedTests {
     pub fn new() -> HardCodedTests {
+        // Matches the waypoint labels the benchmark tracing capsule reports over UART.
+        let benchmark_waypoints = vec![
+            PerformanceWaypoint::new("sam4l-adc"),
+            PerformanceWaypoint::new("adc-capsule"),
+            PerformanceWaypoint::new("application"),
+            PerformanceWaypoint::new("dac-capsule"),
+        ];
+
         HardCodedTests {
             tests: vec![
                 Test::new(
@@ -149,7 +159,8 @@ impl HardCodedTests {
                     (&[]).into_iter().copied(),
                     &[Operation::at(0).idle_sync(Duration::from_millis(18_000_000))],
                     &[Criterion::Energy(EnergyCriterion::new("system", EnergyStat::Average)),
-                      Criterion::Energy(EnergyCriterion::new("system", EnergyStat::Total))],
+                      Criterion::Energy(EnergyCriterion::new("system", EnergyStat::Total)),
+                      Criterion::PerformanceTrace(PerformanceTraceCriterion::new(&benchmark_waypoints))],
                     true),
             ],
         }
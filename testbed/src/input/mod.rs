@@ -5,6 +5,7 @@ use std::fmt::Debug;
 use crate::testing::testbed::Testbed;
 
 pub mod error;
+pub mod file;
 pub mod hard_code;
 pub mod shared_lib;
 
@@ -1,10 +1,81 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::future::Future;
 use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::mpsc;
+use std::task::{Context, Poll};
+use std::thread;
 
-use clockwise_common::input::{self, TestProvider, TestbedProvider};
+use clockwise_common::input::{self, AsyncTestProvider, AsyncTestbedProvider, TestProvider, TestbedProvider};
 use clockwise_common::test::Test;
 use clockwise_common::testbed::Testbed;
 use libloading::{Library, Symbol};
 
+/** A future backed by a thread doing the actual (blocking) work, standing in for a real blocking
+thread pool: each call spawns one dedicated thread rather than drawing from a shared pool, since
+this process only ever has a handful of providers in flight at once. Polling it is cheap (a
+non-blocking channel receive), so it composes with the rest of the codebase's hand-rolled futures
+(see `src/testing/executor.rs`'s `block_on`/`block_on_all`) without needing a real reactor.
+ */
+struct BlockingTask<T> {
+    receiver: mpsc::Receiver<T>,
+}
+
+impl<T> Future for BlockingTask<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<T> {
+        match self.receiver.try_recv() {
+            Ok(value) => Poll::Ready(value),
+            Err(mpsc::TryRecvError::Empty) => Poll::Pending,
+            Err(mpsc::TryRecvError::Disconnected) =>
+                panic!("blocking-pool thread dropped without sending a result"),
+        }
+    }
+}
+
+/// Runs `work` on a dedicated thread and returns a future that resolves with its result.
+fn spawn_blocking<T, F>(work: F) -> BlockingTask<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+    thread::Builder::new()
+        .name("library-provider-load".to_string())
+        .spawn(move || { let _ = sender.send(work()); })
+        .expect("failed to spawn blocking-pool thread");
+
+    BlockingTask { receiver }
+}
+
+/** Checks `library`'s `clockwise_abi_version` symbol against the host's [`input::ABI_VERSION`]
+before anything else is resolved from it.
+
+A plugin built against a different `clockwise_common` may lay out `Testbed`/`Test`/the provider
+traits differently; loading `get_testbed`/`get_test_adapter` from one and transmuting the result to
+this host's idea of those types is undefined behavior. Requiring every plugin to export this symbol
+turns that into a descriptive error instead.
+ */
+fn check_abi_version(library: &Library) -> Result<(), String> {
+    let host_version = input::ABI_VERSION;
+    let plugin_version = unsafe {
+        let version_sym: Symbol<unsafe extern fn() -> u32> = library.get(b"clockwise_abi_version")
+            .map_err(|e| format!("shared library does not export 'clockwise_abi_version':\n{}", e))?;
+
+        version_sym()
+    };
+
+    if plugin_version != host_version {
+        Err(format!(
+            "shared library ABI version mismatch: host is v{}, library is v{}",
+            host_version, plugin_version))
+    } else {
+        Ok(())
+    }
+}
+
 /** Shared library testbed provider.
 
 Produces a [`TestbedProvider`] from a shared library.
@@ -19,19 +90,20 @@ pub struct LibraryTestbedProvider {
 }
 
 impl LibraryTestbedProvider {
-    /// Create a new `LibraryTestbedProvider`.
-    ///
-    /// This call loads the library given at `lib_path`.
-    pub fn new(lib_path: &Path) -> LibraryTestbedProvider {
+    /// Create a new `LibraryTestbedProvider`, loading the library at `lib_path` and checking its
+    /// ABI version before returning. Returns a descriptive error rather than panicking if the
+    /// library can't be loaded or its ABI version doesn't match the host's.
+    pub fn new(lib_path: &Path) -> Result<LibraryTestbedProvider, String> {
         let library = unsafe {
             Library::new(lib_path)
-                .expect("Failed to load library testbed provider's shared library.")
+                .map_err(|e| format!("Failed to load library testbed provider's shared library:\n{}", e))?
         };
+        check_abi_version(&library)?;
 
-        LibraryTestbedProvider {
+        Ok(LibraryTestbedProvider {
             library_path: lib_path.to_owned(),
             library,
-        }
+        })
     }
 }
 
@@ -61,25 +133,29 @@ pub struct LibraryTestProvider {
 }
 
 impl LibraryTestProvider {
-    pub fn new(path: &Path) -> LibraryTestProvider {
+    /// Create a new `LibraryTestProvider`, loading the library at `path` and checking its ABI
+    /// version before returning. Returns a descriptive error rather than panicking if the library
+    /// can't be loaded, its ABI version doesn't match the host's, or it has no `get_test_adapter`.
+    pub fn new(path: &Path) -> Result<LibraryTestProvider, String> {
         let library = unsafe {
             Library::new(path)
-                .expect("Failed to load library test provider's shared library.")
+                .map_err(|e| format!("Failed to load library test provider's shared library:\n{}", e))?
         };
+        check_abi_version(&library)?;
 
         let test_adapter = unsafe {
             let sym: Symbol<unsafe extern fn() -> Box<dyn TestProvider>> =
                 library.get(b"get_test_adapter")
-                .expect("Failed to load function symbol from test provider's shared library.");
+                .map_err(|e| format!("Failed to load function symbol from test provider's shared library:\n{}", e))?;
 
             sym()
         };
 
-        LibraryTestProvider {
+        Ok(LibraryTestProvider {
             library_path: path.to_owned(),
             test_adapter,
             library,
-        }
+        })
     }
 }
 
@@ -88,3 +164,154 @@ impl TestProvider for LibraryTestProvider {
         self.test_adapter.tests()
     }
 }
+
+/** Async adapter over [`LibraryTestbedProvider`].
+
+`begin_create` offloads the library load, symbol resolution, and `get_testbed` call -- all of
+which `LibraryTestbedProvider::new`/`create` otherwise do synchronously on the calling thread -- to
+a dedicated thread, so a caller provisioning several testbeds can kick all of them off before
+waiting on any one's result. `ready` is where that result is actually collected.
+ */
+pub struct AsyncLibraryTestbedProvider {
+    library_path: PathBuf,
+    task: RefCell<Option<BlockingTask<Result<Testbed, String>>>>,
+}
+
+impl AsyncLibraryTestbedProvider {
+    /// Create a provider that will load the library at `lib_path` once `begin_create` is called.
+    pub fn new(lib_path: &Path) -> AsyncLibraryTestbedProvider {
+        AsyncLibraryTestbedProvider {
+            library_path: lib_path.to_owned(),
+            task: RefCell::new(None),
+        }
+    }
+}
+
+impl fmt::Debug for AsyncLibraryTestbedProvider {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AsyncLibraryTestbedProvider")
+            .field("library_path", &self.library_path)
+            .finish()
+    }
+}
+
+impl AsyncTestbedProvider for AsyncLibraryTestbedProvider {
+    fn begin_create(&self) {
+        let library_path = self.library_path.clone();
+        *self.task.borrow_mut() = Some(spawn_blocking(move || {
+            let library = unsafe {
+                Library::new(&library_path)
+                    .map_err(|e| format!("Failed to load library testbed provider's shared library:\n{}", e))?
+            };
+            check_abi_version(&library)?;
+
+            unsafe {
+                library.get(b"get_testbed")
+                    .map_err(|e| format!("Failed to load function symbol from testbed provider's shared library:\n{}", e))
+                    .and_then(|get_testbed_sym: Symbol<unsafe extern fn() -> Result<Testbed, String>>| get_testbed_sym())
+            }
+        }));
+    }
+
+    fn ready(&self) -> Pin<Box<dyn Future<Output = Result<Testbed, String>> + '_>> {
+        Box::pin(async move {
+            let task = self.task.borrow_mut().take()
+                .expect("ready() called before begin_create()");
+            task.await
+        })
+    }
+}
+
+/** A [`TestProvider`] bundled with the [`Library`] it was loaded from, so the library outlives
+whatever symbols the adapter holds into it -- the same lifetime pairing
+[`LibraryTestProvider`] keeps via its own `test_adapter`/`library` fields, just returned as a value
+instead of held behind a provider struct.
+ */
+struct LoadedTestProvider {
+    library_path: PathBuf,
+    // Never read; kept only so the library isn't unloaded out from under `test_adapter`.
+    #[allow(dead_code)]
+    library: Library,
+    test_adapter: Box<dyn TestProvider>,
+}
+
+impl fmt::Debug for LoadedTestProvider {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LoadedTestProvider")
+            .field("library_path", &self.library_path)
+            .finish()
+    }
+}
+
+impl TestProvider for LoadedTestProvider {
+    fn tests(&self) -> Box<dyn Iterator<Item = Test> + '_> {
+        self.test_adapter.tests()
+    }
+}
+
+/** Async adapter over [`LibraryTestProvider`].
+
+`begin_create` does the same eager library load and `get_test_adapter` symbol resolution that
+[`LibraryTestProvider::new`] otherwise does on the calling thread, but on a dedicated thread
+instead. `ready` hands back the loaded [`LoadedTestProvider`] (as a `Box<dyn TestProvider>`), so
+the library stays alive for exactly as long as the returned adapter does, regardless of what
+becomes of `self` afterward.
+ */
+pub struct AsyncLibraryTestProvider {
+    library_path: PathBuf,
+    task: RefCell<Option<BlockingTask<Result<LoadedTestProvider, String>>>>,
+}
+
+impl AsyncLibraryTestProvider {
+    /// Create a provider that will load the library at `path` once `begin_create` is called.
+    pub fn new(path: &Path) -> AsyncLibraryTestProvider {
+        AsyncLibraryTestProvider {
+            library_path: path.to_owned(),
+            task: RefCell::new(None),
+        }
+    }
+
+    fn load(library_path: &Path) -> Result<LoadedTestProvider, String> {
+        let library = unsafe {
+            Library::new(library_path)
+                .map_err(|e| format!("Failed to load library test provider's shared library:\n{}", e))?
+        };
+        check_abi_version(&library)?;
+
+        let test_adapter = unsafe {
+            let sym: Symbol<unsafe extern fn() -> Box<dyn TestProvider>> = library.get(b"get_test_adapter")
+                .map_err(|e| format!("Failed to load function symbol from test provider's shared library:\n{}", e))?;
+
+            sym()
+        };
+
+        Ok(LoadedTestProvider {
+            library_path: library_path.to_owned(),
+            library,
+            test_adapter,
+        })
+    }
+}
+
+impl fmt::Debug for AsyncLibraryTestProvider {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AsyncLibraryTestProvider")
+            .field("library_path", &self.library_path)
+            .finish()
+    }
+}
+
+impl AsyncTestProvider for AsyncLibraryTestProvider {
+    fn begin_create(&self) {
+        let library_path = self.library_path.clone();
+        *self.task.borrow_mut() = Some(spawn_blocking(move || Self::load(&library_path)));
+    }
+
+    fn ready(&self) -> Pin<Box<dyn Future<Output = Result<Box<dyn TestProvider>, String>> + '_>> {
+        Box::pin(async move {
+            let task = self.task.borrow_mut().take()
+                .expect("ready() called before begin_create()");
+            task.await.map(|loaded| Box::new(loaded) as Box<dyn TestProvider>)
+        })
+    }
+}
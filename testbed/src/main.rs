@@ -1,11 +1,15 @@
 //! IoT testing tool
 
 use std::process;
+use std::time::Instant;
 
 use clockwise_common::evaluation::{Evaluator, StandardEvaluator};
 
 mod input;
 mod opts;
+mod output;
+mod progress;
+mod runner;
 
 fn main() {
     let result = opts::parse();
@@ -27,19 +31,16 @@ fn main() {
     let testbed = result.unwrap();
     print!("{}\n", testbed);
 
-    let mut tests = configuration.get_test_adapter().tests();
-    let observations = testbed.execute(&mut tests);
-
     // Use the evaluator to produce results from collected data.
     // Here we only use the StandardEvaluator for now.
     // Later it may be advantageous to allow another kind of evaluator,
     // say, for instance, if a provider wanted to evaluate its own data.
     let evaluator = StandardEvaluator::new();
-    let evaluation_iter = observations.iter()
-        .map(|obs| evaluator.evaluate(obs));
-
-    println!("Results Summary:");
-    for evaluation in evaluation_iter {
-        println!("{}", evaluation);
-    }
+    let progress = progress::default_sink(Instant::now());
+    runner::run(
+        &testbed,
+        configuration.get_test_adapter(),
+        &evaluator,
+        configuration.get_run_options(),
+        progress.as_ref());
 }
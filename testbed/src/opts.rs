@@ -15,6 +15,7 @@ use crate::input::hard_code::{
     HardCodedTests,
 };
 use crate::input::shared_lib::LibraryTestProvider;
+use crate::runner::RunOptions;
 
 type Result<T> = std::result::Result<T, Error>;
 
@@ -61,15 +62,18 @@ impl From<getopts::Fail> for Error {
 pub struct Configuration {
     testbed_reader: Box<dyn TestbedConfigReader>,
     test_adapter: Box<dyn TestProvider>,
+    run_options: RunOptions,
 }
 
 impl Configuration {
     fn new(testbed_reader: Box<dyn TestbedConfigReader>,
-           test_adapter: Box<dyn TestProvider>) -> Configuration
+           test_adapter: Box<dyn TestProvider>,
+           run_options: RunOptions) -> Configuration
     {
         Configuration {
             testbed_reader,
             test_adapter,
+            run_options,
         }
     }
 
@@ -80,12 +84,20 @@ impl Configuration {
     pub fn get_test_adapter(&self) -> &dyn TestProvider {
         self.test_adapter.as_ref()
     }
+
+    pub fn get_run_options(&self) -> &RunOptions {
+        &self.run_options
+    }
 }
 
 fn create_options() -> Options {
     let mut opts = Options::new();
     opts.optopt("b", "testbed-format", "select a testbed input format", "FORMAT");
     opts.optopt("t", "test-format", "select a test input format", "FORMAT");
+    opts.optopt("f", "filter", "only run tests whose id contains SUBSTR", "SUBSTR");
+    opts.optopt("o", "only", "only run the test named ID", "ID");
+    opts.optflag("q", "quiet", "print just a pass/fail summary");
+    opts.optflag("d", "debug", "dump the full observation for failing/erroring tests");
     opts.optflag("h", "help", "show help");
 
     opts
@@ -141,7 +153,8 @@ pub fn parse<'a>() -> Result<Configuration> {
                 "lib" => {
                     let library_path = free_args.next()
                         .ok_or(Error::ArgumentMissing("library path"))?;
-                    let library_provider = LibraryTestProvider::new(Path::new(library_path));
+                    let library_provider = LibraryTestProvider::new(Path::new(library_path))
+                        .map_err(Error::Invalid)?;
                     Ok(Box::new(library_provider) as Box<dyn TestProvider>)
                 }
 
@@ -155,6 +168,16 @@ pub fn parse<'a>() -> Result<Configuration> {
             Box::new(HardCodedTests::new())
         };
 
-        Ok(Configuration::new(testbed_reader, test_adapter))
+        let mut run_options = RunOptions::new()
+            .with_quiet(matches.opt_present("quiet"))
+            .with_debug(matches.opt_present("debug"));
+        if let Some(only) = matches.opt_str("only") {
+            run_options = run_options.with_only(only);
+        }
+        if let Some(filter) = matches.opt_str("filter") {
+            run_options = run_options.with_filter(filter);
+        }
+
+        Ok(Configuration::new(testbed_reader, test_adapter, run_options))
     }
 }
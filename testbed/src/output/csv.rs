@@ -10,15 +10,24 @@ use std::time::{self, Instant, SystemTime};
 
 use clockwise_common::output::DataWriter;
 use clockwise_common::trace::SerialTrace;
-use clockwise_common::test::{Execution, Response, Test};
+use clockwise_common::test::{Execution, Response, Sample, Test};
 use clockwise_shared::trace::TraceData;
 
 struct Point {
-    field: u8,
+    field: usize,
     t: Instant,
     raw: String,
 }
 
+/// Column name for a `TraceData` variant, used to build the dynamic field set in `save_output`.
+fn trace_field_name(data: &TraceData) -> &'static str {
+    match data {
+        TraceData::KernelWork(_) => "kernel_work",
+        TraceData::ProcessSuspended(_) => "process_suspended",
+        TraceData::InterruptServiced(_) => "interrupt_serviced",
+    }
+}
+
 #[derive(Debug)]
 pub struct CSVDataWriter {
     base_path: PathBuf,
@@ -87,7 +96,7 @@ impl DataWriter for CSVDataWriter {
                    execution: &Execution,
                    responses: &[Response],
                    traces: &[SerialTrace],
-                   energy: &HashMap<String, Vec<(Instant, f32)>>)
+                   energy: &HashMap<String, Vec<Sample>>)
                    -> Result<(), String>
     {
         let csv_path = {
@@ -102,14 +111,50 @@ impl DataWriter for CSVDataWriter {
             BufWriter::new(file)
         };
 
-        let columns = vec![
-            "time",
-            "energy_mw",
-            "kernel_work",
-            // "process_suspended",
-            // "interrupt_serviced",
-        ];
-        self.write_header(&mut csv_writer, &columns)?;
+        // reconstruct the individual TraceData entries carried in each trace, keeping the
+        // Instant the chunk of bytes they came in on arrived at
+        let mut trace_points: Vec<(Instant, TraceData)> = Vec::new();
+        for trace in traces {
+            let t = trace.get_time();
+            let data = trace.get_data();
+
+            let mut byte_no = 0;
+            while byte_no < data.len() {
+                let (trace_data, raw_size) = TraceData::deserialize(&data[byte_no..])
+                    .map_err(|_e| "failed to deserialize trace data".to_string())?;
+                trace_points.push((t, trace_data));
+                byte_no += raw_size;
+            }
+        }
+
+        // discover the columns this run actually has data for: one per energy meter key, then
+        // one per distinct TraceData variant observed, both in a stable order, recorded in the
+        // header alongside "time"
+        let mut meter_names: Vec<&String> = energy.keys().collect();
+        meter_names.sort();
+
+        let mut trace_field_names: Vec<&'static str> = Vec::new();
+        for (_t, trace_data) in &trace_points {
+            let name = trace_field_name(trace_data);
+            if !trace_field_names.contains(&name) {
+                trace_field_names.push(name);
+            }
+        }
+
+        let mut columns = vec!["time".to_string()];
+        let mut field_index: HashMap<String, usize> = HashMap::new();
+        for meter in &meter_names {
+            let name = format!("{}_mw", meter);
+            field_index.insert(name.clone(), columns.len());
+            columns.push(name);
+        }
+        for name in &trace_field_names {
+            field_index.insert(name.to_string(), columns.len());
+            columns.push(name.to_string());
+        }
+
+        let column_refs: Vec<&str> = columns.iter().map(String::as_str).collect();
+        self.write_header(&mut csv_writer, &column_refs)?;
 
         /* Coalescing data streams...
         - Sort them by their timestamps.
@@ -117,51 +162,28 @@ impl DataWriter for CSVDataWriter {
         - Record their values at that state, 0 if not defined yet. */
         let mut points = Vec::new();
 
-        // add the energy samples
-        let samples: &Vec<_> = energy.get("system").unwrap();
-        for (t, val) in samples.iter().copied() {
-            points.push(Point {
-                field: 1,
-                t,
-                raw: format!("{:.4}", val),
-            });
+        // add the energy samples, placing each back on the absolute timeline via its offset from
+        // `execution`'s start so it can be merged and sorted alongside the trace points below
+        for meter in &meter_names {
+            let field = field_index[&format!("{}_mw", meter)];
+            for sample in energy.get(*meter).unwrap() {
+                points.push(Point {
+                    field,
+                    t: execution.get_start() + sample.get_offset(),
+                    raw: format!("{:.4}", sample.get_value()),
+                });
+            }
         }
 
-        // add the kernel work samples
-        // first, we get them into a single slice-like...
-        let mut trace_data_timeline: Vec<(Instant, u8)> = Vec::new();
-        for trace in traces {
-            let t = trace.get_time();
-            let data = trace.get_data();
-            let timepoint = &[t];
-            let t_data_it = timepoint.into_iter().cycle().zip(data);
-            trace_data_timeline.extend(t_data_it.map(|(a, b)| (*a, *b)));
-        }
-        let raw_trace: Vec<_> = trace_data_timeline.iter()
-            .map(|(_t, data)| data)
-            .copied()
-            .collect();
-        let timeline: Vec<_> = trace_data_timeline.iter()
-            .map(|(t, _data)| t)
-            .copied()
-            .collect();
-
-        // recreate the TraceData, but we also know the Instant they arrived
-        let mut byte_no = 0;
-        while byte_no < raw_trace.len() {
-            // transform the raw data back into a trace
-            let (trace, raw_size) = TraceData::deserialize(&raw_trace[byte_no..])
-                .map_err(|_e| "failed to deserialize trace data".to_string())?;
-
-            // add the trace(s) to the points
-            let t = timeline[byte_no];
-            points.extend(match trace {
-                TraceData::KernelWork(count) =>
-                    vec![Point { field: 2, t, raw: format!("{}", count) }],
-                _ => vec![]
-            });
-
-            byte_no += raw_size;
+        // add the trace samples
+        for (t, trace_data) in &trace_points {
+            let field = field_index[trace_field_name(trace_data)];
+            let raw = match trace_data {
+                TraceData::KernelWork(count) => format!("{}", count),
+                TraceData::ProcessSuspended(executed_for_us) => format!("{}", executed_for_us),
+                TraceData::InterruptServiced(interrupt_no) => format!("{}", interrupt_no),
+            };
+            points.push(Point { field, t: *t, raw });
         }
 
         // sort the points by their time
@@ -175,19 +197,17 @@ impl DataWriter for CSVDataWriter {
             }
         });
 
-        // get the number of fields
-        let no_fields = points.iter()
-            .map(|p| p.field)
-            .max()
-            .unwrap();
-
-        let mut row = vec![None; no_fields as usize + 1];
+        let mut row: Vec<Option<String>> = vec![None; columns.len()];
         let mut all_valid = false;
-        // set all fields that have a valid initial value
-        row[1] = Some("0".to_string());
+        // energy fields are always valid from the start of the run; trace fields only become
+        // valid once the first trace of that kind arrives
+        for meter in &meter_names {
+            let field = field_index[&format!("{}_mw", meter)];
+            row[field] = Some("0".to_string());
+        }
         for point in points {
             // set the field specified by the point
-            row[point.field as usize] = Some(point.raw);
+            row[point.field] = Some(point.raw);
 
             if !all_valid {
                 // check that all the fields have a value
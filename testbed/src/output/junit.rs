@@ -0,0 +1,132 @@
+//! JUnit XML output formatting for data, for ingestion by CI test dashboards.
+
+use std::collections::HashMap;
+use std::fs::{DirBuilder, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{self, SystemTime};
+
+use clockwise_common::criteria::{Criterion, GPIOCriterion};
+use clockwise_common::output::DataWriter;
+use clockwise_common::trace::SerialTrace;
+use clockwise_common::test::{Execution, Response, Sample, Test};
+
+#[derive(Debug)]
+pub struct JUnitDataWriter {
+    base_path: PathBuf,
+}
+
+impl JUnitDataWriter {
+    pub fn new(base_path: &Path) -> JUnitDataWriter {
+        let mut dir_builder = DirBuilder::new();
+        dir_builder.recursive(true);
+        dir_builder.create(base_path)
+            .expect("could not create JUnit data output directory");
+
+        JUnitDataWriter {
+            base_path: PathBuf::from(base_path),
+        }
+    }
+
+    /** Criteria this writer can positively confirm violated from the raw responses alone.
+
+    Anything else is left unjudged, the same way a `Criterion` with no bounds set is treated as
+    unjudged (`None`) elsewhere rather than forced to a pass/fail.
+     */
+    fn failures(test: &Test, responses: &[Response]) -> Vec<String> {
+        let mut failures = Vec::new();
+
+        for criterion in test.get_criteria() {
+            if let Criterion::GPIO(GPIOCriterion::Any(pin)) = criterion {
+                let observed = responses.iter().any(|r| r.get_pin() == *pin);
+                if !observed {
+                    failures.push(format!(
+                        "assertion miss: expected activity on device pin {} but none was observed",
+                        pin));
+                }
+            }
+        }
+
+        failures
+    }
+}
+
+fn escape_xml_text(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn escape_xml_attr(s: &str) -> String {
+    escape_xml_text(s).replace('"', "&quot;")
+}
+
+impl DataWriter for JUnitDataWriter {
+    fn save_output(&self,
+                   test: &Test,
+                   execution: &Execution,
+                   responses: &[Response],
+                   traces: &[SerialTrace],
+                   _energy: &HashMap<String, Vec<Sample>>)
+                   -> Result<(), String>
+    {
+        let xml_path = {
+            let secs_epoch = SystemTime::now().duration_since(time::UNIX_EPOCH).unwrap();
+            let file_name = format!("{}-{}.xml", test.get_id(), secs_epoch.as_secs());
+            self.base_path.join(&file_name)
+        };
+
+        let mut writer = {
+            let file = File::create(&xml_path)
+                .map_err(|e| format!("cannot open JUnit XML ({}) for writing: {}", xml_path.display(), e))?;
+            BufWriter::new(file)
+        };
+
+        let failures = Self::failures(test, responses);
+        let time_secs = execution.duration().as_secs_f64();
+        let test_id = escape_xml_attr(test.get_id());
+
+        writeln!(writer, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")
+            .map_err(|e| format!("failed to write XML declaration: {}", e))?;
+        writeln!(writer, "<testsuites>")
+            .map_err(|e| format!("failed to write testsuites element: {}", e))?;
+        writeln!(writer,
+                 "  <testsuite name=\"{}\" tests=\"1\" failures=\"{}\" time=\"{:.3}\">",
+                 test_id,
+                 if failures.is_empty() { 0 } else { 1 },
+                 time_secs)
+            .map_err(|e| format!("failed to write testsuite element: {}", e))?;
+        writeln!(writer,
+                 "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\">",
+                 test_id, test_id, time_secs)
+            .map_err(|e| format!("failed to write testcase element: {}", e))?;
+
+        for failure in &failures {
+            writeln!(writer, "      <failure message=\"{}\"/>", escape_xml_attr(failure))
+                .map_err(|e| format!("failed to write failure element: {}", e))?;
+        }
+
+        if !traces.is_empty() {
+            let mut system_out = String::new();
+            for trace in traces {
+                let offset = trace.get_offset(execution.get_start());
+                let text = String::from_utf8_lossy(trace.get_data());
+                system_out.push_str(&format!("[{:?}] {}\n", offset, text));
+            }
+            // CDATA is opaque to the XML parser, so this isn't escaped; it just must not
+            // itself contain "]]>".
+            let system_out = system_out.replace("]]>", "]]]]><![CDATA[>");
+            writeln!(writer, "      <system-out><![CDATA[{}]]></system-out>", system_out)
+                .map_err(|e| format!("failed to write system-out element: {}", e))?;
+        }
+
+        writeln!(writer, "    </testcase>")
+            .map_err(|e| format!("failed to write testcase closing tag: {}", e))?;
+        writeln!(writer, "  </testsuite>")
+            .map_err(|e| format!("failed to write testsuite closing tag: {}", e))?;
+        writeln!(writer, "</testsuites>")
+            .map_err(|e| format!("failed to write testsuites closing tag: {}", e))?;
+
+        Ok(())
+    }
+}
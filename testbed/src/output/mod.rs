@@ -0,0 +1,9 @@
+//! Result output formatting.
+
+pub mod csv;
+pub mod junit;
+pub mod vcd;
+
+pub use csv::CSVDataWriter;
+pub use junit::JUnitDataWriter;
+pub use vcd::VCDDataWriter;
@@ -0,0 +1,177 @@
+//! VCD (Value Change Dump) output formatting for data.
+//!
+//! Exports GPIO activity, energy metering, and serial trace bytes from a single test execution as a
+//! VCD file, allowing the results to be viewed in any standard waveform viewer (e.g. GTKWave).
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{DirBuilder, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{self, Instant, SystemTime};
+
+use clockwise_common::output::DataWriter;
+use clockwise_common::test::{Execution, Response, Sample, Test};
+use clockwise_common::trace::SerialTrace;
+
+/// Characters VCD variable identifiers are built from: energy meters take the first identifiers,
+/// then GPIO pins, then the serial trace wire.
+const IDENTIFIER_CHARS: &str = "!\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~";
+
+fn identifier(index: usize) -> String {
+    let chars: Vec<char> = IDENTIFIER_CHARS.chars().collect();
+    let base = chars.len();
+
+    let mut n = index;
+    let mut id = String::new();
+    loop {
+        id.push(chars[n % base]);
+        n /= base;
+        if n == 0 {
+            break;
+        }
+        n -= 1;
+    }
+
+    id
+}
+
+/// Writer that exports test execution data as a VCD file.
+#[derive(Debug)]
+pub struct VCDDataWriter {
+    base_path: PathBuf,
+}
+
+impl VCDDataWriter {
+    /// Create a new `VCDDataWriter` that writes files into `base_path`.
+    pub fn new(base_path: &Path) -> VCDDataWriter {
+        let mut dir_builder = DirBuilder::new();
+        dir_builder.recursive(true);
+        dir_builder.create(base_path)
+            .expect("could not create VCD data output directory");
+
+        VCDDataWriter {
+            base_path: PathBuf::from(base_path),
+        }
+    }
+}
+
+impl DataWriter for VCDDataWriter {
+    fn save_output(&self,
+                   test: &Test,
+                   execution: &Execution,
+                   responses: &[Response],
+                   traces: &[SerialTrace],
+                   energy: &HashMap<String, Vec<Sample>>)
+                   -> Result<(), String>
+    {
+        let vcd_path = {
+            let secs_epoch = SystemTime::now().duration_since(time::UNIX_EPOCH).unwrap();
+            let file_name = format!("{}-{}.vcd", test.get_id(), secs_epoch.as_secs());
+            self.base_path.join(&file_name)
+        };
+
+        let mut writer = {
+            let file = File::create(&vcd_path)
+                .map_err(|e| format!("cannot open VCD ({}) for writing: {}", vcd_path.display(), e))?;
+            BufWriter::new(file)
+        };
+
+        // Assign one single-bit wire per pin that produced a response, in the order first seen.
+        let mut pin_ids: BTreeMap<u8, String> = BTreeMap::new();
+        for response in responses {
+            if !pin_ids.contains_key(&response.get_pin()) {
+                let id = identifier(pin_ids.len() + energy.len());
+                pin_ids.insert(response.get_pin(), id);
+            }
+        }
+
+        // Assign one real wire per energy meter.
+        let mut meter_ids: BTreeMap<&str, String> = BTreeMap::new();
+        for (index, meter_id) in energy.keys().enumerate() {
+            meter_ids.insert(meter_id.as_str(), identifier(index));
+        }
+
+        // A single wire carries serial trace bytes as they arrive.
+        let trace_id = identifier(pin_ids.len() + meter_ids.len());
+
+        writer.write_all(b"$timescale 1us $end\n")
+            .map_err(|e| format!("failed to write VCD header: {}", e))?;
+        writer.write_all(format!("$scope module {} $end\n", test.get_id()).as_bytes())
+            .map_err(|e| format!("failed to write VCD scope: {}", e))?;
+
+        for (pin_no, id) in &pin_ids {
+            writer.write_all(format!("$var wire 1 {} pin_{} $end\n", id, pin_no).as_bytes())
+                .map_err(|e| format!("failed to write VCD var: {}", e))?;
+        }
+        for (meter_id, id) in &meter_ids {
+            writer.write_all(format!("$var real 32 {} {}_mw $end\n", id, meter_id).as_bytes())
+                .map_err(|e| format!("failed to write VCD var: {}", e))?;
+        }
+        writer.write_all(format!("$var wire 8 {} serial_trace $end\n", trace_id).as_bytes())
+            .map_err(|e| format!("failed to write VCD var: {}", e))?;
+
+        writer.write_all(b"$upscope $end\n$enddefinitions $end\n")
+            .map_err(|e| format!("failed to write VCD footer: {}", e))?;
+
+        // Collect every value change, in time order, then emit them.
+        enum Change<'a> {
+            Pin(u8, bool),
+            Meter(&'a str, f32),
+            Trace(u8),
+        }
+
+        let mut changes: Vec<(Instant, Change)> = Vec::new();
+        for response in responses {
+            let level = matches!(response.get_output(), clockwise_common::comm::Signal::Digital(true));
+            changes.push((response.get_time(), Change::Pin(response.get_pin(), level)));
+        }
+        for (meter_id, samples) in energy {
+            for sample in samples {
+                let t = execution.get_start() + sample.get_offset();
+                changes.push((t, Change::Meter(meter_id.as_str(), sample.get_value())));
+            }
+        }
+        for trace in traces {
+            for byte in trace.get_data() {
+                changes.push((trace.get_time(), Change::Trace(*byte)));
+            }
+        }
+
+        changes.sort_by_key(|(t, _)| *t);
+
+        writer.write_all(b"$dumpvars\n")
+            .map_err(|e| format!("failed to write VCD dumpvars: {}", e))?;
+        for id in pin_ids.values() {
+            writer.write_all(format!("0{}\n", id).as_bytes())
+                .map_err(|e| format!("failed to write VCD initial value: {}", e))?;
+        }
+        for id in meter_ids.values() {
+            writer.write_all(format!("r0 {}\n", id).as_bytes())
+                .map_err(|e| format!("failed to write VCD initial value: {}", e))?;
+        }
+        writer.write_all(format!("b0 {}\n", trace_id).as_bytes())
+            .map_err(|e| format!("failed to write VCD initial value: {}", e))?;
+        writer.write_all(b"$end\n")
+            .map_err(|e| format!("failed to write VCD dumpvars end: {}", e))?;
+
+        let mut last_time_us: Option<u128> = None;
+        for (t, change) in changes {
+            let time_us = t.saturating_duration_since(execution.get_start()).as_micros();
+            if last_time_us != Some(time_us) {
+                writer.write_all(format!("#{}\n", time_us).as_bytes())
+                    .map_err(|e| format!("failed to write VCD timestamp: {}", e))?;
+                last_time_us = Some(time_us);
+            }
+
+            let line = match change {
+                Change::Pin(pin_no, level) => format!("{}{}\n", if level { '1' } else { '0' }, pin_ids[&pin_no]),
+                Change::Meter(meter_id, power) => format!("r{} {}\n", power, meter_ids[meter_id]),
+                Change::Trace(byte) => format!("b{:08b} {}\n", byte, trace_id),
+            };
+            writer.write_all(line.as_bytes())
+                .map_err(|e| format!("failed to write VCD value change: {}", e))?;
+        }
+
+        Ok(())
+    }
+}
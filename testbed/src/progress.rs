@@ -0,0 +1,91 @@
+//! Throttled live progress reporting for a batch of running tests.
+
+use std::cell::Cell;
+use std::io::IsTerminal;
+use std::time::{Duration, Instant};
+
+/// How long to wait before the first progress line, so a batch that finishes quickly never prints
+/// one at all.
+const DEFAULT_QUIET_THRESHOLD: Duration = Duration::from_millis(500);
+
+/// Cadence progress lines are printed at once the quiet threshold has passed.
+const DEFAULT_CADENCE: Duration = Duration::from_secs(2);
+
+/** Destination for live progress updates as [`run`](crate::runner::run) works through a batch of
+tests.
+
+A test that runs on real hardware can spend tens of seconds idling between operations, and nothing
+else in the runner prints anything until its `Evaluation` is fully formatted -- to someone watching
+a slow run, that looks identical to a hung device. [`update`](ProgressSink::update) is called once
+per test as the runner reaches it; implementations decide whether/when that turns into output.
+[`TickingProgressSink`] is the interactive default; [`NoopProgressSink`] is used when stdout isn't a
+terminal, so piped or logged output isn't spammed with status lines nobody will read live.
+ */
+pub trait ProgressSink {
+    /// Record that `completed` of `total` tests have finished and `current_test_id` is the one
+    /// the runner is about to evaluate next.
+    fn update(&self, completed: usize, total: usize, current_test_id: &str);
+}
+
+/** Prints a status line at most once per `cadence`, starting only after `quiet_threshold` has
+elapsed since the batch began.
+
+Mirrors the main crate's per-test `ProgressLog` (`src/testing/test.rs`) rate-limiting: don't print
+on every update, just often enough to show the run hasn't stalled. The quiet threshold is kept
+separate from the steady-state cadence so a fast batch stays silent throughout.
+ */
+pub struct TickingProgressSink {
+    start: Instant,
+    quiet_threshold: Duration,
+    cadence: Duration,
+    next_at: Cell<Instant>,
+}
+
+impl TickingProgressSink {
+    /// Create a sink using [`DEFAULT_QUIET_THRESHOLD`] and [`DEFAULT_CADENCE`], timed from `now`.
+    pub fn new(now: Instant) -> TickingProgressSink {
+        TickingProgressSink::with_timing(now, DEFAULT_QUIET_THRESHOLD, DEFAULT_CADENCE)
+    }
+
+    /// Create a sink with an explicit quiet threshold and cadence, timed from `now`.
+    pub fn with_timing(now: Instant, quiet_threshold: Duration, cadence: Duration) -> TickingProgressSink {
+        TickingProgressSink {
+            start: now,
+            quiet_threshold,
+            cadence,
+            next_at: Cell::new(now + quiet_threshold),
+        }
+    }
+}
+
+impl ProgressSink for TickingProgressSink {
+    fn update(&self, completed: usize, total: usize, current_test_id: &str) {
+        let now = Instant::now();
+        if now < self.next_at.get() {
+            return;
+        }
+        self.next_at.set(now + self.cadence);
+
+        println!(
+            "{}/{} complete, current: {}, elapsed {:.1}s",
+            completed, total, current_test_id, (now - self.start).as_secs_f64());
+    }
+}
+
+/// Discards every update; used when stdout isn't a terminal so a non-interactive log isn't
+/// flooded with status lines meant for a human watching live.
+#[derive(Debug, Default)]
+pub struct NoopProgressSink;
+
+impl ProgressSink for NoopProgressSink {
+    fn update(&self, _completed: usize, _total: usize, _current_test_id: &str) {}
+}
+
+/// Picks [`TickingProgressSink`] when stdout is a terminal, [`NoopProgressSink`] otherwise.
+pub fn default_sink(now: Instant) -> Box<dyn ProgressSink> {
+    if std::io::stdout().is_terminal() {
+        Box::new(TickingProgressSink::new(now))
+    } else {
+        Box::new(NoopProgressSink)
+    }
+}
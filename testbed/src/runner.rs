@@ -0,0 +1,136 @@
+//! A selection- and reporting-aware layer over [`TestProvider`] + [`Evaluator`].
+//!
+//! The bare `execute()`-everything flow works for a handful of hard-coded tests, but it doesn't
+//! scale to a large, disk-backed suite: you need to narrow down to one failing case and see
+//! everything about it. [`RunOptions`] captures the selection (`filter`/`only`) and reporting
+//! (`quiet`/`debug`) knobs, and [`run`] drives a [`Testbed`] accordingly.
+
+use clockwise_common::evaluation::{Evaluator, Status};
+use clockwise_common::input::TestProvider;
+use clockwise_common::test::Test;
+use clockwise_common::testbed::{Observation, RunOptions as ExecOptions, Testbed};
+
+use crate::progress::ProgressSink;
+
+/// Selection and reporting options for a test run.
+#[derive(Clone, Debug, Default)]
+pub struct RunOptions {
+    filter: Option<String>,
+    only: Option<String>,
+    quiet: bool,
+    debug: bool,
+}
+
+impl RunOptions {
+    /// Create options that run every test and print each `Evaluation`.
+    pub fn new() -> RunOptions {
+        RunOptions::default()
+    }
+
+    /// Only run tests whose id contains `filter`.
+    pub fn with_filter(self, filter: String) -> Self {
+        Self { filter: Some(filter), ..self }
+    }
+
+    /// Only run the test named `only`.
+    pub fn with_only(self, only: String) -> Self {
+        Self { only: Some(only), ..self }
+    }
+
+    /// Print just a pass/fail summary instead of every `Evaluation`.
+    pub fn with_quiet(self, quiet: bool) -> Self {
+        Self { quiet, ..self }
+    }
+
+    /// Dump the full `Observation` for any test that fails or errors.
+    pub fn with_debug(self, debug: bool) -> Self {
+        Self { debug, ..self }
+    }
+
+    /// Returns true if the test named `test_id` should be run under these options.
+    fn selects(&self, test_id: &str) -> bool {
+        match &self.only {
+            Some(only) => test_id == only,
+            None => self.filter.as_ref().map_or(true, |f| test_id.contains(f.as_str())),
+        }
+    }
+}
+
+/** Run the tests `tests` provides against `testbed`, reporting through `evaluator`.
+
+Tests are narrowed down per `options` before executing, so a large suite can be re-run scoped to
+one failing case. Reporting follows `options.quiet`/`options.debug`: by default every `Evaluation`
+is printed, `quiet` collapses that to a single pass/fail summary line, and `debug` additionally
+dumps the full `Observation` (GPIO timeline, energy samples, aligned serial traces) for anything
+that comes back `Fail`, `Error` or `UnexpectedPass`. Each test's `Observation` is evaluated as
+soon as that test finishes, and `progress` gets an update just before, independent of
+`options.quiet`, so a slow run still shows signs of life even when told to keep the per-test output
+down.
+ */
+pub fn run(
+    testbed: &Testbed,
+    tests: &dyn TestProvider,
+    evaluator: &dyn Evaluator,
+    options: &RunOptions,
+    progress: &dyn ProgressSink,
+) {
+    let selected: Vec<Test> = tests.tests()
+        .filter(|test| options.selects(test.get_id()))
+        .collect();
+    let total = selected.len();
+
+    let mut passed = 0usize;
+    let mut failed = 0usize;
+    let mut ignored = 0usize;
+    let mut completed = 0usize;
+
+    testbed.execute_streaming(&mut selected.into_iter(), &ExecOptions::new(), |observation| {
+        progress.update(completed, total, observation.source_test().get_id());
+
+        let evaluation = evaluator.evaluate(&observation);
+
+        match evaluation.status() {
+            Status::Fail | Status::Error | Status::UnexpectedPass => {
+                failed += 1;
+                if options.debug {
+                    dump_observation(&observation);
+                }
+            },
+            Status::Pass | Status::Complete => passed += 1,
+            Status::Ignored => ignored += 1,
+        }
+
+        if !options.quiet {
+            println!("{}", evaluation);
+        }
+        completed += 1;
+    });
+
+    println!(
+        "Summary: {} passed, {} failed, {} ignored (of {})",
+        passed, failed, ignored, passed + failed + ignored,
+    );
+}
+
+/// Print everything collected for `observation`'s test run, for triaging a failure.
+fn dump_observation(observation: &Observation) {
+    println!("=== {} (full observation) ===", observation.source_test().get_id());
+
+    println!("--- GPIO timeline ---");
+    for response in observation.gpio_responses() {
+        println!("  {}", response);
+    }
+
+    println!("--- Energy samples ---");
+    for (meter, samples) in observation.energy_metrics() {
+        println!("  {} ({} samples):", meter, samples.len());
+        for (t, value) in samples {
+            println!("    {:?}: {:.2} mW", t, value);
+        }
+    }
+
+    println!("--- Serial traces ---");
+    for trace in observation.traces() {
+        println!("  {}", trace);
+    }
+}
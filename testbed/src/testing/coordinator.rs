@@ -0,0 +1,163 @@
+//! Fan tests out across several testbeds and merge their results back into one ordered list.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::thread::JoinHandle;
+
+use flexbed_common::criteria::Criterion;
+use flexbed_common::test::Test;
+
+use super::evaluation::Evaluation;
+use super::testbed::Testbed;
+
+/** What a connected agent's testbed can actually run.
+
+The coordinator uses this to route a [`Test`] only to agents capable of running it, rather than
+discovering the mismatch after dispatch. An agent advertises this once, when it registers.
+ */
+#[derive(Clone, Debug)]
+pub struct AgentCapabilities {
+    /// Platform identifier the agent's testbed targets (e.g. `"hail"`, `"imxrt1050"`).
+    pub platform: String,
+    /// Identifiers of the energy meters wired up to the agent's testbed.
+    pub energy_meters: Vec<String>,
+    /// Number of dedicated trace pins the agent's testbed exposes.
+    pub trace_pin_count: usize,
+}
+
+impl AgentCapabilities {
+    /// Whether a test requiring `required_meters` can run on an agent with these capabilities.
+    pub fn can_run(&self, required_meters: &[String]) -> bool {
+        required_meters.iter().all(|meter| self.energy_meters.contains(meter))
+    }
+}
+
+/// A dispatched job, tagged with its position in the original ordering so results can be merged
+/// back in order regardless of which agent finishes first.
+type Job = (usize, Test);
+
+/// A connected agent: a local handle driving its own [`Testbed`] on a dedicated thread.
+struct Agent {
+    capabilities: AgentCapabilities,
+    job_tx: Sender<Option<Job>>,
+    handle: JoinHandle<()>,
+}
+
+/** Fans tests out across several testbeds and merges their results back into one ordered list.
+
+Each registered agent owns a [`Testbed`] and runs it on a dedicated thread, pulling jobs off a
+channel and pushing results back on another -- the same spawn-plus-typed-channel shape
+[`Testbed::execute`] itself uses to talk to its instruments. [`dispatch`](Coordinator::dispatch)
+routes each [`Test`] to the first agent whose [`AgentCapabilities`] satisfy it, lets every agent run
+concurrently, and reassembles the per-test evaluations in the caller's original order once every
+dispatched job has reported back.
+
+A real deployment would have each `Agent` live in its own process, reached over a socket instead of
+a channel, with jobs and results serialized across the wire. `Test` currently can't make that trip
+as-is -- `Operation` carries closures and `Execution` carries live hardware handles -- so this
+coordinator is written against a local, in-process transport for now. The `Job`/result shapes below
+carry the `Test` and `Evaluation` values themselves, so a socket transport would also need a wire
+form for both.
+ */
+pub struct Coordinator {
+    agents: Vec<Agent>,
+    result_tx: Sender<(usize, Evaluation)>,
+    result_rx: Receiver<(usize, Evaluation)>,
+}
+
+impl Coordinator {
+    /// Create a coordinator with no agents registered yet.
+    pub fn new() -> Coordinator {
+        let (result_tx, result_rx) = mpsc::channel();
+        Coordinator {
+            agents: Vec::new(),
+            result_tx,
+            result_rx,
+        }
+    }
+
+    /// Register an agent backed by a local `testbed`, advertising `capabilities`.
+    pub fn register(&mut self, testbed: Testbed, capabilities: AgentCapabilities) {
+        let (job_tx, job_rx) = mpsc::channel::<Option<Job>>();
+        let result_tx = self.result_tx.clone();
+
+        let agent_name = capabilities.platform.clone();
+        let handle = thread::Builder::new()
+            .name(format!("agent-{}", agent_name))
+            .spawn(move || {
+                while let Ok(Some((job_id, test))) = job_rx.recv() {
+                    let mut single_test = std::iter::once(test);
+                    let evaluations = testbed.execute(&mut single_test)
+                        .unwrap_or_else(|e| {
+                            println!("coordinator: agent '{}' failed to execute job {}: {}",
+                                     agent_name, job_id, e);
+                            Vec::new()
+                        });
+
+                    if let Some(evaluation) = evaluations.into_iter().next() {
+                        result_tx.send((job_id, evaluation)).unwrap();
+                    }
+                }
+            })
+            .expect("Could not spawn agent thread.");
+
+        self.agents.push(Agent { capabilities, job_tx, handle });
+    }
+
+    /** Dispatch `tests` across registered agents and return their evaluations in the same order.
+
+    Each test is routed to the first registered agent whose capabilities satisfy it. A test with no
+    capable agent is logged and omitted from the result rather than silently collapsing the
+    ordering, so callers should match results back up by test ID rather than assuming one evaluation
+    per input test.
+     */
+    pub fn dispatch(&self, tests: Vec<Test>) -> Vec<Evaluation> {
+        let mut pending = 0;
+        for (job_id, test) in tests.into_iter().enumerate() {
+            let required_meters = required_meters(&test);
+
+            let agent = self.agents.iter()
+                .find(|agent| agent.capabilities.can_run(&required_meters));
+            match agent {
+                Some(agent) => {
+                    agent.job_tx.send(Some((job_id, test))).unwrap();
+                    pending += 1;
+                },
+                None => println!(
+                    "coordinator: no registered agent can run test '{}'; skipping",
+                    test.get_id()),
+            }
+        }
+
+        let mut results: Vec<(usize, Evaluation)> = Vec::with_capacity(pending);
+        while results.len() < pending {
+            results.push(self.result_rx.recv().unwrap());
+        }
+
+        results.sort_by_key(|(job_id, _)| *job_id);
+        results.into_iter().map(|(_, evaluation)| evaluation).collect()
+    }
+
+    /// Tell every agent there's no more work and wait for its thread to exit.
+    pub fn shutdown(self) {
+        for agent in &self.agents {
+            agent.job_tx.send(None).unwrap();
+        }
+
+        for agent in self.agents {
+            agent.handle.join().unwrap_or_else(|_e| {
+                println!("coordinator: failed to join with agent thread");
+            });
+        }
+    }
+}
+
+/// The energy meters a test's criteria reference, used to route it to a capable agent.
+fn required_meters(test: &Test) -> Vec<String> {
+    test.get_criteria().iter()
+        .filter_map(|criterion| match criterion {
+            Criterion::Energy(energy_criterion) => Some(energy_criterion.get_meter().to_string()),
+            _ => None,
+        })
+        .collect()
+}
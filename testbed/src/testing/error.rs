@@ -18,6 +18,8 @@ pub enum Error {
     Reset(io::Error),
     /// Error originating from interacting with software ([`sw::error::Error`]).
     Software(sw::error::Error),
+    /// An instrument did not rendezvous before the per-test deadline passed.
+    Timeout,
 }
 
 impl error::Error for Error {
@@ -26,6 +28,7 @@ impl error::Error for Error {
             Error::Execution(ref e) => Some(e),
             Error::Reset(ref e) => Some(e),
             Error::Software(ref e) => Some(e),
+            Error::Timeout => None,
         }
     }
 }
@@ -48,6 +51,7 @@ impl Display for Error {
             Error::Execution(ref e) => write!(f, "test execution error: {}", e),
             Error::Reset(ref e) => write!(f, "failed to reset device: {}", e),
             Error::Software(ref e) => write!(f, "software interaction error: {}", e),
+            Error::Timeout => write!(f, "an instrument did not respond before the test deadline"),
         }
     }
 }
@@ -3,13 +3,14 @@
 use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Display;
-use std::time::{Duration, Instant};
+use std::time::Instant;
 
 use clockwise_common::criteria::{
     Criterion,
     GPIOCriterion,
     EnergyStat,
 };
+use clockwise_common::histogram::Histogram;
 use clockwise_common::sw::instrument::Spec;
 use clockwise_common::test::{
     Execution,
@@ -21,6 +22,30 @@ use clockwise_common::trace::SerialTrace;
 
 type Result<T> = std::result::Result<T, TestbedError>;
 
+/// Summary of an `Evaluation`.
+#[derive(Copy, Clone, Debug)]
+pub enum Status {
+    /// Execution finished without error.
+    Complete,
+    /// Execution completed and all criteria are satisfied.
+    Pass,
+    /// Execution completed, but one or more criteria are violated.
+    Fail,
+    /// Execution did not complete successfully.
+    Error,
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Status::Complete => write!(f, "Complete"),
+            Status::Pass => write!(f, "Pass"),
+            Status::Fail => write!(f, "Fail"),
+            Status::Error => write!(f, "Error"),
+        }
+    }
+}
+
 /// In-depth information about a test execution.
 #[derive(Debug)]
 pub struct Evaluation {
@@ -78,7 +103,187 @@ impl Evaluation {
 
     // Come up with an evaluation for the given criterion.
     fn evaluate(&self, criterion: &Criterion) -> (Status, Option<String>) {
+        let execution = match self.exec_result {
+            Ok(ref execution) => execution,
+            Err(_) => return (Status::Error, None),
+        };
+        let t0 = execution.get_start();
+
+        match criterion {
+            Criterion::GPIO(gpio_criterion) => {
+                match gpio_criterion {
+                    GPIOCriterion::Any(pin_no) => {
+                        let matching = self.device_responses.iter()
+                            .find(|response| response.get_pin() == *pin_no);
 
+                        match matching {
+                            Some(response) => (
+                                Status::Pass,
+                                Some(format!("activity @{:?}", response.get_offset(t0))),
+                            ),
+                            None => (
+                                Status::Fail,
+                                Some(format!("no activity observed on pin {}", pin_no)),
+                            ),
+                        }
+                    },
+
+                    GPIOCriterion::Analog(pin_no) => {
+                        let matching = self.device_responses.iter()
+                            .find(|response| response.get_pin() == *pin_no);
+
+                        match matching {
+                            Some(response) => (
+                                Status::Pass,
+                                Some(format!("activity @{:?}", response.get_offset(t0))),
+                            ),
+                            None => (
+                                Status::Fail,
+                                Some(format!("no activity observed on pin {}", pin_no)),
+                            ),
+                        }
+                    },
+                }
+            },
+
+            Criterion::Energy(energy_criterion) => {
+                let samples = match self.energy_metrics.get(energy_criterion.get_meter()) {
+                    Some(samples) => samples,
+                    None => return (
+                        Status::Error,
+                        Some(format!("no samples recorded for meter '{}'", energy_criterion.get_meter())),
+                    ),
+                };
+
+                if samples.is_empty() {
+                    return (
+                        Status::Error,
+                        Some("not enough samples to compute an energy statistic".to_string()),
+                    );
+                }
+
+                if samples.len() == 1 && !matches!(energy_criterion.get_stat(), EnergyStat::Total) {
+                    return (
+                        Status::Error,
+                        Some("not enough samples to compute an energy statistic".to_string()),
+                    );
+                }
+
+                let value = match energy_criterion.get_stat() {
+                    EnergyStat::Total if samples.len() == 1 => {
+                        // Nothing to interpolate between: fall back to a flat rate over the
+                        // whole execution rather than reporting zero.
+                        let (_t, power) = samples[0];
+                        power * execution.duration().as_secs_f32()
+                    },
+
+                    EnergyStat::Total => {
+                        // Trapezoidal integration of power (mW) over time yields energy in mJ.
+                        let mut total = 0f32;
+                        for window in samples.windows(2) {
+                            let (t_a, p_a) = window[0];
+                            let (t_b, p_b) = window[1];
+                            let dt = (t_b - t_a).as_secs_f32();
+                            total += dt * (p_a + p_b) / 2.0;
+                        }
+                        total
+                    },
+
+                    EnergyStat::Average => {
+                        let mut total = 0f32;
+                        for window in samples.windows(2) {
+                            let (t_a, p_a) = window[0];
+                            let (t_b, p_b) = window[1];
+                            let dt = (t_b - t_a).as_secs_f32();
+                            total += dt * (p_a + p_b) / 2.0;
+                        }
+                        let (first_t, _) = samples.first().unwrap();
+                        let (last_t, _) = samples.last().unwrap();
+                        let total_duration = (*last_t - *first_t).as_secs_f32();
+                        if total_duration > 0.0 {
+                            total / total_duration
+                        } else {
+                            0.0
+                        }
+                    },
+
+                    EnergyStat::Max => samples.iter()
+                        .map(|(_t, power)| *power)
+                        .fold(f32::MIN, f32::max),
+
+                    EnergyStat::Min => samples.iter()
+                        .map(|(_t, power)| *power)
+                        .fold(f32::MAX, f32::min),
+
+                    EnergyStat::Percentile(p) => {
+                        let histogram = Histogram::from_samples(samples.iter().map(|(_t, power)| *power));
+                        histogram.percentile(p)
+                    },
+
+                    EnergyStat::TimeAbove(threshold) => {
+                        // Proportion each interval the threshold crosses partway through, by
+                        // linear interpolation between its two readings, rather than counting it
+                        // whole or not at all.
+                        let mut above_ms = 0f32;
+                        for window in samples.windows(2) {
+                            let (t_a, p_a) = window[0];
+                            let (t_b, p_b) = window[1];
+                            if t_b <= t_a {
+                                continue;
+                            }
+
+                            let dt_ms = (t_b - t_a).as_secs_f32() * 1000.0;
+                            let fraction_above = if p_a >= threshold && p_b >= threshold {
+                                1.0
+                            } else if p_a < threshold && p_b < threshold {
+                                0.0
+                            } else {
+                                let crossing = (threshold - p_a) / (p_b - p_a);
+                                if p_b >= threshold { 1.0 - crossing } else { crossing }
+                            };
+
+                            above_ms += dt_ms * fraction_above;
+                        }
+                        above_ms
+                    },
+                };
+
+                let unit = match energy_criterion.get_stat() {
+                    EnergyStat::Total => "mJ",
+                    EnergyStat::TimeAbove(_) => "ms",
+                    _ => "mW",
+                };
+                let status = match energy_criterion.violated(value) {
+                    Some(true) => Status::Fail,
+                    Some(false) => Status::Pass,
+                    None => Status::Complete,
+                };
+
+                (status, Some(format!("{:.2}{} measured", value, unit)))
+            },
+
+            Criterion::SerialTrace(trace_criterion) => {
+                if let Some(aligned) = trace_criterion.align(t0, self.serial_traces.as_slice()) {
+                    let mut message = "satisfied by: ".to_string();
+                    let count = aligned.len();
+                    for (trace, no) in aligned.into_iter().zip(1..) {
+                        message.push_str(&format!("@{:?}", trace.get_offset(t0)));
+                        if no < count {
+                            message.push_str(" → ");
+                        }
+                    }
+                    (Status::Pass, Some(message))
+                } else {
+                    (Status::Fail, Some("trace conditions were not satisfied".to_string()))
+                }
+            },
+
+            Criterion::Memory(memory_criterion) => (
+                Status::Error,
+                Some(format!("memory trace data is not collected by this testbed (counter: {})",
+                              memory_criterion.get_counter())),
+            ),
+        }
     }
 }
 
@@ -0,0 +1,30 @@
+//! Pluggable subsystems that run alongside test execution in lockstep with the executor.
+
+use std::sync::{Arc, RwLock};
+use std::thread::JoinHandle;
+use std::time::Instant;
+
+use flexbed_common::test::Test;
+
+use super::rendezvous::TimeoutBarrier;
+
+/** A subsystem that observes or drives some aspect of a test run on its own thread.
+
+Instruments synchronize with the executor and each other through a shared [`TimeoutBarrier`],
+replacing what used to be a fixed four-party `Barrier` shared by the executor, the GPIO observer,
+the energy meter, and the serial tracer. The executor now sizes the barrier from however many
+instruments are active and drives each of them identically, so adding a new subsystem only means
+implementing this trait rather than hand-wiring another barrier party. Using a `TimeoutBarrier`
+instead of `std::sync::Barrier` also means a wedged instrument can't freeze the whole suite: each
+rendezvous is bounded by the shared per-test deadline.
+ */
+pub trait Instrument: Send {
+    /// A short, human-readable name used for thread naming and diagnostics.
+    fn name(&self) -> &str;
+
+    /// Launch the instrument's lockstep loop on its own thread, consuming it in the process.
+    fn launch(self: Box<Self>,
+             current_test: Arc<RwLock<Option<Test>>>,
+             deadline: Arc<RwLock<Instant>>,
+             barrier: Arc<TimeoutBarrier>) -> JoinHandle<()>;
+}
@@ -1,8 +1,11 @@
 /*! Defining and executing tests and evaluating their results.
  */
 
+pub mod coordinator;
 pub mod error;
 pub mod evaluation;
+pub mod instrument;
+pub mod rendezvous;
 pub mod testbed;
 
 use flexbed_common::error::Error;
@@ -0,0 +1,66 @@
+//! A barrier rendezvous that can time out instead of blocking forever.
+
+use std::sync::{Condvar, Mutex};
+use std::time::Instant;
+
+/// A [`TimeoutBarrier::wait`] deadline passed before every party arrived.
+#[derive(Copy, Clone, Debug)]
+pub struct Timeout;
+
+/** Like [`std::sync::Barrier`], but `wait` is bounded by a deadline instead of blocking forever.
+
+A wedged UART read in `test.trace` or an interrupt that never fires in `test.observe` would hang a
+plain `Barrier` (and every other party waiting on it) indefinitely. `TimeoutBarrier` tracks arrivals
+and a generation counter behind a `Mutex` and wakes waiters through a `Condvar`, so a caller can give
+up once its deadline passes instead of blocking the rest of the suite forever.
+ */
+#[derive(Debug)]
+pub struct TimeoutBarrier {
+    parties: usize,
+    state: Mutex<(usize, u64)>,
+    condvar: Condvar,
+}
+
+impl TimeoutBarrier {
+    /// Create a barrier that rendezvouses `parties` callers at a time.
+    pub fn new(parties: usize) -> TimeoutBarrier {
+        TimeoutBarrier {
+            parties,
+            state: Mutex::new((0, 0)),
+            condvar: Condvar::new(),
+        }
+    }
+
+    /** Wait for every party to arrive, or until `deadline` passes.
+
+    Returns `Ok(())` once the last party arrives, releasing every other waiter in the same
+    generation. Returns `Err(Timeout)` if `deadline` passes first, without having released anyone.
+     */
+    pub fn wait(&self, deadline: Instant) -> Result<(), Timeout> {
+        let mut state = self.state.lock().unwrap();
+        let generation = state.1;
+
+        state.0 += 1;
+        if state.0 == self.parties {
+            state.0 = 0;
+            state.1 = state.1.wrapping_add(1);
+            self.condvar.notify_all();
+            return Ok(());
+        }
+
+        while state.1 == generation {
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Timeout);
+            }
+
+            let (guard, result) = self.condvar.wait_timeout(state, deadline - now).unwrap();
+            state = guard;
+            if result.timed_out() && state.1 == generation {
+                return Err(Timeout);
+            }
+        }
+
+        Ok(())
+    }
+}
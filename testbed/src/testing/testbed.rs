@@ -6,37 +6,43 @@ use std::fmt::Display;
 use std::sync::mpsc;
 use std::sync::mpsc::SyncSender;
 use std::sync::{Arc,
-                Barrier,
                 Mutex,
                 RwLock};
 use std::thread;
 use std::thread::JoinHandle;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use flexbed_common::facility::EnergyMetering;
-use flexbed_common::io::Mapping;
-use flexbed_common::mem::StreamOperation;
+use flexbed_common::io::{DeviceOutputs, Mapping};
 use flexbed_common::test::{Response, Test};
 use flexbed_common::trace;
 use flexbed_common::trace::SerialTrace;
+use rppal::uart::Uart;
 
 use crate::sw::PlatformSupport;
 
 use super::Error;
 use super::evaluation::Evaluation;
+use super::instrument::Instrument;
+use super::rendezvous::TimeoutBarrier;
 
 type Result<T> = std::result::Result<T, Error>;
 
+/// How long after the executor picks up a test every instrument rendezvous for that test must
+/// complete before the test is considered wedged.
+const DEFAULT_TEST_TIMEOUT: Duration = Duration::from_secs(30);
+
 /// Test suite executor
 #[derive(Debug)]
 pub struct Testbed {
     pin_mapping: Mapping,
     platform_support: Box<dyn PlatformSupport>,
     energy_meters: Arc<Mutex<HashMap<String, Box<dyn EnergyMetering>>>>,
+    test_timeout: Duration,
 }
 
 impl Testbed {
-    /// Create a new `Testbed`.
+    /// Create a new `Testbed`, using [`DEFAULT_TEST_TIMEOUT`] as the per-test instrument deadline.
     pub fn new(pin_mapping: Mapping,
                platform_support: Box<dyn PlatformSupport>,
                energy_meters: HashMap<String, Box<dyn EnergyMetering>>) -> Testbed
@@ -45,12 +51,30 @@ impl Testbed {
             pin_mapping,
             platform_support,
             energy_meters: Arc::new(Mutex::new(energy_meters)),
+            test_timeout: DEFAULT_TEST_TIMEOUT,
         }
     }
 
-    /** Run tests.
+    /// Create a new `Testbed` with an explicit per-test instrument deadline.
+    pub fn with_test_timeout(pin_mapping: Mapping,
+                              platform_support: Box<dyn PlatformSupport>,
+                              energy_meters: HashMap<String, Box<dyn EnergyMetering>>,
+                              test_timeout: Duration) -> Testbed
+    {
+        Testbed {
+            pin_mapping,
+            platform_support,
+            energy_meters: Arc::new(Mutex::new(energy_meters)),
+            test_timeout,
+        }
+    }
 
-    Execute the given tests one after the other.
+    /** Run tests, collecting every `Evaluation` before returning.
+
+    Execute the given tests one after the other. This holds the whole suite's results (and every
+    GPIO/trace/energy sample backing them) in memory until the last test finishes; for long suites,
+    or callers that want to see results as they land, use [`execute_into`](Testbed::execute_into)
+    with a sink that writes through instead of accumulating.
 
     # Examples
     ```
@@ -63,32 +87,55 @@ impl Testbed {
         T: Iterator<Item = Test>,
     {
         let mut test_results = Vec::new();
+        self.execute_into(tests, &mut test_results)?;
+        Ok(test_results)
+    }
+
+    /** Run tests, pushing each `Evaluation` into `sink` as soon as its test finishes.
 
-        let barrier = Arc::new(Barrier::new(4));
+    This is [`execute`](Testbed::execute)'s underlying loop, generalized over anything that
+    implements [`Extend<Evaluation>`] -- a `Vec`, or a sink that writes each evaluation out to disk
+    or a socket as it arrives instead of holding the whole suite in memory. `execute` is just this
+    with a `Vec` sink.
+     */
+    pub fn execute_into<'b, T, S>(&self, tests: &mut T, sink: &mut S) -> Result<()>
+    where
+        T: Iterator<Item = Test>,
+        S: Extend<Evaluation>,
+    {
         let current_test: Arc<RwLock<Option<Test>>> = Arc::new(RwLock::new(None));
+        let deadline: Arc<RwLock<Instant>> = Arc::new(RwLock::new(Instant::now() + self.test_timeout));
 
         let (observer_schannel, observer_rchannel) = mpsc::sync_channel(0);
-        let watch_thread = self.launch_observer(Arc::clone(&current_test),
-                                                Arc::clone(&barrier),
-                                                observer_schannel);
+        let observer = self.make_observer(observer_schannel);
 
         let (energy_schannel, energy_rchannel) = mpsc::sync_channel(0);
-        let energy_thread = self.launch_metering(Arc::clone(&current_test),
-                                                 Arc::clone(&barrier),
-                                                 energy_schannel);
+        let metering = self.make_metering(energy_schannel);
 
         let (trace_schannel, trace_rchannel) = mpsc::sync_channel(0);
-        let trace_thread = self.launch_tracing(Arc::clone(&current_test),
-                                               Arc::clone(&barrier),
-                                               trace_schannel);
-
-        // let (mem_schannel, mem_rchannel) = mpsc::sync_channel(0);
-        // let mem_thread =
+        let tracing = self.make_tracing(trace_schannel);
+
+        // The executor is one party to the barrier; every instrument launched is another. Sizing
+        // the barrier this way means adding another instrument never requires touching this count.
+        let instruments: Vec<Box<dyn Instrument>> = vec![
+            Box::new(observer),
+            Box::new(metering),
+            Box::new(tracing),
+        ];
+        let barrier = Arc::new(TimeoutBarrier::new(1 + instruments.len()));
+        let instrument_threads: Vec<JoinHandle<()>> = instruments.into_iter()
+            .map(|instrument| instrument.launch(
+                Arc::clone(&current_test),
+                Arc::clone(&deadline),
+                Arc::clone(&barrier)))
+            .collect();
 
         for test in tests {
             println!("executor: running '{}'", test.get_id());
             println!("{}", test);
 
+            *deadline.write().unwrap() = Instant::now() + self.test_timeout;
+
             // Reconfigure target if necessary.
             // Just always configuring when there are trace points
             // instead of doing anything idempotent.
@@ -101,7 +148,7 @@ impl Testbed {
                     &test,
                     None,
                     Error::Software(reconfig_err));
-                test_results.push(eval);
+                sink.extend(std::iter::once(eval));
                 continue;
             }
             let platform_spec = res.unwrap();
@@ -113,7 +160,7 @@ impl Testbed {
                     &test,
                     Some(&platform_spec),
                     load_err);
-                test_results.push(eval);
+                sink.extend(std::iter::once(eval));
                 continue;
             }
 
@@ -123,22 +170,30 @@ impl Testbed {
                 .expect("Could not obtain GPIO inputs from executor thread.");
 
             // wait for observer, metering thread to be ready
-            barrier.wait();
+            if barrier.wait(*deadline.read().unwrap()).is_err() {
+                println!("executor: instrument rendezvous timed out before test start");
+                sink.extend(std::iter::once(Evaluation::failed(&test, Some(&platform_spec), Error::Timeout)));
+                continue;
+            }
 
             if test.get_reset_on_start() {
                 let reset_res = self.pin_mapping.get_device().reset(&mut inputs);
                 if let Err(e) = reset_res {
-                    test_results.push(
+                    sink.extend(std::iter::once(
                         Evaluation::failed(
                             &test,
                             Some(&platform_spec),
-                            Error::Reset(e)));
+                            Error::Reset(e))));
                     continue;
                 }
             }
 
             // wait for test to begin
-            barrier.wait();
+            if barrier.wait(*deadline.read().unwrap()).is_err() {
+                println!("executor: instrument rendezvous timed out before test start");
+                sink.extend(std::iter::once(Evaluation::failed(&test, Some(&platform_spec), Error::Timeout)));
+                continue;
+            }
             println!("executor: starting test '{}'", test.get_id());
 
             // if test.get_reset_on_start() {
@@ -152,7 +207,11 @@ impl Testbed {
 
             // release observer thread
             println!("executor: test execution complete");
-            barrier.wait();
+            if barrier.wait(*deadline.read().unwrap()).is_err() {
+                println!("executor: instrument rendezvous timed out after test execution");
+                sink.extend(std::iter::once(Evaluation::failed(&test, Some(&platform_spec), Error::Timeout)));
+                continue;
+            }
 
             // get GPIO responses
             let (parallel_traces, gpio_activity) = {
@@ -204,42 +263,104 @@ impl Testbed {
                 parallel_traces,
                 serial_traces,
                 energy_data);
-            test_results.push(evaluation);
+            sink.extend(std::iter::once(evaluation));
             println!("executor: test finished.");
         }
 
         *current_test.write().unwrap() = None;
+        *deadline.write().unwrap() = Instant::now() + self.test_timeout;
         println!("executor: final wait");
-        barrier.wait();
+        if barrier.wait(*deadline.read().unwrap()).is_err() {
+            println!("executor: instrument rendezvous timed out during shutdown");
+        }
 
         // Not too concerned with joining these without error
         // since testing is complete at this point. It shouldn't
         // result in a crash either.
-        watch_thread.join().unwrap_or_else(|_e| {
-            println!("executor: failed to join with observer thread");
-        });
-        energy_thread.join().unwrap_or_else(|_e| {
-            println!("executor: failed to join with metering thread");
-        });
-        trace_thread.join().unwrap_or_else(|_e| {
-            println!("executor: failed to join with tracing thread");
-        });
+        for handle in instrument_threads {
+            handle.join().unwrap_or_else(|_e| {
+                println!("executor: failed to join with an instrument thread");
+            });
+        }
 
-        Ok(test_results)
+        Ok(())
     }
 
-    fn launch_observer(
-        &self,
-        test_container: Arc<RwLock<Option<Test>>>,
-        barrier: Arc<Barrier>,
-        response_schannel: SyncSender<Option<Response>>,
-    ) -> JoinHandle<()> {
-        let mut outputs = self.pin_mapping.get_gpio_outputs()
+    /// Build the [`Observer`] instrument for this testbed's pin mapping.
+    fn make_observer(&self, response_schannel: SyncSender<Option<Response>>) -> Observer {
+        let outputs = self.pin_mapping.get_gpio_outputs()
             .expect("Could not obtain GPIO outputs from observer thread.");
         let trace_pins = self.pin_mapping.get_trace_pin_nos().clone();
 
+        Observer {
+            outputs,
+            trace_pins,
+            response_schannel,
+        }
+    }
+
+    /// Build the [`Metering`] instrument for this testbed's energy meters.
+    fn make_metering(&self, energy_schannel: SyncSender<Option<(String, f32)>>) -> Metering {
+        Metering {
+            meters: Arc::clone(&self.energy_meters),
+            energy_schannel,
+        }
+    }
+
+    /// Build the [`Tracing`] instrument for this testbed's serial tracing UART.
+    fn make_tracing(&self, trace_schannel: SyncSender<Option<SerialTrace>>) -> Tracing {
+        let uart = self.pin_mapping.get_uart()
+            .expect("Could not obtain UART from tracing thread.");
+
+        Tracing {
+            uart,
+            trace_schannel,
+        }
+    }
+
+    /// Load specified applications onto the device.
+    fn load_apps(&self, test: &Test) -> Result<()> {
+        println!("executor: loading/unloading {} software", self.platform_support.platform());
+        let currently_loaded = self.platform_support.loaded_software();
+        for app_id in &currently_loaded {
+            if !test.get_app_ids().contains(app_id) {
+                println!("executor: removing '{}'", app_id);
+                self.platform_support.unload(app_id)?;
+            }
+        }
+
+        for app_name in test.get_app_ids() {
+            if !currently_loaded.contains(app_name) {
+                println!("executor: loading '{}'", app_name);
+                self.platform_support.load(app_name)
+                    .map_err(|e| Error::Software(e))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Watches GPIO pins for activity during a test and reports back the responses observed.
+#[derive(Debug)]
+struct Observer {
+    outputs: DeviceOutputs,
+    trace_pins: Vec<u8>,
+    response_schannel: SyncSender<Option<Response>>,
+}
+
+impl Instrument for Observer {
+    fn name(&self) -> &str {
+        "test-observer"
+    }
+
+    fn launch(mut self: Box<Self>,
+              test_container: Arc<RwLock<Option<Test>>>,
+              deadline: Arc<RwLock<Instant>>,
+              barrier: Arc<TimeoutBarrier>) -> JoinHandle<()>
+    {
         thread::Builder::new()
-            .name("test-observer".to_string())
+            .name(self.name().to_string())
             .spawn(move || {
                 println!("observer: started.");
 
@@ -247,36 +368,45 @@ impl Testbed {
                 responses.reserve(1000);
                 loop {
                     // wait for next test
-                    barrier.wait();
+                    if barrier.wait(*deadline.read().unwrap()).is_err() {
+                        println!("observer: rendezvous timed out; resyncing");
+                        continue;
+                    }
 
                     // set up to watch for responses according to criteria
                     if let Some(ref test) = *test_container.read().unwrap() {
-                        let interrupt_pin_nos = test.prep_observe(&mut outputs, &trace_pins)
+                        let interrupt_pin_nos = test.prep_observe(&mut self.outputs, &self.trace_pins)
                             .unwrap(); // <-- communicate back?
                         let interrupt_pins = interrupt_pin_nos.into_iter()
-                            .map(|pin_no| outputs.get_pin(pin_no).unwrap())
+                            .map(|pin_no| self.outputs.get_pin(pin_no).unwrap())
                             .collect();
 
                         // wait for test to begin
                         println!("observer: ready to begin test");
-                        barrier.wait();
+                        if barrier.wait(*deadline.read().unwrap()).is_err() {
+                            println!("observer: rendezvous timed out; resyncing");
+                            continue;
+                        }
                         println!("observer: starting watch");
 
                         let t0 = Instant::now();
-                        test.observe(t0, &interrupt_pins, &mut responses)
+                        test.observe(t0, &interrupt_pins, &mut responses, None, None)
                             .unwrap();
 
-                        barrier.wait();
+                        if barrier.wait(*deadline.read().unwrap()).is_err() {
+                            println!("observer: rendezvous timed out; resyncing");
+                            continue;
+                        }
 
                         println!("observer: cleaning up interrupts");
-                        for pin in &mut outputs {
+                        for pin in &mut self.outputs {
                             pin.clear_interrupt().unwrap();
                         }
 
                         for r in responses.drain(..) {
-                            response_schannel.send(Some(r)).unwrap();
+                            self.response_schannel.send(Some(r)).unwrap();
                         }
-                        response_schannel.send(None).unwrap();
+                        self.response_schannel.send(None).unwrap();
                     } else {
                         // no more tests to run
                         break;
@@ -287,50 +417,72 @@ impl Testbed {
             })
             .expect("Could not spawn observer thread.")
     }
+}
 
-    fn launch_metering(
-        &self,
-        test_container: Arc<RwLock<Option<Test>>>,
-        barrier: Arc<Barrier>,
-        energy_schannel: SyncSender<Option<(String, f32)>>,
-    ) -> JoinHandle<()> {
-        println!("Starting energy metering thread.");
+/// Samples configured energy meters during a test and reports back the readings taken.
+#[derive(Debug)]
+struct Metering {
+    meters: Arc<Mutex<HashMap<String, Box<dyn EnergyMetering>>>>,
+    energy_schannel: SyncSender<Option<(String, f32)>>,
+}
+
+impl Instrument for Metering {
+    fn name(&self) -> &str {
+        "test-metering"
+    }
 
-        let meters = Arc::clone(&self.energy_meters);
+    fn launch(self: Box<Self>,
+              test_container: Arc<RwLock<Option<Test>>>,
+              deadline: Arc<RwLock<Instant>>,
+              barrier: Arc<TimeoutBarrier>) -> JoinHandle<()>
+    {
+        println!("Starting energy metering thread.");
 
         thread::Builder::new()
-            .name("test-metering".to_string())
+            .name(self.name().to_string())
             .spawn(move || {
                 println!("metering: started.");
 
-                let meters = meters.lock().unwrap();
+                let meters = self.meters.lock().unwrap();
                 let mut samples: HashMap<String, Vec<f32>> = meters.keys()
                     .map(|meter_id| { (meter_id.clone(), Vec::new()) })
                     .collect();
 
                 loop {
                     // wait for next test
-                    barrier.wait();
+                    if barrier.wait(*deadline.read().unwrap()).is_err() {
+                        println!("metering: rendezvous timed out; resyncing");
+                        continue;
+                    }
 
                     if let Some(ref test) = *test_container.read().unwrap() {
                         // here, better error management across threads would be nice!
                         let need_metering = test.prep_meter(&meters, &mut samples).unwrap();
                         if !need_metering {
                             println!("metering: idling; not needed for this test");
-                            barrier.wait();
+                            if barrier.wait(*deadline.read().unwrap()).is_err() {
+                                println!("metering: rendezvous timed out; resyncing");
+                                continue;
+                            }
                         } else {
                             // wait for test to begin
                             println!("metering: ready to begin test");
-                            barrier.wait();
+                            if barrier.wait(*deadline.read().unwrap()).is_err() {
+                                println!("metering: rendezvous timed out; resyncing");
+                                continue;
+                            }
 
-                            test.meter(&meters, &mut samples);
+                            test.meter(&meters, &mut samples, None);
                         }
                     } else {
                         // no more tests to run
                         break;
                     }
 
-                    barrier.wait();
+                    if barrier.wait(*deadline.read().unwrap()).is_err() {
+                        println!("metering: rendezvous timed out; resyncing");
+                        continue;
+                    }
 
                     // communicate results back
                     for (meter_id, samples) in &samples {
@@ -338,99 +490,88 @@ impl Testbed {
                             // .to_string()... kinda wasteful, but it works;
                             // perhaps better comm. types wanted?
                             let message = Some((meter_id.to_string(), *sample));
-                            energy_schannel.send(message).unwrap();
+                            self.energy_schannel.send(message).unwrap();
                         }
                     }
-                    energy_schannel.send(None).unwrap(); // done communicating results
+                    self.energy_schannel.send(None).unwrap(); // done communicating results
                 }
             })
             .expect("Could not spawn metering thread.")
     }
+}
 
-    fn launch_tracing(
-        &self,
-        test_container: Arc<RwLock<Option<Test>>>,
-        barrier: Arc<Barrier>,
-        trace_schannel: SyncSender<Option<SerialTrace>>,
-    ) -> JoinHandle<()> {
-        println!("Starting tracing thread.");
+/// Captures UART traffic during a test and reports back the reconstructed serial traces.
+#[derive(Debug)]
+struct Tracing {
+    uart: Uart,
+    trace_schannel: SyncSender<Option<SerialTrace>>,
+}
 
-        let uart = self.pin_mapping.get_uart()
-            .expect("Could not obtain UART from tracing thread.");
+impl Instrument for Tracing {
+    fn name(&self) -> &str {
+        "test-stracing"
+    }
+
+    fn launch(mut self: Box<Self>,
+              test_container: Arc<RwLock<Option<Test>>>,
+              deadline: Arc<RwLock<Instant>>,
+              barrier: Arc<TimeoutBarrier>) -> JoinHandle<()>
+    {
+        println!("Starting tracing thread.");
 
         thread::Builder::new()
-            .name("test-stracing".to_string())
+            .name(self.name().to_string())
             .spawn(move || {
                 println!("stracing: started.");
 
-                let mut uart = uart;
                 let mut buffer: Vec<u8> = Vec::new();
                 let mut schedule: Vec<(Instant, usize)> = Vec::new();
                 let mut bytes_rx;
 
                 loop {
                     // wait for next test
-                    barrier.wait();
+                    if barrier.wait(*deadline.read().unwrap()).is_err() {
+                        println!("stracing: rendezvous timed out; resyncing");
+                        continue;
+                    }
 
                     if let Some(ref test) = *test_container.read().unwrap() {
-                        test.prep_tracing(&mut uart, &mut buffer, &mut schedule).unwrap();
+                        test.prep_tracing(&mut self.uart, &mut buffer, &mut schedule).unwrap();
 
-                        barrier.wait();
+                        if barrier.wait(*deadline.read().unwrap()).is_err() {
+                            println!("stracing: rendezvous timed out; resyncing");
+                            continue;
+                        }
+                        let t0 = Instant::now();
                         bytes_rx = test.trace(
-                            &mut uart,
+                            &mut self.uart,
                             &mut buffer,
-                            &mut schedule).unwrap();
+                            &mut schedule,
+                            t0,
+                            None).unwrap();
                         println!("stracing: received {} bytes over UART", bytes_rx);
                     } else {
                         // no more tests to run
                         break;
                     }
 
-                    barrier.wait();
+                    if barrier.wait(*deadline.read().unwrap()).is_err() {
+                        println!("stracing: rendezvous timed out; resyncing");
+                        continue;
+                    }
 
                     let serial_traces = trace::reconstruct_serial(
                         &buffer.as_slice()[0..bytes_rx],
                         &schedule);
                     // communicate results back
                     for trace in serial_traces {
-                        trace_schannel.send(Some(trace)).unwrap();
+                        self.trace_schannel.send(Some(trace)).unwrap();
                     }
-                    trace_schannel.send(None).unwrap(); // done communicating results
+                    self.trace_schannel.send(None).unwrap(); // done communicating results
                 }
             })
             .expect("Could not spawn tracing thread.")
     }
-
-    // fn launch_memory(
-    //     &self,
-    //     test_container: Arc<RwLock<Option<Test>>>,
-    //     barrier: Arc<Barrier>,
-    //     mem_schannel: SyncSender<Option<StreamOperation>>,
-    // ) -> JoinHandle<()> {
-    //     println!("Starting memory thread.");
-    // }
-
-    /// Load specified applications onto the device.
-    fn load_apps(&self, test: &Test) -> Result<()> {
-        println!("executor: loading/unloading {} software", self.platform_support.platform());
-        let currently_loaded = self.platform_support.loaded_software();
-        for app_id in &currently_loaded {
-            if !test.get_app_ids().contains(app_id) {
-                println!("executor: removing '{}'", app_id);
-                self.platform_support.unload(app_id)?;
-            }
-        }
-
-        for app_name in test.get_app_ids() {
-            if !currently_loaded.contains(app_name) {
-                println!("executor: loading '{}'", app_name);
-                self.platform_support.load(app_name)
-                    .map_err(|e| Error::Software(e))?;
-            }
-        }
-
-        Ok(())
-    }
 }
 
 impl Display for Testbed {
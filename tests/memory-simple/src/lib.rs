@@ -63,3 +63,10 @@ impl TestProvider for SampleTestProvider {
 pub fn get_test_adapter() -> Box<dyn TestProvider> {
     Box::new(SampleTestProvider::new())
 }
+
+/// ABI handshake the host checks before resolving `get_test_adapter`; must always return the
+/// `ABI_VERSION` of the `flexbed_common` this plugin was built against.
+#[no_mangle]
+pub fn clockwise_abi_version() -> u32 {
+    flexbed_common::input::ABI_VERSION
+}
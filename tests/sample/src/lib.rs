@@ -97,3 +97,10 @@ impl TestProvider for SampleTestProvider {
 pub fn get_test_adapter() -> Box<dyn TestProvider> {
     Box::new(SampleTestProvider::new())
 }
+
+/// ABI handshake the host checks before resolving `get_test_adapter`; must always return the
+/// `ABI_VERSION` of the `clockwise_common` this plugin was built against.
+#[no_mangle]
+pub fn clockwise_abi_version() -> u32 {
+    clockwise_common::input::ABI_VERSION
+}